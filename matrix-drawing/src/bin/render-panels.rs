@@ -0,0 +1,127 @@
+//! Renders what the panels would show, for previews and for checking layout changes
+//!
+//! Run with `cargo run -p matrix-drawing --features render --bin render-panels -- <ARGS>`
+
+use clap::{Parser, Subcommand};
+use matrix_drawing::{
+    render::{write_gif, write_png, GifFrame},
+    transmitted_data::TransmittedData,
+    PanelGeometry,
+};
+use std::{
+    error::Error,
+    fs::{self, File},
+    io::BufWriter,
+    path::PathBuf,
+    time::Duration,
+};
+use uwh_common::{game_snapshot::GameSnapshot, recording::read_recording};
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Cli {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Render a single JSON encoded snapshot to a PNG
+    Png {
+        /// A snapshot, as sent on the refbox's JSON port
+        snapshot: PathBuf,
+        output: PathBuf,
+        #[clap(long, short, default_value = "4")]
+        /// Size in image pixels of each panel pixel
+        scale: u32,
+        #[clap(long, short, default_value = "256x64")]
+        /// Arrangement of the LED panels: 256x64, 192x64 or 128x32
+        geometry: PanelGeometry,
+        #[clap(long)]
+        white_on_right: bool,
+        #[clap(long)]
+        /// Draw the panels as they look while flashing
+        flash: bool,
+    },
+    /// Render a recording made with the refbox's `--record` option to an animated GIF
+    Gif {
+        recording: PathBuf,
+        output: PathBuf,
+        #[clap(long, short, default_value = "4")]
+        /// Size in image pixels of each panel pixel
+        scale: u32,
+        #[clap(long, short, default_value = "256x64")]
+        /// Arrangement of the LED panels: 256x64, 192x64 or 128x32
+        geometry: PanelGeometry,
+        #[clap(long, default_value = "1")]
+        /// How many times faster than real time to play the recording
+        speed: f64,
+    },
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    match Cli::parse().command {
+        Command::Png {
+            snapshot,
+            output,
+            scale,
+            geometry,
+            white_on_right,
+            flash,
+        } => {
+            let snapshot: GameSnapshot = serde_json::from_str(&fs::read_to_string(snapshot)?)?;
+            let data = TransmittedData {
+                white_on_right,
+                flash,
+                snapshot: snapshot.into(),
+            };
+            write_png(
+                BufWriter::new(File::create(output)?),
+                &data,
+                geometry,
+                scale,
+            )?;
+        }
+        Command::Gif {
+            recording,
+            output,
+            scale,
+            geometry,
+            speed,
+        } => {
+            if !(speed > 0.0 && speed.is_finite()) {
+                return Err(format!("Invalid speed: {speed}").into());
+            }
+            let records = read_recording(&recording)?;
+            if records.is_empty() {
+                return Err("The recording is empty".into());
+            }
+
+            // Each frame is shown until the next snapshot was sent. The last one has nothing to
+            // follow it, so it gets a second.
+            let next_millis = records
+                .iter()
+                .skip(1)
+                .map(|r| r.millis)
+                .chain(Some(records.last().unwrap().millis + 1000));
+            let frames = records
+                .iter()
+                .zip(next_millis)
+                .map(|(record, next)| GifFrame {
+                    data: TransmittedData {
+                        white_on_right: record.white_on_right,
+                        flash: false,
+                        snapshot: record.snapshot.clone().into(),
+                    },
+                    delay: Duration::from_millis(next.saturating_sub(record.millis)).div_f64(speed),
+                });
+            write_gif(
+                BufWriter::new(File::create(output)?),
+                frames,
+                geometry,
+                scale,
+            )?;
+        }
+    }
+    Ok(())
+}
@@ -1,7 +1,8 @@
-use arrayvec::ArrayString;
+use arrayvec::{ArrayString, ArrayVec};
 use core::{
-    fmt::{Debug, Display, Write},
+    fmt::{self, Debug, Display, Write},
     ops::{Div, Rem},
+    str::FromStr,
 };
 use embedded_graphics::{
     geometry::{Point, Size},
@@ -15,6 +16,92 @@ use fonts::fonts::{FONT_10X25, FONT_14X31, FONT_20X46, FONT_28X64, FONT_5X8, FON
 use more_asserts::*;
 use uwh_common::{drawing_support::*, game_snapshot::*};
 
+const RED: Rgb888 = Rgb888::RED;
+const YELLOW: Rgb888 = Rgb888::YELLOW;
+const GREEN: Rgb888 = Rgb888::GREEN;
+const BLUE: Rgb888 = Rgb888::new(64, 128, 255); //purple (225, 0, 255)
+const WHITE: Rgb888 = Rgb888::WHITE;
+const FLASH_COLOR: Rgb888 = Rgb888::new(0, 200, 200);
+
+const CENTERED: TextStyle = TextStyleBuilder::new()
+    .alignment(Alignment::Center)
+    .baseline(Baseline::Top)
+    .line_height(LineHeight::Percent(100))
+    .build();
+
+const LEFT_ALGN: TextStyle = TextStyleBuilder::new()
+    .alignment(Alignment::Left)
+    .baseline(Baseline::Top)
+    .line_height(LineHeight::Percent(100))
+    .build();
+
+const RIGHT_ALGN: TextStyle = TextStyleBuilder::new()
+    .alignment(Alignment::Right)
+    .baseline(Baseline::Top)
+    .line_height(LineHeight::Percent(100))
+    .build();
+
+/// The supported arrangements of LED panels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub enum PanelGeometry {
+    /// Two 64x64 score panels either side of two 64x64 time panels
+    #[default]
+    Panel256x64,
+    /// Three 64x64 panels
+    Panel192x64,
+    /// A single 128x32 panel
+    Panel128x32,
+}
+
+impl PanelGeometry {
+    pub const ALL: [Self; 3] = [Self::Panel256x64, Self::Panel192x64, Self::Panel128x32];
+
+    pub const fn size(self) -> Size {
+        match self {
+            Self::Panel256x64 => Size::new(256, 64),
+            Self::Panel192x64 => Size::new(192, 64),
+            Self::Panel128x32 => Size::new(128, 32),
+        }
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Panel256x64 => "256x64",
+            Self::Panel192x64 => "192x64",
+            Self::Panel128x32 => "128x32",
+        }
+    }
+}
+
+impl Display for PanelGeometry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnknownGeometry;
+
+impl Display for UnknownGeometry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Unknown panel geometry, expected one of 256x64, 192x64 or 128x32")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UnknownGeometry {}
+
+impl FromStr for PanelGeometry {
+    type Err = UnknownGeometry;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|geometry| geometry.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or(UnknownGeometry)
+    }
+}
+
 /// Draws all the details of the game onto the provided display. Assumes the dispaly is 256x64
 ///
 /// Assumes the penalties have already been sorted
@@ -24,39 +111,47 @@ pub fn draw_panels<D: DrawTarget<Color = Rgb888>>(
     white_on_right: bool,
     flash: bool,
 ) -> Result<(), D::Error> {
-    const RED: Rgb888 = Rgb888::RED;
-    const YELLOW: Rgb888 = Rgb888::YELLOW;
-    const GREEN: Rgb888 = Rgb888::GREEN;
-    const BLUE: Rgb888 = Rgb888::new(64, 128, 255); //purple (225, 0, 255)
-    const WHITE: Rgb888 = Rgb888::WHITE;
-    const FLASH_COLOR: Rgb888 = Rgb888::new(0, 200, 200);
-
-    const CENTERED: TextStyle = TextStyleBuilder::new()
-        .alignment(Alignment::Center)
-        .baseline(Baseline::Top)
-        .line_height(LineHeight::Percent(100))
-        .build();
-
-    const LEFT_ALGN: TextStyle = TextStyleBuilder::new()
-        .alignment(Alignment::Left)
-        .baseline(Baseline::Top)
-        .line_height(LineHeight::Percent(100))
-        .build();
-
-    const RIGHT_ALGN: TextStyle = TextStyleBuilder::new()
-        .alignment(Alignment::Right)
-        .baseline(Baseline::Top)
-        .line_height(LineHeight::Percent(100))
-        .build();
+    draw_panels_with_geometry(
+        display,
+        state,
+        white_on_right,
+        flash,
+        PanelGeometry::Panel256x64,
+    )
+}
 
+/// Draws all the details of the game onto the provided display, using the layout for `geometry`.
+/// Assumes the display is `geometry.size()`
+///
+/// Assumes the penalties have already been sorted
+pub fn draw_panels_with_geometry<D: DrawTarget<Color = Rgb888>>(
+    display: &mut D,
+    state: GameSnapshotNoHeap,
+    white_on_right: bool,
+    flash: bool,
+    geometry: PanelGeometry,
+) -> Result<(), D::Error> {
     if flash {
-        Rectangle::new(Point::new(0, 0), Size::new(255, 64))
+        let size = match geometry {
+            // The original layout has always left the last column dark
+            PanelGeometry::Panel256x64 => Size::new(255, 64),
+            _ => geometry.size(),
+        };
+        Rectangle::new(Point::new(0, 0), size)
             .into_styled(PrimitiveStyle::with_fill(FLASH_COLOR))
             .draw(display)?;
         return Ok(());
     }
 
-    let game_color = match state.timeout {
+    match geometry {
+        PanelGeometry::Panel256x64 => draw_256x64(display, state, white_on_right),
+        PanelGeometry::Panel192x64 => draw_192x64(display, state, white_on_right),
+        PanelGeometry::Panel128x32 => draw_128x32(display, state, white_on_right),
+    }
+}
+
+fn game_color(state: &GameSnapshotNoHeap) -> Rgb888 {
+    match state.timeout {
         TimeoutSnapshot::PenaltyShot(_) => RED,
         TimeoutSnapshot::Ref(_) => YELLOW,
         _ => match state.current_period {
@@ -71,14 +166,94 @@ pub fn draw_panels<D: DrawTarget<Color = Rgb888>>(
             | GamePeriod::PreSuddenDeath => YELLOW,
             GamePeriod::SuddenDeath => RED,
         },
-    };
+    }
+}
 
-    let timeout_color = match state.timeout {
+fn timeout_color(state: &GameSnapshotNoHeap) -> Rgb888 {
+    match state.timeout {
         TimeoutSnapshot::White(_) => WHITE,
         TimeoutSnapshot::Black(_) => BLUE,
         TimeoutSnapshot::Ref(_) => YELLOW,
         _ => RED,
-    };
+    }
+}
+
+/// Two line period names, for when there isn't room for the full name
+fn short_period_text(period: GamePeriod) -> &'static str {
+    match period {
+        GamePeriod::BetweenGames => "NEXT\nGAME",
+        GamePeriod::FirstHalf => "1ST\nHALF",
+        GamePeriod::HalfTime => "HALF\nTIME",
+        GamePeriod::SecondHalf => "2ND\nHALF",
+        GamePeriod::PreOvertime => "PRE OT\nBREAK",
+        GamePeriod::OvertimeFirstHalf => "OT 1ST\nHALF",
+        GamePeriod::OvertimeHalfTime => "OT HLF\nTIME",
+        GamePeriod::OvertimeSecondHalf => "OT 2ND\nHALF",
+        GamePeriod::PreSuddenDeath => "PRE SD\nBREAK",
+        GamePeriod::SuddenDeath => "SUDDEN\nDEATH",
+    }
+}
+
+/// The (penalties, score, color) of the teams on the left and right of the display
+type Side = (ArrayVec<PenaltySnapshot, PANEL_PENALTY_COUNT>, u8, Rgb888);
+
+fn sides(state: GameSnapshotNoHeap, white_on_right: bool) -> (Side, Side) {
+    let black = (state.b_penalties, state.b_score, BLUE);
+    let white = (state.w_penalties, state.w_score, WHITE);
+    if white_on_right {
+        (black, white)
+    } else {
+        (white, black)
+    }
+}
+
+fn score_string(score: u8) -> ArrayString<2> {
+    let mut score_string = ArrayString::new();
+    write!(&mut score_string, "{}", score).unwrap();
+    score_string
+}
+
+fn penalty_time_string(penalty: &PenaltySnapshot) -> ArrayString<5> {
+    match penalty.time {
+        PenaltyTime::Seconds(secs) => ArrayString::from(secs_to_time_string(secs).trim()).unwrap(),
+        PenaltyTime::TotalDismissal => ArrayString::from("DSMS").unwrap(),
+    }
+}
+
+/// Draws a penalty as the player number above the remaining time, centered on `x_pos`
+fn draw_penalty<D: DrawTarget<Color = Rgb888>>(
+    display: &mut D,
+    x_pos: i32,
+    y_pos: i32,
+    color: Rgb888,
+    penalty: &PenaltySnapshot,
+) -> Result<(), D::Error> {
+    let mut penalty_string = ArrayString::<3>::new();
+    write!(&mut penalty_string, "#{}", penalty.player_number).unwrap();
+    Text::with_text_style(
+        &penalty_string,
+        Point::new(x_pos, y_pos),
+        MonoTextStyle::new(&FONT_5X8, color),
+        CENTERED,
+    )
+    .draw(display)?;
+    Text::with_text_style(
+        &penalty_time_string(penalty),
+        Point::new(x_pos, y_pos + 8),
+        MonoTextStyle::new(&FONT_5X8, RED),
+        CENTERED,
+    )
+    .draw(display)?;
+    Ok(())
+}
+
+fn draw_256x64<D: DrawTarget<Color = Rgb888>>(
+    display: &mut D,
+    state: GameSnapshotNoHeap,
+    white_on_right: bool,
+) -> Result<(), D::Error> {
+    let game_color = game_color(&state);
+    let timeout_color = timeout_color(&state);
 
     // EVERYTHING TO BE DISPLAYED ON THE CENTER 2 TIME PANELS
     match state.timeout {
@@ -123,18 +298,7 @@ pub fn draw_panels<D: DrawTarget<Color = Rgb888>>(
             )
             .draw(display)?;
 
-            let period_text = match state.current_period {
-                GamePeriod::BetweenGames => "NEXT\nGAME",
-                GamePeriod::FirstHalf => "1ST\nHALF",
-                GamePeriod::HalfTime => "HALF\nTIME",
-                GamePeriod::SecondHalf => "2ND\nHALF",
-                GamePeriod::PreOvertime => "PRE OT\nBREAK",
-                GamePeriod::OvertimeFirstHalf => "OT 1ST\nHALF",
-                GamePeriod::OvertimeHalfTime => "OT HLF\nTIME",
-                GamePeriod::OvertimeSecondHalf => "OT 2ND\nHALF",
-                GamePeriod::PreSuddenDeath => "PRE SD\nBREAK",
-                GamePeriod::SuddenDeath => "SUDDEN\nDEATH",
-            };
+            let period_text = short_period_text(state.current_period);
 
             Text::with_text_style(
                 period_text,
@@ -219,32 +383,11 @@ pub fn draw_panels<D: DrawTarget<Color = Rgb888>>(
         }
     };
 
-    let left_penalties;
-    let right_penalties;
-    let left_score;
-    let right_score;
-    let left_color;
-    let right_color;
-
-    if white_on_right {
-        left_penalties = state.b_penalties;
-        right_penalties = state.w_penalties;
-        left_score = state.b_score;
-        right_score = state.w_score;
-        left_color = BLUE;
-        right_color = WHITE;
-    } else {
-        left_penalties = state.w_penalties;
-        right_penalties = state.b_penalties;
-        left_score = state.w_score;
-        right_score = state.b_score;
-        left_color = WHITE;
-        right_color = BLUE;
-    }
+    let ((left_penalties, left_score, left_color), (right_penalties, right_score, right_color)) =
+        sides(state, white_on_right);
 
     // Score on Left Score Panel
-    let mut left_score_string = ArrayString::<2>::new();
-    write!(&mut left_score_string, "{}", left_score).unwrap();
+    let left_score_string = score_string(left_score);
     if left_penalties.is_empty() {
         Text::with_text_style(
             &left_score_string,
@@ -274,8 +417,7 @@ pub fn draw_panels<D: DrawTarget<Color = Rgb888>>(
     };
 
     // Score on Right Score Panel
-    let mut right_score_string = ArrayString::<2>::new();
-    write!(&mut right_score_string, "{}", right_score).unwrap();
+    let right_score_string = score_string(right_score);
     if right_penalties.is_empty() {
         Text::with_text_style(
             &right_score_string,
@@ -304,34 +446,6 @@ pub fn draw_panels<D: DrawTarget<Color = Rgb888>>(
         .draw(display)?;
     };
 
-    // Define layout for Penalties
-    let mut draw_penalty =
-        |x_pos: i32, y_pos: i32, color, penalty: &PenaltySnapshot| -> Result<(), D::Error> {
-            let mut penalty_string = ArrayString::<3>::new();
-            write!(&mut penalty_string, "#{}", penalty.player_number).unwrap();
-            Text::with_text_style(
-                &penalty_string,
-                Point::new(x_pos, y_pos),
-                MonoTextStyle::new(&FONT_5X8, color),
-                CENTERED,
-            )
-            .draw(display)?;
-            let time: ArrayString<4> = match penalty.time {
-                PenaltyTime::Seconds(secs) => {
-                    ArrayString::from(secs_to_time_string(secs).trim()).unwrap()
-                }
-                PenaltyTime::TotalDismissal => ArrayString::from("DSMS").unwrap(),
-            };
-            Text::with_text_style(
-                &time,
-                Point::new(x_pos, y_pos + 8),
-                MonoTextStyle::new(&FONT_5X8, RED),
-                CENTERED,
-            )
-            .draw(display)?;
-            Ok(())
-        };
-
     // Penalties on Left Score Panel
     if left_score < 10 {
         // Vertical Penalties (Up to 3) - Justified Left/Outside/Away from Time Panels
@@ -339,7 +453,7 @@ pub fn draw_panels<D: DrawTarget<Color = Rgb888>>(
         for (i, penalty) in [0i32, 1, 2].into_iter().zip(left_penalties.iter()) {
             let x_pos = 15;
             let y_pos = 47 - i * 22;
-            draw_penalty(x_pos, y_pos, left_color, penalty)?;
+            draw_penalty(display, x_pos, y_pos, left_color, penalty)?;
         }
     } else {
         // Horizontal Penalties (Up to 2) - Justified Left/Outside/Away from Time Panels
@@ -347,7 +461,7 @@ pub fn draw_panels<D: DrawTarget<Color = Rgb888>>(
         for (i, penalty) in [0i32, 1].into_iter().zip(left_penalties.iter()) {
             let x_pos = 17 + i * 29;
             let y_pos = 47;
-            draw_penalty(x_pos, y_pos, left_color, penalty)?;
+            draw_penalty(display, x_pos, y_pos, left_color, penalty)?;
         }
     }
 
@@ -358,7 +472,7 @@ pub fn draw_panels<D: DrawTarget<Color = Rgb888>>(
         for (i, penalty) in [0i32, 1, 2].into_iter().zip(right_penalties.iter()) {
             let x_pos = 240;
             let y_pos = 47i32 - i * 22i32;
-            draw_penalty(x_pos, y_pos, right_color, penalty)?;
+            draw_penalty(display, x_pos, y_pos, right_color, penalty)?;
         }
     } else {
         // Horizontal Penalties (Up to 2) - Justified Right/Outside/Away from Time Panels
@@ -366,9 +480,218 @@ pub fn draw_panels<D: DrawTarget<Color = Rgb888>>(
         for (i, penalty) in [0i32, 1].into_iter().zip(right_penalties.iter()) {
             let x_pos = 238 - i * 29;
             let y_pos = 47;
-            draw_penalty(x_pos, y_pos, right_color, penalty)?;
+            draw_penalty(display, x_pos, y_pos, right_color, penalty)?;
+        }
+    }
+    Ok(())
+}
+
+/// Three 64x64 panels, with a 48 pixel wide score area on each side. The time is shown in the
+/// middle, with the period above it, or the timeout above it during timeouts. Up to 2 penalties
+/// are shown for each team.
+fn draw_192x64<D: DrawTarget<Color = Rgb888>>(
+    display: &mut D,
+    state: GameSnapshotNoHeap,
+    white_on_right: bool,
+) -> Result<(), D::Error> {
+    let game_color = game_color(&state);
+    let timeout_color = timeout_color(&state);
+
+    Text::with_text_style(
+        &secs_to_time_string(state.secs_in_period),
+        Point::new(95, 33),
+        MonoTextStyle::new(&FONT_14X31, game_color),
+        CENTERED,
+    )
+    .draw(display)?;
+
+    let text = match state.timeout {
+        TimeoutSnapshot::None => Some(short_period_text(state.current_period)),
+        TimeoutSnapshot::White(secs) | TimeoutSnapshot::Black(secs) => {
+            let text = if let TimeoutSnapshot::White(_) = state.timeout {
+                "WHITE\nTIMEOUT"
+            } else {
+                "BLACK\nTIMEOUT"
+            };
+            Text::with_text_style(
+                text,
+                Point::new(73, 2),
+                MonoTextStyle::new(&FONT_7X15, timeout_color),
+                CENTERED,
+            )
+            .draw(display)?;
+            Text::with_text_style(
+                &secs_to_short_time_string(secs),
+                Point::new(100, 2),
+                MonoTextStyle::new(&FONT_14X31, timeout_color),
+                LEFT_ALGN,
+            )
+            .draw(display)?;
+            None
+        }
+        TimeoutSnapshot::Ref(_) => Some("REF\nTIMEOUT"),
+        TimeoutSnapshot::PenaltyShot(_) => Some("PENALTY\nSHOT"),
+    };
+    if let Some(text) = text {
+        let color = if state.timeout == TimeoutSnapshot::None {
+            game_color
+        } else {
+            timeout_color
+        };
+        Text::with_text_style(
+            text,
+            Point::new(95, 2),
+            MonoTextStyle::new(&FONT_7X15, color),
+            CENTERED,
+        )
+        .draw(display)?;
+    }
+
+    let (left, right) = sides(state, white_on_right);
+    // (side, left edge of the score panel, center of the score panel)
+    for ((penalties, score, color), left_edge, center) in [(left, 0, 23), (right, 144, 167)] {
+        let score_string = score_string(score);
+        if penalties.is_empty() {
+            let (font, y_pos) = if score < 10 {
+                (&FONT_28X64, 2)
+            } else {
+                (&FONT_20X46, 9)
+            };
+            Text::with_text_style(
+                &score_string,
+                Point::new(center, y_pos),
+                MonoTextStyle::new(font, color),
+                CENTERED,
+            )
+            .draw(display)?;
+        } else {
+            // 3/4 Size Score, with Penalties (Up to 2) Below, One per Line. Penalties "Fall-Off"
+            // the Bottom as they run out
+            Text::with_text_style(
+                &score_string,
+                Point::new(center, 2),
+                MonoTextStyle::new(&FONT_20X46, color),
+                CENTERED,
+            )
+            .draw(display)?;
+            for (i, penalty) in [0i32, 1].into_iter().zip(penalties.iter()) {
+                let y_pos = 56 - i * 8;
+                let mut penalty_string = ArrayString::<3>::new();
+                write!(&mut penalty_string, "#{}", penalty.player_number).unwrap();
+                Text::with_text_style(
+                    &penalty_string,
+                    Point::new(left_edge + 1, y_pos),
+                    MonoTextStyle::new(&FONT_5X8, color),
+                    LEFT_ALGN,
+                )
+                .draw(display)?;
+                Text::with_text_style(
+                    &penalty_time_string(penalty),
+                    Point::new(left_edge + 47, y_pos),
+                    MonoTextStyle::new(&FONT_5X8, RED),
+                    RIGHT_ALGN,
+                )
+                .draw(display)?;
+            }
         }
     }
+
+    Ok(())
+}
+
+/// A single 128x32 panel, with a 32 pixel wide score area on each side. The period or timeout is
+/// shown in small text above the time. There is only room to show the penalty with the least time
+/// remaining for each team, without the player number.
+fn draw_128x32<D: DrawTarget<Color = Rgb888>>(
+    display: &mut D,
+    state: GameSnapshotNoHeap,
+    white_on_right: bool,
+) -> Result<(), D::Error> {
+    let game_color = game_color(&state);
+    let timeout_color = timeout_color(&state);
+
+    Text::with_text_style(
+        &secs_to_time_string(state.secs_in_period),
+        Point::new(63, 7),
+        MonoTextStyle::new(&FONT_10X25, game_color),
+        CENTERED,
+    )
+    .draw(display)?;
+
+    let mut text = ArrayString::<10>::new();
+    let color = match state.timeout {
+        TimeoutSnapshot::None => {
+            text.push_str(match state.current_period {
+                GamePeriod::BetweenGames => "NEXT GAME",
+                GamePeriod::FirstHalf => "1ST HALF",
+                GamePeriod::HalfTime => "HALF TIME",
+                GamePeriod::SecondHalf => "2ND HALF",
+                GamePeriod::PreOvertime => "OT BREAK",
+                GamePeriod::OvertimeFirstHalf => "OT 1ST HLF",
+                GamePeriod::OvertimeHalfTime => "OT HLF TM",
+                GamePeriod::OvertimeSecondHalf => "OT 2ND HLF",
+                GamePeriod::PreSuddenDeath => "SD BREAK",
+                GamePeriod::SuddenDeath => "SUDDEN DTH",
+            });
+            game_color
+        }
+        TimeoutSnapshot::White(secs) => {
+            write!(&mut text, "WHT T/O{}", secs_to_short_time_string(secs)).unwrap();
+            timeout_color
+        }
+        TimeoutSnapshot::Black(secs) => {
+            write!(&mut text, "BLK T/O{}", secs_to_short_time_string(secs)).unwrap();
+            timeout_color
+        }
+        TimeoutSnapshot::Ref(_) => {
+            text.push_str("REF T/O");
+            timeout_color
+        }
+        TimeoutSnapshot::PenaltyShot(_) => {
+            text.push_str("PEN SHOT");
+            timeout_color
+        }
+    };
+    Text::with_text_style(
+        &text,
+        Point::new(63, 0),
+        MonoTextStyle::new(&FONT_5X8, color),
+        CENTERED,
+    )
+    .draw(display)?;
+
+    let (left, right) = sides(state, white_on_right);
+    for ((penalties, score, color), center) in [(left, 15), (right, 111)] {
+        let score_string = score_string(score);
+        match penalties.first() {
+            None => {
+                Text::with_text_style(
+                    &score_string,
+                    Point::new(center, 1),
+                    MonoTextStyle::new(&FONT_14X31, color),
+                    CENTERED,
+                )
+                .draw(display)?;
+            }
+            Some(penalty) => {
+                Text::with_text_style(
+                    &score_string,
+                    Point::new(center, 0),
+                    MonoTextStyle::new(&FONT_10X25, color),
+                    CENTERED,
+                )
+                .draw(display)?;
+                Text::with_text_style(
+                    &penalty_time_string(penalty),
+                    Point::new(center, 24),
+                    MonoTextStyle::new(&FONT_5X8, RED),
+                    CENTERED,
+                )
+                .draw(display)?;
+            }
+        }
+    }
+
     Ok(())
 }
 
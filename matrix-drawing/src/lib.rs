@@ -4,3 +4,6 @@ mod drawing;
 pub use drawing::*;
 
 pub mod transmitted_data;
+
+#[cfg(feature = "render")]
+pub mod render;
@@ -0,0 +1,563 @@
+//! Renders panel frames to images, for regression tests and previews. Requires the `render`
+//! feature.
+
+use crate::{draw_panels_with_geometry, transmitted_data::TransmittedData, PanelGeometry};
+use core::fmt;
+use embedded_graphics::{pixelcolor::Rgb888, prelude::*};
+use std::{io::Write, time::Duration};
+
+#[derive(Debug)]
+pub enum RenderError {
+    InvalidScale(u32),
+    Png(png::EncodingError),
+    Gif(gif::EncodingError),
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidScale(scale) => write!(f, "Invalid scale: {scale}"),
+            Self::Png(e) => write!(f, "PNG encoding failed: {e}"),
+            Self::Gif(e) => write!(f, "GIF encoding failed: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+impl From<png::EncodingError> for RenderError {
+    fn from(e: png::EncodingError) -> Self {
+        Self::Png(e)
+    }
+}
+
+impl From<gif::EncodingError> for RenderError {
+    fn from(e: gif::EncodingError) -> Self {
+        Self::Gif(e)
+    }
+}
+
+/// An in-memory panel. Pixels that haven't been drawn are black.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameBuffer {
+    width: u32,
+    height: u32,
+    pixels: Vec<Rgb888>,
+}
+
+impl FrameBuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![Rgb888::BLACK; (width * height) as usize],
+        }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Rgb888 {
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    /// Returns the image as 8-bit RGB triples, with each panel pixel drawn as a
+    /// `scale` x `scale` square
+    pub fn to_rgb(&self, scale: u32) -> Vec<u8> {
+        self.scaled(scale)
+            .flat_map(|color| [color.r(), color.g(), color.b()])
+            .collect()
+    }
+
+    fn scaled(&self, scale: u32) -> impl Iterator<Item = Rgb888> + '_ {
+        (0..self.height * scale).flat_map(move |y| {
+            (0..self.width * scale).map(move |x| self.pixel(x / scale, y / scale))
+        })
+    }
+
+    fn scaled_size(&self, scale: u32) -> Result<(u32, u32), RenderError> {
+        // GIFs are limited to 65535 pixels in each direction
+        match (
+            self.width.checked_mul(scale),
+            self.height.checked_mul(scale),
+        ) {
+            (Some(w), Some(h)) if scale > 0 && w <= u16::MAX as u32 && h <= u16::MAX as u32 => {
+                Ok((w, h))
+            }
+            _ => Err(RenderError::InvalidScale(scale)),
+        }
+    }
+}
+
+impl DrawTarget for FrameBuffer {
+    type Color = Rgb888;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(coord, color) in pixels {
+            if let (Ok(x), Ok(y)) = (u32::try_from(coord.x), u32::try_from(coord.y)) {
+                if x < self.width && y < self.height {
+                    self.pixels[(y * self.width + x) as usize] = color;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl OriginDimensions for FrameBuffer {
+    fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+}
+
+/// Draws `data` onto a new frame the size of `geometry`
+pub fn render(data: &TransmittedData, geometry: PanelGeometry) -> FrameBuffer {
+    let size = geometry.size();
+    let mut frame = FrameBuffer::new(size.width, size.height);
+    draw_panels_with_geometry(
+        &mut frame,
+        data.snapshot.clone(),
+        data.white_on_right,
+        data.flash,
+        geometry,
+    )
+    .unwrap();
+    frame
+}
+
+/// Writes `data` as a PNG, with each panel pixel drawn as a `scale` x `scale` square
+pub fn write_png<W: Write>(
+    w: W,
+    data: &TransmittedData,
+    geometry: PanelGeometry,
+    scale: u32,
+) -> Result<(), RenderError> {
+    let frame = render(data, geometry);
+    let (width, height) = frame.scaled_size(scale)?;
+
+    let mut encoder = png::Encoder::new(w, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&frame.to_rgb(scale))?;
+    writer.finish()?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GifFrame {
+    pub data: TransmittedData,
+    /// How long the frame is shown for. GIFs store this in hundredths of a second.
+    pub delay: Duration,
+}
+
+/// Writes `frames` as an animated GIF that loops forever, with each panel pixel drawn as a
+/// `scale` x `scale` square
+pub fn write_gif<W: Write, I: IntoIterator<Item = GifFrame>>(
+    w: W,
+    frames: I,
+    geometry: PanelGeometry,
+    scale: u32,
+) -> Result<(), RenderError> {
+    let size = geometry.size();
+    let (width, height) = FrameBuffer::new(size.width, size.height).scaled_size(scale)?;
+    let (width, height) = (width as u16, height as u16);
+
+    let mut encoder = gif::Encoder::new(w, width, height, &[])?;
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+
+    for GifFrame { data, delay } in frames {
+        let frame = render(&data, geometry);
+
+        // The panels only use a handful of colors, so an exact palette can almost always be
+        // used. If not, fall back to letting the encoder pick one.
+        let mut palette: Vec<Rgb888> = Vec::new();
+        let indices: Option<Vec<u8>> = frame
+            .scaled(scale)
+            .map(|color| match palette.iter().position(|c| *c == color) {
+                Some(i) => Some(i as u8),
+                None if palette.len() < 256 => {
+                    palette.push(color);
+                    Some((palette.len() - 1) as u8)
+                }
+                None => None,
+            })
+            .collect();
+
+        let mut gif_frame = match indices {
+            Some(indices) => {
+                let palette: Vec<u8> = palette.iter().flat_map(|c| [c.r(), c.g(), c.b()]).collect();
+                gif::Frame::from_palette_pixels(width, height, &indices, &palette, None)
+            }
+            None => gif::Frame::from_rgb_speed(width, height, &frame.to_rgb(scale), 10),
+        };
+        gif_frame.delay = (delay.as_millis() / 10).try_into().unwrap_or(u16::MAX);
+        encoder.write_frame(&gif_frame)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use arrayvec::ArrayVec;
+    use std::{fs::File, io::BufReader, path::PathBuf};
+    use uwh_common::game_snapshot::{
+        GamePeriod, GameSnapshotNoHeap, PenaltySnapshot, PenaltyTime, TimeoutSnapshot,
+    };
+
+    fn golden_path(name: &str) -> PathBuf {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("golden");
+        path.push(format!("{name}.png"));
+        path
+    }
+
+    /// Compares the rendering of `data` with the stored golden image. Set `UPDATE_GOLDEN=1` to
+    /// replace the golden images after an intentional change to the layout.
+    fn check_golden(name: &str, data: &TransmittedData, geometry: PanelGeometry) {
+        let path = golden_path(name);
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            write_png(File::create(&path).unwrap(), data, geometry, 1).unwrap();
+        }
+
+        let decoder = png::Decoder::new(BufReader::new(File::open(&path).unwrap()));
+        let mut reader = decoder.read_info().unwrap();
+        let mut golden = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut golden).unwrap();
+        assert_eq!(Size::new(info.width, info.height), geometry.size());
+        assert_eq!(info.color_type, png::ColorType::Rgb);
+
+        let rendered = render(data, geometry).to_rgb(1);
+        let differing = rendered
+            .chunks(3)
+            .zip(golden[..info.buffer_size()].chunks(3))
+            .filter(|(a, b)| a != b)
+            .count();
+        assert_eq!(differing, 0, "{differing} pixels differ from {path:?}");
+    }
+
+    fn data(snapshot: GameSnapshotNoHeap) -> TransmittedData {
+        TransmittedData {
+            white_on_right: false,
+            flash: false,
+            snapshot,
+        }
+    }
+
+    fn first_half() -> GameSnapshotNoHeap {
+        GameSnapshotNoHeap {
+            current_period: GamePeriod::FirstHalf,
+            secs_in_period: 754,
+            timeout: TimeoutSnapshot::None,
+            b_score: 2,
+            w_score: 1,
+            b_penalties: ArrayVec::new(),
+            w_penalties: ArrayVec::new(),
+            is_old_game: false,
+        }
+    }
+
+    #[test]
+    fn test_golden_between_games() {
+        check_golden(
+            "between_games",
+            &data(GameSnapshotNoHeap {
+                current_period: GamePeriod::BetweenGames,
+                secs_in_period: 183,
+                ..first_half()
+            }),
+            PanelGeometry::Panel256x64,
+        );
+    }
+
+    #[test]
+    fn test_golden_first_half() {
+        check_golden(
+            "first_half",
+            &data(first_half()),
+            PanelGeometry::Panel256x64,
+        );
+    }
+
+    fn with_penalties() -> GameSnapshotNoHeap {
+        let mut snapshot = first_half();
+        snapshot.b_penalties.push(PenaltySnapshot {
+            player_number: 4,
+            time: PenaltyTime::Seconds(48),
+        });
+        snapshot.w_penalties.push(PenaltySnapshot {
+            player_number: 12,
+            time: PenaltyTime::Seconds(96),
+        });
+        snapshot.w_penalties.push(PenaltySnapshot {
+            player_number: 7,
+            time: PenaltyTime::TotalDismissal,
+        });
+        snapshot
+    }
+
+    #[test]
+    fn test_golden_penalties() {
+        check_golden(
+            "penalties_white_on_right",
+            &TransmittedData {
+                white_on_right: true,
+                ..data(with_penalties())
+            },
+            PanelGeometry::Panel256x64,
+        );
+    }
+
+    #[test]
+    fn test_golden_timeout() {
+        check_golden(
+            "ref_timeout",
+            &data(GameSnapshotNoHeap {
+                timeout: TimeoutSnapshot::Ref(37),
+                ..first_half()
+            }),
+            PanelGeometry::Panel256x64,
+        );
+    }
+
+    #[test]
+    fn test_golden_flash() {
+        check_golden(
+            "flash",
+            &TransmittedData {
+                flash: true,
+                ..data(first_half())
+            },
+            PanelGeometry::Panel256x64,
+        );
+    }
+
+    #[test]
+    fn test_golden_192x64() {
+        let geometry = PanelGeometry::Panel192x64;
+        check_golden("192x64_first_half", &data(first_half()), geometry);
+        check_golden("192x64_penalties", &data(with_penalties()), geometry);
+        check_golden(
+            "192x64_double_digit_penalties",
+            &TransmittedData {
+                white_on_right: true,
+                ..data(GameSnapshotNoHeap {
+                    b_score: 12,
+                    w_score: 10,
+                    ..with_penalties()
+                })
+            },
+            geometry,
+        );
+        check_golden(
+            "192x64_white_timeout",
+            &data(GameSnapshotNoHeap {
+                timeout: TimeoutSnapshot::White(42),
+                ..first_half()
+            }),
+            geometry,
+        );
+    }
+
+    #[test]
+    fn test_golden_128x32() {
+        let geometry = PanelGeometry::Panel128x32;
+        check_golden("128x32_first_half", &data(first_half()), geometry);
+        check_golden("128x32_penalties", &data(with_penalties()), geometry);
+        check_golden(
+            "128x32_black_timeout",
+            &data(GameSnapshotNoHeap {
+                timeout: TimeoutSnapshot::Black(9),
+                b_score: 11,
+                ..first_half()
+            }),
+            geometry,
+        );
+    }
+
+    /// Records any pixels drawn outside of `size`
+    struct BoundsChecker {
+        size: Size,
+        outside: Vec<Point>,
+    }
+
+    impl DrawTarget for BoundsChecker {
+        type Color = Rgb888;
+        type Error = core::convert::Infallible;
+
+        fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<Self::Color>>,
+        {
+            let bounds = self.bounding_box();
+            self.outside.extend(
+                pixels
+                    .into_iter()
+                    .map(|Pixel(point, _)| point)
+                    .filter(|point| !bounds.contains(*point)),
+            );
+            Ok(())
+        }
+    }
+
+    impl OriginDimensions for BoundsChecker {
+        fn size(&self) -> Size {
+            self.size
+        }
+    }
+
+    #[test]
+    fn test_geometries_stay_in_bounds() {
+        let periods = [
+            GamePeriod::BetweenGames,
+            GamePeriod::FirstHalf,
+            GamePeriod::HalfTime,
+            GamePeriod::SecondHalf,
+            GamePeriod::PreOvertime,
+            GamePeriod::OvertimeFirstHalf,
+            GamePeriod::OvertimeHalfTime,
+            GamePeriod::OvertimeSecondHalf,
+            GamePeriod::PreSuddenDeath,
+            GamePeriod::SuddenDeath,
+        ];
+        let timeouts = [
+            TimeoutSnapshot::None,
+            TimeoutSnapshot::White(60),
+            TimeoutSnapshot::Black(5),
+            TimeoutSnapshot::Ref(100),
+            TimeoutSnapshot::PenaltyShot(45),
+        ];
+
+        for geometry in PanelGeometry::ALL {
+            for current_period in periods {
+                for timeout in timeouts {
+                    for (b_score, w_score) in [(0, 9), (10, 99)] {
+                        for penalties in [false, true] {
+                            let base = if penalties {
+                                with_penalties()
+                            } else {
+                                first_half()
+                            };
+                            let snapshot = GameSnapshotNoHeap {
+                                current_period,
+                                timeout,
+                                secs_in_period: 5999,
+                                b_score,
+                                w_score,
+                                ..base
+                            };
+                            for white_on_right in [false, true] {
+                                let mut checker = BoundsChecker {
+                                    size: geometry.size(),
+                                    outside: Vec::new(),
+                                };
+                                draw_panels_with_geometry(
+                                    &mut checker,
+                                    snapshot.clone(),
+                                    white_on_right,
+                                    false,
+                                    geometry,
+                                )
+                                .unwrap();
+                                assert_eq!(
+                                    checker.outside.len(),
+                                    0,
+                                    "{geometry}: {snapshot:?} drew outside the display at {:?}",
+                                    checker.outside.first()
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_geometry() {
+        for geometry in PanelGeometry::ALL {
+            assert_eq!(geometry.to_string().parse(), Ok(geometry));
+        }
+        assert_eq!(" 128X32".parse(), Ok(PanelGeometry::Panel128x32));
+        assert_eq!(
+            "64x32".parse::<PanelGeometry>(),
+            Err(crate::UnknownGeometry)
+        );
+    }
+
+    #[test]
+    fn test_png_scale() {
+        let data = data(first_half());
+        let mut png = Vec::new();
+        write_png(&mut png, &data, PanelGeometry::Panel256x64, 3).unwrap();
+
+        let mut reader = png::Decoder::new(&png[..]).read_info().unwrap();
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf).unwrap();
+        assert_eq!((info.width, info.height), (256 * 3, 64 * 3));
+
+        // Each panel pixel becomes a 3x3 square
+        let frame = render(&data, PanelGeometry::Panel256x64);
+        let row_len = 256 * 3 * 3;
+        for (x, y) in [(0, 0), (40, 20), (128, 50), (255, 63)] {
+            let expected = frame.pixel(x, y);
+            for (dx, dy) in [(0, 0), (2, 2), (1, 2)] {
+                let i = ((y * 3 + dy) as usize) * row_len + ((x * 3 + dx) * 3) as usize;
+                assert_eq!(
+                    buf[i..i + 3],
+                    [expected.r(), expected.g(), expected.b()],
+                    "pixel ({x}, {y})"
+                );
+            }
+        }
+
+        assert!(matches!(
+            write_png(Vec::new(), &data, PanelGeometry::Panel256x64, 0),
+            Err(RenderError::InvalidScale(0))
+        ));
+    }
+
+    #[test]
+    fn test_gif() {
+        let frames = [(first_half(), 1000), (first_half(), 250)]
+            .into_iter()
+            .enumerate()
+            .map(|(i, (mut snapshot, millis))| {
+                snapshot.secs_in_period -= i as u16;
+                GifFrame {
+                    data: data(snapshot),
+                    delay: Duration::from_millis(millis),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut gif = Vec::new();
+        write_gif(&mut gif, frames.clone(), PanelGeometry::Panel128x32, 2).unwrap();
+
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let mut decoder = options.read_info(&gif[..]).unwrap();
+        assert_eq!(
+            (decoder.width() as u32, decoder.height() as u32),
+            (128 * 2, 32 * 2)
+        );
+
+        for expected in frames.iter() {
+            let frame = decoder.read_next_frame().unwrap().unwrap();
+            assert_eq!(frame.delay as u128, expected.delay.as_millis() / 10);
+            let rgb: Vec<u8> = frame
+                .buffer
+                .chunks(4)
+                .flat_map(|px| px[..3].to_vec())
+                .collect();
+            assert_eq!(
+                rgb,
+                render(&expected.data, PanelGeometry::Panel128x32).to_rgb(2)
+            );
+        }
+        assert!(decoder.read_next_frame().unwrap().is_none());
+    }
+}
@@ -1,7 +1,65 @@
 use arrayref::array_ref;
+use core::fmt;
 use serde_derive::{Deserialize, Serialize};
 use uwh_common::game_snapshot::{DecodingError, EncodingError, GameSnapshotNoHeap};
 
+/// Marks the start of a frame
+pub const FRAME_SYNC: [u8; 2] = [0xA5, 0x5A];
+pub const FRAME_VERSION: u8 = 1;
+/// Bytes in a frame before the payload: sync, version and length
+const FRAME_HEADER_LEN: usize = FRAME_SYNC.len() + 2;
+const FRAME_CRC_LEN: usize = 2;
+
+/// Calculates the CRC-16/CCITT-FALSE of `data`
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0xFFFFu16;
+    for byte in data {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum FrameError {
+    UnsupportedVersion(u8),
+    WrongLength(u8),
+    CrcMismatch { expected: u16, calculated: u16 },
+    DecodingError(DecodingError),
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedVersion(v) => write!(f, "Unsupported frame version: {v}"),
+            Self::WrongLength(len) => write!(f, "Wrong payload length: {len}"),
+            Self::CrcMismatch {
+                expected,
+                calculated,
+            } => write!(
+                f,
+                "CRC mismatch: expected {expected:#06x}, calculated {calculated:#06x}"
+            ),
+            Self::DecodingError(e) => write!(f, "Failed to decode payload: {e}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FrameError {}
+
+impl From<DecodingError> for FrameError {
+    fn from(e: DecodingError) -> Self {
+        Self::DecodingError(e)
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct TransmittedData {
     pub white_on_right: bool,
@@ -30,6 +88,132 @@ impl TransmittedData {
             ])?,
         })
     }
+
+    pub const FRAMED_LEN: usize = FRAME_HEADER_LEN + Self::ENCODED_LEN + FRAME_CRC_LEN;
+
+    /// Encodes the data in a frame that can be found in a noisy byte stream by a `FrameDecoder`.
+    /// The frame is laid out as follows:
+    ///
+    /// | Bytes | Description                                     |
+    /// | ----- | ----------------------------------------------- |
+    /// | 2     | Sync marker, `0xA5 0x5A`                        |
+    /// | 1     | Version, currently 1                            |
+    /// | 1     | Payload length                                  |
+    /// | n     | Payload, as produced by `encode()`              |
+    /// | 2     | CRC-16/CCITT-FALSE of version, length & payload |
+    pub fn encode_framed(&self) -> Result<[u8; Self::FRAMED_LEN], EncodingError> {
+        let mut frame = [0u8; Self::FRAMED_LEN];
+        frame[..FRAME_SYNC.len()].copy_from_slice(&FRAME_SYNC);
+        frame[FRAME_SYNC.len()] = FRAME_VERSION;
+        frame[FRAME_SYNC.len() + 1] = Self::ENCODED_LEN as u8;
+        frame[FRAME_HEADER_LEN..Self::FRAMED_LEN - FRAME_CRC_LEN].copy_from_slice(&self.encode()?);
+        let crc = crc16(&frame[FRAME_SYNC.len()..Self::FRAMED_LEN - FRAME_CRC_LEN]);
+        frame[Self::FRAMED_LEN - FRAME_CRC_LEN..].copy_from_slice(&crc.to_be_bytes());
+        Ok(frame)
+    }
+
+    /// Decodes a single complete frame
+    pub fn decode_framed(frame: &[u8; Self::FRAMED_LEN]) -> Result<Self, FrameError> {
+        check_header(frame)?;
+        let body_end = Self::FRAMED_LEN - FRAME_CRC_LEN;
+        let expected = u16::from_be_bytes([frame[body_end], frame[body_end + 1]]);
+        let calculated = crc16(&frame[FRAME_SYNC.len()..body_end]);
+        if expected != calculated {
+            return Err(FrameError::CrcMismatch {
+                expected,
+                calculated,
+            });
+        }
+        Ok(Self::decode(array_ref![
+            frame,
+            FRAME_HEADER_LEN,
+            TransmittedData::ENCODED_LEN
+        ])?)
+    }
+}
+
+/// Checks the version and length bytes of a frame, which must start with the sync marker
+fn check_header(frame: &[u8]) -> Result<(), FrameError> {
+    let version = frame[FRAME_SYNC.len()];
+    if version != FRAME_VERSION {
+        return Err(FrameError::UnsupportedVersion(version));
+    }
+    let len = frame[FRAME_SYNC.len() + 1];
+    if len as usize != TransmittedData::ENCODED_LEN {
+        return Err(FrameError::WrongLength(len));
+    }
+    Ok(())
+}
+
+/// Finds frames in a stream of bytes, one byte at a time. Bytes that aren't part of a valid frame
+/// are skipped, so decoding resumes with the first good frame after any noise on the line.
+#[derive(Debug, Clone)]
+pub struct FrameDecoder {
+    buf: [u8; TransmittedData::FRAMED_LEN],
+    len: usize,
+}
+
+impl Default for FrameDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameDecoder {
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; TransmittedData::FRAMED_LEN],
+            len: 0,
+        }
+    }
+
+    /// Adds a byte to the stream. Returns `Some` once a complete frame has been received, or if
+    /// the bytes received so far can't be a valid frame.
+    pub fn push(&mut self, byte: u8) -> Option<Result<TransmittedData, FrameError>> {
+        self.buf[self.len] = byte;
+        self.len += 1;
+        self.skip_to_sync();
+
+        let result = if self.len == TransmittedData::FRAMED_LEN {
+            TransmittedData::decode_framed(&self.buf)
+        } else if self.len >= FRAME_HEADER_LEN {
+            match check_header(&self.buf[..self.len]) {
+                Ok(()) => return None,
+                Err(e) => Err(e),
+            }
+        } else {
+            return None;
+        };
+
+        match result {
+            Ok(_) => self.len = 0,
+            Err(_) => {
+                // The sync marker was probably noise, so look for the next one
+                self.discard(1);
+                self.skip_to_sync();
+            }
+        }
+        Some(result)
+    }
+
+    fn discard(&mut self, count: usize) {
+        self.buf.copy_within(count..self.len, 0);
+        self.len -= count;
+    }
+
+    /// Drops bytes from the start of the buffer until it starts with (part of) the sync marker
+    fn skip_to_sync(&mut self) {
+        let start = (0..self.len)
+            .find(|&i| {
+                let candidate = &self.buf[i..self.len];
+                let n = candidate.len().min(FRAME_SYNC.len());
+                candidate[..n] == FRAME_SYNC[..n]
+            })
+            .unwrap_or(self.len);
+        if start > 0 {
+            self.discard(start);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -97,4 +281,131 @@ mod test {
 
         Ok(())
     }
+
+    fn sample_data() -> TransmittedData {
+        let mut snapshot = GameSnapshotNoHeap {
+            current_period: GamePeriod::SecondHalf,
+            secs_in_period: 612,
+            timeout: TimeoutSnapshot::None,
+            b_score: 3,
+            w_score: 4,
+            b_penalties: ArrayVec::new(),
+            w_penalties: ArrayVec::new(),
+            is_old_game: false,
+        };
+        snapshot.w_penalties.push(PenaltySnapshot {
+            player_number: 7,
+            time: PenaltyTime::TotalDismissal,
+        });
+        TransmittedData {
+            white_on_right: false,
+            flash: true,
+            snapshot,
+        }
+    }
+
+    #[test]
+    fn test_crc16() {
+        // The standard check value for CRC-16/CCITT-FALSE
+        assert_eq!(crc16(b"123456789"), 0x29B1);
+        assert_eq!(crc16(&[]), 0xFFFF);
+    }
+
+    #[test]
+    fn test_framed_round_trip() {
+        let data = sample_data();
+        let frame = data.encode_framed().unwrap();
+        // The lengths given in wire_format.md
+        assert_eq!(TransmittedData::ENCODED_LEN, 20);
+        assert_eq!(frame.len(), 26);
+        assert_eq!(frame[..2], FRAME_SYNC);
+        assert_eq!(frame[2], FRAME_VERSION);
+        assert_eq!(frame[3] as usize, TransmittedData::ENCODED_LEN);
+        assert_eq!(frame[4..frame.len() - 2], data.encode().unwrap());
+        assert_eq!(TransmittedData::decode_framed(&frame), Ok(data));
+    }
+
+    #[test]
+    fn test_framed_errors() {
+        let frame = sample_data().encode_framed().unwrap();
+
+        let mut bad = frame;
+        bad[2] = 2;
+        assert_eq!(
+            TransmittedData::decode_framed(&bad),
+            Err(FrameError::UnsupportedVersion(2))
+        );
+
+        let mut bad = frame;
+        bad[3] = 3;
+        assert_eq!(
+            TransmittedData::decode_framed(&bad),
+            Err(FrameError::WrongLength(3))
+        );
+
+        // Every single bit error in the body is caught by the CRC
+        for byte in 4..frame.len() {
+            for bit in 0..8 {
+                let mut bad = frame;
+                bad[byte] ^= 1 << bit;
+                assert!(matches!(
+                    TransmittedData::decode_framed(&bad),
+                    Err(FrameError::CrcMismatch { .. })
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn test_frame_decoder_resync() {
+        let data = sample_data();
+        let frame = data.encode_framed().unwrap();
+        let mut decoder = FrameDecoder::new();
+
+        let mut feed = |bytes: &[u8]| -> (Vec<TransmittedData>, usize) {
+            let mut frames = Vec::new();
+            let mut errors = 0;
+            for byte in bytes {
+                match decoder.push(*byte) {
+                    Some(Ok(data)) => frames.push(data),
+                    Some(Err(_)) => errors += 1,
+                    None => {}
+                }
+            }
+            (frames, errors)
+        };
+
+        // Back to back frames
+        let mut stream = Vec::new();
+        stream.extend_from_slice(&frame);
+        stream.extend_from_slice(&frame);
+        assert_eq!(feed(&stream), (vec![data.clone(), data.clone()], 0));
+
+        // Leading garbage, including a false sync marker, is skipped
+        let mut stream = vec![0x00, 0xA5, 0xA5, 0x5A, 0x07, 0x13, 0xFF];
+        stream.extend_from_slice(&frame);
+        let (frames, errors) = feed(&stream);
+        assert_eq!(frames, vec![data.clone()]);
+        assert_eq!(errors, 1);
+
+        // A frame cut short by noise is dropped, and the next good frame is still found
+        let mut stream = Vec::new();
+        stream.extend_from_slice(&frame[..10]);
+        stream.extend_from_slice(&[0x42, 0x99]);
+        stream.extend_from_slice(&frame);
+        stream.extend_from_slice(&frame);
+        let (frames, errors) = feed(&stream);
+        assert_eq!(frames, vec![data.clone(), data.clone()]);
+        assert!(errors >= 1);
+
+        // A corrupted frame is rejected rather than decoded
+        let mut corrupt = frame;
+        corrupt[8] ^= 0x10;
+        let mut stream = Vec::new();
+        stream.extend_from_slice(&corrupt);
+        stream.extend_from_slice(&frame);
+        let (frames, errors) = feed(&stream);
+        assert_eq!(frames, vec![data]);
+        assert!(errors >= 1);
+    }
 }
@@ -16,7 +16,6 @@ use log4rs::{
 };
 use macroquad::prelude::*;
 use network::{StatePacket, TeamInfo};
-use std::str::FromStr;
 use std::{net::IpAddr, path::PathBuf};
 use uwh_common::game_snapshot::{GamePeriod, GameSnapshot, TimeoutSnapshot};
 
@@ -45,7 +44,9 @@ fn window_conf() -> Conf {
 
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct AppConfig {
-    refbox_ip: IpAddr,
+    /// If not set, the refbox is found by listening for its announcements on the local network
+    #[serde(default)]
+    refbox_ip: Option<IpAddr>,
     refbox_port: u64,
     uwhscores_url: String,
     tournament_logo_path: PathBuf,
@@ -54,7 +55,7 @@ pub struct AppConfig {
 impl Default for AppConfig {
     fn default() -> Self {
         AppConfig {
-            refbox_ip: IpAddr::from_str("127.0.0.1").unwrap(),
+            refbox_ip: None,
             refbox_port: 8000,
             uwhscores_url: String::from("uwhscores.com"),
             tournament_logo_path: PathBuf::new(),
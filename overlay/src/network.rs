@@ -1,9 +1,15 @@
-use log::{debug, error, warn};
+use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::io::Read;
-use std::net::TcpStream;
-use uwh_common::game_snapshot::{Color, GamePeriod, GameSnapshot};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+use uwh_common::{
+    discovery,
+    game_snapshot::{Color, GamePeriod, GameSnapshot},
+};
+
+const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct TeamInfo {
@@ -93,6 +99,29 @@ async fn fetch_game_data(
     .unwrap();
 }
 
+/// Gets the address of the refbox's JSON port, either from the config or by waiting for the
+/// refbox to announce itself
+fn refbox_addr(config: &crate::AppConfig) -> SocketAddr {
+    if let Some(ip) = config.refbox_ip {
+        return SocketAddr::new(ip, config.refbox_port as u16);
+    }
+
+    loop {
+        debug!("Searching for a refbox on the local network");
+        match discovery::discover(DISCOVERY_TIMEOUT) {
+            Ok(Some((ip, announcement))) => {
+                info!("Found refbox at {ip}: {announcement:?}");
+                return SocketAddr::new(ip, announcement.json_port);
+            }
+            Ok(None) => warn!("No refbox found yet, still searching"),
+            Err(e) => {
+                error!("Refbox discovery failed: {e}");
+                std::thread::sleep(DISCOVERY_TIMEOUT);
+            }
+        }
+    }
+}
+
 #[tokio::main]
 pub async fn networking_thread(
     tx: crossbeam_channel::Sender<StatePacket>,
@@ -100,7 +129,7 @@ pub async fn networking_thread(
 ) {
     debug!("Attempting refbox connection!");
     let mut stream = loop {
-        if let Ok(stream) = TcpStream::connect((config.refbox_ip, config.refbox_port as u16)) {
+        if let Ok(stream) = TcpStream::connect(refbox_addr(&config)) {
             break stream;
         }
     };
@@ -117,9 +146,7 @@ pub async fn networking_thread(
         if read_bytes == 0 {
             error!("Connection to refbox lost! Attempting to reconnect!");
             stream = loop {
-                if let Ok(stream) =
-                    TcpStream::connect((config.refbox_ip, config.refbox_port as u16))
-                {
+                if let Ok(stream) = TcpStream::connect(refbox_addr(&config)) {
                     break stream;
                 }
             };
@@ -0,0 +1,94 @@
+use log::*;
+use std::net::{Ipv4Addr, SocketAddr};
+use tokio::{
+    net::UdpSocket,
+    task::{self, JoinHandle},
+    time::{interval, Duration},
+};
+use uwh_common::discovery::{Announcement, DISCOVERY_MULTICAST_ADDR, DISCOVERY_PORT};
+
+const ANNOUNCE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The broadcast and multicast addresses that displays listen on
+pub fn default_destinations() -> Vec<SocketAddr> {
+    vec![
+        (Ipv4Addr::BROADCAST, DISCOVERY_PORT).into(),
+        (DISCOVERY_MULTICAST_ADDR, DISCOVERY_PORT).into(),
+    ]
+}
+
+/// Starts a task that sends `announcement` to each of `destinations` once a second
+pub fn start_announcer(
+    announcement: Announcement,
+    destinations: Vec<SocketAddr>,
+) -> JoinHandle<()> {
+    task::spawn(async move {
+        let socket = match UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await {
+            Ok(s) => s,
+            Err(e) => {
+                error!("Failed to open socket for discovery announcements: {e}");
+                return;
+            }
+        };
+        if let Err(e) = socket.set_broadcast(true) {
+            warn!("Failed to enable broadcast for discovery announcements: {e}");
+        }
+
+        let data = announcement.encode();
+        info!("Announcing refbox to {destinations:?}: {announcement:?}");
+
+        // Avoid filling the log when the network is down by only reporting each failure once
+        let mut failing = vec![false; destinations.len()];
+        let mut ticker = interval(ANNOUNCE_INTERVAL);
+
+        loop {
+            ticker.tick().await;
+            for (dest, failing) in destinations.iter().zip(failing.iter_mut()) {
+                match socket.send_to(&data, dest).await {
+                    Ok(_) => {
+                        if *failing {
+                            info!("Discovery announcements to {dest} are working again");
+                            *failing = false;
+                        }
+                    }
+                    Err(e) => {
+                        if !*failing {
+                            warn!("Failed to send discovery announcement to {dest}: {e}");
+                            *failing = true;
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_announcer() {
+        let receiver = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let announcement = Announcement {
+            binary_port: 1,
+            json_port: 2,
+            websocket_port: Some(3),
+            rest_port: Some(4),
+            snapshot_multicast: None,
+        };
+
+        let join = start_announcer(announcement.clone(), vec![receiver.local_addr().unwrap()]);
+
+        let mut buf = [0u8; 1024];
+        for _ in 0..2 {
+            let len = receiver.recv(&mut buf).await.unwrap();
+            assert_eq!(
+                Announcement::decode(&buf[..len]),
+                Some(announcement.clone())
+            );
+        }
+
+        join.abort();
+    }
+}
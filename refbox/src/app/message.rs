@@ -1,8 +1,9 @@
+use super::remote_control::{RemoteCommand, RemoteResponder};
 use crate::tournament_manager::PenaltyKind;
 use tokio::time::Duration;
 use uwh_common::{
     game_snapshot::{Color as GameColor, GameSnapshot},
-    uwhscores::{GameInfo, TournamentInfo},
+    uwhscores::{GameInfo, TeamInfo, TournamentInfo},
 };
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -46,6 +47,12 @@ pub enum Message {
     AddScoreComplete {
         canceled: bool,
     },
+    ChangeScored(bool),
+    PenaltyShotComplete {
+        canceled: bool,
+    },
+    ShowRoster,
+    PlayerSelected(Option<u8>),
     EditGameConfig,
     ChangeConfigPage(ConfigPage),
     ConfigEditComplete {
@@ -72,10 +79,18 @@ pub enum Message {
     ScoreConfirmation {
         correct: bool,
     },
+    UndoLastAction,
+    RedoLastAction,
     RecvTournamentList(Vec<TournamentInfo>),
     RecvTournament(TournamentInfo),
     RecvGameList(Vec<GameInfo>),
     RecvGame(GameInfo),
+    RecvTeam {
+        tid: u32,
+        team_id: u32,
+        team: TeamInfo,
+    },
+    RemoteCommand(RemoteCommand, RemoteResponder),
     NoAction, // TODO: Remove once UI is functional
 }
 
@@ -89,10 +104,14 @@ impl Message {
             | Self::KeypadButtonPress(_)
             | Self::ToggleBoolParameter(_)
             | Self::CycleParameter(_)
+            | Self::UndoLastAction
+            | Self::RedoLastAction
             | Self::RecvTournamentList(_)
             | Self::RecvTournament(_)
             | Self::RecvGameList(_)
             | Self::RecvGame(_)
+            | Self::RecvTeam { .. }
+            | Self::RemoteCommand(_, _)
             | Self::NoAction => true,
 
             Self::Init
@@ -108,6 +127,10 @@ impl Message {
             | Self::KeypadPage(_)
             | Self::ChangeColor(_)
             | Self::AddScoreComplete { .. }
+            | Self::ChangeScored(_)
+            | Self::PenaltyShotComplete { .. }
+            | Self::ShowRoster
+            | Self::PlayerSelected(_)
             | Self::EditGameConfig
             | Self::ChangeConfigPage(_)
             | Self::ConfigEditComplete { .. }
@@ -167,6 +190,7 @@ pub enum BoolGameParameter {
     RefAlertEnabled,
     AutoSoundStartPlay,
     AutoSoundStopPlay,
+    TimeoutsCountedPerHalf,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -183,30 +207,45 @@ pub enum ScrollOption {
     Black,
     White,
     GameParameter,
+    Roster,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum KeypadPage {
     AddScore(GameColor),
+    PenaltyShot(GameColor, bool),
     Penalty(Option<(GameColor, usize)>, GameColor, PenaltyKind),
     GameNumber,
-    TeamTimeouts(Duration),
+    TeamTimeouts(Duration, bool),
 }
 
 impl KeypadPage {
     pub fn max_val(&self) -> u16 {
         match self {
-            Self::AddScore(_) | Self::Penalty(_, _, _) => 99,
+            Self::AddScore(_) | Self::PenaltyShot(_, _) | Self::Penalty(_, _, _) => 99,
             Self::GameNumber => 9999,
-            Self::TeamTimeouts(_) => 999,
+            Self::TeamTimeouts(_, _) => 999,
+        }
+    }
+
+    /// The team the page is recording something against, if any
+    pub fn color(&self) -> Option<GameColor> {
+        match self {
+            Self::AddScore(color) | Self::PenaltyShot(color, _) | Self::Penalty(_, color, _) => {
+                Some(*color)
+            }
+            Self::GameNumber | Self::TeamTimeouts(_, _) => None,
         }
     }
 
     pub fn text(&self) -> &'static str {
         match self {
-            Self::AddScore(_) | Self::Penalty(_, _, _) => "PLAYER\nNUMBER:",
+            Self::AddScore(_) | Self::PenaltyShot(_, _) | Self::Penalty(_, _, _) => {
+                "PLAYER\nNUMBER:"
+            }
             Self::GameNumber => "GAME\nNUMBER:",
-            Self::TeamTimeouts(_) => "NUM T/Os\nPER HALF:",
+            Self::TeamTimeouts(_, true) => "NUM T/Os\nPER HALF:",
+            Self::TeamTimeouts(_, false) => "NUM T/Os\nPER GAME:",
         }
     }
 }
@@ -232,4 +271,5 @@ pub enum ConfirmationOption {
     GoBack,
     EndGameAndApply,
     KeepGameAndApply,
+    ResumeGame,
 }
@@ -1,5 +1,13 @@
 use super::APP_NAME;
-use crate::{config::Config, penalty_editor::*, sound_controller::*, tournament_manager::*};
+use crate::{
+    config::Config,
+    game_flow,
+    penalty_editor::*,
+    sound_controller::*,
+    state_journal::StateJournal,
+    tournament_manager::*,
+    uwhscores_cache::{fetch, ResponseCache, ScoreOutbox},
+};
 use iced::{
     executor,
     pure::{column, Application, Element},
@@ -10,24 +18,27 @@ use iced_futures::{
     subscription::Recipe,
 };
 use log::*;
-use reqwest::{Client, Method, StatusCode};
+use reqwest::Client;
 use std::{
     cmp::min,
     collections::BTreeMap,
     hash::Hasher,
+    path::PathBuf,
     process::Child,
     sync::{Arc, Mutex},
 };
+use time::PrimitiveDateTime;
 use tokio::{
     sync::{mpsc, watch},
     task,
-    time::{sleep_until, timeout_at, Duration, Instant},
+    time::{timeout_at, Duration, Instant},
 };
 use tokio_serial::SerialPortBuilder;
 use uwh_common::{
     config::Game as GameConfig,
+    discovery::{snapshot_multicast_addr, Announcement},
     drawing_support::*,
-    game_snapshot::{Color as GameColor, GamePeriod, GameSnapshot, TimeoutSnapshot},
+    game_snapshot::{Color as GameColor, GamePeriod, GameSnapshot},
     uwhscores::*,
 };
 
@@ -35,6 +46,7 @@ mod view_builders;
 use view_builders::*;
 
 mod message;
+pub(crate) use message::Message;
 use message::*;
 
 pub mod style;
@@ -43,8 +55,15 @@ use style::{PADDING, SPACING, WINDOW_BACKGROUND};
 pub mod update_sender;
 use update_sender::*;
 
+pub mod discovery;
+
+pub mod rest_api;
+use rest_api::RestApiData;
+
+pub mod remote_control;
+use remote_control::{RemoteCommand, RemoteCommandError};
+
 const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
-const MAX_RETRIES: usize = 6;
 
 pub struct RefBoxApp {
     tm: Arc<Mutex<TournamentManager>>,
@@ -63,24 +82,41 @@ pub struct RefBoxApp {
     using_uwhscores: bool,
     tournaments: Option<BTreeMap<u32, TournamentInfo>>,
     games: Option<BTreeMap<u32, GameInfo>>,
+    rosters: BTreeMap<(u32, u32), TeamInfo>,
     current_tid: Option<u32>,
     current_pool: Option<String>,
     sound: SoundController,
     sim_child: Option<Child>,
     fullscreen: bool,
     list_all_tournaments: bool,
+    state_journal: StateJournal,
+    game_log_dir: PathBuf,
+    uwhscores_cache: ResponseCache,
+    score_outbox: ScoreOutbox,
+    rest_data: RestApiData,
 }
 
 #[derive(Debug)]
 pub struct RefBoxAppFlags {
     pub config: Config,
     pub serial_ports: Vec<SerialPortBuilder>,
+    pub framed_serial: bool,
     pub binary_port: u16,
     pub json_port: u16,
+    pub websocket_port: u16,
+    pub rest_port: u16,
+    pub announce: bool,
+    pub multicast_snapshots: bool,
+    pub record_path: Option<PathBuf>,
+    pub sound_dir: PathBuf,
     pub sim_child: Option<Child>,
     pub require_https: bool,
     pub fullscreen: bool,
     pub list_all_tournaments: bool,
+    pub state_journal: StateJournal,
+    pub game_log_dir: PathBuf,
+    pub uwhscores_cache: ResponseCache,
+    pub score_outbox: ScoreOutbox,
 }
 
 #[derive(Debug, Clone)]
@@ -93,6 +129,7 @@ enum AppState {
     },
     PenaltyOverview(BlackWhiteBundle<usize>),
     KeypadPage(KeypadPage, u16),
+    RosterList(KeypadPage, u16, usize),
     EditGameConfig(ConfigPage),
     ParameterEditor(LengthParameter, Duration),
     ParameterList(ListableParameter, usize),
@@ -103,12 +140,22 @@ enum AppState {
 #[derive(Debug, Clone, PartialEq, Eq)]
 enum ConfirmationKind {
     GameNumberChanged,
-    GameConfigChanged(GameConfig),
+    GameConfigChanged(Box<GameConfig>),
     Error(String),
     UwhScoresIncomplete,
+    ResumeGame(Box<SavedState>),
 }
 
 impl RefBoxApp {
+    fn save_state(&mut self) {
+        // Don't overwrite the saved game until the user has decided whether to resume it
+        if let AppState::ConfirmationPage(ConfirmationKind::ResumeGame(_)) = self.app_state {
+            return;
+        }
+
+        game_flow::save_state(&self.tm, &mut self.state_journal);
+    }
+
     fn apply_snapshot(&mut self, mut new_snapshot: GameSnapshot) {
         if new_snapshot.current_period != self.snapshot.current_period {
             if new_snapshot.current_period == GamePeriod::BetweenGames {
@@ -120,302 +167,220 @@ impl RefBoxApp {
         if let Some(tid) = self.current_tid {
             new_snapshot.tournament_id = tid;
         }
-        self.maybe_play_sound(&new_snapshot);
-        self.update_sender
-            .send_snapshot(new_snapshot.clone(), self.config.hardware.white_on_right)
-            .unwrap();
-        self.snapshot = new_snapshot;
-    }
-
-    fn maybe_play_sound(&self, new_snapshot: &GameSnapshot) {
-        let (play_whistle, play_buzzer) = match new_snapshot.timeout {
-            TimeoutSnapshot::Black(time) | TimeoutSnapshot::White(time) => {
-                match self.snapshot.timeout {
-                    TimeoutSnapshot::Black(old_time) | TimeoutSnapshot::White(old_time) => (
-                        time != old_time && time == 15,
-                        time != old_time && time == 0,
-                    ),
-                    _ => (false, false),
-                }
-            }
-            TimeoutSnapshot::Ref(_) | TimeoutSnapshot::PenaltyShot(_) => (false, false),
-            TimeoutSnapshot::None => {
-                let prereqs = new_snapshot.current_period != GamePeriod::SuddenDeath
-                    && new_snapshot.secs_in_period != self.snapshot.secs_in_period;
-
-                let is_whistle_period = match new_snapshot.current_period {
-                    GamePeriod::BetweenGames
-                    | GamePeriod::HalfTime
-                    | GamePeriod::PreOvertime
-                    | GamePeriod::OvertimeHalfTime
-                    | GamePeriod::PreSuddenDeath => true,
-                    GamePeriod::FirstHalf
-                    | GamePeriod::SecondHalf
-                    | GamePeriod::OvertimeFirstHalf
-                    | GamePeriod::OvertimeSecondHalf
-                    | GamePeriod::SuddenDeath => false,
-                };
-
-                let (end_starts_play, end_stops_play) = match new_snapshot.current_period {
-                    GamePeriod::FirstHalf
-                    | GamePeriod::SecondHalf
-                    | GamePeriod::OvertimeFirstHalf
-                    | GamePeriod::OvertimeSecondHalf => (false, true),
-                    GamePeriod::BetweenGames
-                    | GamePeriod::HalfTime
-                    | GamePeriod::PreOvertime
-                    | GamePeriod::OvertimeHalfTime
-                    | GamePeriod::PreSuddenDeath => (true, false),
-                    GamePeriod::SuddenDeath => (false, false),
-                };
-
-                let is_buzz_period = end_starts_play && self.config.sound.auto_sound_start_play
-                    || end_stops_play && self.config.sound.auto_sound_stop_play;
-
-                (
-                    prereqs && is_whistle_period && new_snapshot.secs_in_period == 30,
-                    prereqs && is_buzz_period && new_snapshot.secs_in_period == 0,
-                )
-            }
-        };
-
-        if play_whistle {
-            info!("Triggering whistle");
-            self.sound.trigger_whistle();
-        } else if play_buzzer {
-            info!("Triggering buzzer");
-            self.sound.trigger_buzzer();
+        if let Some(ref games) = self.games {
+            fill_team_names(&mut new_snapshot, games);
         }
+        game_flow::play_auto_sounds(
+            &self.config.sound,
+            &self.sound,
+            &self.snapshot,
+            &new_snapshot,
+        );
+        game_flow::publish_snapshot(
+            &new_snapshot,
+            &self.update_sender,
+            &self.rest_data,
+            self.config.hardware.white_on_right,
+        );
+        self.snapshot = new_snapshot;
     }
 
-    fn do_get_request<T, F>(&self, url: String, short_name: String, on_success: F)
+    /// Requests `path` (relative to the uwhscores API url). If the request can't be completed, the
+    /// most recent cached response is used instead.
+    fn do_get_request<T, F>(&self, path: String, short_name: String, on_success: F)
     where
-        T: serde::de::DeserializeOwned,
+        T: serde::de::DeserializeOwned + Send,
         F: Fn(T) -> Message + Send + Sync + 'static,
     {
         if let Some(client) = &self.client {
-            info!("Starting request for {short_name}");
-            let request = client.request(Method::GET, url).build().unwrap();
             let client_ = client.clone();
+            let url = self.config.uwhscores.url.clone();
+            let cache = self.uwhscores_cache.clone();
             let msg_tx_ = self.msg_tx.clone();
 
-            let mut delay_until = None;
-
             task::spawn(async move {
-                let mut msg = None;
-                for _ in 0..MAX_RETRIES {
-                    if let Some(time) = delay_until.take() {
-                        sleep_until(time).await;
-                    }
-
-                    let start = Instant::now();
-                    msg = match client_.execute(request.try_clone().unwrap()).await {
-                        Ok(resp) => {
-                            if resp.status() != StatusCode::OK {
-                                error!(
-                                    "Got bad status code from uwhscores when requesting {}: {}",
-                                    short_name,
-                                    resp.status()
-                                );
-                                info!("Maybe retrying");
-                                continue;
-                            } else {
-                                match resp.json::<T>().await {
-                                    Ok(parsed) => Some(on_success(parsed)),
-                                    Err(e) => {
-                                        error!("Couldn't desesrialize {}: {e}", short_name);
-                                        None
-                                    }
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            error!("Request for {} failed: {e}", short_name);
-                            info!("Maybe retrying");
-                            delay_until = Some(start + REQUEST_TIMEOUT);
-                            continue;
-                        }
-                    };
-                    break;
-                }
-
-                if let Some(msg) = msg {
-                    msg_tx_.send(msg).unwrap();
-                } else {
-                    error!("Too many failures when requesting {short_name}, stopping");
-                }
+                fetch(
+                    &client_,
+                    &url,
+                    &path,
+                    &cache,
+                    &short_name,
+                    REQUEST_TIMEOUT,
+                    |parsed| msg_tx_.send(on_success(parsed)).unwrap(),
+                )
+                .await;
             });
         }
     }
 
     fn request_tournament_list(&self) {
-        let url = format!("{}tournaments", self.config.uwhscores.url);
         self.do_get_request(
-            url,
+            "tournaments".to_string(),
             "tournament list".to_string(),
             |parsed: TournamentListResponse| Message::RecvTournamentList(parsed.tournaments),
         );
     }
 
     fn request_tournament_details(&self, tid: u32) {
-        let url = format!("{}tournaments/{tid}", self.config.uwhscores.url);
         self.do_get_request(
-            url,
+            format!("tournaments/{tid}"),
             format!("tournament details for tid {tid}"),
             |parsed: TournamentSingleResponse| Message::RecvTournament(parsed.tournament),
         );
     }
 
     fn request_game_list(&self, tid: u32) {
-        let url = format!("{}tournaments/{tid}/games", self.config.uwhscores.url);
         self.do_get_request(
-            url,
+            format!("tournaments/{tid}/games"),
             format!("game list for tid {tid}"),
             |parsed: GameListResponse| Message::RecvGameList(parsed.games),
         );
     }
 
     fn request_game_details(&self, tid: u32, gid: u32) {
-        let url = format!("{}tournaments/{tid}/games/{gid}", self.config.uwhscores.url);
         self.do_get_request(
-            url,
+            format!("tournaments/{tid}/games/{gid}"),
             format!("game deatils for tid {tid} and gid {gid}"),
             |parsed: GameSingleResponse| Message::RecvGame(parsed.game),
         );
     }
 
-    fn post_game_score(&self, game: &GameInfo, scores: BlackWhiteBundle<u8>) {
-        if let Some(client) = &self.client {
-            let tid = game.tid;
-            let gid = game.gid;
-            let post_url = format!("{}tournaments/{tid}/games/{gid}", self.config.uwhscores.url);
+    fn request_team_details(&self, tid: u32, team_id: u32) {
+        self.do_get_request(
+            format!("tournaments/{tid}/teams/{team_id}"),
+            format!("team details for tid {tid} and team_id {team_id}"),
+            move |parsed: TeamSingleResponse| Message::RecvTeam {
+                tid,
+                team_id,
+                team: parsed.team,
+            },
+        );
+    }
 
-            info!("Starting login request");
+    /// Requests the rosters of both teams playing in game `gid`. Unless `refresh` is set, rosters
+    /// that have already been received are not requested again.
+    fn request_rosters(&self, gid: u32, refresh: bool) {
+        if !self.using_uwhscores {
+            return;
+        }
+        if let Some(game) = self.games.as_ref().and_then(|games| games.get(&gid)) {
+            for team_id in [game.black_id, game.white_id] {
+                if team_id != 0 && (refresh || !self.rosters.contains_key(&(game.tid, team_id))) {
+                    self.request_team_details(game.tid, team_id);
+                }
+            }
+        }
+    }
 
-            let login_request = client
-                .request(Method::GET, format!("{}login", self.config.uwhscores.url))
-                .basic_auth(
-                    self.config.uwhscores.email.clone(),
-                    Some(self.config.uwhscores.password.clone()),
-                )
-                .build()
-                .unwrap();
+    /// The roster of the `color` team in the game being shown, if it has been received
+    fn roster(&self, color: GameColor) -> Option<&TeamInfo> {
+        let game = self
+            .games
+            .as_ref()?
+            .get(&shown_game_number(&self.snapshot))?;
+        let team_id = match color {
+            GameColor::Black => game.black_id,
+            GameColor::White => game.white_id,
+        };
+        self.rosters
+            .get(&(game.tid, team_id))
+            .filter(|team| !team.roster.is_empty())
+    }
 
-            let post_data = GameScorePostData::new(GameScoreInfo {
-                tid,
-                gid,
-                score_b: scores.black,
-                score_w: scores.white,
-                black_id: game.black_id,
-                white_id: game.white_id,
-            });
+    fn post_game_score(&self, game: &GameInfo, scores: BlackWhiteBundle<u8>) {
+        let score = GameScoreInfo {
+            tid: game.tid,
+            gid: game.gid,
+            score_b: scores.black,
+            score_w: scores.white,
+            black_id: game.black_id,
+            white_id: game.white_id,
+        };
+        info!("Queueing score for upload: {score:?}");
+        self.score_outbox.push(score);
+    }
 
-            let client_ = client.clone();
-            task::spawn(async move {
-                let mut login = None;
-                for _ in 0..MAX_RETRIES {
-                    login = match client_.execute(login_request.try_clone().unwrap()).await {
-                        Ok(resp) => {
-                            if resp.status() != StatusCode::OK {
-                                error!(
-                                    "Got bad status code from uwhscores when logging in: {}",
-                                    resp.status()
-                                );
-                                info!("Maybe retrying");
-                                continue;
-                            } else {
-                                match resp.json::<LoginResponse>().await {
-                                    Ok(parsed) => Some(parsed),
-                                    Err(e) => {
-                                        error!("Couldn't desesrialize login: {e}");
-                                        return;
-                                    }
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            error!("Login request failed: {e}");
-                            info!("Maybe retrying");
-                            continue;
-                        }
-                    };
-                    break;
-                }
+    fn apply_remote_command(
+        &mut self,
+        command: RemoteCommand,
+    ) -> std::result::Result<(), RemoteCommandError> {
+        // The editing pages hold their own copies of parts of the game state, so changing the
+        // game underneath them could cause their changes to be applied wrongly
+        if !matches!(self.app_state, AppState::MainPage) {
+            return Err(RemoteCommandError::Busy);
+        }
 
-                let login = if let Some(l) = login {
-                    l
-                } else {
-                    error!("Too many failures when logging in to uwhscores, stopping");
-                    return;
+        let mut tm = self.tm.lock().unwrap();
+        let now = Instant::now();
+
+        match command {
+            RemoteCommand::AddGoal { color, .. }
+                if tm.current_period() == GamePeriod::SuddenDeath =>
+            {
+                // A goal ends the game, so let the ref at the refbox confirm it first
+                tm.stop_clock(now)?;
+                let mut scores = BlackWhiteBundle {
+                    black: tm.get_b_score(),
+                    white: tm.get_w_score(),
                 };
-
-                info!("Posting score: {post_data:?}");
-
-                let post_request = client_
-                    .request(Method::POST, post_url)
-                    .basic_auth::<_, String>(login.token, None)
-                    .json(&post_data)
-                    .build()
-                    .unwrap();
-
-                for _ in 0..MAX_RETRIES {
-                    match client_.execute(post_request.try_clone().unwrap()).await {
-                        Ok(resp) => {
-                            if resp.status() != StatusCode::OK {
-                                error!(
-                                    "Got bad status code from uwhscores when posting score: {}",
-                                    resp.status()
-                                );
-                                info!("Maybe retrying");
-                                continue;
-                            }
-                        }
-                        Err(e) => {
-                            error!("Post score request failed: {e}");
-                            info!("Maybe retrying");
-                            continue;
-                        }
-                    };
-                    break;
-                }
-            });
+                scores[color] = scores[color].saturating_add(1);
+                self.app_state = AppState::ConfirmScores(scores);
+                trace!("AppState changed to {:?}", self.app_state);
+            }
+            command => remote_control::apply_command(&mut tm, command, now)?,
         }
+
+        let snapshot = tm.generate_snapshot(now).unwrap();
+        std::mem::drop(tm);
+        self.apply_snapshot(snapshot);
+        Ok(())
     }
 
     fn handle_game_start(&mut self, new_game_num: u32) {
+        self.request_rosters(new_game_num, true);
+
         if self.using_uwhscores {
             if let (Some(ref games), Some(ref pool)) = (&self.games, &self.current_pool) {
-                let this_game_start = match games.get(&new_game_num) {
-                    Some(g) => g.start_time,
-                    None => {
-                        error!("Could not find new game's start time (gid {new_game_num}");
-                        return;
-                    }
-                };
-
-                let next_game = games
-                    .values()
-                    .filter(|game| game.pool == *pool)
-                    .filter(|game| game.start_time > this_game_start)
-                    .min_by_key(|game| game.start_time);
-
                 let mut tm = self.tm.lock().unwrap();
-                if let Some(next_game) = next_game {
-                    let info = NextGameInfo {
-                        number: next_game.gid,
-                        timing: next_game.timing_rules.clone(),
-                        start_time: Some(next_game.start_time),
-                    };
-                    tm.set_next_game(info);
-                } else {
-                    error!("Couldn't find a next game");
-                }
+                schedule_next_game(&mut tm, games, pool, new_game_num);
                 self.config.game = tm.config().clone();
             }
         }
     }
 
+    /// Settles the final `scores` of a game that is waiting to end, and ends it
+    fn finish_game(
+        &self,
+        tm: &mut TournamentManager,
+        scores: BlackWhiteBundle<u8>,
+        now: Instant,
+    ) -> GameSnapshot {
+        if let Some(game) = self
+            .games
+            .as_ref()
+            .and_then(|games| games.get(&tm.game_number()))
+        {
+            self.post_game_score(game, scores);
+        }
+
+        tm.set_scores(scores.black, scores.white, now);
+        game_flow::end_game(tm, now).unwrap().snapshot
+    }
+
     fn handle_game_end(&self, next_game_num: u32) {
+        let log = self.tm.lock().unwrap().take_completed_game_log();
+        if let Some(mut log) = log {
+            if let Some(info) = self
+                .games
+                .as_ref()
+                .and_then(|games| games.get(&log.game_number))
+            {
+                log.black_team = Some(info.black.clone());
+                log.white_team = Some(info.white.clone());
+            }
+            game_flow::save_game_log(log, &self.game_log_dir, &self.rest_data);
+        }
+
         if self.using_uwhscores {
             if let Some(tid) = self.current_tid {
                 self.request_game_details(tid, next_game_num);
@@ -426,6 +391,63 @@ impl RefBoxApp {
     }
 }
 
+/// Sets the next game of `tm` to the game that follows game `gid` in `pool`
+pub(crate) fn schedule_next_game(
+    tm: &mut TournamentManager,
+    games: &BTreeMap<u32, GameInfo>,
+    pool: &str,
+    gid: u32,
+) {
+    let this_game_start = match games.get(&gid) {
+        Some(g) => g.start_time,
+        None => {
+            error!("Could not find new game's start time (gid {gid}");
+            return;
+        }
+    };
+
+    if let Some(next_game) = next_game_in_pool(games, pool, this_game_start) {
+        let info = NextGameInfo {
+            number: next_game.gid,
+            timing: next_game.timing_rules.clone(),
+            start_time: Some(next_game.start_time),
+        };
+        tm.set_next_game(info);
+    } else {
+        error!("Couldn't find a next game");
+    }
+}
+
+/// Finds the first game in `pool` that is scheduled to start after `after`
+pub(crate) fn next_game_in_pool<'a>(
+    games: &'a BTreeMap<u32, GameInfo>,
+    pool: &str,
+    after: PrimitiveDateTime,
+) -> Option<&'a GameInfo> {
+    games
+        .values()
+        .filter(|game| game.pool == pool)
+        .filter(|game| game.start_time > after)
+        .min_by_key(|game| game.start_time)
+}
+
+/// The number of the game being shown: the upcoming game between games, otherwise the current one
+fn shown_game_number(snapshot: &GameSnapshot) -> u32 {
+    if snapshot.current_period == GamePeriod::BetweenGames && !snapshot.is_old_game {
+        snapshot.next_game_number
+    } else {
+        snapshot.game_number
+    }
+}
+
+/// Sets the team names in `snapshot` to those of the game being shown
+pub(crate) fn fill_team_names(snapshot: &mut GameSnapshot, games: &BTreeMap<u32, GameInfo>) {
+    if let Some(game) = games.get(&shown_game_number(snapshot)) {
+        snapshot.b_team_name = Some(game.black.clone());
+        snapshot.w_team_name = Some(game.white.clone());
+    }
+}
+
 impl Drop for RefBoxApp {
     fn drop(&mut self) {
         if let Some(mut child) = self.sim_child.take() {
@@ -444,12 +466,23 @@ impl Application for RefBoxApp {
         let Self::Flags {
             config,
             serial_ports,
+            framed_serial,
             binary_port,
             json_port,
+            websocket_port,
+            rest_port,
+            announce,
+            multicast_snapshots,
+            record_path,
+            sound_dir,
             sim_child,
             require_https,
             fullscreen,
             list_all_tournaments,
+            state_journal,
+            game_log_dir,
+            uwhscores_cache,
+            score_outbox,
         } = flags;
 
         let (msg_tx, rx) = mpsc::unbounded_channel();
@@ -474,17 +507,72 @@ impl Application for RefBoxApp {
             }
         };
 
+        if let Some(ref client) = client {
+            score_outbox.start_uploader(
+                client.clone(),
+                config.uwhscores.url.clone(),
+                config.uwhscores.email.clone(),
+                config.uwhscores.password.clone(),
+            );
+        }
+
+        if config.remote_control.enabled {
+            remote_control::start(config.remote_control.clone(), msg_tx.clone());
+        }
+
         let clock_running_receiver = tm.get_start_stop_rx();
 
         let tm = Arc::new(Mutex::new(tm));
 
-        let update_sender = UpdateSender::new(serial_ports, binary_port, json_port);
+        let rest_data = RestApiData::new(tm.clone());
+        rest_api::start(rest_port, rest_data.clone());
 
-        let sound =
-            SoundController::new(config.sound.clone(), update_sender.get_trigger_flash_fn());
+        let snapshot_multicast = multicast_snapshots.then(snapshot_multicast_addr);
+
+        let update_sender = UpdateSender::new(
+            serial_ports,
+            framed_serial,
+            binary_port,
+            json_port,
+            websocket_port,
+            snapshot_multicast.map(Into::into),
+        );
+
+        if let Some(path) = record_path {
+            if let Err(e) = update_sender.record_to(&path) {
+                error!("Failed to start recording snapshots to {path:?}: {e}");
+            }
+        }
+
+        if announce {
+            discovery::start_announcer(
+                Announcement {
+                    binary_port,
+                    json_port,
+                    websocket_port: Some(websocket_port),
+                    rest_port: Some(rest_port),
+                    snapshot_multicast,
+                },
+                discovery::default_destinations(),
+            );
+        }
+
+        let sound = SoundController::new(
+            config.sound.clone(),
+            Some(&sound_dir),
+            update_sender.get_trigger_flash_fn(),
+        );
 
         let snapshot = Default::default();
 
+        let app_state = match state_journal.load() {
+            Some(state) if state.current_period != GamePeriod::BetweenGames => {
+                info!("Found an interrupted game, offering to resume it");
+                AppState::ConfirmationPage(ConfirmationKind::ResumeGame(Box::new(state)))
+            }
+            _ => AppState::MainPage,
+        };
+
         (
             Self {
                 pen_edit: PenaltyEditor::new(tm.clone()),
@@ -496,7 +584,7 @@ impl Application for RefBoxApp {
                 config,
                 edited_settings: Default::default(),
                 snapshot,
-                app_state: AppState::MainPage,
+                app_state,
                 last_app_state: AppState::MainPage,
                 last_message: Message::NoAction,
                 update_sender,
@@ -506,12 +594,18 @@ impl Application for RefBoxApp {
                 using_uwhscores: false,
                 tournaments: None,
                 games: None,
+                rosters: BTreeMap::new(),
                 current_tid: None,
                 current_pool: None,
                 sound,
                 sim_child,
                 fullscreen,
                 list_all_tournaments,
+                state_journal,
+                game_log_dir,
+                uwhscores_cache,
+                score_outbox,
+                rest_data,
             },
             Command::none(),
         )
@@ -579,7 +673,9 @@ impl Application for RefBoxApp {
                         }
                     }
                     AppState::ParameterEditor(_, ref mut dur) => (dur, false),
-                    AppState::KeypadPage(KeypadPage::TeamTimeouts(ref mut dur), _) => (dur, false),
+                    AppState::KeypadPage(KeypadPage::TeamTimeouts(ref mut dur, _), _) => {
+                        (dur, false)
+                    }
                     _ => unreachable!(),
                 };
                 if increase {
@@ -652,31 +748,20 @@ impl Application for RefBoxApp {
             }
             Message::ScoreEditComplete { canceled } => {
                 let mut tm = self.tm.lock().unwrap();
-                let mut now = Instant::now();
+                let now = Instant::now();
 
-                self.app_state = if let AppState::ScoreEdit {
+                let (app_state, snapshot) = if let AppState::ScoreEdit {
                     scores,
                     is_confirmation,
                 } = self.app_state
                 {
                     if is_confirmation {
-                        if let Some(game) = self
-                            .games
-                            .as_ref()
-                            .and_then(|games| games.get(&tm.game_number()))
-                        {
-                            self.post_game_score(game, scores);
-                        }
-
-                        tm.set_scores(scores.black, scores.white, now);
-                        tm.start_clock(now);
-
-                        // Update `tm` after game ends to get into Between Games
-                        now += Duration::from_millis(2);
-                        tm.update(now).unwrap();
-                        AppState::MainPage
-                    } else if !canceled {
-                        if tm.current_period() == GamePeriod::SuddenDeath
+                        let snapshot = self.finish_game(&mut tm, scores, now);
+                        (AppState::MainPage, snapshot)
+                    } else {
+                        let app_state = if canceled {
+                            AppState::MainPage
+                        } else if tm.current_period() == GamePeriod::SuddenDeath
                             && (scores.black != scores.white)
                         {
                             tm.stop_clock(now).unwrap();
@@ -684,16 +769,15 @@ impl Application for RefBoxApp {
                         } else {
                             tm.set_scores(scores.black, scores.white, now);
                             AppState::MainPage
-                        }
-                    } else {
-                        AppState::MainPage
+                        };
+                        (app_state, tm.generate_snapshot(now).unwrap())
                     }
                 } else {
                     unreachable!()
                 };
 
-                let snapshot = tm.generate_snapshot(now).unwrap(); // `now` is in the past!
                 std::mem::drop(tm);
+                self.app_state = app_state;
                 self.apply_snapshot(snapshot);
 
                 trace!("AppState changed to {:?}", self.app_state);
@@ -708,7 +792,7 @@ impl Application for RefBoxApp {
                     let idx = match which {
                         ScrollOption::Black => &mut indices.black,
                         ScrollOption::White => &mut indices.white,
-                        ScrollOption::GameParameter => unreachable!(),
+                        ScrollOption::GameParameter | ScrollOption::Roster => unreachable!(),
                     };
                     if up {
                         *idx = idx.saturating_sub(1);
@@ -722,6 +806,13 @@ impl Application for RefBoxApp {
                     } else {
                         *idx = idx.saturating_add(1);
                     }
+                } else if let AppState::RosterList(_, _, ref mut idx) = self.app_state {
+                    debug_assert_eq!(which, ScrollOption::Roster);
+                    if up {
+                        *idx = idx.saturating_sub(1);
+                    } else {
+                        *idx = idx.saturating_add(1);
+                    }
                 } else {
                     unreachable!();
                 }
@@ -790,14 +881,16 @@ impl Application for RefBoxApp {
             }
             Message::KeypadPage(page) => {
                 let init_val = match page {
-                    KeypadPage::AddScore(_) | KeypadPage::Penalty(None, _, _) => 0,
+                    KeypadPage::AddScore(_)
+                    | KeypadPage::PenaltyShot(_, _)
+                    | KeypadPage::Penalty(None, _, _) => 0,
                     KeypadPage::Penalty(Some((color, index)), _, _) => {
                         self.pen_edit
                             .get_penalty(color, index)
                             .unwrap()
                             .player_number as u16
                     }
-                    KeypadPage::TeamTimeouts(_) => self.config.game.team_timeouts_per_half,
+                    KeypadPage::TeamTimeouts(_, _) => self.config.game.team_timeouts_per_half,
                     KeypadPage::GameNumber => self
                         .edited_settings
                         .as_ref()
@@ -806,6 +899,9 @@ impl Application for RefBoxApp {
                         .try_into()
                         .unwrap_or(0),
                 };
+                if page.color().is_some() {
+                    self.request_rosters(shown_game_number(&self.snapshot), false);
+                }
                 self.app_state = AppState::KeypadPage(page, init_val);
                 trace!("AppState changed to {:?}", self.app_state);
             }
@@ -835,6 +931,7 @@ impl Application for RefBoxApp {
             Message::ChangeColor(new_color) => {
                 match self.app_state {
                     AppState::KeypadPage(KeypadPage::AddScore(ref mut color), _)
+                    | AppState::KeypadPage(KeypadPage::PenaltyShot(ref mut color, _), _)
                     | AppState::KeypadPage(KeypadPage::Penalty(_, ref mut color, _), _) => {
                         *color = new_color;
                     }
@@ -882,6 +979,73 @@ impl Application for RefBoxApp {
                 };
                 trace!("AppState changed to {:?}", self.app_state);
             }
+            Message::ChangeScored(new_scored) => {
+                if let AppState::KeypadPage(KeypadPage::PenaltyShot(_, ref mut scored), _) =
+                    self.app_state
+                {
+                    *scored = new_scored;
+                } else {
+                    unreachable!()
+                }
+                trace!("AppState changed to {:?}", self.app_state);
+            }
+            Message::PenaltyShotComplete { canceled } => {
+                if !canceled {
+                    if let AppState::KeypadPage(KeypadPage::PenaltyShot(color, scored), player) =
+                        self.app_state
+                    {
+                        let mut tm = self.tm.lock().unwrap();
+                        let now = Instant::now();
+
+                        let in_sudden_death = tm.current_period() == GamePeriod::SuddenDeath;
+
+                        match tm.record_penalty_shot(color, player.try_into().unwrap(), scored, now)
+                        {
+                            // A goal in sudden death ends the game, so the result needs to be sent
+                            Ok(()) if scored && in_sudden_death => {
+                                if let Some(game) = self
+                                    .games
+                                    .as_ref()
+                                    .and_then(|games| games.get(&tm.game_number()))
+                                {
+                                    let scores = BlackWhiteBundle {
+                                        black: tm.get_b_score(),
+                                        white: tm.get_w_score(),
+                                    };
+                                    self.post_game_score(game, scores);
+                                }
+                            }
+                            Ok(()) => {}
+                            Err(e) => warn!("Failed to record penalty shot: {e}"),
+                        }
+                        let snapshot = tm.generate_snapshot(now).unwrap();
+
+                        std::mem::drop(tm);
+                        self.apply_snapshot(snapshot);
+                    } else {
+                        unreachable!()
+                    }
+                }
+                self.app_state = AppState::MainPage;
+                trace!("AppState changed to {:?}", self.app_state);
+            }
+            Message::ShowRoster => {
+                if let AppState::KeypadPage(page, player_num) = self.app_state {
+                    self.app_state = AppState::RosterList(page, player_num, 0);
+                } else {
+                    unreachable!()
+                }
+                trace!("AppState changed to {:?}", self.app_state);
+            }
+            Message::PlayerSelected(selected) => {
+                if let AppState::RosterList(page, player_num, _) = self.app_state {
+                    let player_num = selected.map_or(player_num, u16::from);
+                    self.app_state = AppState::KeypadPage(page, player_num);
+                } else {
+                    unreachable!()
+                }
+                trace!("AppState changed to {:?}", self.app_state);
+            }
             Message::EditGameConfig => {
                 let edited_settings = EditableSettings {
                     config: self.tm.lock().unwrap().config().clone(),
@@ -945,7 +1109,7 @@ impl Application for RefBoxApp {
                             .unwrap()
                             .timing_rules
                         {
-                            Some(ref rules) => rules.clone().into(),
+                            Some(ref rules) => rules.apply_to(tm.config()),
                             None => tm.config().clone(),
                         }
                     } else {
@@ -957,7 +1121,7 @@ impl Application for RefBoxApp {
                     } else if new_config != *tm.config() {
                         if tm.current_period() != GamePeriod::BetweenGames {
                             AppState::ConfirmationPage(ConfirmationKind::GameConfigChanged(
-                                new_config,
+                                Box::new(new_config),
                             ))
                         } else {
                             tm.set_config(new_config.clone()).unwrap();
@@ -1148,9 +1312,10 @@ impl Application for RefBoxApp {
                         AppState::KeypadPage(KeypadPage::GameNumber, num) => {
                             edited_settings.game_number = num.into();
                         }
-                        AppState::KeypadPage(KeypadPage::TeamTimeouts(len), num) => {
+                        AppState::KeypadPage(KeypadPage::TeamTimeouts(len, per_half), num) => {
                             edited_settings.config.team_timeout_duration = len;
                             edited_settings.config.team_timeouts_per_half = num;
+                            edited_settings.config.timeouts_counted_per_half = per_half;
                         }
                         _ => unreachable!(),
                     }
@@ -1159,7 +1324,9 @@ impl Application for RefBoxApp {
                 let next_page = match self.app_state {
                     AppState::ParameterEditor(_, _) => ConfigPage::Tournament,
                     AppState::KeypadPage(KeypadPage::GameNumber, _) => ConfigPage::Main,
-                    AppState::KeypadPage(KeypadPage::TeamTimeouts(_), _) => ConfigPage::Tournament,
+                    AppState::KeypadPage(KeypadPage::TeamTimeouts(_, _), _) => {
+                        ConfigPage::Tournament
+                    }
                     AppState::ParameterList(param, _) => match param {
                         ListableParameter::Game => ConfigPage::Main,
                         ListableParameter::Tournament | ListableParameter::Pool => {
@@ -1227,13 +1394,25 @@ impl Application for RefBoxApp {
                     BoolGameParameter::AutoSoundStopPlay => {
                         edited_settings.sound.auto_sound_stop_play ^= true
                     }
+                    BoolGameParameter::TimeoutsCountedPerHalf => {
+                        if let AppState::KeypadPage(
+                            KeypadPage::TeamTimeouts(_, ref mut per_half),
+                            _,
+                        ) = self.app_state
+                        {
+                            *per_half ^= true;
+                        }
+                    }
                 }
             }
             Message::CycleParameter(param) => {
                 let sound = &mut self.edited_settings.as_mut().unwrap().sound;
+                let custom_sounds = self.sound.custom_sounds();
                 match param {
-                    CyclingParameter::BuzzerSound => sound.buzzer_sound.cycle(),
-                    CyclingParameter::RemoteBuzzerSound(idx) => sound.remotes[idx].sound.cycle(),
+                    CyclingParameter::BuzzerSound => sound.buzzer_sound.cycle(custom_sounds),
+                    CyclingParameter::RemoteBuzzerSound(idx) => {
+                        sound.remotes[idx].sound.cycle(custom_sounds)
+                    }
                     CyclingParameter::AlertVolume => sound.whistle_vol.cycle(),
                     CyclingParameter::AboveWaterVol => sound.above_water_vol.cycle(),
                     CyclingParameter::UnderWaterVol => sound.under_water_vol.cycle(),
@@ -1286,8 +1465,32 @@ impl Application for RefBoxApp {
                     None
                 };
 
+                let saved_state =
+                    if let AppState::ConfirmationPage(ConfirmationKind::ResumeGame(ref state)) =
+                        self.app_state
+                    {
+                        Some(state.clone())
+                    } else {
+                        None
+                    };
+
                 self.app_state = match selection {
                     ConfirmationOption::DiscardChanges => AppState::MainPage,
+                    ConfirmationOption::ResumeGame => {
+                        let mut tm = self.tm.lock().unwrap();
+                        let now = Instant::now();
+                        tm.restore_state(*saved_state.unwrap(), now);
+                        self.config.game = tm.config().clone();
+                        let snapshot = tm.generate_snapshot(now).unwrap();
+                        let game_time = tm.game_clock_time(now).unwrap();
+                        let timeout_time = tm.timeout_clock_time(now);
+                        std::mem::drop(tm);
+                        self.apply_snapshot(snapshot);
+
+                        // Let the ref check the clock, play resumes when they leave the editor
+                        self.last_app_state = AppState::MainPage;
+                        AppState::TimeEdit(true, game_time, timeout_time)
+                    }
                     ConfirmationOption::GoBack => AppState::EditGameConfig(ConfigPage::Main),
                     ConfirmationOption::EndGameAndApply => {
                         let edited_settings = self.edited_settings.take().unwrap();
@@ -1295,8 +1498,8 @@ impl Application for RefBoxApp {
                         let now = Instant::now();
                         tm.reset_game(now);
                         if let Some(config) = new_config {
-                            tm.set_config(config.clone()).unwrap();
-                            self.config.game = config;
+                            tm.set_config(*config.clone()).unwrap();
+                            self.config.game = *config;
                         }
 
                         let game = edited_settings
@@ -1369,19 +1572,9 @@ impl Application for RefBoxApp {
                 self.app_state = if let AppState::ConfirmScores(scores) = self.app_state {
                     if correct {
                         let mut tm = self.tm.lock().unwrap();
-                        let now = Instant::now();
-
-                        if let Some(game) = self
-                            .games
-                            .as_ref()
-                            .and_then(|games| games.get(&tm.game_number()))
-                        {
-                            self.post_game_score(game, scores);
-                        }
-
-                        tm.set_scores(scores.black, scores.white, now);
-                        tm.start_clock(now);
-                        tm.update(now + Duration::from_millis(2)).unwrap(); // Need to update after game ends
+                        let snapshot = self.finish_game(&mut tm, scores, Instant::now());
+                        std::mem::drop(tm);
+                        self.apply_snapshot(snapshot);
 
                         AppState::MainPage
                     } else {
@@ -1445,7 +1638,7 @@ impl Application for RefBoxApp {
                 let mut tm = self.tm.lock().unwrap();
                 let now = Instant::now();
                 if switch {
-                    tm.switch_to_penalty_shot().unwrap();
+                    tm.switch_to_penalty_shot(now).unwrap();
                 } else {
                     tm.start_penalty_shot(now).unwrap();
                 }
@@ -1467,6 +1660,33 @@ impl Application for RefBoxApp {
                 }
                 trace!("AppState changed to {:?}", self.app_state);
             }
+            Message::UndoLastAction => {
+                let mut tm = self.tm.lock().unwrap();
+                let now = Instant::now();
+                if let Err(e) = tm.undo(now) {
+                    warn!("Failed to undo: {e}");
+                }
+                let snapshot = tm.generate_snapshot(now).unwrap();
+                std::mem::drop(tm);
+                self.apply_snapshot(snapshot);
+            }
+            Message::RedoLastAction => {
+                let mut tm = self.tm.lock().unwrap();
+                let now = Instant::now();
+                if let Err(e) = tm.redo(now) {
+                    warn!("Failed to redo: {e}");
+                }
+                let snapshot = tm.generate_snapshot(now).unwrap();
+                std::mem::drop(tm);
+                self.apply_snapshot(snapshot);
+            }
+            Message::RemoteCommand(command, responder) => {
+                let result = self.apply_remote_command(command);
+                if let Err(ref e) = result {
+                    warn!("Rejected remote command {command:?}: {e}");
+                }
+                responder.respond(result);
+            }
             Message::RecvTournamentList(t_list) => {
                 let active_filter = if self.list_all_tournaments {
                     |_: &TournamentInfo| true
@@ -1525,9 +1745,14 @@ impl Application for RefBoxApp {
                     self.games = Some(BTreeMap::from([(game.gid, game)]));
                 }
             }
+            Message::RecvTeam { tid, team_id, team } => {
+                self.rosters.insert((tid, team_id), team);
+            }
             Message::NoAction => {}
         };
 
+        self.save_state();
+
         Command::none()
     }
 
@@ -1547,7 +1772,9 @@ impl Application for RefBoxApp {
                             .unwrap()
                             .next_game_info()
                             .as_ref()
-                            .and_then(|info| Some(info.timing.as_ref()?.clone().into()))
+                            .and_then(|info| {
+                                Some(info.timing.as_ref()?.apply_to(&self.config.game))
+                            })
                     } else {
                         None
                     };
@@ -1558,7 +1785,20 @@ impl Application for RefBoxApp {
                         &self.config.game
                     };
 
-                    build_main_view(&self.snapshot, config, self.using_uwhscores, &self.games)
+                    let (can_undo, can_redo) = {
+                        let tm = self.tm.lock().unwrap();
+                        (tm.can_undo(), tm.can_redo())
+                    };
+
+                    build_main_view(
+                        &self.snapshot,
+                        config,
+                        self.using_uwhscores,
+                        &self.games,
+                        can_undo,
+                        can_redo,
+                        self.score_outbox.pending_count(),
+                    )
                 }
                 AppState::TimeEdit(_, time, timeout_time) => {
                     build_time_edit_view(&self.snapshot, time, timeout_time)
@@ -1571,9 +1811,21 @@ impl Application for RefBoxApp {
                     &self.snapshot,
                     self.pen_edit.get_printable_lists(Instant::now()).unwrap(),
                     indices,
+                    PenaltyKind::available(self.tm.lock().unwrap().config())[0],
+                ),
+                AppState::KeypadPage(page, player_num) => build_keypad_page(
+                    &self.snapshot,
+                    page,
+                    player_num,
+                    &PenaltyKind::available(self.tm.lock().unwrap().config()),
+                    page.color().and_then(|color| self.roster(color)).is_some(),
                 ),
-                AppState::KeypadPage(page, player_num) => {
-                    build_keypad_page(&self.snapshot, page, player_num)
+                AppState::RosterList(page, _, index) => {
+                    let (name, roster) = match page.color().and_then(|color| self.roster(color)) {
+                        Some(team) => (team.name.as_str(), team.roster.as_slice()),
+                        None => ("", &[][..]),
+                    };
+                    build_roster_list_page(&self.snapshot, name, roster, index)
                 }
                 AppState::EditGameConfig(page) => build_game_config_edit_page(
                     &self.snapshot,
@@ -1644,22 +1896,25 @@ impl<H: Hasher, I> Recipe<H, I> for TimeUpdater {
         };
 
         Box::pin(stream::unfold(state, |mut state| async move {
-            let mut clock_running = true;
             if let Some(next_time) = state.next_time {
                 if next_time > Instant::now() {
                     match timeout_at(next_time, state.clock_running_receiver.changed()).await {
                         Err(_) => {}
                         Ok(Err(_)) => return None,
                         Ok(Ok(())) => {
-                            clock_running = *state.clock_running_receiver.borrow();
-                            debug!("Received clock running message: {clock_running}");
+                            debug!(
+                                "Received clock running message: {}",
+                                *state.clock_running_receiver.borrow()
+                            );
                         }
                     };
                 } else {
                     match state.clock_running_receiver.has_changed() {
                         Ok(true) => {
-                            clock_running = *state.clock_running_receiver.borrow();
-                            debug!("Received clock running message: {clock_running}");
+                            debug!(
+                                "Received clock running message: {}",
+                                *state.clock_running_receiver.borrow()
+                            );
                         }
                         Ok(false) => {}
                         Err(_) => {
@@ -1672,41 +1927,26 @@ impl<H: Hasher, I> Recipe<H, I> for TimeUpdater {
                 match state.clock_running_receiver.changed().await {
                     Err(_) => return None,
                     Ok(()) => {
-                        clock_running = *state.clock_running_receiver.borrow();
-                        debug!("Received clock running message: {clock_running}");
+                        debug!(
+                            "Received clock running message: {}",
+                            *state.clock_running_receiver.borrow()
+                        );
                     }
                 };
             };
 
             let mut tm = state.tm.lock().unwrap();
-            let now = Instant::now();
+            let tick = game_flow::tick(&mut tm, Instant::now()).unwrap();
+            drop(tm);
 
-            let msg_type = if tm.would_end_game(now).unwrap() {
-                tm.halt_clock(now).unwrap();
-                clock_running = false;
+            state.next_time = tick.next_update;
+            let msg_type = if tick.game_ending {
                 Message::ConfirmScores
             } else {
-                tm.update(now).unwrap();
                 Message::NewSnapshot
             };
 
-            let snapshot = match tm.generate_snapshot(now) {
-                Some(val) => val,
-                None => {
-                    error!("Failed to generate snapshot. State:\n{tm:#?}");
-                    panic!("No snapshot");
-                }
-            };
-
-            state.next_time = if clock_running {
-                Some(tm.next_update_time(now).unwrap())
-            } else {
-                None
-            };
-
-            drop(tm);
-
-            Some((msg_type(snapshot), state))
+            Some((msg_type(tick.snapshot), state))
         }))
     }
 }
@@ -1740,3 +1980,65 @@ impl<H: Hasher, I> Recipe<H, I> for MessageListener {
         }))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use time::macros::datetime;
+
+    fn game(gid: u32, black: &str, white: &str) -> GameInfo {
+        GameInfo {
+            black: black.to_string(),
+            black_id: 0,
+            game_type: "RR".to_string(),
+            gid,
+            pool: "1".to_string(),
+            score_b: 0,
+            score_w: 0,
+            start_time: datetime!(2022-06-04 10:00),
+            tid: 1,
+            timing_rules: None,
+            white: white.to_string(),
+            white_id: 0,
+        }
+    }
+
+    #[test]
+    fn test_fill_team_names() {
+        let games: BTreeMap<_, _> = [
+            (4, game(4, "Sharks", "Rays")),
+            (5, game(5, "Eels", "Crabs")),
+        ]
+        .into_iter()
+        .collect();
+
+        let name = |s: &str| Some(s.to_string());
+
+        let mut snapshot = GameSnapshot {
+            current_period: GamePeriod::FirstHalf,
+            game_number: 4,
+            next_game_number: 5,
+            ..Default::default()
+        };
+        fill_team_names(&mut snapshot, &games);
+        assert_eq!(snapshot.b_team_name, name("Sharks"));
+        assert_eq!(snapshot.w_team_name, name("Rays"));
+
+        // Between games the upcoming game is shown
+        snapshot.current_period = GamePeriod::BetweenGames;
+        fill_team_names(&mut snapshot, &games);
+        assert_eq!(snapshot.b_team_name, name("Eels"));
+        assert_eq!(snapshot.w_team_name, name("Crabs"));
+
+        // Unless the game that just ended is still being shown
+        snapshot.is_old_game = true;
+        fill_team_names(&mut snapshot, &games);
+        assert_eq!(snapshot.b_team_name, name("Sharks"));
+        assert_eq!(snapshot.w_team_name, name("Rays"));
+
+        // Unknown games leave the names alone
+        snapshot.game_number = 9;
+        fill_team_names(&mut snapshot, &games);
+        assert_eq!(snapshot.b_team_name, name("Sharks"));
+    }
+}
@@ -0,0 +1,570 @@
+//! Lets a second device (a tablet, a button box, etc.) control the refbox over TCP.
+//!
+//! Each request is a single line of JSON that includes the shared key from the config file and
+//! a command, for example:
+//!
+//! ```text
+//! {"key": "secret", "command": "add_goal", "color": "Black", "player_number": 7}
+//! {"key": "secret", "command": "start_timeout", "kind": "White"}
+//! {"key": "secret", "command": "add_penalty", "color": "White", "player_number": 3, "length": {"seconds": 120}}
+//! ```
+//!
+//! Each request is answered with a single line, either `{"ok":true}` or
+//! `{"ok":false,"error":"..."}`. A request with the wrong key closes the connection.
+
+use super::message::Message;
+use crate::{
+    config::RemoteControl,
+    game_log::TimeoutKind,
+    tournament_manager::{PenaltyKind, TournamentManager, TournamentManagerError},
+};
+use log::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+use thiserror::Error;
+use tokio::{
+    io::{self, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    select,
+    sync::{mpsc, oneshot},
+    task,
+    time::{timeout, Duration, Instant},
+};
+use uwh_common::game_snapshot::Color;
+
+const MAX_LINE_LEN: u64 = 1024;
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PenaltyLength {
+    Seconds(u64),
+    TotalDismissal,
+}
+
+impl From<PenaltyLength> for PenaltyKind {
+    fn from(length: PenaltyLength) -> Self {
+        match length {
+            PenaltyLength::Seconds(secs) => PenaltyKind::from_secs(secs),
+            PenaltyLength::TotalDismissal => PenaltyKind::TotalDismissal,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum RemoteCommand {
+    StartClock,
+    StopClock,
+    AddGoal {
+        color: Color,
+        player_number: u8,
+    },
+    /// Starts the timeout, or switches to it from the matching timeout (e.g. Ref to Penalty Shot)
+    StartTimeout {
+        kind: TimeoutKind,
+    },
+    EndTimeout,
+    AddPenalty {
+        color: Color,
+        player_number: u8,
+        length: PenaltyLength,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+struct RemoteRequest {
+    key: String,
+    #[serde(flatten)]
+    command: RemoteCommand,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct RemoteResponse {
+    ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl From<Result<(), String>> for RemoteResponse {
+    fn from(result: Result<(), String>) -> Self {
+        Self {
+            ok: result.is_ok(),
+            error: result.err(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Error)]
+pub enum RemoteCommandError {
+    #[error("The refbox is showing another page, commands are only accepted from the main page")]
+    Busy,
+    #[error("{0} is not one of the configured penalty lengths")]
+    InvalidPenaltyKind(PenaltyKind),
+    #[error(transparent)]
+    TournamentManagerError(#[from] TournamentManagerError),
+}
+
+/// Carries the result of a command back to the connection it came from. It is only used once, but
+/// needs to be `Clone` and `Eq` so that it can be part of a `Message`.
+#[derive(Debug, Clone)]
+pub struct RemoteResponder(Arc<Mutex<Option<oneshot::Sender<CommandResult>>>>);
+
+type CommandResult = Result<(), String>;
+
+impl RemoteResponder {
+    fn new() -> (Self, oneshot::Receiver<CommandResult>) {
+        let (tx, rx) = oneshot::channel();
+        (Self(Arc::new(Mutex::new(Some(tx)))), rx)
+    }
+
+    pub fn respond(&self, result: Result<(), RemoteCommandError>) {
+        if let Some(tx) = self.0.lock().unwrap().take() {
+            // The connection may have been closed already, in which case there's nobody to tell
+            let _ = tx.send(result.map_err(|e| e.to_string()));
+        }
+    }
+}
+
+impl PartialEq for RemoteResponder {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for RemoteResponder {}
+
+/// Compares the keys without returning early, so the comparison time doesn't reveal how much of
+/// the key was correct
+fn key_matches(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+/// Applies `command` to the game. Timeouts are switched to if possible (e.g. Ref to Penalty Shot),
+/// otherwise they are started.
+pub(crate) fn apply_command(
+    tm: &mut TournamentManager,
+    command: RemoteCommand,
+    now: Instant,
+) -> Result<(), RemoteCommandError> {
+    match command {
+        RemoteCommand::StartClock => tm.start_clock(now),
+        RemoteCommand::StopClock => tm.stop_clock(now)?,
+        RemoteCommand::AddGoal {
+            color,
+            player_number,
+        } => match color {
+            Color::Black => tm.add_b_score(player_number, now),
+            Color::White => tm.add_w_score(player_number, now),
+        },
+        RemoteCommand::StartTimeout { kind } => match kind {
+            TimeoutKind::Black => {
+                if tm.can_switch_to_b_timeout().is_ok() {
+                    tm.switch_to_b_timeout()?;
+                } else {
+                    tm.start_b_timeout(now)?;
+                }
+            }
+            TimeoutKind::White => {
+                if tm.can_switch_to_w_timeout().is_ok() {
+                    tm.switch_to_w_timeout()?;
+                } else {
+                    tm.start_w_timeout(now)?;
+                }
+            }
+            TimeoutKind::Ref => {
+                if tm.can_switch_to_ref_timeout().is_ok() {
+                    tm.switch_to_ref_timeout()?;
+                } else {
+                    tm.start_ref_timeout(now)?;
+                }
+            }
+            TimeoutKind::PenaltyShot => {
+                if tm.can_switch_to_penalty_shot().is_ok() {
+                    tm.switch_to_penalty_shot(now)?;
+                } else {
+                    tm.start_penalty_shot(now)?;
+                }
+            }
+        },
+        RemoteCommand::EndTimeout => tm.end_timeout(now)?,
+        RemoteCommand::AddPenalty {
+            color,
+            player_number,
+            length,
+        } => {
+            let kind = length.into();
+            if !PenaltyKind::available(tm.config()).contains(&kind) {
+                return Err(RemoteCommandError::InvalidPenaltyKind(kind));
+            }
+            tm.start_penalty(color, player_number, kind, now)?;
+        }
+    }
+    Ok(())
+}
+
+/// Reads commands from stdin, one per line, in the same format as the network requests but
+/// without the key. Each response is printed to stdout.
+pub fn start_console(msg_tx: mpsc::UnboundedSender<Message>) {
+    task::spawn(async move {
+        let mut lines = BufReader::new(io::stdin()).lines();
+        loop {
+            let line = match lines.next_line().await {
+                Ok(Some(line)) => line,
+                Ok(None) => {
+                    info!("Console closed, no longer reading commands from it");
+                    return;
+                }
+                Err(e) => {
+                    error!("Failed to read from console: {e}");
+                    return;
+                }
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let result = match serde_json::from_str::<RemoteCommand>(&line) {
+                Ok(command) => handle_command(command, &msg_tx).await,
+                Err(e) => Err(format!("Invalid command: {e}")),
+            };
+            println!(
+                "{}",
+                serde_json::to_string(&RemoteResponse::from(result)).unwrap()
+            );
+        }
+    });
+}
+
+/// Starts listening for remote control connections. Commands are passed to the app as
+/// `Message::RemoteCommand`s on `msg_tx`.
+pub fn start(settings: RemoteControl, msg_tx: mpsc::UnboundedSender<Message>) {
+    if settings.key.is_empty() {
+        error!("Remote control is enabled, but no key is set. Not starting remote control");
+        return;
+    }
+    task::spawn(listener_loop(settings, msg_tx));
+}
+
+async fn listener_loop(settings: RemoteControl, msg_tx: mpsc::UnboundedSender<Message>) {
+    let port = settings.port;
+    let key: Arc<str> = settings.key.into();
+
+    info!("Starting remote control listener on port {port}");
+    let listener_v6 = match TcpListener::bind(("::", port)).await {
+        Ok(l) => l,
+        Err(e) => {
+            error!("Failed to start remote control listener: {e}");
+            return;
+        }
+    };
+    // On some OSs, we must separately listen on IPv4, but on other OSs that
+    // that isn't allowed, so we just try to listen on IPv4
+    let listener_v4 = TcpListener::bind(("0.0.0.0", port)).await.ok();
+
+    loop {
+        let conn = if let Some(ref listener_v4) = listener_v4 {
+            select! {
+                conn = listener_v6.accept() => conn,
+                conn = listener_v4.accept() => conn,
+            }
+        } else {
+            listener_v6.accept().await
+        };
+
+        match conn {
+            Ok((stream, addr)) => {
+                info!("New remote control connection from {addr:?}");
+                task::spawn(handle_connection(stream, addr, key.clone(), msg_tx.clone()));
+            }
+            Err(e) => error!("New remote control connection failed: {e}"),
+        }
+    }
+}
+
+async fn handle_connection(
+    stream: TcpStream,
+    addr: SocketAddr,
+    key: Arc<str>,
+    msg_tx: mpsc::UnboundedSender<Message>,
+) {
+    match serve(stream, &key, &msg_tx).await {
+        Ok(()) => info!("Remote control connection from {addr:?} closed"),
+        Err(e) => warn!("Remote control connection from {addr:?} failed: {e}"),
+    }
+}
+
+async fn serve<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: S,
+    key: &str,
+    msg_tx: &mpsc::UnboundedSender<Message>,
+) -> io::Result<()> {
+    let (read, mut write) = io::split(stream);
+    let mut reader = BufReader::new(read);
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        let len = (&mut reader)
+            .take(MAX_LINE_LEN)
+            .read_line(&mut line)
+            .await?;
+        if len == 0 {
+            return Ok(());
+        }
+        if !line.ends_with('\n') && len as u64 == MAX_LINE_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "request line too long",
+            ));
+        }
+
+        let (response, authorized) = match serde_json::from_str::<RemoteRequest>(&line) {
+            Ok(request) if key_matches(&request.key, key) => {
+                (handle_command(request.command, msg_tx).await, true)
+            }
+            Ok(_) => (Err("Invalid key".to_string()), false),
+            Err(e) => (Err(format!("Invalid request: {e}")), true),
+        };
+
+        let mut response = serde_json::to_string(&RemoteResponse::from(response))?;
+        response.push('\n');
+        write.write_all(response.as_bytes()).await?;
+
+        if !authorized {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "invalid key",
+            ));
+        }
+    }
+}
+
+async fn handle_command(
+    command: RemoteCommand,
+    msg_tx: &mpsc::UnboundedSender<Message>,
+) -> Result<(), String> {
+    info!("Received remote command: {command:?}");
+    let (responder, rx) = RemoteResponder::new();
+    msg_tx
+        .send(Message::RemoteCommand(command, responder))
+        .map_err(|_| "The refbox is shutting down".to_string())?;
+
+    match timeout(RESPONSE_TIMEOUT, rx).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(_)) => Err("The command was dropped without being handled".to_string()),
+        Err(_) => Err("Timed out waiting for the refbox to handle the command".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tokio::io::DuplexStream;
+
+    const KEY: &str = "test-key";
+
+    /// Starts `serve` on one end of a pipe, and handles the resulting messages with `handler`
+    fn start_serve(
+        handler: impl Fn(RemoteCommand) -> Result<(), RemoteCommandError> + Send + 'static,
+    ) -> (BufReader<DuplexStream>, task::JoinHandle<io::Result<()>>) {
+        let (client, server) = io::duplex(4096);
+        let (msg_tx, mut msg_rx) = mpsc::unbounded_channel();
+
+        task::spawn(async move {
+            while let Some(msg) = msg_rx.recv().await {
+                match msg {
+                    Message::RemoteCommand(command, responder) => {
+                        responder.respond(handler(command))
+                    }
+                    other => panic!("Unexpected message: {other:?}"),
+                }
+            }
+        });
+
+        let join = task::spawn(async move { serve(server, KEY, &msg_tx).await });
+        (BufReader::new(client), join)
+    }
+
+    async fn request(client: &mut BufReader<DuplexStream>, line: &str) -> RemoteResponse {
+        client.get_mut().write_all(line.as_bytes()).await.unwrap();
+        client.get_mut().write_all(b"\n").await.unwrap();
+        let mut response = String::new();
+        client.read_line(&mut response).await.unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+
+    #[test]
+    fn test_deser_commands() {
+        let parse = |s| serde_json::from_str::<RemoteRequest>(s).unwrap().command;
+
+        assert_eq!(
+            parse(r#"{"key": "k", "command": "start_clock"}"#),
+            RemoteCommand::StartClock
+        );
+        assert_eq!(
+            parse(r#"{"key": "k", "command": "add_goal", "color": "White", "player_number": 7}"#),
+            RemoteCommand::AddGoal {
+                color: Color::White,
+                player_number: 7
+            }
+        );
+        assert_eq!(
+            parse(r#"{"key": "k", "command": "start_timeout", "kind": "PenaltyShot"}"#),
+            RemoteCommand::StartTimeout {
+                kind: TimeoutKind::PenaltyShot
+            }
+        );
+        assert_eq!(
+            parse(
+                r#"{"key": "k", "command": "add_penalty", "color": "Black", "player_number": 3,
+                    "length": {"seconds": 120}}"#
+            ),
+            RemoteCommand::AddPenalty {
+                color: Color::Black,
+                player_number: 3,
+                length: PenaltyLength::Seconds(120),
+            }
+        );
+        assert_eq!(
+            parse(
+                r#"{"key": "k", "command": "add_penalty", "color": "Black", "player_number": 3,
+                    "length": "total_dismissal"}"#
+            ),
+            RemoteCommand::AddPenalty {
+                color: Color::Black,
+                player_number: 3,
+                length: PenaltyLength::TotalDismissal,
+            }
+        );
+
+        assert!(serde_json::from_str::<RemoteRequest>(r#"{"command": "start_clock"}"#).is_err());
+        assert!(serde_json::from_str::<RemoteRequest>(r#"{"key": "k", "command": "x"}"#).is_err());
+    }
+
+    #[test]
+    fn test_apply_command() {
+        let mut tm = TournamentManager::new(Default::default());
+        let now = Instant::now();
+        tm.start_play_now(now).unwrap();
+
+        apply_command(
+            &mut tm,
+            RemoteCommand::AddGoal {
+                color: Color::White,
+                player_number: 4,
+            },
+            now,
+        )
+        .unwrap();
+        assert_eq!((tm.get_b_score(), tm.get_w_score()), (0, 1));
+
+        // A Ref timeout can be switched to a Penalty Shot
+        let start_timeout = |kind| RemoteCommand::StartTimeout { kind };
+        apply_command(&mut tm, start_timeout(TimeoutKind::Ref), now).unwrap();
+        apply_command(&mut tm, start_timeout(TimeoutKind::PenaltyShot), now).unwrap();
+        let snapshot = tm.generate_snapshot(now).unwrap();
+        assert!(matches!(
+            snapshot.timeout,
+            uwh_common::game_snapshot::TimeoutSnapshot::PenaltyShot(_)
+        ));
+        apply_command(&mut tm, RemoteCommand::EndTimeout, now).unwrap();
+        assert_eq!(
+            apply_command(&mut tm, RemoteCommand::EndTimeout, now),
+            Err(TournamentManagerError::NotInTimeout.into())
+        );
+
+        let penalty = |length| RemoteCommand::AddPenalty {
+            color: Color::Black,
+            player_number: 2,
+            length,
+        };
+        assert_eq!(
+            apply_command(&mut tm, penalty(PenaltyLength::Seconds(17)), now),
+            Err(RemoteCommandError::InvalidPenaltyKind(
+                PenaltyKind::from_secs(17)
+            ))
+        );
+        apply_command(&mut tm, penalty(PenaltyLength::TotalDismissal), now).unwrap();
+        assert_eq!(tm.get_penalties().black.len(), 1);
+    }
+
+    #[test]
+    fn test_key_matches() {
+        assert!(key_matches("abc", "abc"));
+        assert!(!key_matches("abd", "abc"));
+        assert!(!key_matches("ab", "abc"));
+        assert!(!key_matches("", "abc"));
+    }
+
+    #[tokio::test]
+    async fn test_serve() {
+        let (mut client, join) = start_serve(|command| match command {
+            RemoteCommand::StartClock => Ok(()),
+            _ => Err(TournamentManagerError::NotInTimeout.into()),
+        });
+
+        assert_eq!(
+            request(
+                &mut client,
+                r#"{"key": "test-key", "command": "start_clock"}"#
+            )
+            .await,
+            RemoteResponse {
+                ok: true,
+                error: None
+            }
+        );
+
+        assert_eq!(
+            request(
+                &mut client,
+                r#"{"key": "test-key", "command": "end_timeout"}"#
+            )
+            .await,
+            RemoteResponse {
+                ok: false,
+                error: Some(TournamentManagerError::NotInTimeout.to_string())
+            }
+        );
+
+        let response = request(&mut client, "not json").await;
+        assert!(!response.ok);
+        assert!(response.error.unwrap().starts_with("Invalid request"));
+
+        // The connection is closed after a bad key
+        assert_eq!(
+            request(&mut client, r#"{"key": "wrong", "command": "start_clock"}"#).await,
+            RemoteResponse {
+                ok: false,
+                error: Some("Invalid key".to_string())
+            }
+        );
+        assert_eq!(
+            join.await.unwrap().unwrap_err().kind(),
+            io::ErrorKind::PermissionDenied
+        );
+    }
+
+    #[tokio::test]
+    async fn test_serve_long_line() {
+        let (mut client, join) = start_serve(|_| Ok(()));
+        client
+            .get_mut()
+            .write_all(&[b'a'; MAX_LINE_LEN as usize + 1])
+            .await
+            .unwrap();
+        assert_eq!(
+            join.await.unwrap().unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
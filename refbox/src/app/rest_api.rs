@@ -0,0 +1,425 @@
+//! A read-only HTTP API for querying the state of the game and the results of the session.
+//!
+//! All endpoints respond to `GET` requests with JSON:
+//! - `/api/v1/snapshot`: the current `GameSnapshot`
+//! - `/api/v1/config`: the active game config
+//! - `/api/v1/penalties`: the penalty lists for each team, with full penalty details
+//! - `/api/v1/timeouts`: the team timeouts used by each team
+//! - `/api/v1/games`: the logs of the games completed since the refbox was started
+//! - `/api/v1/scorers`: the goals and penalties of each player in the current game
+//! - `/api/v1/scorers/session`: the goals and penalties of each player in the games completed
+//!   since the refbox was started, with players identified by team name and number
+
+use crate::{
+    game_log::GameLog,
+    game_stats::session_player_stats,
+    tournament_manager::{Penalty, PenaltyKind, TournamentManager},
+};
+use hyper::{
+    header,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use log::*;
+use serde::Serialize;
+use std::{
+    convert::Infallible,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+use tokio::{task, time::Instant};
+use uwh_common::game_snapshot::{Color, GamePeriod, GameSnapshot};
+
+/// The data served by the API. The app keeps the snapshot and list of completed games up to date,
+/// everything else is read from the `TournamentManager` when it is requested.
+#[derive(Debug, Clone)]
+pub struct RestApiData {
+    tm: Arc<Mutex<TournamentManager>>,
+    snapshot: Arc<Mutex<GameSnapshot>>,
+    completed_games: Arc<Mutex<Vec<GameLog>>>,
+}
+
+impl RestApiData {
+    pub fn new(tm: Arc<Mutex<TournamentManager>>) -> Self {
+        Self {
+            tm,
+            snapshot: Default::default(),
+            completed_games: Default::default(),
+        }
+    }
+
+    pub fn set_snapshot(&self, snapshot: GameSnapshot) {
+        *self.snapshot.lock().unwrap() = snapshot;
+    }
+
+    pub fn add_completed_game(&self, log: GameLog) {
+        self.completed_games.lock().unwrap().push(log);
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct PenaltyInfo {
+    player_number: u8,
+    kind: String,
+    /// `None` for a Total Dismissal
+    duration_secs: Option<u64>,
+    total_dismissal: bool,
+    start_period: GamePeriod,
+    start_clock_secs: u64,
+    /// `None` for a Total Dismissal
+    secs_remaining: Option<u64>,
+    served: bool,
+}
+
+impl PenaltyInfo {
+    fn new(pen: &Penalty, tm: &TournamentManager, now: Instant) -> Self {
+        let secs_remaining = tm.penalty_secs_remaining(pen, now);
+        Self {
+            player_number: pen.player_number,
+            kind: pen.kind.to_string(),
+            duration_secs: pen.kind.as_duration().map(|d| d.as_secs()),
+            total_dismissal: pen.kind == PenaltyKind::TotalDismissal,
+            start_period: pen.start_period,
+            start_clock_secs: pen.start_time.as_secs(),
+            secs_remaining,
+            served: secs_remaining == Some(0),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct PenaltyLists {
+    black: Vec<PenaltyInfo>,
+    white: Vec<PenaltyInfo>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct TimeoutsUsed {
+    black: u16,
+    white: u16,
+    allowed: u16,
+    counted_per_half: bool,
+}
+
+fn to_json<T: Serialize>(value: &T) -> (StatusCode, String) {
+    match serde_json::to_string(value) {
+        Ok(body) => (StatusCode::OK, body),
+        Err(e) => {
+            error!("Failed to serialize API response: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, String::new())
+        }
+    }
+}
+
+fn route(method: &Method, path: &str, data: &RestApiData) -> (StatusCode, String) {
+    let path = path.trim_end_matches('/');
+    let known_path = matches!(
+        path,
+        "/api/v1/snapshot"
+            | "/api/v1/config"
+            | "/api/v1/penalties"
+            | "/api/v1/timeouts"
+            | "/api/v1/games"
+            | "/api/v1/scorers"
+            | "/api/v1/scorers/session"
+    );
+
+    if !known_path {
+        return (StatusCode::NOT_FOUND, String::new());
+    }
+    if method != Method::GET {
+        return (StatusCode::METHOD_NOT_ALLOWED, String::new());
+    }
+
+    match path {
+        "/api/v1/snapshot" => to_json(&*data.snapshot.lock().unwrap()),
+        "/api/v1/config" => to_json(data.tm.lock().unwrap().config()),
+        "/api/v1/penalties" => {
+            let tm = data.tm.lock().unwrap();
+            let now = Instant::now();
+            let penalties = tm.get_penalties();
+            to_json(&PenaltyLists {
+                black: penalties
+                    .black
+                    .iter()
+                    .map(|pen| PenaltyInfo::new(pen, &tm, now))
+                    .collect(),
+                white: penalties
+                    .white
+                    .iter()
+                    .map(|pen| PenaltyInfo::new(pen, &tm, now))
+                    .collect(),
+            })
+        }
+        "/api/v1/timeouts" => {
+            let tm = data.tm.lock().unwrap();
+            let used = tm.timeouts_used();
+            to_json(&TimeoutsUsed {
+                black: used.black,
+                white: used.white,
+                allowed: tm.config().team_timeouts_per_half,
+                counted_per_half: tm.config().timeouts_counted_per_half,
+            })
+        }
+        "/api/v1/games" => to_json(&*data.completed_games.lock().unwrap()),
+        "/api/v1/scorers" => {
+            let (black, white) = {
+                let snapshot = data.snapshot.lock().unwrap();
+                (
+                    snapshot
+                        .b_team_name
+                        .clone()
+                        .unwrap_or_else(|| Color::Black.to_string()),
+                    snapshot
+                        .w_team_name
+                        .clone()
+                        .unwrap_or_else(|| Color::White.to_string()),
+                )
+            };
+            to_json(
+                &data
+                    .tm
+                    .lock()
+                    .unwrap()
+                    .game_stats()
+                    .player_stats(&black, &white),
+            )
+        }
+        "/api/v1/scorers/session" => to_json(&session_player_stats(
+            data.completed_games.lock().unwrap().iter(),
+        )),
+        _ => unreachable!(),
+    }
+}
+
+fn handle(req: Request<Body>, data: &RestApiData) -> Response<Body> {
+    let (status, body) = route(req.method(), req.uri().path(), data);
+    debug!("REST API: {} {} -> {status}", req.method(), req.uri());
+
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/json")
+        // Allow browser based dashboards served from elsewhere to use the API
+        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .body(Body::from(body))
+        .unwrap()
+}
+
+async fn serve(addr: SocketAddr, data: RestApiData) -> hyper::Result<()> {
+    let make_service = make_service_fn(move |_| {
+        let data = data.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let response = handle(req, &data);
+                async move { Ok::<_, Infallible>(response) }
+            }))
+        }
+    });
+
+    Server::try_bind(&addr)?.serve(make_service).await
+}
+
+/// Starts the HTTP server on `port`
+pub fn start(port: u16, data: RestApiData) {
+    info!("Starting REST API on port {port}");
+    let data_ = data.clone();
+    task::spawn(async move {
+        if let Err(e) = serve(SocketAddr::from(([0u16; 8], port)), data_).await {
+            error!("REST API server failed: {e}");
+        }
+    });
+
+    // On some OSs, we must separately listen on IPv4, but on other OSs that
+    // that isn't allowed, so we just try to listen on IPv4
+    task::spawn(async move {
+        if let Err(e) = serve(SocketAddr::from(([0u8; 4], port)), data).await {
+            debug!("Couldn't start separate IPv4 REST API server: {e}");
+        }
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{game_log::GameEvent, tournament_manager::BlackWhiteBundle};
+    use serde_json::{json, Value};
+    use tokio::time::Duration;
+    use uwh_common::{config::Game as GameConfig, game_snapshot::Color};
+
+    fn get(path: &str, data: &RestApiData) -> Value {
+        let (status, body) = route(&Method::GET, path, data);
+        assert_eq!(status, StatusCode::OK);
+        serde_json::from_str(&body).unwrap()
+    }
+
+    fn new_data() -> RestApiData {
+        let config = GameConfig {
+            team_timeouts_per_half: 2,
+            ..Default::default()
+        };
+        RestApiData::new(Arc::new(Mutex::new(TournamentManager::new(config))))
+    }
+
+    #[test]
+    fn test_routes() {
+        let data = new_data();
+
+        assert_eq!(
+            route(&Method::GET, "/api/v1/nothing", &data).0,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            route(&Method::POST, "/api/v1/snapshot", &data).0,
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+
+        let snapshot = GameSnapshot {
+            b_score: 3,
+            game_number: 14,
+            ..Default::default()
+        };
+        data.set_snapshot(snapshot.clone());
+        assert_eq!(
+            get("/api/v1/snapshot/", &data),
+            serde_json::to_value(&snapshot).unwrap()
+        );
+
+        assert_eq!(
+            get("/api/v1/config", &data),
+            serde_json::to_value(data.tm.lock().unwrap().config()).unwrap()
+        );
+
+        assert_eq!(get("/api/v1/games", &data), json!([]));
+        let log = GameLog {
+            game_number: 14,
+            b_score: 3,
+            w_score: 1,
+            black_team: None,
+            white_team: None,
+            stats: Default::default(),
+            events: Vec::<GameEvent>::new(),
+        };
+        data.add_completed_game(log.clone());
+        assert_eq!(
+            get("/api/v1/games", &data),
+            serde_json::to_value(vec![log]).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_penalties_and_timeouts() {
+        let data = new_data();
+        let now = Instant::now();
+        {
+            let mut tm = data.tm.lock().unwrap();
+            tm.start_play_now(now).unwrap();
+            tm.start_penalty(Color::Black, 4, PenaltyKind::from_secs(120), now)
+                .unwrap();
+            tm.start_penalty(Color::White, 9, PenaltyKind::TotalDismissal, now)
+                .unwrap();
+            tm.start_w_timeout(now).unwrap();
+        }
+
+        let penalties = get("/api/v1/penalties", &data);
+        let black = &penalties["black"][0];
+        assert_eq!(black["player_number"], 4);
+        assert_eq!(black["kind"], "2m");
+        assert_eq!(black["duration_secs"], 120);
+        assert_eq!(black["total_dismissal"], false);
+        assert_eq!(black["start_period"], "FirstHalf");
+        assert_eq!(black["served"], false);
+        // The timeout stopped the game clock, so no time has been served
+        assert_eq!(black["secs_remaining"], 120);
+
+        let white = &penalties["white"][0];
+        assert_eq!(white["player_number"], 9);
+        assert_eq!(white["kind"], "DSMS");
+        assert_eq!(white["duration_secs"], Value::Null);
+        assert_eq!(white["total_dismissal"], true);
+        assert_eq!(white["secs_remaining"], Value::Null);
+
+        assert_eq!(
+            get("/api/v1/timeouts", &data),
+            json!({"black": 0, "white": 1, "allowed": 2, "counted_per_half": true})
+        );
+        assert_eq!(
+            data.tm.lock().unwrap().timeouts_used(),
+            BlackWhiteBundle { black: 0, white: 1 }
+        );
+    }
+
+    #[test]
+    fn test_scorers() {
+        let data = new_data();
+        let now = Instant::now();
+        {
+            let mut tm = data.tm.lock().unwrap();
+            tm.start_play_now(now).unwrap();
+            tm.add_w_score(6, now);
+            tm.add_w_score(6, now);
+            tm.start_penalty(Color::Black, 2, PenaltyKind::from_secs(60), now)
+                .unwrap();
+        }
+
+        assert_eq!(
+            get("/api/v1/scorers", &data),
+            json!([
+                {"team": "White", "player_number": 6, "goals": 2, "penalties": 0,
+                    "penalty_secs": 0, "total_dismissals": 0},
+                {"team": "Black", "player_number": 2, "goals": 0, "penalties": 1,
+                    "penalty_secs": 60, "total_dismissals": 0},
+            ])
+        );
+
+        // The team names are used once they are known
+        data.set_snapshot(GameSnapshot {
+            w_team_name: Some("Orcas".to_string()),
+            ..Default::default()
+        });
+        let scorers = get("/api/v1/scorers", &data);
+        assert_eq!(scorers[0]["team"], "Orcas");
+        assert_eq!(scorers[1]["team"], "Black");
+
+        assert_eq!(get("/api/v1/scorers/session", &data), json!([]));
+        data.tm.lock().unwrap().reset_game(now);
+        let mut log = data.tm.lock().unwrap().take_completed_game_log().unwrap();
+        log.white_team = Some("Orcas".to_string());
+        data.add_completed_game(log);
+        let session = get("/api/v1/scorers/session", &data);
+        assert_eq!(session[0]["team"], "Orcas");
+        assert_eq!(session[0]["goals"], 2);
+        assert_eq!(session[1]["team"], "Game 1 Black");
+    }
+
+    #[tokio::test]
+    async fn test_server() {
+        const PORT: u16 = 12360;
+        let data = new_data();
+        data.set_snapshot(GameSnapshot {
+            w_score: 2,
+            ..Default::default()
+        });
+        start(PORT, data);
+
+        let url = format!("http://localhost:{PORT}/api/v1/snapshot");
+        let mut attempts = 0;
+        let resp = loop {
+            match reqwest::get(&url).await {
+                Ok(resp) => break resp,
+                Err(e) => {
+                    assert!(attempts < 20, "Couldn't connect to the REST API: {e}");
+                    attempts += 1;
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+            }
+        };
+
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        assert_eq!(
+            resp.headers()[reqwest::header::CONTENT_TYPE],
+            "application/json"
+        );
+        let snapshot: GameSnapshot = resp.json().await.unwrap();
+        assert_eq!(snapshot.w_score, 2);
+    }
+}
@@ -5,14 +5,15 @@ use std::{
     collections::HashMap,
     fmt::Debug,
     future::Future,
-    net::SocketAddr,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::Path,
     pin::Pin,
     task::{Context, Poll},
 };
 use thiserror::Error;
 use tokio::{
-    io::{self, AsyncWrite, AsyncWriteExt},
-    net::{TcpListener, TcpStream},
+    io::{self, AsyncRead, AsyncWrite, AsyncWriteExt},
+    net::{TcpListener, TcpStream, UdpSocket},
     select,
     sync::mpsc::{self, error::TrySendError},
     task::{self, JoinHandle},
@@ -21,11 +22,17 @@ use tokio::{
 use tokio_serial::{SerialPortBuilder, SerialPortBuilderExt, SerialStream};
 use uwh_common::game_snapshot::{EncodingError, GameSnapshot, GameSnapshotNoHeap};
 
+pub mod recording;
+mod websocket;
+
+use recording::Recorder;
+
 const TIMEOUT: Duration = Duration::from_millis(500);
 const SERIAL_SEND_SPACING: Duration = Duration::from_millis(100);
 const WORKER_CHANNEL_LEN: usize = 4;
 const FLASH_DURATION: Duration = Duration::from_millis(300); // Used by the simulator
 const FLASH_LENGTH: u8 = 3; // Number of transmit cycles to flash for (each cycle is 100ms)
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[derive(Debug)]
 pub struct UpdateSender {
@@ -35,7 +42,16 @@ pub struct UpdateSender {
 }
 
 impl UpdateSender {
-    pub fn new(initial: Vec<SerialPortBuilder>, binary_port: u16, json_port: u16) -> Self {
+    /// If `framed_serial` is set, the serial ports are sent the framed format that
+    /// `FrameDecoder` expects, otherwise the bare 20 byte `TransmittedData` encoding
+    pub fn new(
+        initial: Vec<SerialPortBuilder>,
+        framed_serial: bool,
+        binary_port: u16,
+        json_port: u16,
+        websocket_port: u16,
+        multicast: Option<SocketAddr>,
+    ) -> Self {
         let (tx, rx) = mpsc::channel(8);
 
         let initial = initial
@@ -43,9 +59,15 @@ impl UpdateSender {
             .map(|builder| builder.open_native_async().unwrap())
             .collect();
 
-        let server_join = task::spawn(Server::new(rx, initial).run_loop());
+        let server_join =
+            task::spawn(Server::new(rx, initial, framed_serial, multicast).run_loop());
 
-        let listener_join = task::spawn(listener_loop(tx.clone(), binary_port, json_port));
+        let listener_join = task::spawn(listener_loop(
+            tx.clone(),
+            binary_port,
+            json_port,
+            websocket_port,
+        ));
 
         Self {
             tx,
@@ -58,9 +80,12 @@ impl UpdateSender {
         &self,
         snapshot: GameSnapshot,
         white_on_right: bool,
-    ) -> Result<(), TrySendError<GameSnapshot>> {
+    ) -> Result<(), TrySendError<Box<GameSnapshot>>> {
         self.tx
-            .try_send(ServerMessage::NewSnapshot(snapshot, white_on_right))
+            .try_send(ServerMessage::NewSnapshot(
+                Box::new(snapshot),
+                white_on_right,
+            ))
             .map_err(|e| match e {
                 TrySendError::Full(ServerMessage::NewSnapshot(snapshot, _)) => {
                     TrySendError::Full(snapshot)
@@ -72,6 +97,15 @@ impl UpdateSender {
             })
     }
 
+    /// Starts recording every snapshot sent to a new file at `path`
+    pub fn record_to(&self, path: &Path) -> io::Result<()> {
+        let recorder = Recorder::new(path)?;
+        info!("Recording snapshots to {path:?}");
+        self.tx
+            .try_send(ServerMessage::StartRecording(Box::new(recorder)))
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))
+    }
+
     pub fn get_trigger_flash_fn(
         &self,
     ) -> impl Send + Fn() -> Result<(), TrySendError<ServerMessage>> {
@@ -121,15 +155,57 @@ async fn worker_loop<T: AsyncWrite + Debug + Unpin + Send>(
     }
 }
 
+/// Sends each message as a single datagram to `dest`, which is normally a multicast group
+async fn udp_worker_loop(
+    mut rx: mpsc::Receiver<Vec<u8>>,
+    dest: SocketAddr,
+) -> Result<(), WorkerError> {
+    let socket = match dest {
+        SocketAddr::V4(_) => UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?,
+        SocketAddr::V6(_) => UdpSocket::bind((Ipv6Addr::UNSPECIFIED, 0)).await?,
+    };
+    info!("Publishing snapshots to {dest}");
+
+    // Avoid filling the log when the network is down by only reporting the first failure
+    let mut failing = false;
+    loop {
+        let data = rx.recv().await.ok_or(WorkerError::ChannelClosed)?;
+
+        match socket.send_to(&data[..], dest).await {
+            Ok(_) => {
+                if failing {
+                    info!("Sending to {dest} is working again");
+                    failing = false;
+                }
+            }
+            Err(e) => {
+                if !failing {
+                    error!("Send to {dest} failed: {e:?}");
+                    failing = true;
+                }
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 enum SerialWorkerMessage {
     NewSnapshot(GameSnapshotNoHeap, bool),
     TriggerFlash,
 }
 
+fn encode_serial(data: &TransmittedData, framed: bool) -> Result<Vec<u8>, EncodingError> {
+    Ok(if framed {
+        data.encode_framed()?.to_vec()
+    } else {
+        data.encode()?.to_vec()
+    })
+}
+
 async fn serial_worker_loop(
     mut rx: mpsc::Receiver<SerialWorkerMessage>,
     mut write: SerialStream,
+    framed: bool,
 ) -> Result<(), WorkerError> {
     let msg = rx.recv().await.ok_or(WorkerError::ChannelClosed)?;
     let (snapshot, white_on_right) = match msg {
@@ -144,7 +220,7 @@ async fn serial_worker_loop(
         flash: false,
         white_on_right,
     };
-    let mut bytes = data.encode()?;
+    let mut bytes = encode_serial(&data, framed)?;
 
     let mut next_send = Instant::now() + SERIAL_SEND_SPACING;
     let mut counter = 0u8;
@@ -162,7 +238,7 @@ async fn serial_worker_loop(
                     counter += 1;
                     if counter >= FLASH_LENGTH {
                         data.flash = false;
-                        bytes = data.encode()?;
+                        bytes = encode_serial(&data, framed)?;
                     }
                 } else {
                     counter = 0;
@@ -173,11 +249,11 @@ async fn serial_worker_loop(
                     Some(SerialWorkerMessage::NewSnapshot(snapshot, white_on_right)) => {
                         data.snapshot = snapshot;
                         data.white_on_right = white_on_right;
-                        bytes = data.encode()?;
+                        bytes = encode_serial(&data, framed)?;
                     }
                     Some(SerialWorkerMessage::TriggerFlash) => {
                         data.flash = true;
-                        bytes = data.encode()?;
+                        bytes = encode_serial(&data, framed)?;
                     }
                     None => return Err(WorkerError::ChannelClosed),
                 }
@@ -190,12 +266,14 @@ async fn serial_worker_loop(
 pub enum SendType {
     Binary,
     Json,
+    WebSocket,
 }
 
 #[derive(Debug)]
 enum WorkerTx {
     Binary(mpsc::Sender<Vec<u8>>),
     Json(mpsc::Sender<Vec<u8>>),
+    WebSocket(mpsc::Sender<Vec<u8>>),
     Serial(mpsc::Sender<SerialWorkerMessage>),
 }
 
@@ -220,6 +298,13 @@ impl WorkerHandle {
         }
     }
 
+    fn new_websocket(tx: mpsc::Sender<Vec<u8>>, join: JoinHandle<Result<(), WorkerError>>) -> Self {
+        WorkerHandle {
+            tx: WorkerTx::WebSocket(tx),
+            join,
+        }
+    }
+
     fn new_serial(
         tx: mpsc::Sender<SerialWorkerMessage>,
         join: JoinHandle<Result<(), WorkerError>>,
@@ -238,6 +323,10 @@ impl WorkerHandle {
         matches!(self.tx, WorkerTx::Json(_))
     }
 
+    fn is_websocket(&self) -> bool {
+        matches!(self.tx, WorkerTx::WebSocket(_))
+    }
+
     fn is_serial(&self) -> bool {
         matches!(self.tx, WorkerTx::Serial(_))
     }
@@ -246,12 +335,14 @@ impl WorkerHandle {
         &self,
         binary: &[u8],
         json: &[u8],
+        websocket: &[u8],
         snapshot: &GameSnapshotNoHeap,
         white_on_right: bool,
     ) -> Result<(), Box<dyn std::error::Error>> {
         match self.tx {
             WorkerTx::Binary(ref tx) => tx.try_send(Vec::from(binary))?,
             WorkerTx::Json(ref tx) => tx.try_send(Vec::from(json))?,
+            WorkerTx::WebSocket(ref tx) => tx.try_send(Vec::from(websocket))?,
             WorkerTx::Serial(ref tx) => tx.try_send(SerialWorkerMessage::NewSnapshot(
                 snapshot.clone(),
                 white_on_right,
@@ -264,8 +355,10 @@ impl WorkerHandle {
 #[derive(Debug)]
 pub enum ServerMessage {
     NewConnection(SendType, TcpStream),
-    NewSnapshot(GameSnapshot, bool),
+    // Boxed to keep the channel's messages small
+    NewSnapshot(Box<GameSnapshot>, bool),
     TriggerFlash,
+    StartRecording(Box<Recorder>),
     Stop,
 }
 
@@ -276,48 +369,67 @@ struct Server {
     rx: mpsc::Receiver<ServerMessage>,
     has_binary: bool,
     has_json: bool,
+    has_websocket: bool,
     snapshot: GameSnapshotNoHeap,
     white_on_right: bool,
     flash: bool,
     binary: Vec<u8>,
     json: Vec<u8>,
+    websocket: Vec<u8>,
+    recorder: Option<Box<Recorder>>,
 }
 
 impl Server {
-    pub fn new(rx: mpsc::Receiver<ServerMessage>, initial: Vec<SerialStream>) -> Self {
+    pub fn new(
+        rx: mpsc::Receiver<ServerMessage>,
+        initial: Vec<SerialStream>,
+        framed_serial: bool,
+        multicast: Option<SocketAddr>,
+    ) -> Self {
         let mut server = Server {
             next_id: 0,
             senders: HashMap::new(),
             rx,
             has_binary: false,
             has_json: false,
+            has_websocket: false,
             snapshot: Default::default(),
             white_on_right: false,
             flash: false,
             binary: Vec::new(),
             json: Vec::new(),
+            websocket: Vec::new(),
+            recorder: None,
         };
 
         for stream in initial {
-            server.add_serial_sender(stream);
+            server.add_serial_sender(stream, framed_serial);
+        }
+
+        if let Some(dest) = multicast {
+            server.add_udp_sender(dest);
         }
 
         server
     }
 
-    fn add_sender<T: 'static + AsyncWrite + Debug + Unpin + Send>(
+    fn add_sender<T: 'static + AsyncRead + AsyncWrite + Debug + Unpin + Send>(
         &mut self,
         send_type: SendType,
         sender: T,
     ) {
         let (tx, rx) = mpsc::channel(WORKER_CHANNEL_LEN);
-        let join = task::spawn(worker_loop(rx, sender));
+        let join = match send_type {
+            SendType::Binary | SendType::Json => task::spawn(worker_loop(rx, sender)),
+            SendType::WebSocket => task::spawn(websocket::worker_loop(rx, sender)),
+        };
 
         self.senders.insert(
             self.next_id,
             match send_type {
                 SendType::Binary => WorkerHandle::new_binary(tx, join),
                 SendType::Json => WorkerHandle::new_json(tx, join),
+                SendType::WebSocket => WorkerHandle::new_websocket(tx, join),
             },
         );
         self.next_id += 1;
@@ -325,12 +437,13 @@ impl Server {
         match send_type {
             SendType::Binary => self.has_binary = true,
             SendType::Json => self.has_json = true,
+            SendType::WebSocket => self.has_websocket = true,
         };
     }
 
-    fn add_serial_sender(&mut self, sender: SerialStream) {
+    fn add_serial_sender(&mut self, sender: SerialStream, framed: bool) {
         let (tx, rx) = mpsc::channel(WORKER_CHANNEL_LEN);
-        let join = task::spawn(serial_worker_loop(rx, sender));
+        let join = task::spawn(serial_worker_loop(rx, sender, framed));
 
         self.senders
             .insert(self.next_id, WorkerHandle::new_serial(tx, join));
@@ -339,14 +452,38 @@ impl Server {
         self.has_binary = true;
     }
 
+    fn add_udp_sender(&mut self, dest: SocketAddr) {
+        let (tx, rx) = mpsc::channel(WORKER_CHANNEL_LEN);
+        let join = task::spawn(udp_worker_loop(rx, dest));
+
+        self.senders
+            .insert(self.next_id, WorkerHandle::new_binary(tx, join));
+        self.next_id += 1;
+
+        self.has_binary = true;
+    }
+
     fn check_types(&mut self) {
         self.has_binary = self.senders.iter().any(|(_, handle)| handle.is_binary());
         self.has_json = self.senders.iter().any(|(_, handle)| handle.is_json());
+        self.has_websocket = self.senders.iter().any(|(_, handle)| handle.is_websocket());
     }
 
     fn encode(&mut self, new_snapshot: GameSnapshot) {
+        let json = if self.has_json || self.has_websocket {
+            serde_json::to_string(&new_snapshot).unwrap()
+        } else {
+            String::new()
+        };
+
+        self.websocket = if self.has_websocket {
+            websocket::encode_text_frame(json.as_bytes())
+        } else {
+            Vec::new()
+        };
+
         self.json = if self.has_json {
-            (serde_json::to_string(&new_snapshot).unwrap() + "\n").into_bytes()
+            (json + "\n").into_bytes()
         } else {
             Vec::new()
         };
@@ -384,6 +521,7 @@ impl Server {
             if let Err(e) = handle.send(
                 &self.binary,
                 &self.json,
+                &self.websocket,
                 &self.snapshot,
                 self.white_on_right,
             ) {
@@ -413,8 +551,14 @@ impl Server {
                             self.check_types();
                         }
                         Some(ServerMessage::NewSnapshot(snapshot, white_on_right)) => {
+                            if let Some(ref mut recorder) = self.recorder {
+                                if let Err(e) = recorder.record(&snapshot, white_on_right) {
+                                    error!("Failed to record snapshot, stopping recording: {e}");
+                                    self.recorder = None;
+                                }
+                            }
                             self.white_on_right = white_on_right;
-                            self.encode(snapshot);
+                            self.encode(*snapshot);
                             self.send_to_workers(false);
                         }
                         Some(ServerMessage::TriggerFlash) => {
@@ -430,6 +574,9 @@ impl Server {
                                 }
                             }
                         }
+                        Some(ServerMessage::StartRecording(recorder)) => {
+                            self.recorder = Some(recorder);
+                        }
                         Some(ServerMessage::Stop) => {
                             break;
                         }
@@ -451,15 +598,60 @@ impl Drop for Server {
     }
 }
 
-async fn listener_loop(tx: mpsc::Sender<ServerMessage>, binary_port: u16, json_port: u16) {
-    info!("Starting Listeners for JSON (port {json_port}) and binary (port {binary_port})");
+/// WebSocket clients have to complete the opening handshake before they can be handed to the
+/// server, which is done in a separate task so that a slow client can't hold up the listeners
+fn start_websocket_handshake(
+    tx: mpsc::Sender<ServerMessage>,
+    mut stream: TcpStream,
+    addr: SocketAddr,
+) {
+    task::spawn(async move {
+        match timeout(HANDSHAKE_TIMEOUT, websocket::accept(&mut stream)).await {
+            Ok(Ok(())) => {
+                info!("New WebSocket connection from {addr:?}");
+                if tx
+                    .send(ServerMessage::NewConnection(SendType::WebSocket, stream))
+                    .await
+                    .is_err()
+                {
+                    error!("Server stopped before WebSocket connection from {addr:?} was added");
+                }
+            }
+            Ok(Err(e)) => warn!("WebSocket handshake with {addr:?} failed: {e}"),
+            Err(_) => warn!("WebSocket handshake with {addr:?} timed out"),
+        }
+    });
+}
+
+async fn listener_loop(
+    tx: mpsc::Sender<ServerMessage>,
+    binary_port: u16,
+    json_port: u16,
+    websocket_port: u16,
+) {
+    info!(
+        "Starting Listeners for JSON (port {json_port}), binary (port {binary_port}) and WebSocket (port {websocket_port})"
+    );
     let binary_listener_v6 = TcpListener::bind(("::", binary_port)).await.unwrap();
     let json_listener_v6 = TcpListener::bind(("::", json_port)).await.unwrap();
+    // WebSocket clients are optional, so failing to listen for them mustn't stop the other feeds
+    let websocket_listener_v6 = match TcpListener::bind(("::", websocket_port)).await {
+        Ok(l) => Some(l),
+        Err(e) => {
+            error!("Failed to start WebSocket listener, WebSocket clients won't be served: {e}");
+            None
+        }
+    };
 
     // On some OSs, we must separately listen on IPv4, but on other OSs that
     // that isn't allowed, so we just try to listen on IPv4
     let binary_listener_v4 = TcpListener::bind(("0.0.0.0", binary_port)).await.ok();
     let json_listener_v4 = TcpListener::bind(("0.0.0.0", json_port)).await.ok();
+    let websocket_listener_v4 = if websocket_listener_v6.is_some() {
+        TcpListener::bind(("0.0.0.0", websocket_port)).await.ok()
+    } else {
+        None
+    };
 
     info!("Listeners started");
 
@@ -477,6 +669,18 @@ async fn listener_loop(tx: mpsc::Sender<ServerMessage>, binary_port: u16, json_p
             } else {
                 Box::pin(iced::futures::future::pending())
             };
+        let websocket_v4_future: Pin<Box<dyn Future<Output = ListenResult> + Send>> =
+            if let Some(listener) = websocket_listener_v4.as_ref() {
+                Box::pin(listener.accept())
+            } else {
+                Box::pin(iced::futures::future::pending())
+            };
+        let websocket_v6_future: Pin<Box<dyn Future<Output = ListenResult> + Send>> =
+            if let Some(listener) = websocket_listener_v6.as_ref() {
+                Box::pin(listener.accept())
+            } else {
+                Box::pin(iced::futures::future::pending())
+            };
 
         select! {
             conn = binary_v4_future => {
@@ -523,6 +727,18 @@ async fn listener_loop(tx: mpsc::Sender<ServerMessage>, binary_port: u16, json_p
                     Err(addr) => error!("New JSON connection to {addr:?} failed"),
                 }
             }
+            conn = websocket_v4_future => {
+                match conn {
+                    Ok((stream, addr)) => start_websocket_handshake(tx.clone(), stream, addr),
+                    Err(addr) => error!("New WebSocket connection to {addr:?} failed"),
+                }
+            }
+            conn = websocket_v6_future => {
+                match conn {
+                    Ok((stream, addr)) => start_websocket_handshake(tx.clone(), stream, addr),
+                    Err(addr) => error!("New WebSocket connection to {addr:?} failed"),
+                }
+            }
         }
     }
 }
@@ -553,11 +769,13 @@ mod test {
 
     const BINARY_PORT: u16 = 12345;
     const JSON_PORT: u16 = 12346;
+    const WEBSOCKET_PORT: u16 = 12347;
     const MAX_CONN_FAILS: usize = 20;
 
     #[tokio::test]
     async fn test_update_sender() {
-        let update_sender = UpdateSender::new(vec![], BINARY_PORT, JSON_PORT);
+        let update_sender =
+            UpdateSender::new(vec![], false, BINARY_PORT, JSON_PORT, WEBSOCKET_PORT, None);
 
         let mut binary_conn;
         let mut fail_count = 0;
@@ -650,6 +868,9 @@ mod test {
             tournament_id: 1,
             recent_goal: None,
             next_period_len_secs: Some(180),
+            b_team_name: Some("Sharks".to_string()),
+            w_team_name: None,
+            period_number: None,
         };
 
         let json_expected = serde_json::to_string(&snapshot).unwrap().into_bytes();
@@ -693,4 +914,244 @@ mod test {
         assert_eq!(expected_binary_bytes, binary_read_so_far);
         assert_eq!(binary_expected, binary_result);
     }
+
+    #[tokio::test]
+    async fn test_websocket_port_in_use() {
+        let taken = TcpListener::bind(("::", 0)).await.unwrap();
+        let websocket_port = taken.local_addr().unwrap().port();
+        let update_sender = UpdateSender::new(vec![], false, 12357, 12358, websocket_port, None);
+
+        // The binary feed keeps working without the WebSocket listener
+        let binary_conn;
+        let mut fail_count = 0;
+        loop {
+            match TcpStream::connect(("localhost", 12357)).await {
+                Ok(stream) => {
+                    binary_conn = stream;
+                    break;
+                }
+                Err(e) => {
+                    if e.kind() == ErrorKind::ConnectionRefused {
+                        assert_le!(fail_count, MAX_CONN_FAILS);
+                        fail_count += 1;
+                    } else {
+                        panic!("Unexpected connection error: {e:?}");
+                    }
+                }
+            };
+        }
+
+        let mut first = [0u8; 1];
+        loop {
+            update_sender
+                .send_snapshot(GameSnapshot::default(), false)
+                .unwrap();
+            if let Ok(peeked) =
+                timeout(Duration::from_millis(50), binary_conn.peek(&mut first)).await
+            {
+                assert_eq!(peeked.unwrap(), 1);
+                break;
+            }
+        }
+    }
+
+    async fn read_ws_frame(conn: &mut TcpStream) -> (u8, Vec<u8>) {
+        let mut header = [0u8; 2];
+        conn.read_exact(&mut header).await.unwrap();
+        assert_eq!(header[1] & 0x80, 0, "Server frames must not be masked");
+        let len = match header[1] & 0x7F {
+            126 => conn.read_u16().await.unwrap() as usize,
+            127 => conn.read_u64().await.unwrap() as usize,
+            len => len as usize,
+        };
+        let mut payload = vec![0u8; len];
+        conn.read_exact(&mut payload).await.unwrap();
+        (header[0], payload)
+    }
+
+    #[tokio::test]
+    async fn test_websocket_sender() {
+        let update_sender = UpdateSender::new(vec![], false, 12348, 12349, 12350, None);
+
+        let mut ws_conn;
+        let mut fail_count = 0;
+        loop {
+            match TcpStream::connect(("localhost", 12350)).await {
+                Ok(stream) => {
+                    ws_conn = stream;
+                    break;
+                }
+                Err(e) => {
+                    if e.kind() == ErrorKind::ConnectionRefused {
+                        assert_le!(fail_count, MAX_CONN_FAILS);
+                        fail_count += 1;
+                    } else {
+                        panic!("Unexpected connection error: {e:?}");
+                    }
+                }
+            };
+        }
+
+        ws_conn
+            .write_all(
+                b"GET /snapshots HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\n\
+                Connection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+                Sec-WebSocket-Version: 13\r\n\r\n",
+            )
+            .await
+            .unwrap();
+
+        let mut response = Vec::new();
+        while !response.ends_with(b"\r\n\r\n") {
+            response.push(ws_conn.read_u8().await.unwrap());
+        }
+        let response = String::from_utf8(response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 101 "));
+        assert!(response.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n"));
+
+        let snapshot = GameSnapshot {
+            current_period: GamePeriod::SecondHalf,
+            secs_in_period: 201,
+            b_score: 4,
+            w_score: 1,
+            ..Default::default()
+        };
+        let json_expected = serde_json::to_string(&snapshot).unwrap().into_bytes();
+
+        // The connection is handed to the server after the handshake completes, so keep sending
+        // until the first snapshot arrives
+        let mut header = [0u8; 1];
+        loop {
+            update_sender
+                .send_snapshot(snapshot.clone(), false)
+                .unwrap();
+            if let Ok(peeked) = timeout(Duration::from_millis(50), ws_conn.peek(&mut header)).await
+            {
+                assert_eq!(peeked.unwrap(), 1);
+                break;
+            }
+        }
+
+        let (first, payload) = read_ws_frame(&mut ws_conn).await;
+        assert_eq!(first, 0x81);
+        assert_eq!(payload, json_expected);
+
+        // A masked ping with payload "hi" gets a pong, possibly after more snapshots
+        ws_conn
+            .write_all(&[0x89, 0x82, 1, 2, 3, 4, b'h' ^ 1, b'i' ^ 2])
+            .await
+            .unwrap();
+        loop {
+            match read_ws_frame(&mut ws_conn).await {
+                (0x81, payload) => assert_eq!(payload, json_expected),
+                (first, payload) => {
+                    assert_eq!(first, 0x8A);
+                    assert_eq!(payload, b"hi");
+                    break;
+                }
+            }
+        }
+
+        // A close gets a close in reply, then the connection ends
+        ws_conn.write_all(&[0x88, 0x80, 1, 2, 3, 4]).await.unwrap();
+        loop {
+            match read_ws_frame(&mut ws_conn).await {
+                (0x81, _) => {}
+                (first, _) => {
+                    assert_eq!(first, 0x88);
+                    break;
+                }
+            }
+        }
+        assert_eq!(ws_conn.read(&mut header).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_udp_sender() {
+        let receiver = UdpSocket::bind(("127.0.0.1", 0)).await.unwrap();
+        let update_sender = UpdateSender::new(
+            vec![],
+            false,
+            12351,
+            12352,
+            12353,
+            Some(receiver.local_addr().unwrap()),
+        );
+
+        let snapshot = GameSnapshot {
+            current_period: GamePeriod::HalfTime,
+            secs_in_period: 95,
+            b_score: 1,
+            w_score: 1,
+            ..Default::default()
+        };
+        let expected = Vec::from(
+            TransmittedData {
+                white_on_right: true,
+                flash: false,
+                snapshot: snapshot.clone().into(),
+            }
+            .encode()
+            .unwrap(),
+        );
+
+        update_sender.send_snapshot(snapshot, true).unwrap();
+
+        let mut buf = [0u8; 1024];
+        let len = timeout(Duration::from_secs(5), receiver.recv(&mut buf))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(buf[..len], expected[..]);
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let receiver = UdpSocket::bind(("127.0.0.1", 0)).await.unwrap();
+        // Nothing connects to the listeners, so let the OS pick their ports
+        let update_sender =
+            UpdateSender::new(vec![], false, 0, 0, 0, Some(receiver.local_addr().unwrap()));
+        let path = std::env::temp_dir().join(format!("refbox-replay-test-{}", std::process::id()));
+        update_sender.record_to(&path).unwrap();
+
+        let records: Vec<_> = [(0, 3, false), (400, 4, true)]
+            .into_iter()
+            .map(
+                |(millis, b_score, white_on_right)| recording::RecordedSnapshot {
+                    millis,
+                    white_on_right,
+                    snapshot: GameSnapshot {
+                        b_score,
+                        ..Default::default()
+                    },
+                },
+            )
+            .collect();
+
+        // At 4x speed, the second snapshot should be sent 100ms after the first
+        let start = Instant::now();
+        recording::replay(&records, &update_sender, 4.0).await;
+        assert_ge!(start.elapsed(), Duration::from_millis(100));
+        assert_lt!(start.elapsed(), Duration::from_millis(400));
+
+        let mut buf = [0u8; 1024];
+        for record in records.iter() {
+            let len = timeout(Duration::from_secs(5), receiver.recv(&mut buf))
+                .await
+                .unwrap()
+                .unwrap();
+            let data = TransmittedData::decode(&buf[..len].try_into().unwrap()).unwrap();
+            assert_eq!(data.snapshot.b_score, record.snapshot.b_score);
+            assert_eq!(data.white_on_right, record.white_on_right);
+        }
+
+        // The replayed snapshots were themselves recorded
+        drop(update_sender);
+        let recorded = recording::read_recording(&path).unwrap();
+        assert_eq!(
+            recorded.iter().map(|r| &r.snapshot).collect::<Vec<_>>(),
+            records.iter().map(|r| &r.snapshot).collect::<Vec<_>>()
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
 }
@@ -0,0 +1,96 @@
+//! Recording of the snapshots sent by the refbox, and replaying of those recordings. The file
+//! format is in `uwh_common::recording`.
+
+use super::UpdateSender;
+use log::*;
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+use tokio::time::{sleep_until, Duration, Instant};
+use uwh_common::game_snapshot::GameSnapshot;
+pub use uwh_common::recording::{read_recording, RecordedSnapshot};
+
+#[derive(Debug)]
+pub struct Recorder {
+    file: BufWriter<File>,
+    start: Instant,
+}
+
+impl Recorder {
+    /// Creates a new recording at `path`, replacing any existing file
+    pub fn new(path: &Path) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(Self {
+            file: BufWriter::new(File::create(path)?),
+            start: Instant::now(),
+        })
+    }
+
+    pub fn record(&mut self, snapshot: &GameSnapshot, white_on_right: bool) -> io::Result<()> {
+        RecordedSnapshot {
+            millis: self.start.elapsed().as_millis() as u64,
+            white_on_right,
+            snapshot: snapshot.clone(),
+        }
+        .write_line(&mut self.file)?;
+        // Flush every time so that nothing is lost if the refbox is killed
+        self.file.flush()
+    }
+}
+
+/// Sends each of the `records` to `sender` at the time it was originally sent. The replay runs
+/// `speed` times faster than the original.
+pub async fn replay(records: &[RecordedSnapshot], sender: &UpdateSender, speed: f64) {
+    let start = Instant::now();
+    for (i, record) in records.iter().enumerate() {
+        let offset = Duration::from_millis(record.millis).div_f64(speed);
+        sleep_until(start + offset).await;
+        // The app sends snapshots at least once a second, so any dropped snapshot will soon
+        // be followed by another
+        if let Err(e) = sender.send_snapshot(record.snapshot.clone(), record.white_on_right) {
+            warn!("Failed to send snapshot {i} of the recording: {e:?}");
+        }
+    }
+    info!("Replay finished, sent {} snapshots", records.len());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env::temp_dir;
+
+    #[tokio::test]
+    async fn test_record_and_read() {
+        let path = temp_dir().join(format!("refbox-recording-test-{}", std::process::id()));
+
+        let first = GameSnapshot {
+            b_score: 1,
+            ..Default::default()
+        };
+        let second = GameSnapshot {
+            w_score: 3,
+            secs_in_period: 412,
+            ..Default::default()
+        };
+
+        let mut recorder = Recorder::new(&path).unwrap();
+        recorder.record(&first, false).unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        recorder.record(&second, true).unwrap();
+        drop(recorder);
+
+        let records = read_recording(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].snapshot, first);
+        assert!(!records[0].white_on_right);
+        assert_eq!(records[1].snapshot, second);
+        assert!(records[1].white_on_right);
+        assert!(records[1].millis >= records[0].millis + 20);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
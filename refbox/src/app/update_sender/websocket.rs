@@ -0,0 +1,321 @@
+//! A minimal WebSocket (RFC 6455) server side, just enough to push snapshots to browsers
+
+use super::{WorkerError, TIMEOUT};
+use log::*;
+use std::fmt::Debug;
+use thiserror::Error;
+use tokio::{
+    io::{self, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+    net::TcpStream,
+    select,
+    sync::mpsc,
+    time::timeout,
+};
+
+const ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+const MAX_HEADER_LINES: usize = 64;
+const MAX_HEADER_LEN: usize = 8192;
+/// Clients only ever need to send us control frames, so anything larger than this is refused
+const MAX_CLIENT_PAYLOAD: u64 = 4096;
+
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+#[derive(Debug, Error)]
+pub(super) enum HandshakeError {
+    #[error("The request was not a valid WebSocket upgrade request")]
+    BadRequest,
+    #[error("The request headers were too long")]
+    TooLong,
+    #[error(transparent)]
+    IoError(#[from] io::Error),
+}
+
+#[derive(Debug, PartialEq, Eq, Error)]
+pub(super) enum FrameError {
+    #[error("Client frames must be masked")]
+    Unmasked,
+    #[error("Frame payload too large: {0} bytes")]
+    TooLarge(u64),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(super) struct Frame {
+    pub opcode: u8,
+    pub payload: Vec<u8>,
+}
+
+/// Computes the value of the `Sec-WebSocket-Accept` header for the client's `Sec-WebSocket-Key`
+pub(super) fn accept_key(key: &str) -> String {
+    let mut hasher = sha1_smol::Sha1::new();
+    hasher.update(key.trim().as_bytes());
+    hasher.update(ACCEPT_GUID.as_bytes());
+    base64::encode(hasher.digest().bytes())
+}
+
+/// Reads the client's HTTP upgrade request and completes the opening handshake
+pub(super) async fn accept(stream: &mut TcpStream) -> Result<(), HandshakeError> {
+    let mut reader = BufReader::new(&mut *stream);
+
+    let mut key = None;
+    let mut upgrade = false;
+    let mut total_len = 0;
+    let mut line = String::new();
+
+    reader.read_line(&mut line).await?;
+    if !line.starts_with("GET ") {
+        return Err(HandshakeError::BadRequest);
+    }
+
+    for _ in 0..MAX_HEADER_LINES {
+        line.clear();
+        total_len += reader.read_line(&mut line).await?;
+        if total_len > MAX_HEADER_LEN {
+            return Err(HandshakeError::TooLong);
+        }
+
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            match name.trim().to_ascii_lowercase().as_str() {
+                "upgrade" => upgrade = value.trim().eq_ignore_ascii_case("websocket"),
+                "sec-websocket-key" => key = Some(value.trim().to_string()),
+                _ => {}
+            }
+        }
+    }
+
+    // A well behaved client won't send anything until it gets the response, so nothing of value
+    // is lost when the reader is dropped
+    if !reader.buffer().is_empty() {
+        return Err(HandshakeError::BadRequest);
+    }
+
+    let key = match key {
+        Some(key) if upgrade => key,
+        _ => {
+            stream
+                .write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n")
+                .await?;
+            return Err(HandshakeError::BadRequest);
+        }
+    };
+
+    let response = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(&key)
+    );
+    stream.write_all(response.as_bytes()).await?;
+    Ok(())
+}
+
+/// Builds an unmasked, unfragmented frame, as sent by a server
+pub(super) fn encode_frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(payload.len() + 10);
+    frame.push(0x80 | opcode);
+    match payload.len() {
+        len @ 0..=125 => frame.push(len as u8),
+        len @ 126..=0xFFFF => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+pub(super) fn encode_text_frame(text: &[u8]) -> Vec<u8> {
+    encode_frame(OPCODE_TEXT, text)
+}
+
+/// Tries to parse one client frame from the start of `buf`. Returns the frame and the number of
+/// bytes it used, or `None` if `buf` doesn't yet hold a complete frame.
+pub(super) fn parse_frame(buf: &[u8]) -> Result<Option<(Frame, usize)>, FrameError> {
+    if buf.len() < 2 {
+        return Ok(None);
+    }
+
+    let opcode = buf[0] & 0x0F;
+    if buf[1] & 0x80 == 0 {
+        return Err(FrameError::Unmasked);
+    }
+
+    let (len, mut pos) = match buf[1] & 0x7F {
+        126 => {
+            if buf.len() < 4 {
+                return Ok(None);
+            }
+            (u16::from_be_bytes([buf[2], buf[3]]) as u64, 4)
+        }
+        127 => {
+            if buf.len() < 10 {
+                return Ok(None);
+            }
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&buf[2..10]);
+            (u64::from_be_bytes(bytes), 10)
+        }
+        len => (len as u64, 2),
+    };
+
+    if len > MAX_CLIENT_PAYLOAD {
+        return Err(FrameError::TooLarge(len));
+    }
+    let len = len as usize;
+
+    if buf.len() < pos + 4 + len {
+        return Ok(None);
+    }
+    let mask = [buf[pos], buf[pos + 1], buf[pos + 2], buf[pos + 3]];
+    pos += 4;
+
+    let payload = buf[pos..pos + len]
+        .iter()
+        .zip(mask.iter().cycle())
+        .map(|(byte, mask)| byte ^ mask)
+        .collect();
+
+    Ok(Some((Frame { opcode, payload }, pos + len)))
+}
+
+/// Sends each already encoded frame received on `rx` to the client, while answering the client's
+/// pings and closing the connection when the client asks to
+pub(super) async fn worker_loop<T: AsyncRead + AsyncWrite + Debug + Unpin + Send>(
+    mut rx: mpsc::Receiver<Vec<u8>>,
+    stream: T,
+) -> Result<(), WorkerError> {
+    let peer = format!("{stream:?}");
+    let (mut read, mut write) = io::split(stream);
+    let mut buf = Vec::new();
+    let mut read_buf = [0u8; 512];
+
+    loop {
+        select! {
+            data = rx.recv() => {
+                let data = data.ok_or(WorkerError::ChannelClosed)?;
+                match timeout(TIMEOUT, write.write_all(&data[..])).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => {
+                        error!("Send to WebSocket {peer} failed: {e:?}");
+                        Err(e)?;
+                    }
+                    Err(_) => {
+                        // A partly sent frame can't be recovered from, so give up on this client
+                        warn!("Send to WebSocket {peer} timed out, disconnecting");
+                        Err(io::Error::from(io::ErrorKind::TimedOut))?;
+                    }
+                }
+            }
+            bytes = read.read(&mut read_buf) => {
+                let bytes = bytes?;
+                if bytes == 0 {
+                    info!("WebSocket {peer} disconnected");
+                    return Ok(());
+                }
+                buf.extend_from_slice(&read_buf[..bytes]);
+
+                while let Some((frame, used)) = parse_frame(&buf).map_err(|e| {
+                    io::Error::new(io::ErrorKind::InvalidData, e)
+                })? {
+                    buf.drain(..used);
+                    match frame.opcode {
+                        OPCODE_PING => {
+                            write.write_all(&encode_frame(OPCODE_PONG, &frame.payload)).await?;
+                        }
+                        OPCODE_CLOSE => {
+                            info!("WebSocket {peer} closed the connection");
+                            write.write_all(&encode_frame(OPCODE_CLOSE, &frame.payload)).await?;
+                            return Ok(());
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn mask_frame(first: u8, payload: &[u8], mask: [u8; 4]) -> Vec<u8> {
+        let mut frame = vec![first, 0x80 | payload.len() as u8];
+        frame.extend_from_slice(&mask);
+        frame.extend(payload.iter().zip(mask.iter().cycle()).map(|(b, m)| b ^ m));
+        frame
+    }
+
+    #[test]
+    fn test_accept_key() {
+        // The example from RFC 6455 section 1.3
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    #[test]
+    fn test_encode_frame() {
+        assert_eq!(encode_text_frame(b"Hello"), b"\x81\x05Hello".to_vec());
+
+        let long = vec![b'a'; 300];
+        let frame = encode_text_frame(&long);
+        assert_eq!(frame[..4], [0x81, 126, 0x01, 0x2C]);
+        assert_eq!(frame[4..], long[..]);
+
+        let longer = vec![b'a'; 70_000];
+        let frame = encode_text_frame(&longer);
+        assert_eq!(frame[..10], [0x81, 127, 0, 0, 0, 0, 0, 0x01, 0x11, 0x70]);
+        assert_eq!(frame.len(), 70_010);
+    }
+
+    #[test]
+    fn test_parse_frame() {
+        // The masked example from RFC 6455 section 5.7
+        let frame = [
+            0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58,
+        ];
+        assert_eq!(
+            parse_frame(&frame),
+            Ok(Some((
+                Frame {
+                    opcode: OPCODE_TEXT,
+                    payload: b"Hello".to_vec()
+                },
+                frame.len()
+            )))
+        );
+
+        // Incomplete frames need more data
+        for len in 0..frame.len() {
+            assert_eq!(parse_frame(&frame[..len]), Ok(None));
+        }
+
+        // Trailing data is left for the next frame
+        let mut two = mask_frame(0x89, b"ping", [1, 2, 3, 4]);
+        let first_len = two.len();
+        two.extend(mask_frame(0x88, b"", [5, 6, 7, 8]));
+        let (ping, used) = parse_frame(&two).unwrap().unwrap();
+        assert_eq!(ping.opcode, OPCODE_PING);
+        assert_eq!(ping.payload, b"ping");
+        assert_eq!(used, first_len);
+        let (close, used) = parse_frame(&two[used..]).unwrap().unwrap();
+        assert_eq!(close.opcode, OPCODE_CLOSE);
+        assert_eq!(used, two.len() - first_len);
+
+        assert_eq!(parse_frame(b"\x81\x05Hello"), Err(FrameError::Unmasked));
+        assert_eq!(
+            parse_frame(&[0x82, 0xFF, 0, 0, 0, 0, 0, 1, 0, 0]),
+            Err(FrameError::TooLarge(65536))
+        );
+    }
+}
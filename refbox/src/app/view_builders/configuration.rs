@@ -16,6 +16,7 @@ use uwh_common::{config::Game as GameConfig, game_snapshot::GameSnapshot, uwhsco
 
 const NO_SELECTION_TXT: &str = "None Selected";
 const LOADING_TXT: &str = "Loading...";
+const CUSTOM_PERIODS_TXT: &str = "CUSTOM";
 
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub(in super::super) struct EditableSettings {
@@ -40,28 +41,47 @@ where
     }
 }
 
-impl Cyclable for BuzzerSound {
-    fn next(&self) -> Self {
-        match self {
-            Self::Buzz => Self::Whoop,
-            Self::Whoop => Self::Crazy,
-            Self::Crazy => Self::DeDeDu,
-            Self::DeDeDu => Self::TwoTone,
-            Self::TwoTone => Self::Buzz,
+/// Like `Cyclable`, but the custom sounds that have been loaded are included in the cycle
+pub(in super::super) trait CyclableSound
+where
+    Self: Sized,
+{
+    fn next(&self, custom_sounds: &[String]) -> Self;
+
+    fn cycle(&mut self, custom_sounds: &[String]) {
+        *self = self.next(custom_sounds);
+    }
+}
+
+/// The built in sounds, followed by the custom sounds
+fn all_sounds(custom_sounds: &[String]) -> impl Iterator<Item = BuzzerSound> + '_ {
+    BuzzerSound::BUILT_IN
+        .into_iter()
+        .chain(custom_sounds.iter().cloned().map(BuzzerSound::Custom))
+}
+
+impl CyclableSound for BuzzerSound {
+    fn next(&self, custom_sounds: &[String]) -> Self {
+        let mut sounds = all_sounds(custom_sounds);
+        // If the current sound is a custom sound that's no longer available, start over
+        if sounds.any(|sound| sound == *self) {
+            if let Some(next) = sounds.next() {
+                return next;
+            }
         }
+        Self::default()
     }
 }
 
-impl Cyclable for Option<BuzzerSound> {
-    fn next(&self) -> Self {
-        match self {
-            Some(BuzzerSound::Buzz) => Some(BuzzerSound::Whoop),
-            Some(BuzzerSound::Whoop) => Some(BuzzerSound::Crazy),
-            Some(BuzzerSound::Crazy) => Some(BuzzerSound::DeDeDu),
-            Some(BuzzerSound::DeDeDu) => Some(BuzzerSound::TwoTone),
-            Some(BuzzerSound::TwoTone) => None,
-            None => Some(BuzzerSound::Buzz),
+impl CyclableSound for Option<BuzzerSound> {
+    fn next(&self, custom_sounds: &[String]) -> Self {
+        let mut sounds = all_sounds(custom_sounds).map(Some).chain([None]);
+        if sounds.any(|sound| sound == *self) {
+            if let Some(next) = sounds.next() {
+                return next;
+            }
         }
+        Some(BuzzerSound::default())
     }
 }
 
@@ -276,15 +296,29 @@ fn make_tournament_config_page<'a>(
                 .into(),
         ]
     } else {
+        // The half lengths aren't used when the config has a custom list of periods
+        let custom_periods = config.uses_custom_periods();
+        let half_length_button = |label, length, param| {
+            if custom_periods {
+                make_value_button(label, CUSTOM_PERIODS_TXT, (false, true), None)
+            } else {
+                make_value_button(
+                    label,
+                    time_string(length),
+                    (false, true),
+                    Some(Message::EditParameter(param)),
+                )
+            }
+        };
+
         [
             row()
                 .spacing(SPACING)
                 .height(Length::Fill)
-                .push(make_value_button(
+                .push(half_length_button(
                     "HALF LENGTH:",
-                    time_string(config.half_play_duration),
-                    (false, true),
-                    Some(Message::EditParameter(LengthParameter::Half)),
+                    config.half_play_duration,
+                    LengthParameter::Half,
                 ))
                 .push(make_value_button(
                     "OVERTIME\nALLOWED:",
@@ -306,11 +340,10 @@ fn make_tournament_config_page<'a>(
             row()
                 .spacing(SPACING)
                 .height(Length::Fill)
-                .push(make_value_button(
+                .push(half_length_button(
                     "HALF TIME\nLENGTH:",
-                    time_string(config.half_time_duration),
-                    (false, true),
-                    Some(Message::EditParameter(LengthParameter::HalfTime)),
+                    config.half_time_duration,
+                    LengthParameter::HalfTime,
                 ))
                 .push(make_value_button(
                     "PRE OT\nBREAK LENGTH:",
@@ -353,11 +386,16 @@ fn make_tournament_config_page<'a>(
                     },
                 ))
                 .push(make_value_button(
-                    "NUM TEAM T/Os\nALLWD PER HALF:",
+                    if config.timeouts_counted_per_half {
+                        "NUM TEAM T/Os\nALLWD PER HALF:"
+                    } else {
+                        "NUM TEAM T/Os\nALLWD PER GAME:"
+                    },
                     config.team_timeouts_per_half.to_string(),
                     (false, true),
                     Some(Message::KeypadPage(KeypadPage::TeamTimeouts(
                         config.team_timeout_duration,
+                        config.timeouts_counted_per_half,
                     ))),
                 ))
                 .into(),
@@ -614,7 +652,7 @@ fn make_remote_config_page<'a>(
         .take(REMOTES_LIST_LEN)
         .map(|rem| {
             if let Some((idx, rem_info)) = rem {
-                let sound_text = if let Some(sound) = rem_info.sound {
+                let sound_text = if let Some(ref sound) = rem_info.sound {
                     sound.to_string().to_uppercase()
                 } else {
                     "DEFAULT".to_owned()
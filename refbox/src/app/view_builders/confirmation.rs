@@ -16,10 +16,14 @@ pub(in super::super) fn build_confirmation_page<'a>(
     kind: &ConfirmationKind,
 ) -> Element<'a, Message> {
     let header_text = match kind {
-        ConfirmationKind::GameConfigChanged(_) => "The game configuration can not be changed while a game is in progress.\n\nWhat would you like to do?",
-        ConfirmationKind::GameNumberChanged => "How would you like to apply this game number change?",
-        ConfirmationKind::Error(string) => string,
-        ConfirmationKind::UwhScoresIncomplete => "When UWHScores is enabled, all fields must be filled out."
+        ConfirmationKind::GameConfigChanged(_) => "The game configuration can not be changed while a game is in progress.\n\nWhat would you like to do?".to_string(),
+        ConfirmationKind::GameNumberChanged => "How would you like to apply this game number change?".to_string(),
+        ConfirmationKind::Error(string) => string.clone(),
+        ConfirmationKind::UwhScoresIncomplete => "When UWHScores is enabled, all fields must be filled out.".to_string(),
+        ConfirmationKind::ResumeGame(state) => format!(
+            "An interrupted game was found.\n\nGame {}, {}\nBlack: {}        White: {}\n\nWould you like to resume it?",
+            state.game_number, state.current_period, state.b_score, state.w_score
+        ),
             };
 
     let buttons = match kind {
@@ -69,6 +73,18 @@ pub(in super::super) fn build_confirmation_page<'a>(
                 ConfirmationOption::DiscardChanges,
             )]
         }
+        ConfirmationKind::ResumeGame(_) => vec![
+            (
+                "RESUME GAME",
+                style::Button::Green,
+                ConfirmationOption::ResumeGame,
+            ),
+            (
+                "DISCARD SAVED GAME",
+                style::Button::Red,
+                ConfirmationOption::DiscardChanges,
+            ),
+        ],
         ConfirmationKind::UwhScoresIncomplete => vec![
             (
                 "GO BACK TO EDITOR",
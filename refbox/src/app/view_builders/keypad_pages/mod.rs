@@ -5,15 +5,18 @@ use super::{
 
 use iced::{
     alignment::{Horizontal, Vertical},
-    pure::{column, container, row, text, Element},
+    pure::{column, container, row, text, widget::Column, Element},
     Alignment, Length,
 };
 
-use uwh_common::game_snapshot::GameSnapshot;
+use uwh_common::game_snapshot::{Color as GameColor, GameSnapshot};
 
 mod score_add;
 use score_add::*;
 
+mod penalty_shot;
+use penalty_shot::*;
+
 mod penalty_edit;
 use penalty_edit::*;
 
@@ -27,6 +30,8 @@ pub(in super::super) fn build_keypad_page<'a>(
     snapshot: &GameSnapshot,
     page: KeypadPage,
     player_num: u16,
+    penalty_kinds: &[PenaltyKind],
+    roster_available: bool,
 ) -> Element<'a, Message> {
     column()
         .spacing(SPACING)
@@ -157,13 +162,65 @@ pub(in super::super) fn build_keypad_page<'a>(
                     .padding(PADDING),
                 )
                 .push(match page {
-                    KeypadPage::AddScore(color) => make_score_add_page(color),
-                    KeypadPage::Penalty(origin, color, kind) => {
-                        make_penalty_edit_page(origin, color, kind)
+                    KeypadPage::AddScore(color) => {
+                        make_score_add_page(snapshot, color, roster_available)
+                    }
+                    KeypadPage::PenaltyShot(color, scored) => {
+                        make_penalty_shot_page(snapshot, color, scored, roster_available)
                     }
+                    KeypadPage::Penalty(origin, color, kind) => make_penalty_edit_page(
+                        snapshot,
+                        origin,
+                        color,
+                        kind,
+                        penalty_kinds,
+                        roster_available,
+                    ),
                     KeypadPage::GameNumber => make_game_number_edit_page(),
-                    KeypadPage::TeamTimeouts(dur) => make_team_timeout_edit_page(dur),
+                    KeypadPage::TeamTimeouts(dur, per_half) => {
+                        make_team_timeout_edit_page(dur, per_half)
+                    }
                 }),
         )
         .into()
 }
+
+/// The team selection buttons shared by the pages that record something against a player, with a
+/// button to pick the player from the selected team's roster when one is available
+fn make_color_picker<'a>(
+    snapshot: &GameSnapshot,
+    color: GameColor,
+    roster_available: bool,
+) -> Column<'a, Message> {
+    const TEAM_NAME_LEN_LIMIT: usize = 10;
+
+    let (black_style, white_style) = match color {
+        GameColor::Black => (style::Button::BlackSelected, style::Button::White),
+        GameColor::White => (style::Button::Black, style::Button::WhiteSelected),
+    };
+
+    let mut picker = column().spacing(SPACING).push(
+        row()
+            .spacing(SPACING)
+            .push(
+                make_button(team_label(snapshot, GameColor::Black, TEAM_NAME_LEN_LIMIT))
+                    .style(black_style)
+                    .on_press(Message::ChangeColor(GameColor::Black)),
+            )
+            .push(
+                make_button(team_label(snapshot, GameColor::White, TEAM_NAME_LEN_LIMIT))
+                    .style(white_style)
+                    .on_press(Message::ChangeColor(GameColor::White)),
+            ),
+    );
+
+    if roster_available {
+        picker = picker.push(
+            make_button("ROSTER")
+                .style(style::Button::Blue)
+                .on_press(Message::ShowRoster),
+        );
+    }
+
+    picker
+}
@@ -8,44 +8,45 @@ use iced::{
     Length,
 };
 
-use uwh_common::game_snapshot::Color as GameColor;
+use uwh_common::game_snapshot::{Color as GameColor, GameSnapshot};
 
 pub(super) fn make_penalty_edit_page<'a>(
+    snapshot: &GameSnapshot,
     origin: Option<(GameColor, usize)>,
     color: GameColor,
     kind: PenaltyKind,
+    penalty_kinds: &[PenaltyKind],
+    roster_available: bool,
 ) -> Element<'a, Message> {
-    let (black_style, white_style) = match color {
-        GameColor::Black => (style::Button::BlackSelected, style::Button::White),
-        GameColor::White => (style::Button::Black, style::Button::WhiteSelected),
-    };
+    // Timed penalties cycle through these styles in order, TDs are always red
+    let mut timed_styles = [
+        (style::Button::Green, style::Button::GreenSelected),
+        (style::Button::Yellow, style::Button::YellowSelected),
+        (style::Button::Orange, style::Button::OrangeSelected),
+    ]
+    .into_iter()
+    .cycle();
 
-    let (one_min_style, two_min_style, five_min_style, td_style) = match kind {
-        PenaltyKind::OneMinute => (
-            style::Button::GreenSelected,
-            style::Button::Yellow,
-            style::Button::Orange,
-            style::Button::Red,
-        ),
-        PenaltyKind::TwoMinute => (
-            style::Button::Green,
-            style::Button::YellowSelected,
-            style::Button::Orange,
-            style::Button::Red,
-        ),
-        PenaltyKind::FiveMinute => (
-            style::Button::Green,
-            style::Button::Yellow,
-            style::Button::OrangeSelected,
-            style::Button::Red,
-        ),
-        PenaltyKind::TotalDismissal => (
-            style::Button::Green,
-            style::Button::Yellow,
-            style::Button::Orange,
-            style::Button::RedSelected,
-        ),
-    };
+    let mut kind_row = row().spacing(SPACING);
+    for &option in penalty_kinds {
+        let (label, (unselected_style, selected_style)) = match option {
+            PenaltyKind::Timed(_) => (option.to_string(), timed_styles.next().unwrap()),
+            PenaltyKind::TotalDismissal => (
+                "TD".to_string(),
+                (style::Button::Red, style::Button::RedSelected),
+            ),
+        };
+        kind_row = kind_row.push(
+            make_button(label)
+                .style(if option == kind {
+                    selected_style
+                } else {
+                    unselected_style
+                })
+                .width(Length::Fill)
+                .on_press(Message::ChangeKind(option)),
+        );
+    }
 
     let mut exit_row = row().spacing(SPACING).push(
         make_button("CANCEL")
@@ -82,45 +83,9 @@ pub(super) fn make_penalty_edit_page<'a>(
     column()
         .spacing(SPACING)
         .push(vertical_space(Length::Fill))
-        .push(
-            row()
-                .spacing(SPACING)
-                .push(
-                    make_button("BLACK")
-                        .style(black_style)
-                        .on_press(Message::ChangeColor(GameColor::Black)),
-                )
-                .push(
-                    make_button("WHITE")
-                        .style(white_style)
-                        .on_press(Message::ChangeColor(GameColor::White)),
-                ),
-        )
+        .push(make_color_picker(snapshot, color, roster_available))
         .push(vertical_space(Length::Fill))
-        .push(
-            row()
-                .spacing(SPACING)
-                .push(
-                    make_button("1m")
-                        .style(one_min_style)
-                        .on_press(Message::ChangeKind(PenaltyKind::OneMinute)),
-                )
-                .push(
-                    make_button("2m")
-                        .style(two_min_style)
-                        .on_press(Message::ChangeKind(PenaltyKind::TwoMinute)),
-                )
-                .push(
-                    make_button("5m")
-                        .style(five_min_style)
-                        .on_press(Message::ChangeKind(PenaltyKind::FiveMinute)),
-                )
-                .push(
-                    make_button("TD")
-                        .style(td_style)
-                        .on_press(Message::ChangeKind(PenaltyKind::TotalDismissal)),
-                ),
-        )
+        .push(kind_row)
         .push(vertical_space(Length::Fill))
         .push(exit_row)
         .into()
@@ -0,0 +1,61 @@
+use super::{
+    style::{self, SPACING},
+    *,
+};
+
+use iced::{
+    pure::{column, row, vertical_space, Element},
+    Length,
+};
+
+use uwh_common::game_snapshot::{Color as GameColor, GameSnapshot};
+
+pub(super) fn make_penalty_shot_page<'a>(
+    snapshot: &GameSnapshot,
+    color: GameColor,
+    scored: bool,
+    roster_available: bool,
+) -> Element<'a, Message> {
+    let (scored_style, missed_style) = if scored {
+        (style::Button::GreenSelected, style::Button::Gray)
+    } else {
+        (style::Button::Gray, style::Button::RedSelected)
+    };
+
+    column()
+        .spacing(SPACING)
+        .push(vertical_space(Length::Fill))
+        .push(make_color_picker(snapshot, color, roster_available))
+        .push(
+            row()
+                .spacing(SPACING)
+                .push(
+                    make_button("SCORED")
+                        .style(scored_style)
+                        .on_press(Message::ChangeScored(true)),
+                )
+                .push(
+                    make_button("MISSED")
+                        .style(missed_style)
+                        .on_press(Message::ChangeScored(false)),
+                ),
+        )
+        .push(vertical_space(Length::Fill))
+        .push(
+            row()
+                .spacing(SPACING)
+                .push(
+                    make_button("CANCEL")
+                        .style(style::Button::Red)
+                        .width(Length::Fill)
+                        .on_press(Message::PenaltyShotComplete { canceled: true }),
+                )
+                .push(
+                    make_button("DONE")
+                        .style(style::Button::Green)
+                        .width(Length::Fill)
+                        .on_press(Message::PenaltyShotComplete { canceled: false }),
+                ),
+        )
+        .into()
+}
@@ -8,31 +8,17 @@ use iced::{
     Length,
 };
 
-use uwh_common::game_snapshot::Color as GameColor;
-
-pub(super) fn make_score_add_page<'a>(color: GameColor) -> Element<'a, Message> {
-    let (black_style, white_style) = match color {
-        GameColor::Black => (style::Button::BlackSelected, style::Button::White),
-        GameColor::White => (style::Button::Black, style::Button::WhiteSelected),
-    };
+use uwh_common::game_snapshot::{Color as GameColor, GameSnapshot};
 
+pub(super) fn make_score_add_page<'a>(
+    snapshot: &GameSnapshot,
+    color: GameColor,
+    roster_available: bool,
+) -> Element<'a, Message> {
     column()
         .spacing(SPACING)
         .push(vertical_space(Length::Fill))
-        .push(
-            row()
-                .spacing(SPACING)
-                .push(
-                    make_button("BLACK")
-                        .style(black_style)
-                        .on_press(Message::ChangeColor(GameColor::Black)),
-                )
-                .push(
-                    make_button("WHITE")
-                        .style(white_style)
-                        .on_press(Message::ChangeColor(GameColor::White)),
-                ),
-        )
+        .push(make_color_picker(snapshot, color, roster_available))
         .push(vertical_space(Length::Fill))
         .push(
             row()
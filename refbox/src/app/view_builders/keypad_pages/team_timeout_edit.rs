@@ -1,5 +1,5 @@
 use super::{
-    style::{self, SPACING},
+    style::{self, MIN_BUTTON_SIZE, SPACING},
     *,
 };
 
@@ -10,7 +10,10 @@ use iced::{
 
 use std::time::Duration;
 
-pub(super) fn make_team_timeout_edit_page<'a>(duration: Duration) -> Element<'a, Message> {
+pub(super) fn make_team_timeout_edit_page<'a>(
+    duration: Duration,
+    per_half: bool,
+) -> Element<'a, Message> {
     column()
         .spacing(SPACING)
         .push(vertical_space(Length::Fill))
@@ -21,6 +24,17 @@ pub(super) fn make_team_timeout_edit_page<'a>(duration: Duration) -> Element<'a,
                 .push(horizontal_space(Length::Fill)),
         )
         .push(vertical_space(Length::Fill))
+        .push(
+            make_value_button(
+                "TIMEOUTS\nCOUNTED:",
+                if per_half { "PER HALF" } else { "PER GAME" },
+                (false, true),
+                Some(Message::ToggleBoolParameter(
+                    BoolGameParameter::TimeoutsCountedPerHalf,
+                )),
+            )
+            .height(Length::Units(MIN_BUTTON_SIZE)),
+        )
         .push(
             row()
                 .spacing(SPACING)
@@ -21,7 +21,12 @@ pub(in super::super) fn build_main_view<'a>(
     config: &GameConfig,
     using_uwhscores: bool,
     games: &Option<BTreeMap<u32, GameInfo>>,
+    can_undo: bool,
+    can_redo: bool,
+    pending_uploads: usize,
 ) -> Element<'a, Message> {
+    const TEAM_NAME_LEN_LIMIT: usize = 10;
+
     let time_button = make_game_time_button(snapshot, true, true).on_press(Message::EditTime);
 
     let mut center_col = column()
@@ -30,16 +35,32 @@ pub(in super::super) fn build_main_view<'a>(
         .push(time_button);
 
     match snapshot.timeout {
-        TimeoutSnapshot::White(_)
-        | TimeoutSnapshot::Black(_)
-        | TimeoutSnapshot::Ref(_)
-        | TimeoutSnapshot::PenaltyShot(_) => {
+        TimeoutSnapshot::White(_) | TimeoutSnapshot::Black(_) | TimeoutSnapshot::Ref(_) => {
             center_col = center_col.push(
                 make_button("END TIMEOUT")
                     .style(style::Button::Yellow)
                     .on_press(Message::EndTimeout),
             )
         }
+        TimeoutSnapshot::PenaltyShot(_) => {
+            center_col = center_col.push(
+                row()
+                    .spacing(SPACING)
+                    .push(
+                        make_button("END TIMEOUT")
+                            .style(style::Button::Yellow)
+                            .on_press(Message::EndTimeout),
+                    )
+                    .push(
+                        make_button("RECORD SHOT")
+                            .style(style::Button::Red)
+                            .on_press(Message::KeypadPage(KeypadPage::PenaltyShot(
+                                GameColor::Black,
+                                true,
+                            ))),
+                    ),
+            )
+        }
         TimeoutSnapshot::None => {
             match snapshot.current_period {
                 GamePeriod::BetweenGames
@@ -62,9 +83,27 @@ pub(in super::super) fn build_main_view<'a>(
         }
     };
 
+    center_col = center_col.push(
+        row()
+            .spacing(SPACING)
+            .push(
+                make_message_button("UNDO", can_undo.then_some(Message::UndoLastAction))
+                    .style(style::Button::Blue),
+            )
+            .push(
+                make_message_button("REDO", can_redo.then_some(Message::RedoLastAction))
+                    .style(style::Button::Blue),
+            ),
+    );
+
+    let mut game_info = config_string(snapshot, config, using_uwhscores, games);
+    if pending_uploads > 0 {
+        game_info += &format!("Results Pending Upload: {pending_uploads}\n");
+    }
+
     center_col = center_col.push(
         button(
-            text(config_string(snapshot, config, using_uwhscores, games))
+            text(game_info)
                 .size(SMALL_TEXT)
                 .vertical_alignment(Vertical::Center)
                 .horizontal_alignment(Horizontal::Left),
@@ -106,7 +145,11 @@ pub(in super::super) fn build_main_view<'a>(
         column()
             .align_items(Alignment::Center)
             .width(Length::Fill)
-            .push("BLACK")
+            .push(text(team_label(
+                snapshot,
+                GameColor::Black,
+                TEAM_NAME_LEN_LIMIT,
+            )))
             .push(text(snapshot.b_score.to_string()).size(LARGE_TEXT)),
     )
     .padding(PADDING)
@@ -119,7 +162,11 @@ pub(in super::super) fn build_main_view<'a>(
         column()
             .align_items(Alignment::Center)
             .width(Length::Fill)
-            .push("WHITE")
+            .push(text(team_label(
+                snapshot,
+                GameColor::White,
+                TEAM_NAME_LEN_LIMIT,
+            )))
             .push(text(snapshot.w_score.to_string()).size(LARGE_TEXT)),
     )
     .padding(PADDING)
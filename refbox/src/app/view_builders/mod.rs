@@ -18,6 +18,9 @@ pub(super) use main_view::*;
 pub mod penalties;
 pub(super) use penalties::*;
 
+pub mod roster_list;
+pub(super) use roster_list::*;
+
 pub mod score_edit;
 pub(super) use score_edit::*;
 
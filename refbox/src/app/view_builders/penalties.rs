@@ -15,6 +15,7 @@ pub(in super::super) fn build_penalty_overview_page<'a>(
     snapshot: &GameSnapshot,
     penalties: BlackWhiteBundle<Vec<(String, FormatHint, PenaltyKind)>>,
     indices: BlackWhiteBundle<usize>,
+    default_kind: PenaltyKind,
 ) -> Element<'a, Message> {
     column()
         .spacing(SPACING)
@@ -28,11 +29,13 @@ pub(in super::super) fn build_penalty_overview_page<'a>(
                     penalties.black,
                     indices.black,
                     GameColor::Black,
+                    default_kind,
                 ))
                 .push(make_penalty_list(
                     penalties.white,
                     indices.white,
                     GameColor::White,
+                    default_kind,
                 )),
         )
         .push(
@@ -51,7 +54,7 @@ pub(in super::super) fn build_penalty_overview_page<'a>(
                         .on_press(Message::KeypadPage(KeypadPage::Penalty(
                             None,
                             GameColor::Black,
-                            PenaltyKind::OneMinute,
+                            default_kind,
                         ))),
                 )
                 .push(
@@ -68,6 +71,7 @@ fn make_penalty_list<'a>(
     penalties: Vec<(String, FormatHint, PenaltyKind)>,
     index: usize,
     color: GameColor,
+    default_kind: PenaltyKind,
 ) -> Container<'a, Message> {
     const PENALTY_LIST_LEN: usize = 3;
 
@@ -119,7 +123,7 @@ fn make_penalty_list<'a>(
                     .on_press(Message::KeypadPage(KeypadPage::Penalty(
                         None,
                         color,
-                        PenaltyKind::OneMinute,
+                        default_kind,
                     )))
                     .into()
             }
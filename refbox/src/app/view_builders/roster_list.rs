@@ -0,0 +1,135 @@
+use super::{
+    style::{self, MIN_BUTTON_SIZE, PADDING, SPACING},
+    *,
+};
+use collect_array::CollectArrayResult;
+use iced::{
+    alignment::{Horizontal, Vertical},
+    pure::{button, column, horizontal_space, row, text, vertical_space, Element},
+    Length,
+};
+
+use uwh_common::{game_snapshot::GameSnapshot, uwhscores::PlayerInfo};
+
+pub(in super::super) fn build_roster_list_page<'a>(
+    snapshot: &GameSnapshot,
+    team_name: &str,
+    roster: &[PlayerInfo],
+    index: usize,
+) -> Element<'a, Message> {
+    const LIST_LEN: usize = 4;
+
+    let title = text(format!("SELECT PLAYER - {team_name}"))
+        .height(Length::Fill)
+        .width(Length::Fill)
+        .horizontal_alignment(Horizontal::Center)
+        .vertical_alignment(Vertical::Center);
+
+    let players = sorted_roster(roster);
+
+    let buttons: CollectArrayResult<_, LIST_LEN> = players
+        .iter()
+        .skip(index)
+        .map(Some)
+        .chain([None].into_iter().cycle())
+        .take(LIST_LEN)
+        .map(|player| {
+            if let Some(player) = player {
+                let label = match player.number {
+                    Some(num) => format!("#{num} {}", player.name),
+                    None => format!("#? {}", player.name),
+                };
+                let text = text(label)
+                    .vertical_alignment(Vertical::Center)
+                    .horizontal_alignment(Horizontal::Left)
+                    .width(Length::Fill);
+
+                let mut button = button(text)
+                    .padding(PADDING)
+                    .height(Length::Units(MIN_BUTTON_SIZE))
+                    .width(Length::Fill)
+                    .style(style::Button::Gray);
+                if let Some(num) = player.number {
+                    button = button.on_press(Message::PlayerSelected(Some(num)));
+                }
+                button.into()
+            } else {
+                button(horizontal_space(Length::Shrink))
+                    .height(Length::Units(MIN_BUTTON_SIZE))
+                    .width(Length::Fill)
+                    .style(style::Button::Gray)
+                    .into()
+            }
+        })
+        .collect();
+
+    let scroll_list = make_scroll_list(
+        buttons.unwrap(),
+        players.len(),
+        index,
+        title,
+        ScrollOption::Roster,
+        style::Container::LightGray,
+    )
+    .width(Length::FillPortion(4));
+
+    column()
+        .spacing(SPACING)
+        .height(Length::Fill)
+        .push(make_game_time_button(snapshot, false, true).on_press(Message::EditTime))
+        .push(
+            row()
+                .spacing(SPACING)
+                .height(Length::Fill)
+                .width(Length::Fill)
+                .push(scroll_list)
+                .push(
+                    column()
+                        .width(Length::Fill)
+                        .push(vertical_space(Length::Fill))
+                        .push(
+                            make_button("CANCEL")
+                                .style(style::Button::Red)
+                                .width(Length::Fill)
+                                .height(Length::Units(MIN_BUTTON_SIZE))
+                                .on_press(Message::PlayerSelected(None)),
+                        ),
+                ),
+        )
+        .into()
+}
+
+// Players without a number can't be recorded, so they are listed last and can't be selected
+fn sorted_roster(roster: &[PlayerInfo]) -> Vec<&PlayerInfo> {
+    let mut players: Vec<_> = roster.iter().collect();
+    players.sort_by_key(|p| (p.number.is_none(), p.number));
+    players
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn player(name: &str, number: Option<u8>) -> PlayerInfo {
+        PlayerInfo {
+            name: name.to_string(),
+            number,
+        }
+    }
+
+    #[test]
+    fn test_sorted_roster() {
+        let roster = vec![
+            player("A", None),
+            player("B", Some(12)),
+            player("C", Some(3)),
+            player("D", None),
+        ];
+
+        let names: Vec<_> = sorted_roster(&roster)
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["C", "B", "A", "D"]);
+    }
+}
@@ -16,6 +16,8 @@ pub(in super::super) fn build_score_edit_view<'a>(
     scores: BlackWhiteBundle<u8>,
     is_confirmation: bool,
 ) -> Element<'a, Message> {
+    const TEAM_NAME_LEN_LIMIT: usize = 10;
+
     let cancel_btn_msg = if is_confirmation {
         None
     } else {
@@ -51,7 +53,11 @@ pub(in super::super) fn build_score_edit_view<'a>(
                     .spacing(SPACING)
                     .width(Length::Fill)
                     .align_items(Alignment::Center)
-                    .push("BLACK")
+                    .push(text(team_label(
+                        snapshot,
+                        GameColor::Black,
+                        TEAM_NAME_LEN_LIMIT,
+                    )))
                     .push(text(scores.black.to_string()).size(LARGE_TEXT)),
             ),
     )
@@ -68,7 +74,11 @@ pub(in super::super) fn build_score_edit_view<'a>(
                     .spacing(SPACING)
                     .width(Length::Fill)
                     .align_items(Alignment::Center)
-                    .push("WHITE")
+                    .push(text(team_label(
+                        snapshot,
+                        GameColor::White,
+                        TEAM_NAME_LEN_LIMIT,
+                    )))
                     .push(text(scores.white.to_string()).size(LARGE_TEXT)),
             )
             .push(
@@ -25,8 +25,10 @@ use std::{
     time::Duration,
 };
 use uwh_common::{
-    config::Game as GameConfig,
-    game_snapshot::{GamePeriod, GameSnapshot, PenaltySnapshot, PenaltyTime, TimeoutSnapshot},
+    config::{Game as GameConfig, PeriodKind, StopClockPolicy},
+    game_snapshot::{
+        Color as GameColor, GamePeriod, GameSnapshot, PenaltySnapshot, PenaltyTime, TimeoutSnapshot,
+    },
 };
 
 pub(super) fn make_scroll_list<'a, const LIST_LEN: usize>(
@@ -303,6 +305,16 @@ pub(super) fn make_game_time_button<'a>(
         };
     }
 
+    // Regulation periods from a custom list share their labels, so they are numbered instead
+    let numbered_text;
+    if let Some(number) = snapshot.period_number {
+        numbered_text = match snapshot.current_period {
+            GamePeriod::HalfTime => format!("BREAK {number}"),
+            _ => format!("PERIOD {number}"),
+        };
+        period_text = &numbered_text;
+    }
+
     macro_rules! make_time_view {
         ($base:ident, $per_text:ident, $time_text:ident) => {
             $base
@@ -517,27 +529,35 @@ pub(super) fn game_string_short(game: &GameInfo) -> String {
     format!("{}{}", game.game_type, game.gid)
 }
 
-pub(super) fn game_string_long(game: &GameInfo, len_limit: usize) -> String {
+fn limit_len(orig: &str, len_limit: usize) -> Cow<'_, str> {
     const ELIPSIS: [char; 3] = ['.', '.', '.'];
 
-    macro_rules! limit {
-        ($orig:ident) => {
-            if $orig.len() > len_limit {
-                Cow::Owned($orig.chars().take(len_limit - 1).chain(ELIPSIS).collect())
-            } else {
-                Cow::Borrowed($orig)
-            }
-        };
+    if orig.len() > len_limit {
+        Cow::Owned(orig.chars().take(len_limit - 1).chain(ELIPSIS).collect())
+    } else {
+        Cow::Borrowed(orig)
     }
+}
 
-    let black = &game.black;
-    let black = limit!(black);
-    let white = &game.white;
-    let white = limit!(white);
+pub(super) fn game_string_long(game: &GameInfo, len_limit: usize) -> String {
+    let black = limit_len(&game.black, len_limit);
+    let white = limit_len(&game.white, len_limit);
 
     format!("{}{} - {} vs {}", game.game_type, game.gid, black, white)
 }
 
+/// The name of the `color` team if it is known, otherwise the name of the color
+pub(super) fn team_label(snapshot: &GameSnapshot, color: GameColor, len_limit: usize) -> String {
+    let name = match color {
+        GameColor::Black => &snapshot.b_team_name,
+        GameColor::White => &snapshot.w_team_name,
+    };
+    match name {
+        Some(name) => limit_len(name, len_limit).into_owned(),
+        None => color.to_string().to_uppercase(),
+    }
+}
+
 pub(super) fn config_string(
     snapshot: &GameSnapshot,
     config: &GameConfig,
@@ -594,13 +614,27 @@ pub(super) fn config_string(
         }
         format!("Game: {}\n\n", game)
     };
+    let regulation = config.regulation_periods();
+    result += &if regulation == config.periods {
+        let periods: Vec<_> = regulation
+            .iter()
+            .map(|p| match p.kind {
+                PeriodKind::Play => format!("Play {}", time_string(p.duration)),
+                PeriodKind::Break => format!("Break {}", time_string(p.duration)),
+            })
+            .collect();
+        format!("Periods: {}\n", periods.join(", "))
+    } else {
+        format!(
+            "Half Length: {}\n\
+             Half Time Length: {}\n",
+            time_string(config.half_play_duration),
+            time_string(config.half_time_duration),
+        )
+    };
     result += &format!(
-        "Half Length: {}\n\
-         Half Time Length: {}\n\
-         Overtime Allowed: {}\n",
-        time_string(config.half_play_duration),
-        time_string(config.half_time_duration),
-        bool_string(config.overtime_allowed),
+        "Overtime Allowed: {}\n",
+        bool_string(config.overtime_allowed)
     );
     result += &if config.overtime_allowed {
         format!(
@@ -627,7 +661,12 @@ pub(super) fn config_string(
         String::new()
     };
     result += &format!(
-        "Team Timeouts Allowed Per Half: {}\n",
+        "Team Timeouts Allowed Per {}: {}\n",
+        if config.timeouts_counted_per_half {
+            "Half"
+        } else {
+            "Game"
+        },
         config.team_timeouts_per_half
     );
     result += &if config.team_timeouts_per_half != 0 {
@@ -638,6 +677,29 @@ pub(super) fn config_string(
     } else {
         String::new()
     };
+    result += &format!(
+        "Ref Timeouts Stop Clock: {}\n",
+        match config.stop_clock_policy {
+            StopClockPolicy::Always => "Always".to_string(),
+            StopClockPolicy::Never => "Never".to_string(),
+            StopClockPolicy::LastMinutes => {
+                format!("Last {}", time_string(config.stop_clock_window))
+            }
+        }
+    );
+    result += &format!(
+        "Penalty Shot Duration: {}\n",
+        time_string(config.penalty_shot_duration)
+    );
+    result += &format!(
+        "Penalty Lengths: {}\n",
+        config
+            .penalty_durations
+            .iter()
+            .map(|dur| time_string(*dur))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
     if !using_uwhscores {
         result += &format!(
             "Nominal Time Between Games: {}\n",
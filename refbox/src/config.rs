@@ -39,12 +39,32 @@ impl Default for UwhScores {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoteControl {
+    pub enabled: bool,
+    pub port: u16,
+    /// Shared secret that must be included in every remote control request
+    pub key: String,
+}
+
+impl Default for RemoteControl {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 8003,
+            key: String::new(),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Config {
     pub game: Game,
     pub hardware: Hardware,
     pub uwhscores: UwhScores,
     pub sound: SoundSettings,
+    #[serde(default)]
+    pub remote_control: RemoteControl,
 }
 
 #[cfg(test)]
@@ -67,6 +87,23 @@ mod test {
         assert_eq!(deser, Ok(u));
     }
 
+    #[test]
+    fn test_ser_remote_control() {
+        let rc: RemoteControl = Default::default();
+        let serialized = toml::to_string(&rc).unwrap();
+        let deser = toml::from_str(&serialized);
+        assert_eq!(deser, Ok(rc));
+    }
+
+    #[test]
+    fn test_deser_config_without_remote_control() {
+        let config: Config = Default::default();
+        let mut serialized: toml::Value = toml::Value::try_from(&config).unwrap();
+        serialized.as_table_mut().unwrap().remove("remote_control");
+        let deser: Config = serialized.try_into().unwrap();
+        assert_eq!(deser, config);
+    }
+
     #[test]
     fn test_ser_config() {
         let config: Config = Default::default();
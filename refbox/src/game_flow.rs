@@ -0,0 +1,152 @@
+//! The steps of running a game that are shared by the GUI and the headless mode
+
+use crate::{
+    app::{rest_api::RestApiData, update_sender::UpdateSender},
+    game_log::GameLog,
+    sound_controller::{SoundController, SoundSettings},
+    state_journal::StateJournal,
+    tournament_manager::{Result, TournamentManager, TournamentManagerError},
+};
+use log::*;
+use std::{path::Path, sync::Mutex};
+use tokio::time::{Duration, Instant};
+use uwh_common::game_snapshot::GameSnapshot;
+
+/// The state of the game after it has been brought up to date
+#[derive(Debug)]
+pub(crate) struct Tick {
+    pub snapshot: GameSnapshot,
+    /// The game clock has run out at the end of the game. The clock has been halted so that the
+    /// final scores can be settled before the game is ended with `end_game`.
+    pub game_ending: bool,
+    /// When the game next needs to be brought up to date, if the clock is running
+    pub next_update: Option<Instant>,
+}
+
+/// Brings the game up to date with `now`
+pub(crate) fn tick(tm: &mut TournamentManager, now: Instant) -> Result<Tick> {
+    let game_ending = tm.would_end_game(now)?;
+    if game_ending {
+        tm.halt_clock(now)?;
+    } else {
+        tm.update(now)?;
+    }
+    current_state(tm, now, game_ending)
+}
+
+/// Ends a game whose clock was halted by `tick`, once its scores have been settled
+pub(crate) fn end_game(tm: &mut TournamentManager, now: Instant) -> Result<Tick> {
+    tm.start_clock(now);
+    // The game only moves on to Between Games once it has been updated after the end
+    let now = now + Duration::from_millis(2);
+    tm.update(now)?;
+    current_state(tm, now, false)
+}
+
+fn current_state(tm: &mut TournamentManager, now: Instant, game_ending: bool) -> Result<Tick> {
+    let snapshot = match tm.generate_snapshot(now) {
+        Some(snapshot) => snapshot,
+        None => {
+            error!("Failed to generate snapshot. State:\n{tm:#?}");
+            return Err(TournamentManagerError::NoSnapshot);
+        }
+    };
+    let next_update = if tm.clock_is_running() {
+        tm.next_update_time(now)
+    } else {
+        None
+    };
+    Ok(Tick {
+        snapshot,
+        game_ending,
+        next_update,
+    })
+}
+
+/// Writes the log of a completed game to `dir` and adds it to the session results
+pub(crate) fn save_game_log(log: GameLog, dir: &Path, rest_data: &RestApiData) {
+    match log.write_to_dir(dir) {
+        Ok(path) => info!("Wrote game log to {path:?}"),
+        Err(e) => error!("Failed to write game log: {e}"),
+    }
+    rest_data.add_completed_game(log);
+}
+
+/// Plays the sounds that `settings` call for on the change from `old` to `new`
+pub(crate) fn play_auto_sounds(
+    settings: &SoundSettings,
+    sound: &SoundController,
+    old: &GameSnapshot,
+    new: &GameSnapshot,
+) {
+    let (play_whistle, play_buzzer) = settings.auto_sounds(old, new);
+    if play_whistle {
+        info!("Triggering whistle");
+        sound.trigger_whistle();
+    } else if play_buzzer {
+        info!("Triggering buzzer");
+        sound.trigger_buzzer();
+    }
+}
+
+/// Sends `snapshot` to the displays and makes it available to the REST API
+pub(crate) fn publish_snapshot(
+    snapshot: &GameSnapshot,
+    update_sender: &UpdateSender,
+    rest_data: &RestApiData,
+    white_on_right: bool,
+) {
+    rest_data.set_snapshot(snapshot.clone());
+    if let Err(e) = update_sender.send_snapshot(snapshot.clone(), white_on_right) {
+        error!("Failed to send snapshot: {e:?}");
+    }
+}
+
+/// Saves the state of the game to `journal`
+pub(crate) fn save_state(tm: &Mutex<TournamentManager>, journal: &mut StateJournal) {
+    let now = Instant::now();
+    let (state, clock_running) = {
+        let tm = tm.lock().unwrap();
+        (tm.saved_state(now), tm.clock_is_running())
+    };
+    if let Some(state) = state {
+        if let Err(e) = journal.record(state, clock_running, now) {
+            error!("Failed to save game state: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use uwh_common::{config::Game as GameConfig, game_snapshot::GamePeriod};
+
+    #[test]
+    fn test_tick_and_end_game() {
+        let mut tm = TournamentManager::new(GameConfig::default());
+        let start = Instant::now();
+        tm.set_period_and_game_clock_time(GamePeriod::SecondHalf, Duration::from_secs(2));
+        tm.set_scores(2, 1, start);
+        tm.start_clock(start);
+
+        let first = tick(&mut tm, start).unwrap();
+        assert!(!first.game_ending);
+        assert_eq!(first.snapshot.current_period, GamePeriod::SecondHalf);
+        assert!(first.next_update.is_some());
+
+        // The game waits at the end until the scores are settled
+        let end = start + Duration::from_secs(3);
+        let ending = tick(&mut tm, end).unwrap();
+        assert!(ending.game_ending);
+        assert!(!tm.clock_is_running());
+        assert_eq!(ending.next_update, None);
+        assert_eq!(ending.snapshot.current_period, GamePeriod::SecondHalf);
+
+        let ended = end_game(&mut tm, end).unwrap();
+        assert!(!ended.game_ending);
+        assert_eq!(ended.snapshot.current_period, GamePeriod::BetweenGames);
+        assert_eq!((ended.snapshot.b_score, ended.snapshot.w_score), (2, 1));
+        assert!(ended.next_update.is_some());
+        assert!(tm.take_completed_game_log().is_some());
+    }
+}
@@ -0,0 +1,168 @@
+use crate::{game_stats::GameStats, tournament_manager::PenaltyKind};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+use time::OffsetDateTime;
+use uwh_common::game_snapshot::{Color, GamePeriod};
+
+/// Something that happened during a game, along with when it happened
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameEvent {
+    #[serde(with = "time::serde::rfc3339")]
+    pub wall_time: OffsetDateTime,
+    pub period: GamePeriod,
+    /// The value of the game clock when the event occurred, in seconds
+    pub clock_secs: u64,
+    pub event: GameEventKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameEventKind {
+    PeriodStarted {
+        period: GamePeriod,
+    },
+    GoalScored {
+        color: Color,
+        player_number: u8,
+    },
+    PenaltyStarted {
+        color: Color,
+        player_number: u8,
+        kind: PenaltyKind,
+    },
+    PenaltyEdited {
+        old_color: Color,
+        old_player_number: u8,
+        old_kind: PenaltyKind,
+        new_color: Color,
+        new_player_number: u8,
+        new_kind: PenaltyKind,
+    },
+    PenaltyDeleted {
+        color: Color,
+        player_number: u8,
+        kind: PenaltyKind,
+    },
+    TimeoutStarted {
+        kind: TimeoutKind,
+    },
+    TimeoutSwitched {
+        kind: TimeoutKind,
+    },
+    TimeoutEnded,
+    PenaltyShotTaken {
+        color: Color,
+        player_number: u8,
+        scored: bool,
+    },
+    GameClockSet {
+        secs: u64,
+    },
+    TimeoutClockSet {
+        secs: u64,
+    },
+    ScoresSet {
+        black: u8,
+        white: u8,
+    },
+    GameEnded {
+        black: u8,
+        white: u8,
+    },
+    GameReset,
+    ActionUndone,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeoutKind {
+    Black,
+    White,
+    Ref,
+    PenaltyShot,
+}
+
+/// The full record of a completed game
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameLog {
+    pub game_number: u32,
+    pub b_score: u8,
+    pub w_score: u8,
+    /// The team names, if they are known
+    #[serde(default)]
+    pub black_team: Option<String>,
+    #[serde(default)]
+    pub white_team: Option<String>,
+    #[serde(default)]
+    pub stats: GameStats,
+    pub events: Vec<GameEvent>,
+}
+
+impl GameLog {
+    /// Writes the log as JSON to a new file in `dir`, returning the path of the file
+    pub fn write_to_dir(&self, dir: &Path) -> io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+
+        let start = self
+            .events
+            .first()
+            .map(|e| e.wall_time)
+            .unwrap_or_else(OffsetDateTime::now_utc);
+
+        let mut path = dir.to_path_buf();
+        path.push(format!(
+            "game-{}-{:04}{:02}{:02}-{:02}{:02}{:02}.json",
+            self.game_number,
+            start.year(),
+            start.month() as u8,
+            start.day(),
+            start.hour(),
+            start.minute(),
+            start.second()
+        ));
+
+        let mut file = fs::File::create(&path)?;
+        file.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_write_to_dir() {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("refbox-game-log-test-{}", std::process::id()));
+
+        let log = GameLog {
+            game_number: 12,
+            b_score: 1,
+            w_score: 0,
+            black_team: None,
+            white_team: None,
+            stats: GameStats::default(),
+            events: vec![GameEvent {
+                wall_time: datetime!(2022-06-04 10:15:30 UTC),
+                period: GamePeriod::FirstHalf,
+                clock_secs: 431,
+                event: GameEventKind::GoalScored {
+                    color: Color::Black,
+                    player_number: 5,
+                },
+            }],
+        };
+
+        let path = log.write_to_dir(&dir).unwrap();
+        assert_eq!(path.file_name().unwrap(), "game-12-20220604-101530.json");
+
+        let read: GameLog = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(read, log);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
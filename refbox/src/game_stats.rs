@@ -0,0 +1,419 @@
+use crate::{
+    game_log::GameLog,
+    tournament_manager::{Penalty, PenaltyKind},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uwh_common::game_snapshot::{Color, GamePeriod};
+
+/// The goals scored and penalties given during a game, for building scorer tables
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameStats {
+    pub goals: Vec<GoalRecord>,
+    pub penalties: Vec<PenaltyRecord>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GoalRecord {
+    pub color: Color,
+    pub player_number: u8,
+    pub period: GamePeriod,
+    /// The value of the game clock when the goal was scored, in seconds
+    pub clock_secs: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PenaltyRecord {
+    pub color: Color,
+    pub player_number: u8,
+    pub kind: PenaltyKind,
+    pub period: GamePeriod,
+    /// The value of the game clock when the penalty started, in seconds
+    pub clock_secs: u64,
+}
+
+impl PenaltyRecord {
+    pub(crate) fn new(color: Color, penalty: &Penalty) -> Self {
+        Self {
+            color,
+            player_number: penalty.player_number,
+            kind: penalty.kind,
+            period: penalty.start_period,
+            clock_secs: penalty.start_time.as_secs(),
+        }
+    }
+}
+
+/// One row of a scorer table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PlayerStats {
+    pub team: String,
+    pub player_number: u8,
+    pub goals: u32,
+    /// The number of penalties, including Total Dismissals
+    pub penalties: u32,
+    /// The total length of the timed penalties
+    pub penalty_secs: u64,
+    pub total_dismissals: u32,
+}
+
+impl PlayerStats {
+    fn new(team: String, player_number: u8) -> Self {
+        Self {
+            team,
+            player_number,
+            goals: 0,
+            penalties: 0,
+            penalty_secs: 0,
+            total_dismissals: 0,
+        }
+    }
+}
+
+impl GameStats {
+    /// Removes the most recent goal scored by `color` player #`player_number`
+    pub(crate) fn remove_goal(&mut self, color: Color, player_number: u8) -> Option<GoalRecord> {
+        let index = self
+            .goals
+            .iter()
+            .rposition(|goal| goal.color == color && goal.player_number == player_number)?;
+        Some(self.goals.remove(index))
+    }
+
+    /// Removes the most recent goals of each color until Black has no more than `black` goals
+    /// and White no more than `white`. Returns the removed goals with their former positions, in
+    /// order, so that they can be put back by `restore_goals`.
+    pub(crate) fn trim_goals(&mut self, black: u8, white: u8) -> Vec<(usize, GoalRecord)> {
+        let count = |color| self.goals.iter().filter(|goal| goal.color == color).count();
+        let mut b_excess = count(Color::Black).saturating_sub(black.into());
+        let mut w_excess = count(Color::White).saturating_sub(white.into());
+
+        let mut removed = vec![];
+        for index in (0..self.goals.len()).rev() {
+            let excess = match self.goals[index].color {
+                Color::Black => &mut b_excess,
+                Color::White => &mut w_excess,
+            };
+            if *excess > 0 {
+                *excess -= 1;
+                removed.push((index, self.goals.remove(index)));
+            }
+        }
+        removed.reverse();
+        removed
+    }
+
+    /// Puts back the goals removed by `trim_goals`
+    pub(crate) fn restore_goals(&mut self, removed: &[(usize, GoalRecord)]) {
+        for &(index, goal) in removed {
+            self.goals.insert(index.min(self.goals.len()), goal);
+        }
+    }
+
+    /// Removes the record of `penalty` given to `color`
+    pub(crate) fn remove_penalty(
+        &mut self,
+        color: Color,
+        penalty: &Penalty,
+    ) -> Option<PenaltyRecord> {
+        let record = PenaltyRecord::new(color, penalty);
+        let index = self.penalties.iter().rposition(|pen| *pen == record)?;
+        Some(self.penalties.remove(index))
+    }
+
+    /// Replaces the record of `old` given to `old_color` with a record of `new` given to
+    /// `new_color`
+    pub(crate) fn replace_penalty(
+        &mut self,
+        old_color: Color,
+        old: &Penalty,
+        new_color: Color,
+        new: &Penalty,
+    ) {
+        let old = PenaltyRecord::new(old_color, old);
+        let new = PenaltyRecord::new(new_color, new);
+        match self.penalties.iter_mut().rev().find(|pen| **pen == old) {
+            Some(pen) => *pen = new,
+            None => self.penalties.push(new),
+        }
+    }
+
+    fn add_to_table(
+        &self,
+        table: &mut BTreeMap<(String, u8), PlayerStats>,
+        team_name: impl Fn(Color) -> String,
+    ) {
+        for goal in self.goals.iter() {
+            table_entry(table, team_name(goal.color), goal.player_number).goals += 1;
+        }
+        for pen in self.penalties.iter() {
+            let stats = table_entry(table, team_name(pen.color), pen.player_number);
+            stats.penalties += 1;
+            match pen.kind {
+                PenaltyKind::Timed(dur) => stats.penalty_secs += dur.as_secs(),
+                PenaltyKind::TotalDismissal => stats.total_dismissals += 1,
+            }
+        }
+    }
+
+    /// The scorer table for this game, with the teams named `black_team` and `white_team`
+    pub fn player_stats(&self, black_team: &str, white_team: &str) -> Vec<PlayerStats> {
+        let mut table = BTreeMap::new();
+        self.add_to_table(&mut table, |color| match color {
+            Color::Black => black_team.to_string(),
+            Color::White => white_team.to_string(),
+        });
+        sort_table(table)
+    }
+}
+
+impl GameLog {
+    /// The name of the team that played as `color`. If the name isn't known, the team is named
+    /// after the game number and color so that it isn't merged with other teams.
+    pub fn team_name(&self, color: Color) -> String {
+        let name = match color {
+            Color::Black => self.black_team.as_ref(),
+            Color::White => self.white_team.as_ref(),
+        };
+        name.cloned()
+            .unwrap_or_else(|| format!("Game {} {color}", self.game_number))
+    }
+}
+
+/// The combined scorer table for `games`. Players are matched between games by team name and
+/// player number.
+pub fn session_player_stats<'a>(games: impl IntoIterator<Item = &'a GameLog>) -> Vec<PlayerStats> {
+    let mut table = BTreeMap::new();
+    for game in games {
+        game.stats
+            .add_to_table(&mut table, |color| game.team_name(color));
+    }
+    sort_table(table)
+}
+
+fn table_entry(
+    table: &mut BTreeMap<(String, u8), PlayerStats>,
+    team: String,
+    player_number: u8,
+) -> &mut PlayerStats {
+    table
+        .entry((team, player_number))
+        .or_insert_with_key(|(team, number)| PlayerStats::new(team.clone(), *number))
+}
+
+/// Sorts the top scorers first, then by team and player number
+fn sort_table(table: BTreeMap<(String, u8), PlayerStats>) -> Vec<PlayerStats> {
+    let mut rows: Vec<_> = table.into_values().collect();
+    rows.sort_by_key(|row| std::cmp::Reverse(row.goals));
+    rows
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    fn goal(color: Color, player_number: u8) -> GoalRecord {
+        GoalRecord {
+            color,
+            player_number,
+            period: GamePeriod::FirstHalf,
+            clock_secs: 300,
+        }
+    }
+
+    fn penalty(player_number: u8, kind: PenaltyKind) -> Penalty {
+        Penalty {
+            player_number,
+            kind,
+            start_period: GamePeriod::SecondHalf,
+            start_time: Duration::from_secs(200),
+            start_index: None,
+        }
+    }
+
+    fn log(game_number: u32, names: Option<(&str, &str)>, stats: GameStats) -> GameLog {
+        GameLog {
+            game_number,
+            b_score: 0,
+            w_score: 0,
+            black_team: names.map(|(b, _)| b.to_string()),
+            white_team: names.map(|(_, w)| w.to_string()),
+            stats,
+            events: vec![],
+        }
+    }
+
+    #[test]
+    fn test_trim_goals() {
+        let goals = vec![
+            goal(Color::Black, 3),
+            goal(Color::White, 5),
+            goal(Color::Black, 4),
+            goal(Color::White, 6),
+            goal(Color::Black, 3),
+        ];
+        let mut stats = GameStats {
+            goals: goals.clone(),
+            penalties: vec![],
+        };
+
+        assert!(stats.trim_goals(3, 2).is_empty());
+        assert!(stats.trim_goals(5, 5).is_empty());
+        assert_eq!(stats.goals, goals);
+
+        let removed = stats.trim_goals(1, 1);
+        assert_eq!(
+            removed,
+            vec![
+                (2, goal(Color::Black, 4)),
+                (3, goal(Color::White, 6)),
+                (4, goal(Color::Black, 3)),
+            ]
+        );
+        assert_eq!(
+            stats.goals,
+            vec![goal(Color::Black, 3), goal(Color::White, 5)]
+        );
+
+        stats.restore_goals(&removed);
+        assert_eq!(stats.goals, goals);
+
+        let removed = stats.trim_goals(0, 2);
+        assert_eq!(
+            stats.goals,
+            vec![goal(Color::White, 5), goal(Color::White, 6)]
+        );
+        stats.restore_goals(&removed);
+        assert_eq!(stats.goals, goals);
+    }
+
+    #[test]
+    fn test_game_table() {
+        let stats = GameStats {
+            goals: vec![
+                goal(Color::Black, 3),
+                goal(Color::White, 7),
+                goal(Color::Black, 3),
+                goal(Color::Black, 9),
+            ],
+            penalties: vec![
+                PenaltyRecord::new(Color::White, &penalty(7, PenaltyKind::from_secs(60))),
+                PenaltyRecord::new(Color::White, &penalty(7, PenaltyKind::from_secs(120))),
+                PenaltyRecord::new(Color::Black, &penalty(1, PenaltyKind::TotalDismissal)),
+            ],
+        };
+
+        let row = |team: &str, player_number, goals, penalties, penalty_secs, total_dismissals| {
+            PlayerStats {
+                team: team.to_string(),
+                player_number,
+                goals,
+                penalties,
+                penalty_secs,
+                total_dismissals,
+            }
+        };
+
+        assert_eq!(
+            stats.player_stats("Black", "White"),
+            vec![
+                row("Black", 3, 2, 0, 0, 0),
+                row("Black", 9, 1, 0, 0, 0),
+                row("White", 7, 1, 2, 180, 0),
+                row("Black", 1, 0, 1, 0, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_edit_records() {
+        let mut stats = GameStats {
+            goals: vec![
+                goal(Color::Black, 3),
+                goal(Color::White, 3),
+                GoalRecord {
+                    clock_secs: 100,
+                    ..goal(Color::Black, 3)
+                },
+            ],
+            penalties: vec![PenaltyRecord::new(
+                Color::Black,
+                &penalty(4, PenaltyKind::from_secs(60)),
+            )],
+        };
+
+        assert_eq!(stats.remove_goal(Color::Black, 3).unwrap().clock_secs, 100);
+        assert_eq!(stats.remove_goal(Color::Black, 4), None);
+        assert_eq!(
+            stats.goals,
+            vec![goal(Color::Black, 3), goal(Color::White, 3)]
+        );
+
+        let old = penalty(4, PenaltyKind::from_secs(60));
+        let new = penalty(5, PenaltyKind::from_secs(120));
+        stats.replace_penalty(Color::Black, &old, Color::White, &new);
+        assert_eq!(
+            stats.penalties,
+            vec![PenaltyRecord::new(Color::White, &new)]
+        );
+        assert_eq!(stats.remove_penalty(Color::Black, &new), None);
+        assert!(stats.remove_penalty(Color::White, &new).is_some());
+        assert!(stats.penalties.is_empty());
+    }
+
+    #[test]
+    fn test_session_table() {
+        let game_1 = log(
+            1,
+            Some(("Sharks", "Orcas")),
+            GameStats {
+                goals: vec![goal(Color::Black, 3), goal(Color::White, 8)],
+                penalties: vec![],
+            },
+        );
+        let game_2 = log(
+            2,
+            Some(("Orcas", "Sharks")),
+            GameStats {
+                goals: vec![goal(Color::White, 3), goal(Color::Black, 2)],
+                penalties: vec![PenaltyRecord::new(
+                    Color::Black,
+                    &penalty(8, PenaltyKind::from_secs(60)),
+                )],
+            },
+        );
+        let game_3 = log(
+            3,
+            None,
+            GameStats {
+                goals: vec![goal(Color::White, 3)],
+                penalties: vec![],
+            },
+        );
+
+        let table = session_player_stats([&game_1, &game_2, &game_3]);
+        let summary: Vec<_> = table
+            .iter()
+            .map(|row| {
+                (
+                    row.team.as_str(),
+                    row.player_number,
+                    row.goals,
+                    row.penalties,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Sharks", 3, 2, 0),
+                ("Game 3 White", 3, 1, 0),
+                ("Orcas", 2, 1, 0),
+                ("Orcas", 8, 1, 1),
+            ]
+        );
+
+        assert_eq!(session_player_stats([&game_3]), table[1..2]);
+    }
+}
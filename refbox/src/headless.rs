@@ -0,0 +1,266 @@
+//! Runs a court without the GUI, for example on a Raspberry Pi with no screen attached.
+//!
+//! The game is controlled with the remote control API (see `app::remote_control`) and with the
+//! same commands typed on the console, one JSON object per line without the key. Games use the
+//! timings from the config file, uwhscores is not used.
+
+use crate::{
+    app::{
+        discovery,
+        remote_control::{self, RemoteCommand, RemoteCommandError},
+        rest_api::{self, RestApiData},
+        update_sender::UpdateSender,
+        Message,
+    },
+    config::Config,
+    game_flow,
+    sound_controller::SoundController,
+    state_journal::StateJournal,
+    tournament_manager::TournamentManager,
+};
+use log::*;
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+use tokio::{
+    runtime, select,
+    sync::mpsc,
+    time::{sleep_until, Duration, Instant},
+};
+use tokio_serial::SerialPortBuilder;
+use uwh_common::{
+    discovery::{snapshot_multicast_addr, Announcement},
+    game_snapshot::{GamePeriod, GameSnapshot},
+};
+
+/// How long to wait before trying again when the game couldn't be updated
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+pub struct HeadlessFlags {
+    pub config: Config,
+    pub serial_ports: Vec<SerialPortBuilder>,
+    pub framed_serial: bool,
+    pub binary_port: u16,
+    pub json_port: u16,
+    pub websocket_port: u16,
+    pub rest_port: u16,
+    pub announce: bool,
+    pub multicast_snapshots: bool,
+    pub record_path: Option<PathBuf>,
+    pub play_sounds: bool,
+    pub sound_dir: PathBuf,
+    pub state_journal: StateJournal,
+    pub game_log_dir: PathBuf,
+}
+
+/// Runs the refbox until the process is stopped
+pub fn run(flags: HeadlessFlags) -> std::io::Result<()> {
+    let runtime = runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    runtime.block_on(async move {
+        // Holding on to `msg_tx` keeps the game running even if the console is closed and remote
+        // control is disabled
+        let (msg_tx, msg_rx) = mpsc::unbounded_channel();
+        let server = HeadlessRefBox::new(flags, msg_tx.clone());
+        server.run(msg_rx).await;
+    });
+    Ok(())
+}
+
+struct HeadlessRefBox {
+    tm: Arc<Mutex<TournamentManager>>,
+    config: Config,
+    snapshot: GameSnapshot,
+    update_sender: UpdateSender,
+    sound: Option<SoundController>,
+    rest_data: RestApiData,
+    state_journal: StateJournal,
+    game_log_dir: PathBuf,
+}
+
+impl HeadlessRefBox {
+    fn new(flags: HeadlessFlags, msg_tx: mpsc::UnboundedSender<Message>) -> Self {
+        let HeadlessFlags {
+            config,
+            serial_ports,
+            framed_serial,
+            binary_port,
+            json_port,
+            websocket_port,
+            rest_port,
+            announce,
+            multicast_snapshots,
+            record_path,
+            play_sounds,
+            sound_dir,
+            state_journal,
+            game_log_dir,
+        } = flags;
+
+        let mut tm = TournamentManager::new(config.game.clone());
+        tm.set_timezone(config.uwhscores.timezone);
+        let now = Instant::now();
+        match state_journal.load() {
+            Some(state) if state.current_period != GamePeriod::BetweenGames => {
+                // There's nobody to ask whether to resume, so always resume with the clock
+                // stopped, and let the ref restart it
+                info!("Resuming an interrupted game with the clock stopped");
+                tm.restore_state(state, now);
+            }
+            _ => tm.start_clock(now),
+        }
+        let tm = Arc::new(Mutex::new(tm));
+
+        if config.remote_control.enabled {
+            remote_control::start(config.remote_control.clone(), msg_tx.clone());
+        } else {
+            warn!("Remote control is disabled, commands will only be accepted from the console");
+        }
+        remote_control::start_console(msg_tx);
+
+        let rest_data = RestApiData::new(tm.clone());
+        rest_api::start(rest_port, rest_data.clone());
+
+        let snapshot_multicast = multicast_snapshots.then(snapshot_multicast_addr);
+        let update_sender = UpdateSender::new(
+            serial_ports,
+            framed_serial,
+            binary_port,
+            json_port,
+            websocket_port,
+            snapshot_multicast.map(Into::into),
+        );
+        if let Some(path) = record_path {
+            if let Err(e) = update_sender.record_to(&path) {
+                error!("Failed to start recording snapshots to {path:?}: {e}");
+            }
+        }
+
+        if announce {
+            discovery::start_announcer(
+                Announcement {
+                    binary_port,
+                    json_port,
+                    websocket_port: Some(websocket_port),
+                    rest_port: Some(rest_port),
+                    snapshot_multicast,
+                },
+                discovery::default_destinations(),
+            );
+        }
+
+        let sound = play_sounds.then(|| {
+            SoundController::new(
+                config.sound.clone(),
+                Some(&sound_dir),
+                update_sender.get_trigger_flash_fn(),
+            )
+        });
+
+        Self {
+            tm,
+            config,
+            snapshot: Default::default(),
+            update_sender,
+            sound,
+            rest_data,
+            state_journal,
+            game_log_dir,
+        }
+    }
+
+    async fn run(mut self, mut msg_rx: mpsc::UnboundedReceiver<Message>) {
+        let mut clock_running_rx = self.tm.lock().unwrap().get_start_stop_rx();
+        let mut next_update = Some(Instant::now());
+
+        loop {
+            let update_due = async {
+                match next_update {
+                    Some(time) => sleep_until(time).await,
+                    None => core::future::pending().await,
+                }
+            };
+
+            select! {
+                _ = update_due => {}
+                changed = clock_running_rx.changed() => {
+                    if changed.is_err() {
+                        return;
+                    }
+                }
+                msg = msg_rx.recv() => match msg {
+                    Some(Message::RemoteCommand(command, responder)) => {
+                        responder.respond(self.apply_command(command));
+                    }
+                    Some(_) => {}
+                    None => return,
+                },
+            }
+
+            next_update = self.update();
+        }
+    }
+
+    fn apply_command(&mut self, command: RemoteCommand) -> Result<(), RemoteCommandError> {
+        // The new state is sent out by the update that follows every message
+        let mut tm = self.tm.lock().unwrap();
+        remote_control::apply_command(&mut tm, command, Instant::now())
+    }
+
+    /// Brings the game up to date, sends out the new snapshot, and returns when the next update
+    /// is due
+    fn update(&mut self) -> Option<Instant> {
+        let now = Instant::now();
+        let tick = {
+            let mut tm = self.tm.lock().unwrap();
+            game_flow::tick(&mut tm, now).and_then(|tick| {
+                if tick.game_ending {
+                    // There's nobody to confirm the scores, so the game ends as soon as time runs
+                    // out
+                    game_flow::end_game(&mut tm, now)
+                } else {
+                    Ok(tick)
+                }
+            })
+        };
+
+        match tick {
+            Ok(tick) => {
+                self.apply_snapshot(tick.snapshot);
+                tick.next_update
+            }
+            Err(e) => {
+                error!("Failed to update the game: {e}");
+                Some(now + RETRY_DELAY)
+            }
+        }
+    }
+
+    fn apply_snapshot(&mut self, snapshot: GameSnapshot) {
+        if snapshot.current_period == GamePeriod::BetweenGames
+            && self.snapshot.current_period != GamePeriod::BetweenGames
+        {
+            let log = self.tm.lock().unwrap().take_completed_game_log();
+            if let Some(log) = log {
+                game_flow::save_game_log(log, &self.game_log_dir, &self.rest_data);
+            }
+        }
+
+        if let Some(ref sound) = self.sound {
+            game_flow::play_auto_sounds(&self.config.sound, sound, &self.snapshot, &snapshot);
+        }
+
+        game_flow::publish_snapshot(
+            &snapshot,
+            &self.update_sender,
+            &self.rest_data,
+            self.config.hardware.white_on_right,
+        );
+        self.snapshot = snapshot;
+
+        game_flow::save_state(&self.tm, &mut self.state_journal);
+    }
+}
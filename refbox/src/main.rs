@@ -1,5 +1,6 @@
 #![cfg_attr(windows, windows_subsystem = "windows")]
 
+use app::update_sender::{recording, UpdateSender};
 use clap::Parser;
 use iced::{pure::Application, window::icon::Icon, Settings};
 use log::*;
@@ -15,23 +16,34 @@ use log4rs::{
     config::{Appender, Config as LogConfig, Logger, Root},
     encode::pattern::PatternEncoder,
 };
+use matrix_drawing::PanelGeometry;
 use std::{
     path::PathBuf,
     process::{Command, Stdio},
+    time::Duration,
 };
 use tokio_serial::{DataBits, FlowControl, Parity, StopBits};
 
 mod app;
 mod app_icon;
+mod game_flow;
+mod game_log;
+mod game_stats;
+mod headless;
 mod penalty_editor;
 mod sim_app;
 mod sound_controller;
+mod state_journal;
 mod tournament_manager;
+mod uwhscores_cache;
+#[cfg(test)]
+mod uwhscores_mock;
 
 mod config;
 use config::Config;
 
 const APP_NAME: &str = "refbox";
+const REPLAY_START_DELAY: Duration = Duration::from_secs(2);
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
@@ -52,6 +64,10 @@ struct Cli {
     /// Spacing between pixels in the panel the simulator
     spacing: Option<f32>,
 
+    #[clap(long, default_value = "256x64")]
+    /// Arrangement of the LED panels to show in the simulator: 256x64, 192x64 or 128x32
+    panel_geometry: PanelGeometry,
+
     #[clap(long, short)]
     /// Make the app fullscreen
     fullscreen: bool,
@@ -64,6 +80,22 @@ struct Cli {
     /// Port to listen on for TCP connections with a JSON send type
     json_port: u16,
 
+    #[clap(long, default_value = "8002")]
+    /// Port to listen on for WebSocket connections, which are sent JSON snapshots
+    websocket_port: u16,
+
+    #[clap(long, default_value = "8004")]
+    /// Port for the read-only HTTP API
+    rest_port: u16,
+
+    #[clap(long)]
+    /// Don't announce the refbox on the local network for displays to discover
+    no_discovery: bool,
+
+    #[clap(long)]
+    /// Publish binary snapshots to a multicast group on the local network
+    multicast_snapshots: bool,
+
     #[clap(long, default_missing_value = "/dev/ttyUSB0")]
     /// Serial Port to send snapshots to
     serial_port: Option<String>,
@@ -72,6 +104,35 @@ struct Cli {
     /// Baud rate for the serial port
     baud_rate: u32,
 
+    #[clap(long)]
+    /// Send the framed, checksummed format to the serial port instead of the bare format
+    framed_serial: bool,
+
+    #[clap(long)]
+    /// Run without the GUI, controlled by the remote control API and commands on stdin
+    headless: bool,
+
+    #[clap(long, requires = "headless")]
+    /// Don't play any sounds when running headless
+    no_sound: bool,
+
+    #[clap(long)]
+    /// Record every snapshot sent to the displays to this file
+    record: Option<PathBuf>,
+
+    #[clap(long, conflicts_with = "record")]
+    /// Replay a recording made with `--record` to the displays instead of running the refbox
+    replay: Option<PathBuf>,
+
+    #[clap(long, default_value = "1")]
+    /// How many times faster than real time to replay a recording
+    replay_speed: f64,
+
+    #[clap(long)]
+    /// Directory to load custom buzzer sounds from (WAV, FLAC or Ogg Vorbis files), default is
+    /// `sounds` next to the config file
+    sound_dir: Option<PathBuf>,
+
     #[clap(long)]
     /// Don't require HTTPS to connect to uwhscores
     allow_http: bool,
@@ -119,6 +180,8 @@ fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
     } else {
         "refbox"
     };
+    let mut game_log_path = log_base_path.clone();
+    game_log_path.push("games");
     let mut log_path = log_base_path.clone();
     let mut archived_log_path = log_base_path.clone();
     log_path.push(format!("{app_name}-log.txt"));
@@ -186,10 +249,11 @@ fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
     if args.is_simulator {
         let flags = sim_app::SimRefBoxAppFlags {
             tcp_port: args.binary_port,
+            geometry: args.panel_geometry,
         };
 
         let mut settings = Settings::with_flags(flags);
-        settings.window.size = sim_app::window_size(args.scale, spacing);
+        settings.window.size = sim_app::window_size(args.scale, spacing, args.panel_geometry);
         settings.window.resizable = true;
         settings.window.icon = Some(icon);
         info!("Starting Simulator UI");
@@ -200,7 +264,7 @@ fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
         info!("Starting RefBox App");
     }
 
-    let child = if args.no_simulate {
+    let child = if args.no_simulate || args.headless {
         None
     } else {
         let bin_name = std::env::current_exe()?.into_os_string();
@@ -210,6 +274,7 @@ fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
         let json_port = args.json_port.to_string();
         let scale = args.scale.to_string();
         let spacing = spacing.to_string();
+        let panel_geometry = args.panel_geometry.to_string();
         let log_location = log_base_path.to_str().unwrap().to_string();
         let log_max_file_size = args.log_max_file_size.to_string();
         let num_old_logs = args.num_old_logs.to_string();
@@ -224,6 +289,8 @@ fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
             &scale,
             "--spacing",
             &spacing,
+            "--panel-geometry",
+            &panel_geometry,
             "--log-location",
             &log_location,
             "--log-max-file-size",
@@ -260,6 +327,50 @@ fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
         vec![]
     };
 
+    if let Some(path) = args.replay {
+        if !(args.replay_speed.is_finite() && args.replay_speed > 0.0) {
+            return Err(format!("Invalid replay speed: {}", args.replay_speed).into());
+        }
+        let records = recording::read_recording(&path)?;
+        info!(
+            "Replaying {} snapshots from {path:?} at {}x speed",
+            records.len(),
+            args.replay_speed
+        );
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        runtime.block_on(async {
+            let update_sender = UpdateSender::new(
+                serial_ports,
+                args.framed_serial,
+                args.binary_port,
+                args.json_port,
+                args.websocket_port,
+                None,
+            );
+            // Give the displays a chance to connect before the replay starts
+            tokio::time::sleep(REPLAY_START_DELAY).await;
+            recording::replay(&records, &update_sender, args.replay_speed).await;
+
+            // Keep serving the last snapshot until the simulator is closed, or until the user
+            // stops the replay if there is no simulator
+            if let Some(mut child) = child {
+                if let Err(e) = tokio::task::spawn_blocking(move || child.wait()).await {
+                    error!("Failed to wait for the simulator: {e}");
+                }
+            } else {
+                info!("Replay finished, press Ctrl-C to exit");
+                if let Err(e) = tokio::signal::ctrl_c().await {
+                    error!("Failed to wait for Ctrl-C: {e}");
+                }
+            }
+        });
+
+        return Ok(());
+    }
+
     info!(
         "Reading config file from {:?}",
         confy::get_configuration_file_path(APP_NAME, None).unwrap()
@@ -275,6 +386,13 @@ fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
         }
     };
 
+    if !config.game.periods.is_empty() && !config.game.uses_custom_periods() {
+        warn!(
+            "The periods list in the config file is ignored, because it must start and end with \
+             a play period and can't have two breaks in a row. Two halves will be played instead."
+        );
+    }
+
     if let Ok(offset) = time::UtcOffset::current_local_offset() {
         if offset != config.uwhscores.timezone {
             warn!(
@@ -287,6 +405,44 @@ fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
         confy::store(APP_NAME, None, &config).unwrap();
     }
 
+    let state_path = confy::get_configuration_file_path(APP_NAME, None)
+        .unwrap()
+        .with_file_name("game-state.json");
+    info!("Saving game state to {state_path:?}");
+
+    let sound_dir = args
+        .sound_dir
+        .unwrap_or_else(|| state_path.with_file_name("sounds"));
+    // Create the directory so that it's easy to find where to put custom sounds
+    if let Err(e) = std::fs::create_dir_all(&sound_dir) {
+        warn!("Failed to create the custom sound directory {sound_dir:?}: {e}");
+    }
+    info!("Loading custom sounds from {sound_dir:?}");
+
+    let uwhscores_cache_path = state_path.with_file_name("uwhscores-cache");
+    let score_outbox_path = state_path.with_file_name("score-outbox.json");
+
+    if args.headless {
+        info!("Running headless");
+        headless::run(headless::HeadlessFlags {
+            config,
+            serial_ports,
+            framed_serial: args.framed_serial,
+            binary_port: args.binary_port,
+            json_port: args.json_port,
+            websocket_port: args.websocket_port,
+            rest_port: args.rest_port,
+            announce: !args.no_discovery,
+            multicast_snapshots: args.multicast_snapshots,
+            record_path: args.record,
+            play_sounds: !args.no_sound,
+            sound_dir,
+            state_journal: state_journal::StateJournal::new(state_path),
+            game_log_dir: game_log_path,
+        })?;
+        return Ok(());
+    }
+
     let window_size = (
         config.hardware.screen_x as u32,
         config.hardware.screen_y as u32,
@@ -295,12 +451,23 @@ fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
     let flags = app::RefBoxAppFlags {
         config,
         serial_ports,
+        framed_serial: args.framed_serial,
         binary_port: args.binary_port,
         json_port: args.json_port,
+        websocket_port: args.websocket_port,
+        rest_port: args.rest_port,
+        announce: !args.no_discovery,
+        multicast_snapshots: args.multicast_snapshots,
+        record_path: args.record,
+        sound_dir,
         sim_child: child,
         require_https: !args.allow_http,
         fullscreen: args.fullscreen,
         list_all_tournaments: args.all_tournaments,
+        state_journal: state_journal::StateJournal::new(state_path),
+        game_log_dir: game_log_path,
+        uwhscores_cache: uwhscores_cache::ResponseCache::new(uwhscores_cache_path),
+        score_outbox: uwhscores_cache::ScoreOutbox::load(score_outbox_path),
     };
 
     let mut settings = Settings::with_flags(flags);
@@ -306,13 +306,7 @@ fn generate_printable_list(
                 EditablePenalty::Deleted(_, _) => FormatHint::Deleted,
                 EditablePenalty::New(_, _) => FormatHint::New,
             };
-            let kind_str = match kind {
-                PenaltyKind::OneMinute => "1m",
-                PenaltyKind::TwoMinute => "2m",
-                PenaltyKind::FiveMinute => "5m",
-                PenaltyKind::TotalDismissal => "DSMS",
-            };
-            Some((format!("Player {p_num} - {time} ({kind_str})"), hint, kind))
+            Some((format!("Player {p_num} - {time} ({kind})"), hint, kind))
         })
         .collect()
 }
@@ -394,21 +388,23 @@ mod test {
         let mut pen_edit = PenaltyEditor::new(tm.clone());
 
         let b_pen = Penalty {
-            kind: PenaltyKind::OneMinute,
+            kind: PenaltyKind::from_secs(60),
             player_number: 3,
             start_period: GamePeriod::FirstHalf,
             start_time: Duration::from_secs(880),
+            start_index: Some(1),
         };
 
         let w_pen = Penalty {
-            kind: PenaltyKind::TwoMinute,
+            kind: PenaltyKind::from_secs(120),
             player_number: 13,
             start_period: GamePeriod::FirstHalf,
             start_time: Duration::from_secs(880),
+            start_index: Some(1),
         };
 
         assert_eq!(
-            pen_edit.add_penalty(Color::Black, 4, PenaltyKind::OneMinute),
+            pen_edit.add_penalty(Color::Black, 4, PenaltyKind::from_secs(60)),
             Err(PenaltyEditorError::NotInSession)
         );
 
@@ -464,17 +460,19 @@ mod test {
         now += Duration::from_secs(5);
 
         let b_pen_0 = Penalty {
-            kind: PenaltyKind::OneMinute,
+            kind: PenaltyKind::from_secs(60),
             player_number: 7,
             start_period: GamePeriod::FirstHalf,
             start_time: Duration::from_secs(895),
+            start_index: Some(1),
         };
 
         let w_pen_0 = Penalty {
-            kind: PenaltyKind::FiveMinute,
+            kind: PenaltyKind::from_secs(300),
             player_number: 4,
             start_period: GamePeriod::FirstHalf,
             start_time: Duration::from_secs(895),
+            start_index: Some(1),
         };
 
         let b_pen_1 = Penalty {
@@ -482,13 +480,15 @@ mod test {
             player_number: 13,
             start_period: GamePeriod::FirstHalf,
             start_time: Duration::from_secs(895),
+            start_index: Some(1),
         };
 
         let w_pen_1 = Penalty {
-            kind: PenaltyKind::OneMinute,
+            kind: PenaltyKind::from_secs(60),
             player_number: 6,
             start_period: GamePeriod::FirstHalf,
             start_time: Duration::from_secs(895),
+            start_index: Some(1),
         };
 
         tm.start_penalty(Color::Black, b_pen_0.player_number, b_pen_0.kind, now)
@@ -583,7 +583,7 @@ mod test {
         pen_edit
             .penalties
             .black
-            .push(EditablePenalty::New(PenaltyKind::TwoMinute, 9));
+            .push(EditablePenalty::New(PenaltyKind::from_secs(120), 9));
 
         pen_edit
             .penalties
@@ -592,7 +592,7 @@ mod test {
         pen_edit
             .penalties
             .white
-            .push(EditablePenalty::New(PenaltyKind::TwoMinute, 3));
+            .push(EditablePenalty::New(PenaltyKind::from_secs(120), 3));
 
         pen_edit.delete_penalty(Color::Black, 1).unwrap();
         pen_edit.delete_penalty(Color::Black, 2).unwrap();
@@ -651,26 +651,28 @@ mod test {
         now += Duration::from_secs(5);
 
         let b_pen_0 = Penalty {
-            kind: PenaltyKind::OneMinute,
+            kind: PenaltyKind::from_secs(60),
             player_number: 7,
             start_period: GamePeriod::FirstHalf,
             start_time: Duration::from_secs(895),
+            start_index: Some(1),
         };
 
         let b_pen_0_ed = Penalty {
-            kind: PenaltyKind::TwoMinute,
+            kind: PenaltyKind::from_secs(120),
             ..b_pen_0
         };
 
         let w_pen_0 = Penalty {
-            kind: PenaltyKind::FiveMinute,
+            kind: PenaltyKind::from_secs(300),
             player_number: 4,
             start_period: GamePeriod::FirstHalf,
             start_time: Duration::from_secs(895),
+            start_index: Some(1),
         };
 
         let w_pen_0_ed = Penalty {
-            kind: PenaltyKind::TwoMinute,
+            kind: PenaltyKind::from_secs(120),
             ..w_pen_0
         };
 
@@ -679,46 +681,50 @@ mod test {
             player_number: 13,
             start_period: GamePeriod::FirstHalf,
             start_time: Duration::from_secs(895),
+            start_index: Some(1),
         };
 
         let b_pen_1_ed = Penalty {
-            kind: PenaltyKind::FiveMinute,
+            kind: PenaltyKind::from_secs(300),
             ..b_pen_1
         };
 
         let w_pen_1 = Penalty {
-            kind: PenaltyKind::OneMinute,
+            kind: PenaltyKind::from_secs(60),
             player_number: 6,
             start_period: GamePeriod::FirstHalf,
             start_time: Duration::from_secs(895),
+            start_index: Some(1),
         };
 
         let w_pen_1_ed = Penalty {
-            kind: PenaltyKind::FiveMinute,
+            kind: PenaltyKind::from_secs(300),
             ..w_pen_1
         };
 
         let b_pen_2 = Penalty {
-            kind: PenaltyKind::FiveMinute,
+            kind: PenaltyKind::from_secs(300),
             player_number: 1,
             start_period: GamePeriod::FirstHalf,
             start_time: Duration::from_secs(895),
+            start_index: Some(1),
         };
 
         let b_pen_2_ed = Penalty {
-            kind: PenaltyKind::TwoMinute,
+            kind: PenaltyKind::from_secs(120),
             ..b_pen_2
         };
 
         let w_pen_2 = Penalty {
-            kind: PenaltyKind::TwoMinute,
+            kind: PenaltyKind::from_secs(120),
             player_number: 8,
             start_period: GamePeriod::FirstHalf,
             start_time: Duration::from_secs(895),
+            start_index: Some(1),
         };
 
         let w_pen_2_ed = Penalty {
-            kind: PenaltyKind::OneMinute,
+            kind: PenaltyKind::from_secs(60),
             player_number: 9,
             ..w_pen_2
         };
@@ -736,7 +742,7 @@ mod test {
         let mut pen_edit = PenaltyEditor::new(tm.clone());
 
         assert_eq!(
-            pen_edit.edit_penalty(Color::Black, 0, Color::Black, 2, PenaltyKind::OneMinute),
+            pen_edit.edit_penalty(Color::Black, 0, Color::Black, 2, PenaltyKind::from_secs(60)),
             Err(PenaltyEditorError::NotInSession)
         );
 
@@ -849,7 +855,7 @@ mod test {
         pen_edit
             .penalties
             .white
-            .push(EditablePenalty::New(PenaltyKind::TwoMinute, 2));
+            .push(EditablePenalty::New(PenaltyKind::from_secs(120), 2));
 
         pen_edit
             .edit_penalty(
@@ -880,7 +886,13 @@ mod test {
             )
             .unwrap();
         pen_edit
-            .edit_penalty(Color::White, 3, Color::White, 3, PenaltyKind::FiveMinute)
+            .edit_penalty(
+                Color::White,
+                3,
+                Color::White,
+                3,
+                PenaltyKind::from_secs(300),
+            )
             .unwrap();
 
         assert_eq!(
@@ -898,7 +910,7 @@ mod test {
                 EditablePenalty::Edited(w_origin(0), w_pen_0_ed.clone()),
                 EditablePenalty::Edited(b_origin(1), b_pen_1_ed.clone()),
                 EditablePenalty::Edited(w_origin(2), w_pen_2_ed),
-                EditablePenalty::New(PenaltyKind::FiveMinute, 3)
+                EditablePenalty::New(PenaltyKind::from_secs(300), 3)
             ]
         );
 
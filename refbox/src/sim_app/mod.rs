@@ -12,7 +12,7 @@ use iced_futures::{
     subscription::Recipe,
 };
 use log::*;
-use matrix_drawing::{draw_panels, transmitted_data::TransmittedData};
+use matrix_drawing::{draw_panels_with_geometry, transmitted_data::TransmittedData, PanelGeometry};
 use std::{hash::Hasher, rc::Rc, sync::Mutex};
 use tokio::{
     net::TcpStream,
@@ -23,13 +23,16 @@ mod display_simulator;
 use display_simulator::*;
 
 const WINDOW_BACKGROUND: Color = Color::BLACK;
-const WIDTH: usize = 256;
-const HEIGHT: usize = 64;
+/// Big enough for any of the supported geometries
+const MAX_WIDTH: usize = 256;
+const MAX_HEIGHT: usize = 64;
 
-pub fn window_size(scale: f32, spacing: f32) -> (u32, u32) {
+pub fn window_size(scale: f32, spacing: f32, geometry: PanelGeometry) -> (u32, u32) {
+    let size = geometry.size();
+    let (width, height) = (size.width as f32, size.height as f32);
     (
-        (WIDTH as f32 * scale + ((WIDTH as f32 + 1.0) * spacing)).ceil() as u32,
-        (HEIGHT as f32 * scale + ((HEIGHT as f32 + 1.0) * spacing)).ceil() as u32,
+        (width * scale + ((width + 1.0) * spacing)).ceil() as u32,
+        (height * scale + ((height + 1.0) * spacing)).ceil() as u32,
     )
 }
 
@@ -42,7 +45,8 @@ pub enum Message {
 
 #[derive(Debug)]
 pub struct SimRefBoxApp {
-    buffer: Rc<Mutex<DisplayBuffer<WIDTH, HEIGHT>>>,
+    buffer: Rc<Mutex<DisplayBuffer<MAX_WIDTH, MAX_HEIGHT>>>,
+    geometry: PanelGeometry,
     cache: Cache,
     listener: SnapshotListener,
     should_stop: bool,
@@ -51,6 +55,7 @@ pub struct SimRefBoxApp {
 #[derive(Clone, Debug)]
 pub struct SimRefBoxAppFlags {
     pub tcp_port: u16,
+    pub geometry: PanelGeometry,
 }
 
 impl Application for SimRefBoxApp {
@@ -59,11 +64,12 @@ impl Application for SimRefBoxApp {
     type Flags = SimRefBoxAppFlags;
 
     fn new(flags: Self::Flags) -> (Self, Command<Message>) {
-        let Self::Flags { tcp_port } = flags;
+        let Self::Flags { tcp_port, geometry } = flags;
 
         (
             Self {
                 buffer: Rc::new(Mutex::new(Default::default())),
+                geometry,
                 cache: Cache::new(),
                 listener: SnapshotListener { port: tcp_port },
                 should_stop: false,
@@ -77,7 +83,7 @@ impl Application for SimRefBoxApp {
     }
 
     fn title(&self) -> String {
-        "Panel Simulator".into()
+        format!("Panel Simulator ({})", self.geometry)
     }
 
     fn background_color(&self) -> iced::Color {
@@ -94,7 +100,14 @@ impl Application for SimRefBoxApp {
             Message::NewSnapshot(data) => {
                 let mut buffer = self.buffer.lock().unwrap();
                 buffer.clear_buffer();
-                draw_panels(&mut *buffer, data.snapshot, data.white_on_right, data.flash).unwrap();
+                draw_panels_with_geometry(
+                    &mut *buffer,
+                    data.snapshot,
+                    data.white_on_right,
+                    data.flash,
+                    self.geometry,
+                )
+                .unwrap();
                 self.cache.clear();
             }
             Message::Stop => self.should_stop = true,
@@ -115,12 +128,14 @@ impl Application for SimRefBoxApp {
 impl<Message> Program<Message> for SimRefBoxApp {
     fn draw(&self, bounds: Rectangle, _cursor: Cursor) -> Vec<Geometry> {
         let buffer_ = self.buffer.clone();
+        let size = self.geometry.size();
+        let (width, height) = (size.width as usize, size.height as usize);
         let panel =
             self.cache.draw(bounds.size(), |frame| {
                 let buffer = buffer_.lock().unwrap();
 
-                let horiz_spacing = frame.width() / ((WIDTH * 5 + 1) as f32);
-                let vert_spacing = frame.height() / ((HEIGHT * 5 + 1) as f32);
+                let horiz_spacing = frame.width() / ((width * 5 + 1) as f32);
+                let vert_spacing = frame.height() / ((height * 5 + 1) as f32);
                 let spacing = if horiz_spacing > vert_spacing {
                     vert_spacing
                 } else {
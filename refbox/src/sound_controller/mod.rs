@@ -1,6 +1,4 @@
 #[cfg(target_os = "linux")]
-use arrayref::array_ref;
-#[cfg(target_os = "linux")]
 use core::{
     future::Future,
     pin::Pin,
@@ -15,7 +13,7 @@ use macro_attr_2018::macro_attr;
 #[cfg(target_os = "linux")]
 use rppal::gpio::{Gpio, InputPin, Level, Trigger};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::{path::Path, sync::Arc};
 #[cfg(target_os = "linux")]
 use tokio::{
     sync::watch::Receiver,
@@ -43,19 +41,18 @@ const FADE_WAIT: Duration = Duration::from_millis(50); // TODO: base this on `FA
 
 const SOUND_LEN: f64 = 2.0;
 
-#[cfg(target_os = "linux")]
-const MESSAGE_LEN: usize = 24;
-#[cfg(target_os = "linux")]
-const ID_LEN: usize = 20;
-#[cfg(target_os = "linux")]
-const DATA_LEN: usize = MESSAGE_LEN - ID_LEN;
-
 #[cfg(target_os = "linux")]
 const BUTTON_TIMEOUT: Duration = Duration::from_millis(500);
 
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+mod remote_decoder;
+
 mod sounds;
 pub use sounds::*;
 
+mod warnings;
+pub use warnings::*;
+
 use crate::app::update_sender::ServerMessage;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Derivative)]
@@ -75,6 +72,9 @@ pub struct SoundSettings {
     #[derivative(Default(value = "true"))]
     pub auto_sound_stop_play: bool,
     pub remotes: Vec<RemoteInfo>,
+    #[serde(default = "default_warning_rules")]
+    #[derivative(Default(value = "default_warning_rules()"))]
+    pub warning_rules: Vec<WarningRule>,
 }
 
 macro_attr! {
@@ -120,6 +120,7 @@ enum SoundMessage {
 
 pub struct SoundController {
     _context: Arc<AudioContext>,
+    custom_sounds: Vec<String>,
     msg_tx: UnboundedSender<SoundMessage>,
     settings_tx: Sender<SoundSettings>,
     stop_tx: Sender<bool>,
@@ -131,8 +132,9 @@ pub struct SoundController {
 }
 
 impl SoundController {
+    /// Custom sounds are loaded from the files in `sound_dir`, see `list_custom_sounds()`
     #[cfg_attr(not(target_os = "linux"), allow(unused_mut))]
-    pub fn new<F>(mut settings: SoundSettings, trigger_flash: F) -> Self
+    pub fn new<F>(mut settings: SoundSettings, sound_dir: Option<&Path>, trigger_flash: F) -> Self
     where
         F: Send
             + Fn() -> Result<(), tokio::sync::mpsc::error::TrySendError<ServerMessage>>
@@ -145,7 +147,8 @@ impl SoundController {
 
         let context = Arc::new(AudioContext::new(opts));
 
-        let library = SoundLibrary::new(&context);
+        let library = SoundLibrary::new(&*context, sound_dir);
+        let custom_sounds = library.custom_sounds();
 
         let (msg_tx, mut msg_rx) = unbounded_channel();
 
@@ -178,7 +181,7 @@ impl SoundController {
                                     SoundMessage::TriggerBuzzer => {
                                         info!("Auto-triggering buzzer");
                                         let volumes = ChannelVolumes::new(&_settings, false);
-                                        let sound = Sound::new(_context.clone(), volumes, library[_settings.buzzer_sound].clone(), true, true);
+                                        let sound = Sound::new(_context.clone(), volumes, library[&_settings.buzzer_sound].clone(), true, true);
                                         trigger_flash().unwrap();
                                         last_sound = Some(sound);
                                     }
@@ -191,9 +194,9 @@ impl SoundController {
                                     #[cfg(target_os = "linux")]
                                     SoundMessage::StartBuzzer(sound_option) => {
                                         info!("Starting buzzer");
-                                        let buzzer_sound = sound_option.unwrap_or(_settings.buzzer_sound);
+                                        let buzzer_sound = sound_option.unwrap_or_else(|| _settings.buzzer_sound.clone());
                                         let volumes = ChannelVolumes::new(&_settings, false);
-                                        let sound = Sound::new(_context.clone(), volumes, library[buzzer_sound].clone(), true, false);
+                                        let sound = Sound::new(_context.clone(), volumes, library[&buzzer_sound].clone(), true, false);
                                         trigger_flash().unwrap();
                                         last_sound = Some(sound);
                                     }
@@ -231,12 +234,6 @@ impl SoundController {
             let gpio = Gpio::new().unwrap();
 
             let mut ant_pin = gpio.get(16).unwrap().into_input_pullup();
-            let (ant_tx, mut ant_rx) = unbounded_channel();
-            ant_pin
-                .set_async_interrupt(Trigger::Both, move |level| {
-                    ant_tx.send((level, Instant::now())).unwrap()
-                })
-                .unwrap();
 
             let mut wired_pin = gpio.get(12).unwrap().into_input_pullup();
             let (wired_tx, mut wired_rx) = unbounded_channel();
@@ -244,121 +241,8 @@ impl SoundController {
                 .set_async_interrupt(Trigger::Both, move |level| wired_tx.send(level).unwrap())
                 .unwrap();
 
-            let ant_start_state = ant_pin.read();
-
-            let (wireless_tx, mut wireless_rx) = unbounded_channel();
-            let (remote_id_tx, mut remote_id_rx) = watch::channel(0);
-            remote_id_rx.borrow_and_update();
-
-            let mut _stop_rx = stop_rx.clone();
-
-            let wireless_button_listener = task::spawn(async move {
-                let mut state = RemoteDetectorState {
-                    preamble_detected: false,
-                    bits: vec![],
-                    last_pin_state: ant_start_state,
-                    last_edge_time: Instant::now(),
-                    last_pulse: None,
-                };
-
-                loop {
-                    tokio::select! {
-                        pin_update = ant_rx.recv() => {
-                            match pin_update {
-                                Some((l @ Level::High, now)) => {
-                                    if state.last_pin_state != l {
-                                        let pulse = now.duration_since(state.last_edge_time).as_micros();
-                                        trace!("Detected LOW  pulse {pulse:>5}us long");
-                                        state.last_pin_state = l;
-                                        state.last_edge_time = now;
-
-                                        let maybe_pulse_type = identify_pulse(pulse);
-                                        debug!("Detected a LOW  pulse of length {maybe_pulse_type:?}");
-
-                                        if let Some(pulse_type) = maybe_pulse_type {
-                                            if !state.preamble_detected {
-                                                if matches!(state.last_pulse, Some(PulseType::Short))
-                                                    && pulse_type == PulseType::Preamble
-                                                {
-                                                    debug!("Detected a preamble");
-                                                    state.preamble_detected = true;
-                                                }
-                                            } else {
-                                                if matches!(state.last_pulse, Some(PulseType::Short))
-                                                    && pulse_type == PulseType::Long
-                                                {
-                                                    debug!("Detected a low bit");
-                                                    state.bits.push(false);
-                                                } else if matches!(state.last_pulse, Some(PulseType::Long))
-                                                    && pulse_type == PulseType::Short
-                                                {
-                                                    debug!("Detected a high bit");
-                                                    state.bits.push(true);
-                                                } else {
-                                                    info!("Detected an invalid pulse sequence");
-                                                    state.preamble_detected = false;
-                                                    state.bits.clear();
-                                                }
-
-                                                if state.bits.len() == MESSAGE_LEN {
-                                                    let message: String = state
-                                                        .bits
-                                                        .iter()
-                                                        .map(|bit| if *bit { '1' } else { '0' })
-                                                        .collect();
-                                                    debug!("Received a complete message: 0b{message}");
-
-                                                    let remote_id = state.bits[..ID_LEN]
-                                                        .iter()
-                                                        .fold(0, |acc, &b| acc * 2 + b as u32);
-                                                    let data = array_ref![state.bits, ID_LEN, DATA_LEN];
-
-                                                    debug!("Remote {remote_id} sent data {data:?}");
-                                                    wireless_tx.send(remote_id).unwrap();
-                                                    remote_id_tx.send(remote_id).unwrap();
-
-                                                    state.preamble_detected = false;
-                                                    state.bits.clear();
-                                                }
-                                            }
-                                        } else {
-                                            trace!("Detected an invalid pulse");
-                                            state.preamble_detected = false;
-                                            state.bits.clear();
-                                        }
-
-                                        state.last_pulse = maybe_pulse_type;
-                                    }
-                                }
-                                Some((l @ Level::Low, now)) => {
-                                    if state.last_pin_state != l {
-                                        let pulse = now.duration_since(state.last_edge_time).as_micros();
-                                        trace!("Detected HIGH pulse {pulse:>5}us long");
-                                        state.last_pin_state = l;
-                                        state.last_edge_time = now;
-
-                                        let maybe_pulse_type = identify_pulse(pulse);
-                                        debug!("Detected a LOW  pulse of length {maybe_pulse_type:?}");
-
-                                        if maybe_pulse_type.is_none() {
-                                            trace!("Detected an invalid pulse");
-                                            state.preamble_detected = false;
-                                            state.bits.clear();
-                                        }
-
-                                        state.last_pulse = maybe_pulse_type;
-                                    }
-                                }
-                                None => panic!("The Pin has been dropped"),
-                            }
-                        }
-                        _ = _stop_rx.changed() => {
-                            break;
-                        }
-                    }
-                }
-            });
-
+            let (wireless_button_listener, mut wireless_rx, remote_id_rx) =
+                remote_decoder::spawn_remote_listener(&mut ant_pin, stop_rx.clone());
             tasks.push(wireless_button_listener);
 
             let mut _msg_tx = msg_tx.clone();
@@ -399,7 +283,7 @@ impl SoundController {
                                 Some(id) => if let Some(rem) = settings.remotes.iter().find(|rem| rem.id == id) {
                                     wireless_pressed = true;
                                     wireless_expires = Some(Instant::now() + BUTTON_TIMEOUT);
-                                    sound = rem.sound;
+                                    sound = rem.sound.clone();
                                 }
                                 None => break,
                             }
@@ -423,13 +307,13 @@ impl SoundController {
                     if pressed != was_pressed || sound != last_sound {
                         _msg_tx
                             .send(if pressed {
-                                SoundMessage::StartBuzzer(sound)
+                                SoundMessage::StartBuzzer(sound.clone())
                             } else {
                                 SoundMessage::StopBuzzer
                             })
                             .unwrap();
                         was_pressed = pressed;
-                        last_sound = sound;
+                        last_sound = sound.clone();
                     }
                 }
             });
@@ -443,6 +327,7 @@ impl SoundController {
 
         Self {
             _context: context,
+            custom_sounds,
             msg_tx,
             settings_tx,
             stop_tx,
@@ -454,6 +339,11 @@ impl SoundController {
         }
     }
 
+    /// The file names of the custom sounds that were loaded, for use in `BuzzerSound::Custom`
+    pub fn custom_sounds(&self) -> &[String] {
+        &self.custom_sounds
+    }
+
     pub fn update_settings(&self, settings: SoundSettings) {
         self.settings_tx.send(settings).unwrap()
     }
@@ -618,41 +508,6 @@ impl Sound {
     }
 }
 
-#[cfg(target_os = "linux")]
-const fn identify_pulse(len: u128) -> Option<PulseType> {
-    const SHORT_PULSE_BOT_THRESH: u128 = 200;
-    const SHORT_PULSE_TOP_THRESH: u128 = 500;
-    const LONG_PULSE_BOT_THRESH: u128 = 800;
-    const LONG_PULSE_TOP_THRESH: u128 = 1500;
-    const PREAMBLE_PULSE_BOT_THRESH: u128 = 9000;
-    const PREAMBLE_PULSE_TOP_THRESH: u128 = 12000;
-
-    match len {
-        SHORT_PULSE_BOT_THRESH..=SHORT_PULSE_TOP_THRESH => Some(PulseType::Short),
-        LONG_PULSE_BOT_THRESH..=LONG_PULSE_TOP_THRESH => Some(PulseType::Long),
-        PREAMBLE_PULSE_BOT_THRESH..=PREAMBLE_PULSE_TOP_THRESH => Some(PulseType::Preamble),
-        _ => None,
-    }
-}
-
-#[cfg(target_os = "linux")]
-#[derive(Debug, PartialEq, Eq)]
-struct RemoteDetectorState {
-    preamble_detected: bool,
-    bits: Vec<bool>,
-    last_pin_state: Level,
-    last_edge_time: Instant,
-    last_pulse: Option<PulseType>,
-}
-
-#[cfg(target_os = "linux")]
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-enum PulseType {
-    Short,
-    Long,
-    Preamble,
-}
-
 #[cfg(target_os = "linux")]
 enum WirelessTimeout {
     Never(core::future::Pending<()>),
@@ -0,0 +1,483 @@
+//! Decoding of the messages sent by the wireless remotes
+//!
+//! The receiver's output is a series of high and low pulses. Each message starts with a short high
+//! pulse followed by a very long low pulse (the preamble), then `MESSAGE_LEN` bits. A zero bit is a
+//! short high pulse followed by a long low pulse, a one bit is a long high pulse followed by a short
+//! low pulse. The first `ID_LEN` bits are the id of the remote, the rest are data.
+
+use arrayref::array_ref;
+use log::*;
+use std::time::Instant;
+use tokio::{
+    sync::{
+        mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+        watch::{self, Receiver},
+    },
+    task::{self, JoinHandle},
+};
+
+pub const MESSAGE_LEN: usize = 24;
+pub const ID_LEN: usize = 20;
+pub const DATA_LEN: usize = MESSAGE_LEN - ID_LEN;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PinLevel {
+    Low,
+    High,
+}
+
+#[cfg(target_os = "linux")]
+impl From<rppal::gpio::Level> for PinLevel {
+    fn from(level: rppal::gpio::Level) -> Self {
+        match level {
+            rppal::gpio::Level::Low => Self::Low,
+            rppal::gpio::Level::High => Self::High,
+        }
+    }
+}
+
+/// A change of the receiver's output to `level`, which happened at `time`
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Edge {
+    pub level: PinLevel,
+    pub time: Instant,
+}
+
+/// A complete message received from a remote
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct RemoteMessage {
+    pub id: u32,
+    pub data: [bool; DATA_LEN],
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum PulseType {
+    Short,
+    Long,
+    Preamble,
+}
+
+const fn identify_pulse(len: u128) -> Option<PulseType> {
+    const SHORT_PULSE_BOT_THRESH: u128 = 200;
+    const SHORT_PULSE_TOP_THRESH: u128 = 500;
+    const LONG_PULSE_BOT_THRESH: u128 = 800;
+    const LONG_PULSE_TOP_THRESH: u128 = 1500;
+    const PREAMBLE_PULSE_BOT_THRESH: u128 = 9000;
+    const PREAMBLE_PULSE_TOP_THRESH: u128 = 12000;
+
+    match len {
+        SHORT_PULSE_BOT_THRESH..=SHORT_PULSE_TOP_THRESH => Some(PulseType::Short),
+        LONG_PULSE_BOT_THRESH..=LONG_PULSE_TOP_THRESH => Some(PulseType::Long),
+        PREAMBLE_PULSE_BOT_THRESH..=PREAMBLE_PULSE_TOP_THRESH => Some(PulseType::Preamble),
+        _ => None,
+    }
+}
+
+/// State machine that turns the edges of the receiver's output into `RemoteMessage`s
+#[derive(Debug, PartialEq, Eq)]
+pub struct RemoteDecoder {
+    preamble_detected: bool,
+    bits: Vec<bool>,
+    last_level: PinLevel,
+    last_edge_time: Instant,
+    last_pulse: Option<PulseType>,
+}
+
+impl RemoteDecoder {
+    /// `level` is the level of the receiver's output at `time`
+    pub fn new(level: PinLevel, time: Instant) -> Self {
+        Self {
+            preamble_detected: false,
+            bits: Vec::with_capacity(MESSAGE_LEN),
+            last_level: level,
+            last_edge_time: time,
+            last_pulse: None,
+        }
+    }
+
+    /// Processes one edge, returning a message if `edge` completed one. Repeated edges with the
+    /// same level are ignored.
+    pub fn edge(&mut self, edge: Edge) -> Option<RemoteMessage> {
+        if edge.level == self.last_level {
+            return None;
+        }
+
+        let pulse = edge
+            .time
+            .saturating_duration_since(self.last_edge_time)
+            .as_micros();
+        self.last_level = edge.level;
+        self.last_edge_time = edge.time;
+
+        let maybe_pulse_type = identify_pulse(pulse);
+        let message = match edge.level {
+            // The end of a low pulse
+            PinLevel::High => {
+                trace!("Detected LOW  pulse {pulse:>5}us long");
+                debug!("Detected a LOW  pulse of length {maybe_pulse_type:?}");
+                self.low_pulse(maybe_pulse_type)
+            }
+            // The end of a high pulse
+            PinLevel::Low => {
+                trace!("Detected HIGH pulse {pulse:>5}us long");
+                debug!("Detected a HIGH pulse of length {maybe_pulse_type:?}");
+                if maybe_pulse_type.is_none() {
+                    trace!("Detected an invalid pulse");
+                    self.reset();
+                }
+                None
+            }
+        };
+
+        self.last_pulse = maybe_pulse_type;
+        message
+    }
+
+    fn low_pulse(&mut self, maybe_pulse_type: Option<PulseType>) -> Option<RemoteMessage> {
+        let Some(pulse_type) = maybe_pulse_type else {
+            trace!("Detected an invalid pulse");
+            self.reset();
+            return None;
+        };
+
+        if !self.preamble_detected {
+            if self.last_pulse == Some(PulseType::Short) && pulse_type == PulseType::Preamble {
+                debug!("Detected a preamble");
+                self.preamble_detected = true;
+            }
+            return None;
+        }
+
+        match (self.last_pulse, pulse_type) {
+            (Some(PulseType::Short), PulseType::Long) => {
+                debug!("Detected a low bit");
+                self.bits.push(false);
+            }
+            (Some(PulseType::Long), PulseType::Short) => {
+                debug!("Detected a high bit");
+                self.bits.push(true);
+            }
+            _ => {
+                info!("Detected an invalid pulse sequence");
+                self.reset();
+                return None;
+            }
+        }
+
+        if self.bits.len() < MESSAGE_LEN {
+            return None;
+        }
+
+        let message: String = self
+            .bits
+            .iter()
+            .map(|bit| if *bit { '1' } else { '0' })
+            .collect();
+        debug!("Received a complete message: 0b{message}");
+
+        let id = self.bits[..ID_LEN]
+            .iter()
+            .fold(0, |acc, &b| acc * 2 + b as u32);
+        let data = *array_ref![self.bits, ID_LEN, DATA_LEN];
+        debug!("Remote {id} sent data {data:?}");
+
+        self.reset();
+        Some(RemoteMessage { id, data })
+    }
+
+    fn reset(&mut self) {
+        self.preamble_detected = false;
+        self.bits.clear();
+    }
+}
+
+/// An input that the receiver's output can be read from
+pub trait EdgeSource {
+    /// The current level of the input
+    fn level(&self) -> PinLevel;
+
+    /// Starts sending every edge of the input to `tx`
+    fn send_edges(&mut self, tx: UnboundedSender<Edge>);
+}
+
+#[cfg(target_os = "linux")]
+impl EdgeSource for rppal::gpio::InputPin {
+    fn level(&self) -> PinLevel {
+        self.read().into()
+    }
+
+    fn send_edges(&mut self, tx: UnboundedSender<Edge>) {
+        self.set_async_interrupt(rppal::gpio::Trigger::Both, move |level| {
+            tx.send(Edge {
+                level: level.into(),
+                time: Instant::now(),
+            })
+            .unwrap()
+        })
+        .unwrap();
+    }
+}
+
+/// Spawns a task that decodes the edges from `source`. The ids of the remotes are sent on both
+/// returned channels, the first of which receives every message, while the second only holds the
+/// latest id. The task exits when `stop_rx` changes.
+pub fn spawn_remote_listener<S: EdgeSource>(
+    source: &mut S,
+    mut stop_rx: Receiver<bool>,
+) -> (JoinHandle<()>, UnboundedReceiver<u32>, Receiver<u32>) {
+    let (edge_tx, mut edge_rx) = unbounded_channel();
+    let mut decoder = RemoteDecoder::new(source.level(), Instant::now());
+    source.send_edges(edge_tx);
+
+    let (wireless_tx, wireless_rx) = unbounded_channel();
+    let (remote_id_tx, mut remote_id_rx) = watch::channel(0);
+    remote_id_rx.borrow_and_update();
+
+    let listener = task::spawn(async move {
+        loop {
+            tokio::select! {
+                edge = edge_rx.recv() => {
+                    match edge {
+                        Some(edge) => {
+                            if let Some(message) = decoder.edge(edge) {
+                                if wireless_tx.send(message.id).is_err() {
+                                    break;
+                                }
+                                remote_id_tx.send(message.id).ok();
+                            }
+                        }
+                        None => panic!("The edge source has been dropped"),
+                    }
+                }
+                _ = stop_rx.changed() => {
+                    break;
+                }
+            }
+        }
+    });
+
+    (listener, wireless_rx, remote_id_rx)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    /// Parses a recorded trace, which has one edge per line in the format `<H|L> <microseconds>`,
+    /// with the time measured from the start of the recording. Lines starting with `#` are
+    /// comments. Returns the start level, the start time, and the edges.
+    fn parse_trace(trace: &str) -> (PinLevel, Instant, Vec<Edge>) {
+        let start = Instant::now();
+        let mut lines = trace
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let (level, micros) = line.split_once(' ').unwrap();
+                let level = match level {
+                    "H" => PinLevel::High,
+                    "L" => PinLevel::Low,
+                    _ => panic!("Invalid level {level}"),
+                };
+                Edge {
+                    level,
+                    time: start + Duration::from_micros(micros.trim().parse().unwrap()),
+                }
+            });
+        let first = lines.next().unwrap();
+        assert_eq!(
+            first.time, start,
+            "Trace must start with the initial level at time 0"
+        );
+        (first.level, start, lines.collect())
+    }
+
+    fn decode(trace: &str) -> Vec<RemoteMessage> {
+        let (level, start, edges) = parse_trace(trace);
+        let mut decoder = RemoteDecoder::new(level, start);
+        edges
+            .into_iter()
+            .filter_map(|edge| decoder.edge(edge))
+            .collect()
+    }
+
+    /// Builds the edges of a message from `bits`, starting with the preamble at `start` micros
+    fn message_edges(bits: &str, start: u64) -> Vec<(PinLevel, u64)> {
+        let mut time = start;
+        let mut edges = vec![];
+        let mut pulse = |edges: &mut Vec<_>, high, low| {
+            edges.push((PinLevel::High, time));
+            time += high;
+            edges.push((PinLevel::Low, time));
+            time += low;
+        };
+        pulse(&mut edges, 350, 10_000);
+        for bit in bits.chars() {
+            match bit {
+                '0' => pulse(&mut edges, 350, 1050),
+                '1' => pulse(&mut edges, 1050, 350),
+                _ => panic!("Invalid bit {bit}"),
+            }
+        }
+        edges.push((PinLevel::High, time));
+        edges
+    }
+
+    fn decode_edges(edges: &[(PinLevel, u64)]) -> Vec<RemoteMessage> {
+        let start = Instant::now();
+        let mut decoder = RemoteDecoder::new(PinLevel::Low, start);
+        edges
+            .iter()
+            .filter_map(|&(level, micros)| {
+                decoder.edge(Edge {
+                    level,
+                    time: start + Duration::from_micros(micros),
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_identify_pulse() {
+        assert_eq!(identify_pulse(0), None);
+        assert_eq!(identify_pulse(199), None);
+        assert_eq!(identify_pulse(200), Some(PulseType::Short));
+        assert_eq!(identify_pulse(500), Some(PulseType::Short));
+        assert_eq!(identify_pulse(650), None);
+        assert_eq!(identify_pulse(800), Some(PulseType::Long));
+        assert_eq!(identify_pulse(1500), Some(PulseType::Long));
+        assert_eq!(identify_pulse(5000), None);
+        assert_eq!(identify_pulse(9000), Some(PulseType::Preamble));
+        assert_eq!(identify_pulse(12000), Some(PulseType::Preamble));
+        assert_eq!(identify_pulse(12001), None);
+    }
+
+    #[test]
+    fn test_decode_message() {
+        let edges = message_edges("101100000000000000011001", 0);
+        assert_eq!(
+            decode_edges(&edges),
+            vec![RemoteMessage {
+                id: 0b1011_0000_0000_0000_0001,
+                data: [true, false, false, true],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_repeated_messages() {
+        // The next preamble starts with the edge that ends the first message
+        let mut edges = message_edges("000000000000000000010000", 0);
+        let end = edges.pop().unwrap().1;
+        edges.extend(message_edges("000000000000000000010000", end));
+
+        let messages = decode_edges(&edges);
+        assert_eq!(messages.len(), 2);
+        assert!(messages.iter().all(|m| m.id == 1 && m.data == [false; 4]));
+    }
+
+    #[test]
+    fn test_invalid_sequences() {
+        // No preamble
+        let edges: Vec<_> = message_edges("000000000000000000010000", 0)
+            .into_iter()
+            .skip(2)
+            .collect();
+        assert_eq!(decode_edges(&edges), vec![]);
+
+        // A pulse that's too long in the middle of the message
+        let mut edges = message_edges("000000000000000000010000", 0);
+        for edge in edges.iter_mut().skip(20) {
+            edge.1 += 3000;
+        }
+        assert_eq!(decode_edges(&edges), vec![]);
+
+        // Two short pulses can't make a bit
+        let mut edges = message_edges("000000000000000000010000", 0);
+        edges[4].1 -= 700;
+        assert_eq!(decode_edges(&edges), vec![]);
+
+        // Truncated message
+        let edges = message_edges("00000000000000000001000", 0);
+        assert_eq!(decode_edges(&edges), vec![]);
+    }
+
+    #[test]
+    fn test_repeated_levels_are_ignored() {
+        let edges = message_edges("000000000000000000010000", 0);
+        let doubled: Vec<_> = edges
+            .iter()
+            .flat_map(|&(level, micros)| [(level, micros), (level, micros + 50)])
+            .collect();
+        assert_eq!(decode_edges(&doubled), decode_edges(&edges));
+        assert_eq!(decode_edges(&edges).len(), 1);
+    }
+
+    #[test]
+    fn test_recorded_traces() {
+        let messages = decode(include_str!("traces/single_press.trace"));
+        assert_eq!(
+            messages,
+            vec![
+                RemoteMessage {
+                    id: 0b0110_1001_1100_0011_0101,
+                    data: [false, false, true, false],
+                };
+                3
+            ]
+        );
+
+        let messages = decode(include_str!("traces/noise.trace"));
+        assert_eq!(messages, vec![]);
+
+        let messages = decode(include_str!("traces/two_remotes.trace"));
+        let ids: Vec<_> = messages.iter().map(|m| m.id).collect();
+        assert_eq!(
+            ids,
+            vec![0b1111_0000_1010_0101_0011, 0b0000_1111_0101_1010_1100]
+        );
+    }
+
+    struct RecordedSource {
+        level: PinLevel,
+        edges: Vec<Edge>,
+        // Keeps the channel open, like a real input would
+        tx: Option<UnboundedSender<Edge>>,
+    }
+
+    impl EdgeSource for RecordedSource {
+        fn level(&self) -> PinLevel {
+            self.level
+        }
+
+        fn send_edges(&mut self, tx: UnboundedSender<Edge>) {
+            for edge in self.edges.drain(..) {
+                tx.send(edge).unwrap();
+            }
+            self.tx = Some(tx);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_spawn_remote_listener() {
+        let (level, _, edges) = parse_trace(include_str!("traces/two_remotes.trace"));
+        let mut source = RecordedSource {
+            level,
+            edges,
+            tx: None,
+        };
+        let (stop_tx, stop_rx) = watch::channel(false);
+
+        let (listener, mut wireless_rx, mut remote_id_rx) =
+            spawn_remote_listener(&mut source, stop_rx);
+
+        assert_eq!(wireless_rx.recv().await, Some(0b1111_0000_1010_0101_0011));
+        assert_eq!(wireless_rx.recv().await, Some(0b0000_1111_0101_1010_1100));
+        remote_id_rx.changed().await.unwrap();
+        assert_eq!(*remote_id_rx.borrow(), 0b0000_1111_0101_1010_1100);
+
+        stop_tx.send(true).unwrap();
+        listener.await.unwrap();
+    }
+}
@@ -1,11 +1,16 @@
 use array_concat::*;
 use derivative::Derivative;
+use log::*;
 use serde::{Deserialize, Serialize};
-use std::{fmt::Display, ops::Index};
-use web_audio_api::{
-    context::{AudioContext, BaseAudioContext},
-    AudioBuffer,
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    fs::{self, File},
+    io,
+    ops::Index,
+    path::Path,
 };
+use web_audio_api::{context::BaseAudioContext, AudioBuffer};
 
 const fn process_array<const N: usize, const M: usize>(input: &[u8; M]) -> [f32; N] {
     let mut output = [0f32; N];
@@ -56,8 +61,15 @@ static TWO_TONE: [f32; TWO_TONE_LEN] =
 
 pub const SAMPLE_RATE: f32 = 44100.0;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Derivative)]
+/// File extensions of the formats that custom sounds can be in
+pub const CUSTOM_SOUND_EXTENSIONS: [&str; 4] = ["wav", "flac", "ogg", "oga"];
+
+/// Serialized as a string, the name of the variant for the built in sounds, or the file name for
+/// custom sounds. Custom sound files always have an extension, so they can't be confused with
+/// the built in sounds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Derivative)]
 #[derivative(Default)]
+#[serde(from = "String", into = "String")]
 pub enum BuzzerSound {
     #[derivative(Default)]
     Buzz,
@@ -65,6 +77,44 @@ pub enum BuzzerSound {
     Crazy,
     DeDeDu,
     TwoTone,
+    /// A sound file in the sound directory, identified by its file name
+    Custom(String),
+}
+
+impl BuzzerSound {
+    pub const BUILT_IN: [Self; 5] = [
+        Self::Buzz,
+        Self::Whoop,
+        Self::Crazy,
+        Self::DeDeDu,
+        Self::TwoTone,
+    ];
+}
+
+impl From<String> for BuzzerSound {
+    fn from(name: String) -> Self {
+        match name.as_str() {
+            "Buzz" => Self::Buzz,
+            "Whoop" => Self::Whoop,
+            "Crazy" => Self::Crazy,
+            "DeDeDu" => Self::DeDeDu,
+            "TwoTone" => Self::TwoTone,
+            _ => Self::Custom(name),
+        }
+    }
+}
+
+impl From<BuzzerSound> for String {
+    fn from(sound: BuzzerSound) -> Self {
+        match sound {
+            BuzzerSound::Buzz => "Buzz".to_string(),
+            BuzzerSound::Whoop => "Whoop".to_string(),
+            BuzzerSound::Crazy => "Crazy".to_string(),
+            BuzzerSound::DeDeDu => "DeDeDu".to_string(),
+            BuzzerSound::TwoTone => "TwoTone".to_string(),
+            BuzzerSound::Custom(name) => name,
+        }
+    }
 }
 
 impl Display for BuzzerSound {
@@ -75,8 +125,78 @@ impl Display for BuzzerSound {
             Self::Crazy => write!(f, "Crazy"),
             Self::DeDeDu => write!(f, "De De Du"),
             Self::TwoTone => write!(f, "Two Tone"),
+            Self::Custom(name) => match Path::new(name).file_stem() {
+                Some(stem) => write!(f, "{}", stem.to_string_lossy()),
+                None => write!(f, "{name}"),
+            },
+        }
+    }
+}
+
+/// Lists the file names of the sounds in `dir` that are in a supported format, sorted by name
+pub fn list_custom_sounds(dir: &Path) -> io::Result<Vec<String>> {
+    let mut names = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let supported = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map_or(false, |ext| {
+                CUSTOM_SOUND_EXTENSIONS
+                    .iter()
+                    .any(|supported| ext.eq_ignore_ascii_case(supported))
+            });
+        if supported && path.is_file() {
+            if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
+                names.push(name.to_string());
+            }
         }
     }
+    names.sort();
+    Ok(names)
+}
+
+/// Decodes all the sounds in `dir`, resampled to the sample rate of `context`. Files that can't be
+/// decoded are skipped.
+pub(super) fn load_custom_sounds<C: BaseAudioContext>(
+    context: &C,
+    dir: &Path,
+) -> BTreeMap<String, AudioBuffer> {
+    let names = match list_custom_sounds(dir) {
+        Ok(names) => names,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            info!("No custom sound directory at {dir:?}");
+            return BTreeMap::new();
+        }
+        Err(e) => {
+            error!("Failed to read the custom sound directory {dir:?}: {e}");
+            return BTreeMap::new();
+        }
+    };
+
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let path = dir.join(&name);
+            let decoded = File::open(&path)
+                .map_err(|e| e.into())
+                .and_then(|file| context.decode_audio_data_sync(file));
+            match decoded {
+                Ok(buffer) if buffer.length() > 0 => {
+                    info!("Loaded custom sound {path:?}");
+                    Some((name, buffer))
+                }
+                Ok(_) => {
+                    warn!("Custom sound {path:?} is empty, skipping it");
+                    None
+                }
+                Err(e) => {
+                    error!("Failed to load custom sound {path:?}: {e}");
+                    None
+                }
+            }
+        })
+        .collect()
 }
 
 pub(super) struct SoundLibrary {
@@ -86,24 +206,31 @@ pub(super) struct SoundLibrary {
     de_de_du: AudioBuffer,
     two_tone: AudioBuffer,
     whistle: AudioBuffer,
+    custom: BTreeMap<String, AudioBuffer>,
 }
 
-impl Index<BuzzerSound> for SoundLibrary {
+impl Index<&BuzzerSound> for SoundLibrary {
     type Output = AudioBuffer;
 
-    fn index(&self, sound: BuzzerSound) -> &Self::Output {
+    fn index(&self, sound: &BuzzerSound) -> &Self::Output {
         match sound {
             BuzzerSound::Buzz => &self.buzz,
             BuzzerSound::Whoop => &self.whoop,
             BuzzerSound::Crazy => &self.crazy,
             BuzzerSound::DeDeDu => &self.de_de_du,
             BuzzerSound::TwoTone => &self.two_tone,
+            BuzzerSound::Custom(name) => self.custom.get(name).unwrap_or_else(|| {
+                warn!("Custom sound {name:?} is not loaded, playing the default buzzer instead");
+                &self.buzz
+            }),
         }
     }
 }
 
 impl SoundLibrary {
-    pub(super) fn new(context: &AudioContext) -> Self {
+    /// Loads the built in sounds, and the custom sounds in `custom_dir` if there is one. The
+    /// context should run at `SAMPLE_RATE`.
+    pub(super) fn new<C: BaseAudioContext>(context: &C, custom_dir: Option<&Path>) -> Self {
         let mut buzz = context.create_buffer(1, BUZZ_LEN, SAMPLE_RATE);
         buzz.copy_to_channel(&BUZZ, 0);
 
@@ -122,6 +249,10 @@ impl SoundLibrary {
         let mut whistle = context.create_buffer(1, WHISTLE_LEN, SAMPLE_RATE);
         whistle.copy_to_channel(&WHISTLE, 0);
 
+        let custom = custom_dir
+            .map(|dir| load_custom_sounds(context, dir))
+            .unwrap_or_default();
+
         Self {
             buzz,
             whoop,
@@ -129,10 +260,117 @@ impl SoundLibrary {
             de_de_du,
             two_tone,
             whistle,
+            custom,
         }
     }
 
     pub(super) fn whistle(&self) -> &AudioBuffer {
         &self.whistle
     }
+
+    /// The file names of the custom sounds that were loaded successfully
+    pub(super) fn custom_sounds(&self) -> Vec<String> {
+        self.custom.keys().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sound_controller::{RemoteInfo, SoundSettings};
+    use std::env::temp_dir;
+    use web_audio_api::context::OfflineAudioContext;
+
+    /// Builds a mono 16 bit WAV file
+    fn wav(sample_rate: u32, samples: &[i16]) -> Vec<u8> {
+        let data_len = samples.len() as u32 * 2;
+        let mut file = Vec::new();
+        file.extend_from_slice(b"RIFF");
+        file.extend_from_slice(&(36 + data_len).to_le_bytes());
+        file.extend_from_slice(b"WAVEfmt ");
+        file.extend_from_slice(&16u32.to_le_bytes());
+        file.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        file.extend_from_slice(&1u16.to_le_bytes()); // Mono
+        file.extend_from_slice(&sample_rate.to_le_bytes());
+        file.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+        file.extend_from_slice(&2u16.to_le_bytes());
+        file.extend_from_slice(&16u16.to_le_bytes());
+        file.extend_from_slice(b"data");
+        file.extend_from_slice(&data_len.to_le_bytes());
+        for sample in samples {
+            file.extend_from_slice(&sample.to_le_bytes());
+        }
+        file
+    }
+
+    #[test]
+    fn test_load_custom_sounds() {
+        let dir = temp_dir().join(format!("refbox-sounds-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let samples: Vec<i16> = (0..22050).map(|i| ((i % 100) * 100) as i16).collect();
+        fs::write(dir.join("Horn.WAV"), wav(22050, &samples)).unwrap();
+        fs::write(dir.join("broken.ogg"), b"not really a sound").unwrap();
+        fs::write(dir.join("notes.txt"), b"ignored").unwrap();
+        fs::create_dir_all(dir.join("folder.wav")).unwrap();
+
+        assert_eq!(
+            list_custom_sounds(&dir).unwrap(),
+            vec!["Horn.WAV".to_string(), "broken.ogg".to_string()]
+        );
+
+        let context = OfflineAudioContext::new(1, 1, SAMPLE_RATE);
+        let sounds = load_custom_sounds(&context, &dir);
+        assert_eq!(sounds.keys().collect::<Vec<_>>(), vec!["Horn.WAV"]);
+
+        // One second at 22050Hz is resampled to one second at `SAMPLE_RATE`
+        let horn = &sounds["Horn.WAV"];
+        assert_eq!(horn.sample_rate(), SAMPLE_RATE);
+        assert!((horn.duration() - 1.0).abs() < 0.01, "{}", horn.duration());
+
+        let library = SoundLibrary::new(&context, Some(&dir));
+        assert_eq!(library.custom_sounds(), vec!["Horn.WAV".to_string()]);
+        let custom = BuzzerSound::Custom("Horn.WAV".to_string());
+        assert_eq!(library[&custom].length(), horn.length());
+        // Missing sounds fall back to the default buzzer
+        let missing = BuzzerSound::Custom("gone.wav".to_string());
+        assert_eq!(library[&missing].length(), BUZZ_LEN);
+
+        assert!(load_custom_sounds(&context, &dir.join("missing")).is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_display_and_serialize() {
+        assert_eq!(BuzzerSound::DeDeDu.to_string(), "De De Du");
+        let custom = BuzzerSound::Custom("Pool Horn.flac".to_string());
+        assert_eq!(custom.to_string(), "Pool Horn");
+
+        // Settings saved before custom sounds existed are still read correctly
+        let old: SoundSettings = toml::from_str(
+            "sound_enabled = true\nwhistle_enabled = true\nbuzzer_sound = \"Whoop\"\n\
+             whistle_vol = \"Medium\"\nabove_water_vol = \"Max\"\nunder_water_vol = \"Max\"\n\
+             auto_sound_start_play = true\nauto_sound_stop_play = true\n\
+             [[remotes]]\nid = 12\nsound = \"Crazy\"\n",
+        )
+        .unwrap();
+        assert_eq!(old.buzzer_sound, BuzzerSound::Whoop);
+        assert_eq!(old.remotes[0].sound, Some(BuzzerSound::Crazy));
+
+        let settings = SoundSettings {
+            buzzer_sound: custom.clone(),
+            remotes: vec![RemoteInfo {
+                id: 12,
+                sound: Some(custom),
+            }],
+            ..Default::default()
+        };
+        let serialized = toml::to_string(&settings).unwrap();
+        assert!(serialized.contains("buzzer_sound = \"Pool Horn.flac\""));
+        assert_eq!(
+            toml::from_str::<SoundSettings>(&serialized).unwrap(),
+            settings
+        );
+    }
 }
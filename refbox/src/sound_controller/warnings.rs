@@ -0,0 +1,429 @@
+//! The rules that decide when sounds are played automatically as the clocks run down
+
+use super::SoundSettings;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use uwh_common::game_snapshot::{GamePeriod, GameSnapshot, TimeoutSnapshot};
+
+/// The clock that a `WarningRule` watches. The game periods refer to the game clock while there
+/// is no timeout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WarningClock {
+    BetweenGames,
+    FirstHalf,
+    HalfTime,
+    SecondHalf,
+    PreOvertime,
+    OvertimeFirstHalf,
+    OvertimeHalfTime,
+    OvertimeSecondHalf,
+    PreSuddenDeath,
+    SuddenDeath,
+    TeamTimeout,
+    RefTimeout,
+    PenaltyShot,
+}
+
+impl From<GamePeriod> for WarningClock {
+    fn from(period: GamePeriod) -> Self {
+        match period {
+            GamePeriod::BetweenGames => Self::BetweenGames,
+            GamePeriod::FirstHalf => Self::FirstHalf,
+            GamePeriod::HalfTime => Self::HalfTime,
+            GamePeriod::SecondHalf => Self::SecondHalf,
+            GamePeriod::PreOvertime => Self::PreOvertime,
+            GamePeriod::OvertimeFirstHalf => Self::OvertimeFirstHalf,
+            GamePeriod::OvertimeHalfTime => Self::OvertimeHalfTime,
+            GamePeriod::OvertimeSecondHalf => Self::OvertimeSecondHalf,
+            GamePeriod::PreSuddenDeath => Self::PreSuddenDeath,
+            GamePeriod::SuddenDeath => Self::SuddenDeath,
+        }
+    }
+}
+
+impl WarningClock {
+    pub const BREAKS: [Self; 5] = [
+        Self::BetweenGames,
+        Self::HalfTime,
+        Self::PreOvertime,
+        Self::OvertimeHalfTime,
+        Self::PreSuddenDeath,
+    ];
+
+    pub const PLAY_PERIODS: [Self; 4] = [
+        Self::FirstHalf,
+        Self::SecondHalf,
+        Self::OvertimeFirstHalf,
+        Self::OvertimeSecondHalf,
+    ];
+
+    /// The clock shown by `snapshot`, and its value in seconds
+    fn of(snapshot: &GameSnapshot) -> (Self, u32) {
+        match snapshot.timeout {
+            TimeoutSnapshot::None => (snapshot.current_period.into(), snapshot.secs_in_period),
+            TimeoutSnapshot::White(secs) | TimeoutSnapshot::Black(secs) => {
+                (Self::TeamTimeout, secs.into())
+            }
+            TimeoutSnapshot::Ref(secs) => (Self::RefTimeout, secs.into()),
+            TimeoutSnapshot::PenaltyShot(secs) => (Self::PenaltyShot, secs.into()),
+        }
+    }
+
+    /// The value of this clock in `snapshot`, if `snapshot` has this clock. The game clock keeps
+    /// its value during timeouts.
+    fn value_in(self, snapshot: &GameSnapshot) -> Option<u32> {
+        match (self, snapshot.timeout) {
+            (Self::TeamTimeout, TimeoutSnapshot::White(secs) | TimeoutSnapshot::Black(secs))
+            | (Self::RefTimeout, TimeoutSnapshot::Ref(secs))
+            | (Self::PenaltyShot, TimeoutSnapshot::PenaltyShot(secs)) => Some(secs.into()),
+            (Self::TeamTimeout | Self::RefTimeout | Self::PenaltyShot, _) => None,
+            (period, _) => {
+                (Self::from(snapshot.current_period) == period).then_some(snapshot.secs_in_period)
+            }
+        }
+    }
+}
+
+impl Display for WarningClock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BetweenGames => write!(f, "Between Games"),
+            Self::FirstHalf => write!(f, "First Half"),
+            Self::HalfTime => write!(f, "Half Time"),
+            Self::SecondHalf => write!(f, "Second Half"),
+            Self::PreOvertime => write!(f, "Pre Overtime"),
+            Self::OvertimeFirstHalf => write!(f, "Overtime First Half"),
+            Self::OvertimeHalfTime => write!(f, "Overtime Half Time"),
+            Self::OvertimeSecondHalf => write!(f, "Overtime Second Half"),
+            Self::PreSuddenDeath => write!(f, "Pre Sudden Death"),
+            Self::SuddenDeath => write!(f, "Sudden Death"),
+            Self::TeamTimeout => write!(f, "Team Timeout"),
+            Self::RefTimeout => write!(f, "Ref Timeout"),
+            Self::PenaltyShot => write!(f, "Penalty Shot"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WarningSound {
+    Whistle,
+    /// The buzzer sound selected in the settings
+    Buzzer,
+}
+
+/// Plays `sound` when `clock` reaches `secs`. `secs` is the value shown on the clock, which is the
+/// time remaining for the game periods, team timeouts and penalty shots, and the time elapsed for
+/// ref timeouts and sudden death.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WarningRule {
+    pub clock: WarningClock,
+    pub secs: u32,
+    pub sound: WarningSound,
+}
+
+impl WarningRule {
+    pub const fn new(clock: WarningClock, secs: u32, sound: WarningSound) -> Self {
+        Self { clock, secs, sound }
+    }
+
+    /// Whether the change from `old` to `new` makes the clock reach this rule's time
+    fn triggers(&self, old: &GameSnapshot, new: &GameSnapshot) -> bool {
+        WarningClock::of(new) == (self.clock, self.secs)
+            && self.clock.value_in(old) != Some(self.secs)
+    }
+}
+
+/// The buzzer at the end of a penalty shot, which is always played regardless of the configured
+/// rules
+const PENALTY_SHOT_BUZZER: WarningRule =
+    WarningRule::new(WarningClock::PenaltyShot, 0, WarningSound::Buzzer);
+
+/// A whistle 30 seconds before the end of each break, and 15 seconds before the end of team
+/// timeouts, and a buzzer at the end of each of those and of the periods of play
+pub fn default_warning_rules() -> Vec<WarningRule> {
+    let breaks = WarningClock::BREAKS.into_iter().flat_map(|clock| {
+        [
+            WarningRule::new(clock, 30, WarningSound::Whistle),
+            WarningRule::new(clock, 0, WarningSound::Buzzer),
+        ]
+    });
+    let play = WarningClock::PLAY_PERIODS
+        .into_iter()
+        .map(|clock| WarningRule::new(clock, 0, WarningSound::Buzzer));
+    let timeouts = [
+        WarningRule::new(WarningClock::TeamTimeout, 15, WarningSound::Whistle),
+        WarningRule::new(WarningClock::TeamTimeout, 0, WarningSound::Buzzer),
+    ];
+    breaks.chain(play).chain(timeouts).collect()
+}
+
+impl SoundSettings {
+    /// Whether `rule` is allowed to play by the `auto_sound_start_play` and `auto_sound_stop_play`
+    /// settings
+    fn buzzer_allowed(&self, rule: &WarningRule) -> bool {
+        if rule.sound != WarningSound::Buzzer || rule.secs != 0 {
+            true
+        } else if WarningClock::BREAKS.contains(&rule.clock) {
+            self.auto_sound_start_play
+        } else if WarningClock::PLAY_PERIODS.contains(&rule.clock) {
+            self.auto_sound_stop_play
+        } else {
+            true
+        }
+    }
+
+    /// Decides whether the change from `old_snapshot` to `new_snapshot` should automatically
+    /// trigger the whistle and/or the buzzer, according to `warning_rules`. The buzzer is also
+    /// played at the end of a penalty shot.
+    ///
+    /// A buzzer at the end of a break or period of play is only played if `auto_sound_start_play`
+    /// or `auto_sound_stop_play` respectively is set.
+    pub fn auto_sounds(
+        &self,
+        old_snapshot: &GameSnapshot,
+        new_snapshot: &GameSnapshot,
+    ) -> (bool, bool) {
+        self.warning_rules
+            .iter()
+            .chain([&PENALTY_SHOT_BUZZER])
+            .filter(|rule| rule.triggers(old_snapshot, new_snapshot))
+            .filter(|rule| self.buzzer_allowed(rule))
+            .fold((false, false), |(whistle, buzzer), rule| match rule.sound {
+                WarningSound::Whistle => (true, buzzer),
+                WarningSound::Buzzer => (whistle, true),
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn snapshot(current_period: GamePeriod, secs_in_period: u32) -> GameSnapshot {
+        GameSnapshot {
+            current_period,
+            secs_in_period,
+            ..Default::default()
+        }
+    }
+
+    fn with_timeout(timeout: TimeoutSnapshot) -> GameSnapshot {
+        GameSnapshot {
+            timeout,
+            ..snapshot(GamePeriod::FirstHalf, 300)
+        }
+    }
+
+    #[test]
+    fn test_default_rules() {
+        let settings = SoundSettings::default();
+        let sounds = |old, new| settings.auto_sounds(&old, &new);
+
+        for period in [
+            GamePeriod::BetweenGames,
+            GamePeriod::HalfTime,
+            GamePeriod::PreOvertime,
+            GamePeriod::OvertimeHalfTime,
+            GamePeriod::PreSuddenDeath,
+        ] {
+            assert_eq!(
+                sounds(snapshot(period, 31), snapshot(period, 30)),
+                (true, false)
+            );
+            assert_eq!(
+                sounds(snapshot(period, 30), snapshot(period, 30)),
+                (false, false)
+            );
+            assert_eq!(
+                sounds(snapshot(period, 1), snapshot(period, 0)),
+                (false, true)
+            );
+            assert_eq!(
+                sounds(snapshot(period, 16), snapshot(period, 15)),
+                (false, false)
+            );
+        }
+
+        for period in [
+            GamePeriod::FirstHalf,
+            GamePeriod::SecondHalf,
+            GamePeriod::OvertimeFirstHalf,
+            GamePeriod::OvertimeSecondHalf,
+        ] {
+            assert_eq!(
+                sounds(snapshot(period, 31), snapshot(period, 30)),
+                (false, false)
+            );
+            assert_eq!(
+                sounds(snapshot(period, 1), snapshot(period, 0)),
+                (false, true)
+            );
+        }
+
+        // Sudden death counts up, so it has no warnings
+        let sd = GamePeriod::SuddenDeath;
+        assert_eq!(sounds(snapshot(sd, 29), snapshot(sd, 30)), (false, false));
+        assert_eq!(sounds(snapshot(sd, 0), snapshot(sd, 0)), (false, false));
+
+        for timeout in [TimeoutSnapshot::White, TimeoutSnapshot::Black] {
+            assert_eq!(
+                sounds(with_timeout(timeout(16)), with_timeout(timeout(15))),
+                (true, false)
+            );
+            assert_eq!(
+                sounds(with_timeout(timeout(1)), with_timeout(timeout(0))),
+                (false, true)
+            );
+            assert_eq!(
+                sounds(with_timeout(timeout(0)), with_timeout(timeout(0))),
+                (false, false)
+            );
+        }
+
+        let penalty_shot = |secs| with_timeout(TimeoutSnapshot::PenaltyShot(secs));
+        assert_eq!(sounds(penalty_shot(16), penalty_shot(15)), (false, false));
+        assert_eq!(sounds(penalty_shot(1), penalty_shot(0)), (false, true));
+        assert_eq!(sounds(penalty_shot(0), penalty_shot(0)), (false, false));
+
+        // The game clock isn't running during timeouts
+        let mut ref_timeout = with_timeout(TimeoutSnapshot::Ref(5));
+        ref_timeout.secs_in_period = 30;
+        assert_eq!(
+            sounds(snapshot(GamePeriod::HalfTime, 31), ref_timeout.clone()),
+            (false, false)
+        );
+        // Ending a timeout doesn't repeat a warning that was played before the timeout
+        ref_timeout.current_period = GamePeriod::HalfTime;
+        assert_eq!(
+            sounds(ref_timeout, snapshot(GamePeriod::HalfTime, 30)),
+            (false, false)
+        );
+    }
+
+    #[test]
+    fn test_auto_sound_flags() {
+        let mut settings = SoundSettings {
+            auto_sound_start_play: false,
+            ..Default::default()
+        };
+        let half_time = |secs| snapshot(GamePeriod::HalfTime, secs);
+        let first_half = |secs| snapshot(GamePeriod::FirstHalf, secs);
+
+        assert_eq!(
+            settings.auto_sounds(&half_time(1), &half_time(0)),
+            (false, false)
+        );
+        assert_eq!(
+            settings.auto_sounds(&half_time(31), &half_time(30)),
+            (true, false)
+        );
+        assert_eq!(
+            settings.auto_sounds(&first_half(1), &first_half(0)),
+            (false, true)
+        );
+
+        settings.auto_sound_start_play = true;
+        settings.auto_sound_stop_play = false;
+        assert_eq!(
+            settings.auto_sounds(&half_time(1), &half_time(0)),
+            (false, true)
+        );
+        assert_eq!(
+            settings.auto_sounds(&first_half(1), &first_half(0)),
+            (false, false)
+        );
+
+        // Team timeouts aren't affected
+        let timeout = |secs| with_timeout(TimeoutSnapshot::Black(secs));
+        assert_eq!(
+            settings.auto_sounds(&timeout(1), &timeout(0)),
+            (false, true)
+        );
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        let settings = SoundSettings {
+            warning_rules: vec![
+                WarningRule::new(WarningClock::FirstHalf, 60, WarningSound::Whistle),
+                WarningRule::new(WarningClock::PenaltyShot, 10, WarningSound::Buzzer),
+                WarningRule::new(WarningClock::RefTimeout, 0, WarningSound::Whistle),
+                WarningRule::new(WarningClock::TeamTimeout, 20, WarningSound::Whistle),
+                WarningRule::new(WarningClock::TeamTimeout, 20, WarningSound::Buzzer),
+            ],
+            ..Default::default()
+        };
+        let sounds = |old, new| settings.auto_sounds(&old, &new);
+        let first_half = |secs| snapshot(GamePeriod::FirstHalf, secs);
+
+        assert_eq!(sounds(first_half(61), first_half(60)), (true, false));
+        assert_eq!(
+            sounds(
+                snapshot(GamePeriod::SecondHalf, 61),
+                snapshot(GamePeriod::SecondHalf, 60)
+            ),
+            (false, false)
+        );
+        // The built in rules have been replaced
+        assert_eq!(sounds(first_half(1), first_half(0)), (false, false));
+        assert_eq!(
+            sounds(
+                snapshot(GamePeriod::HalfTime, 31),
+                snapshot(GamePeriod::HalfTime, 30)
+            ),
+            (false, false)
+        );
+
+        let penalty_shot = |secs| with_timeout(TimeoutSnapshot::PenaltyShot(secs));
+        assert_eq!(sounds(penalty_shot(9), penalty_shot(10)), (false, true));
+        assert_eq!(sounds(penalty_shot(10), penalty_shot(10)), (false, false));
+        // The end of a penalty shot isn't one of the replaced rules
+        assert_eq!(sounds(penalty_shot(1), penalty_shot(0)), (false, true));
+
+        // A rule for the start of a timeout is played as the timeout starts
+        assert_eq!(
+            sounds(first_half(300), with_timeout(TimeoutSnapshot::Ref(0))),
+            (true, false)
+        );
+
+        // Several rules can trigger at once
+        let team_timeout = |secs| with_timeout(TimeoutSnapshot::White(secs));
+        assert_eq!(sounds(team_timeout(21), team_timeout(20)), (true, true));
+    }
+
+    #[test]
+    fn test_penalty_shot_buzzer_without_rules() {
+        let settings = SoundSettings {
+            warning_rules: vec![],
+            ..Default::default()
+        };
+        let penalty_shot = |secs| with_timeout(TimeoutSnapshot::PenaltyShot(secs));
+
+        assert_eq!(
+            settings.auto_sounds(&penalty_shot(1), &penalty_shot(0)),
+            (false, true)
+        );
+        assert_eq!(
+            settings.auto_sounds(&penalty_shot(0), &penalty_shot(0)),
+            (false, false)
+        );
+        let timeout = |secs| with_timeout(TimeoutSnapshot::Black(secs));
+        assert_eq!(
+            settings.auto_sounds(&timeout(1), &timeout(0)),
+            (false, false)
+        );
+    }
+
+    #[test]
+    fn test_ser_rules() {
+        let settings = SoundSettings::default();
+        let serialized = toml::to_string(&settings).unwrap();
+        assert_eq!(toml::from_str(&serialized), Ok(settings.clone()));
+
+        // Settings saved before the rules existed get the default rules
+        let mut value = toml::Value::try_from(&settings).unwrap();
+        value.as_table_mut().unwrap().remove("warning_rules");
+        let deser: SoundSettings = value.try_into().unwrap();
+        assert_eq!(deser.warning_rules, default_warning_rules());
+    }
+}
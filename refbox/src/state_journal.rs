@@ -0,0 +1,180 @@
+use crate::tournament_manager::{SavedState, SavedTimeout};
+use log::*;
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::PathBuf,
+};
+use tokio::time::{Duration, Instant};
+
+/// While a clock is running and nothing but the clock times have changed, the state is written at
+/// most this often
+const CLOCK_SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Keeps a copy of the most recent game state on disk so that a game can be resumed if the app
+/// is closed unexpectedly
+#[derive(Debug)]
+pub struct StateJournal {
+    path: PathBuf,
+    last_saved: Option<SavedState>,
+    last_write: Option<Instant>,
+}
+
+impl StateJournal {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            last_saved: None,
+            last_write: None,
+        }
+    }
+
+    /// Reads the saved state from disk, returns `None` if there is no valid saved state
+    pub(crate) fn load(&self) -> Option<SavedState> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(c) => c,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                warn!("Failed to read saved game state from {:?}: {e}", self.path);
+                return None;
+            }
+        };
+
+        match serde_json::from_str(&contents) {
+            Ok(state) => Some(state),
+            Err(e) => {
+                warn!("Failed to parse saved game state: {e}");
+                None
+            }
+        }
+    }
+
+    /// Writes the state to disk if it differs from the last state written. While `clock_running`
+    /// is set, changes to only the clock times are written at most every `CLOCK_SAVE_INTERVAL`.
+    /// The file is replaced atomically so that a crash part way through a write doesn't corrupt
+    /// the saved state.
+    pub(crate) fn record(
+        &mut self,
+        state: SavedState,
+        clock_running: bool,
+        now: Instant,
+    ) -> io::Result<()> {
+        if let Some(ref last) = self.last_saved {
+            if *last == state {
+                return Ok(());
+            }
+            let recently_written = matches!(
+                self.last_write,
+                Some(time) if now.saturating_duration_since(time) < CLOCK_SAVE_INTERVAL
+            );
+            if clock_running && recently_written && without_clocks(last) == without_clocks(&state) {
+                return Ok(());
+            }
+        }
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let tmp_path = self.path.with_extension("json.tmp");
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(serde_json::to_string(&state)?.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, &self.path)?;
+
+        self.last_saved = Some(state);
+        self.last_write = Some(now);
+        Ok(())
+    }
+}
+
+fn without_clocks(state: &SavedState) -> SavedState {
+    let mut state = state.clone();
+    state.clock_time = Duration::ZERO;
+    state.timeout = match state.timeout {
+        SavedTimeout::None => SavedTimeout::None,
+        SavedTimeout::Black(_) => SavedTimeout::Black(Duration::ZERO),
+        SavedTimeout::White(_) => SavedTimeout::White(Duration::ZERO),
+        SavedTimeout::Ref(_) => SavedTimeout::Ref(Duration::ZERO),
+        SavedTimeout::PenaltyShot(_) => SavedTimeout::PenaltyShot(Duration::ZERO),
+    };
+    state
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tournament_manager::TournamentManager;
+    use tokio::time::Instant;
+    use uwh_common::config::Game as GameConfig;
+
+    #[test]
+    fn test_record_and_load() {
+        let mut path = std::env::temp_dir();
+        path.push(format!("refbox-journal-test-{}", std::process::id()));
+        path.push("game-state.json");
+
+        let mut journal = StateJournal::new(path.clone());
+        assert_eq!(journal.load(), None);
+
+        let mut tm = TournamentManager::new(GameConfig::default());
+        let now = Instant::now();
+        tm.set_scores(3, 2, now);
+        let state = tm.saved_state(now).unwrap();
+
+        journal.record(state.clone(), false, now).unwrap();
+        assert_eq!(journal.load(), Some(state));
+
+        fs::write(&path, "not json").unwrap();
+        assert_eq!(journal.load(), None);
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_clock_changes_throttled() {
+        let mut path = std::env::temp_dir();
+        path.push(format!(
+            "refbox-journal-throttle-test-{}",
+            std::process::id()
+        ));
+        path.push("game-state.json");
+
+        let mut journal = StateJournal::new(path.clone());
+
+        let mut tm = TournamentManager::new(GameConfig::default());
+        let start = Instant::now();
+        tm.start_play_now(start).unwrap();
+        let first = tm.saved_state(start).unwrap();
+        journal.record(first.clone(), true, start).unwrap();
+        assert_eq!(journal.load(), Some(first.clone()));
+
+        // Only the clock has moved, so the write is skipped
+        let now = start + Duration::from_secs(1);
+        journal
+            .record(tm.saved_state(now).unwrap(), true, now)
+            .unwrap();
+        assert_eq!(journal.load(), Some(first));
+
+        // Other changes are written straight away
+        tm.set_scores(1, 0, now);
+        let scored = tm.saved_state(now).unwrap();
+        journal.record(scored.clone(), true, now).unwrap();
+        assert_eq!(journal.load(), Some(scored));
+
+        // Once the interval has passed, the clock time is written too
+        let later = now + CLOCK_SAVE_INTERVAL;
+        let state = tm.saved_state(later).unwrap();
+        journal.record(state.clone(), true, later).unwrap();
+        assert_eq!(journal.load(), Some(state));
+
+        // When the clock stops, its final time is written without waiting
+        let stop = later + Duration::from_secs(1);
+        tm.stop_clock(stop).unwrap();
+        let state = tm.saved_state(stop).unwrap();
+        journal.record(state.clone(), false, stop).unwrap();
+        assert_eq!(journal.load(), Some(state));
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -1,8 +1,15 @@
+use crate::{
+    game_log::{GameEvent, GameEventKind, GameLog, TimeoutKind},
+    game_stats::{GameStats, GoalRecord, PenaltyRecord},
+};
 use derivative::Derivative;
 use log::*;
+use serde::{Deserialize, Serialize};
 use std::{
     cmp::{max, min, Ordering},
+    collections::VecDeque,
     convert::TryInto,
+    fmt,
     ops::{Index, IndexMut},
 };
 use thiserror::Error;
@@ -22,6 +29,7 @@ use uwh_common::{
 
 const MAX_TIME_VAL: Duration = Duration::from_secs(MAX_LONG_STRINGABLE_SECS as u64);
 const RECENT_GOAL_TIME: Duration = Duration::from_secs(10);
+const MAX_UNDO_HISTORY: usize = 20;
 
 #[derive(Debug)]
 pub struct TournamentManager {
@@ -29,6 +37,8 @@ pub struct TournamentManager {
     game_number: u32,
     game_start_time: Instant,
     current_period: GamePeriod,
+    /// The index of the current period in `config.period_sequence()`
+    period_index: usize,
     clock_state: ClockState,
     timeout_state: TimeoutState,
     b_timeouts_used: u16,
@@ -45,6 +55,11 @@ pub struct TournamentManager {
     reset_game_time: Duration,
     timezone: UtcOffset,
     recent_goal: Option<(Color, u8, GamePeriod, Duration)>,
+    game_events: Vec<GameEvent>,
+    game_stats: GameStats,
+    completed_game_log: Option<GameLog>,
+    undo_history: VecDeque<HistoryEntry>,
+    redo_history: Vec<HistoryEntry>,
 }
 
 impl TournamentManager {
@@ -54,6 +69,7 @@ impl TournamentManager {
             game_number: 0,
             game_start_time: Instant::now(),
             current_period: GamePeriod::BetweenGames,
+            period_index: 0,
             clock_state: ClockState::Stopped {
                 clock_time: config.nominal_break,
             },
@@ -73,6 +89,11 @@ impl TournamentManager {
             config,
             timezone: UtcOffset::UTC,
             recent_goal: None,
+            game_events: vec![],
+            game_stats: GameStats::default(),
+            completed_game_log: None,
+            undo_history: VecDeque::new(),
+            redo_history: vec![],
         }
     }
 
@@ -94,7 +115,19 @@ impl TournamentManager {
         self.recent_goal = self
             .game_clock_time(now)
             .map(|time| (Color::Black, player_num, self.current_period, time));
-        self.set_scores(self.b_score + 1, self.w_score, now);
+        self.log_event(
+            GameEventKind::GoalScored {
+                color: Color::Black,
+                player_number: player_num,
+            },
+            now,
+        );
+        self.record_goal(Color::Black, player_num, now);
+        self.push_undo(UndoableAction::AddScore {
+            color: Color::Black,
+            player_number: player_num,
+        });
+        self.update_scores(self.b_score + 1, self.w_score, now);
     }
 
     pub fn add_w_score(&mut self, player_num: u8, now: Instant) {
@@ -105,7 +138,19 @@ impl TournamentManager {
         self.recent_goal = self
             .game_clock_time(now)
             .map(|time| (Color::White, player_num, self.current_period, time));
-        self.set_scores(self.b_score, self.w_score + 1, now);
+        self.log_event(
+            GameEventKind::GoalScored {
+                color: Color::White,
+                player_number: player_num,
+            },
+            now,
+        );
+        self.record_goal(Color::White, player_num, now);
+        self.push_undo(UndoableAction::AddScore {
+            color: Color::White,
+            player_number: player_num,
+        });
+        self.update_scores(self.b_score, self.w_score + 1, now);
     }
 
     pub fn get_b_score(&self) -> u8 {
@@ -117,6 +162,29 @@ impl TournamentManager {
     }
 
     pub fn set_scores(&mut self, b_score: u8, w_score: u8, now: Instant) {
+        self.log_event(
+            GameEventKind::ScoresSet {
+                black: b_score,
+                white: w_score,
+            },
+            now,
+        );
+        let removed_goals = self.game_stats.trim_goals(b_score, w_score);
+        self.push_undo(UndoableAction::SetScores {
+            old: BlackWhiteBundle {
+                black: self.b_score,
+                white: self.w_score,
+            },
+            new: BlackWhiteBundle {
+                black: b_score,
+                white: w_score,
+            },
+            removed_goals,
+        });
+        self.update_scores(b_score, w_score, now);
+    }
+
+    fn update_scores(&mut self, b_score: u8, w_score: u8, now: Instant) {
         self.b_score = b_score;
         self.w_score = w_score;
         info!(
@@ -128,6 +196,56 @@ impl TournamentManager {
         }
     }
 
+    fn record_goal(&mut self, color: Color, player_number: u8, now: Instant) {
+        let clock_secs = self
+            .game_clock_time(now)
+            .map(|time| time.as_secs())
+            .unwrap_or(0);
+        self.game_stats.goals.push(GoalRecord {
+            color,
+            player_number,
+            period: self.current_period,
+            clock_secs,
+        });
+    }
+
+    /// The goals and penalties of the current game
+    pub fn game_stats(&self) -> &GameStats {
+        &self.game_stats
+    }
+
+    /// Returns the log of the most recently completed game, if it hasn't already been taken
+    pub fn take_completed_game_log(&mut self) -> Option<GameLog> {
+        self.completed_game_log.take()
+    }
+
+    fn log_event(&mut self, event: GameEventKind, now: Instant) {
+        let clock_secs = self
+            .game_clock_time(now)
+            .map(|time| time.as_secs())
+            .unwrap_or(0);
+        let event = GameEvent {
+            wall_time: OffsetDateTime::now_utc().to_offset(self.timezone),
+            period: self.current_period,
+            clock_secs,
+            event,
+        };
+        debug!("Logging game event: {event:?}");
+        self.game_events.push(event);
+    }
+
+    fn complete_game_log(&mut self) {
+        self.completed_game_log = Some(GameLog {
+            game_number: self.game_number,
+            b_score: self.b_score,
+            w_score: self.w_score,
+            black_team: None,
+            white_team: None,
+            stats: std::mem::take(&mut self.game_stats),
+            events: std::mem::take(&mut self.game_events),
+        });
+    }
+
     pub fn set_timezone(&mut self, timezone: UtcOffset) {
         self.timezone = timezone;
     }
@@ -136,10 +254,73 @@ impl TournamentManager {
         self.current_period
     }
 
+    /// Moves to the first period in the configured sequence that is reported as `period`
+    fn set_period(&mut self, period: GamePeriod) {
+        self.current_period = period;
+        self.period_index = self.config.period_index(period).unwrap_or_default();
+    }
+
+    /// Moves to the period at `index` in the configured sequence
+    fn set_period_index(&mut self, index: usize) {
+        self.current_period = self.config.period_sequence()[index].period;
+        self.period_index = index;
+    }
+
+    /// The length of the current period, if it has a fixed length
+    fn period_duration(&self) -> Option<Duration> {
+        self.config
+            .period_sequence()
+            .get(self.period_index)?
+            .duration
+    }
+
+    fn in_final_regulation_period(&self) -> bool {
+        self.period_index == self.config.final_regulation_index()
+    }
+
+    /// Moves on to the next period of regulation time. Returns `true` if it is a play period.
+    fn enter_next_regulation_period(&mut self, now: Instant) -> bool {
+        self.set_period_index(self.period_index + 1);
+        info!(
+            "{} Entering {}",
+            self.status_string(now),
+            self.current_period.to_string().to_lowercase()
+        );
+
+        let is_play = self.current_period != GamePeriod::HalfTime;
+        if is_play && self.config.timeouts_counted_per_half {
+            self.w_timeouts_used = 0;
+            self.b_timeouts_used = 0;
+        }
+        is_play
+    }
+
+    /// The length of the period after the current one, if it is known and will be played
+    fn next_period_duration(&self) -> Option<Duration> {
+        let final_index = self.config.final_regulation_index();
+        if self.period_index < final_index {
+            self.config
+                .period_sequence()
+                .get(self.period_index + 1)?
+                .duration
+        } else if self.period_index == final_index {
+            GamePeriod::SecondHalf.next_period_dur(&self.config)
+        } else {
+            self.current_period.next_period_dur(&self.config)
+        }
+    }
+
     pub fn config(&self) -> &GameConfig {
         &self.config
     }
 
+    pub fn timeouts_used(&self) -> BlackWhiteBundle<u16> {
+        BlackWhiteBundle {
+            black: self.b_timeouts_used,
+            white: self.w_timeouts_used,
+        }
+    }
+
     pub fn next_game_info(&self) -> &Option<NextGameInfo> {
         &self.next_game
     }
@@ -184,12 +365,18 @@ impl TournamentManager {
         info!("{} Resetting Game", self.status_string(now));
         let was_running = self.clock_is_running();
 
-        self.current_period = GamePeriod::BetweenGames;
+        if self.current_period != GamePeriod::BetweenGames {
+            self.log_event(GameEventKind::GameReset, now);
+            self.complete_game_log();
+        }
+
+        self.set_period(GamePeriod::BetweenGames);
         self.clock_state = ClockState::Stopped {
             clock_time: self.config.minimum_break,
         };
         self.timeout_state = TimeoutState::None;
         self.reset();
+        self.clear_history();
 
         if was_running {
             self.start_game_clock(now);
@@ -341,6 +528,13 @@ impl TournamentManager {
         match self.can_start_w_timeout() {
             Ok(()) => {
                 info!("{} Starting a white timeout", self.status_string(now));
+                self.log_event(
+                    GameEventKind::TimeoutStarted {
+                        kind: TimeoutKind::White,
+                    },
+                    now,
+                );
+                let prev_timeout = self.timeout_state.clone();
                 if self.clock_is_running() {
                     self.stop_game_clock(now)?;
                     self.timeout_state = TimeoutState::White(ClockState::CountingDown {
@@ -353,6 +547,10 @@ impl TournamentManager {
                     });
                 }
                 self.w_timeouts_used += 1;
+                self.push_undo(UndoableAction::StartTimeout {
+                    kind: TimeoutKind::White,
+                    prev_timeout,
+                });
                 Ok(())
             }
             e @ Err(_) => e,
@@ -363,6 +561,13 @@ impl TournamentManager {
         match self.can_start_b_timeout() {
             Ok(()) => {
                 info!("{} Starting a black timeout", self.status_string(now));
+                self.log_event(
+                    GameEventKind::TimeoutStarted {
+                        kind: TimeoutKind::Black,
+                    },
+                    now,
+                );
+                let prev_timeout = self.timeout_state.clone();
                 if self.clock_is_running() {
                     self.stop_game_clock(now)?;
                     self.timeout_state = TimeoutState::Black(ClockState::CountingDown {
@@ -375,6 +580,10 @@ impl TournamentManager {
                     });
                 }
                 self.b_timeouts_used += 1;
+                self.push_undo(UndoableAction::StartTimeout {
+                    kind: TimeoutKind::Black,
+                    prev_timeout,
+                });
                 Ok(())
             }
             e @ Err(_) => e,
@@ -385,8 +594,15 @@ impl TournamentManager {
         match self.can_start_ref_timeout() {
             Ok(()) => {
                 info!("{} Starting a ref timeout", self.status_string(now));
+                self.log_event(
+                    GameEventKind::TimeoutStarted {
+                        kind: TimeoutKind::Ref,
+                    },
+                    now,
+                );
+                let prev_timeout = self.timeout_state.clone();
                 if self.clock_is_running() {
-                    self.stop_game_clock(now)?;
+                    self.stop_game_clock_for_ref(now)?;
                     self.timeout_state = TimeoutState::Ref(ClockState::CountingUp {
                         start_time: now,
                         time_at_start: Duration::ZERO,
@@ -396,6 +612,10 @@ impl TournamentManager {
                         clock_time: Duration::ZERO,
                     });
                 }
+                self.push_undo(UndoableAction::StartTimeout {
+                    kind: TimeoutKind::Ref,
+                    prev_timeout,
+                });
                 Ok(())
             }
             e @ Err(_) => e,
@@ -406,17 +626,28 @@ impl TournamentManager {
         match self.can_start_penalty_shot() {
             Ok(()) => {
                 info!("{} Starting a penalty shot", self.status_string(now));
+                self.log_event(
+                    GameEventKind::TimeoutStarted {
+                        kind: TimeoutKind::PenaltyShot,
+                    },
+                    now,
+                );
+                let prev_timeout = self.timeout_state.clone();
                 if self.clock_is_running() {
-                    self.stop_game_clock(now)?;
-                    self.timeout_state = TimeoutState::PenaltyShot(ClockState::CountingUp {
+                    self.stop_game_clock_for_ref(now)?;
+                    self.timeout_state = TimeoutState::PenaltyShot(ClockState::CountingDown {
                         start_time: now,
-                        time_at_start: Duration::ZERO,
+                        time_remaining_at_start: self.config.penalty_shot_duration,
                     });
                 } else {
                     self.timeout_state = TimeoutState::PenaltyShot(ClockState::Stopped {
-                        clock_time: Duration::ZERO,
+                        clock_time: self.config.penalty_shot_duration,
                     });
                 }
+                self.push_undo(UndoableAction::StartTimeout {
+                    kind: TimeoutKind::PenaltyShot,
+                    prev_timeout,
+                });
                 Ok(())
             }
             e @ Err(_) => e,
@@ -427,6 +658,12 @@ impl TournamentManager {
         match self.can_switch_to_w_timeout() {
             Ok(()) => {
                 info!("Switching to a white timeout");
+                self.log_event(
+                    GameEventKind::TimeoutSwitched {
+                        kind: TimeoutKind::White,
+                    },
+                    Instant::now(),
+                );
                 if let TimeoutState::Black(cs) = &self.timeout_state {
                     self.timeout_state = TimeoutState::White(cs.clone());
                 }
@@ -442,6 +679,12 @@ impl TournamentManager {
         match self.can_switch_to_b_timeout() {
             Ok(()) => {
                 info!("Switching to a black timeout");
+                self.log_event(
+                    GameEventKind::TimeoutSwitched {
+                        kind: TimeoutKind::Black,
+                    },
+                    Instant::now(),
+                );
                 if let TimeoutState::White(cs) = &self.timeout_state {
                     self.timeout_state = TimeoutState::Black(cs.clone());
                 }
@@ -457,8 +700,28 @@ impl TournamentManager {
         match self.can_switch_to_ref_timeout() {
             Ok(()) => {
                 info!("Switching to a ref timeout");
+                self.log_event(
+                    GameEventKind::TimeoutSwitched {
+                        kind: TimeoutKind::Ref,
+                    },
+                    Instant::now(),
+                );
+                // The ref timeout counts up from however long the penalty shot has been going
+                let shot_duration = self.config.penalty_shot_duration;
                 if let TimeoutState::PenaltyShot(cs) = &self.timeout_state {
-                    self.timeout_state = TimeoutState::Ref(cs.clone());
+                    self.timeout_state = TimeoutState::Ref(match *cs {
+                        ClockState::CountingDown {
+                            start_time,
+                            time_remaining_at_start,
+                        } => ClockState::CountingUp {
+                            start_time,
+                            time_at_start: shot_duration.saturating_sub(time_remaining_at_start),
+                        },
+                        ClockState::Stopped { clock_time } => ClockState::Stopped {
+                            clock_time: shot_duration.saturating_sub(clock_time),
+                        },
+                        ClockState::CountingUp { .. } => cs.clone(),
+                    });
                 }
                 Ok(())
             }
@@ -466,12 +729,29 @@ impl TournamentManager {
         }
     }
 
-    pub fn switch_to_penalty_shot(&mut self) -> Result<()> {
+    pub fn switch_to_penalty_shot(&mut self, now: Instant) -> Result<()> {
         match self.can_switch_to_penalty_shot() {
             Ok(()) => {
-                info!("Switching to a penalty shot");
+                info!("{} Switching to a penalty shot", self.status_string(now));
+                self.log_event(
+                    GameEventKind::TimeoutSwitched {
+                        kind: TimeoutKind::PenaltyShot,
+                    },
+                    now,
+                );
+                // The shooter gets the full penalty shot time, regardless of how long the ref
+                // timeout has been going
                 if let TimeoutState::Ref(cs) = &self.timeout_state {
-                    self.timeout_state = TimeoutState::PenaltyShot(cs.clone());
+                    self.timeout_state = TimeoutState::PenaltyShot(if cs.is_running() {
+                        ClockState::CountingDown {
+                            start_time: now,
+                            time_remaining_at_start: self.config.penalty_shot_duration,
+                        }
+                    } else {
+                        ClockState::Stopped {
+                            clock_time: self.config.penalty_shot_duration,
+                        }
+                    });
                 }
                 Ok(())
             }
@@ -479,7 +759,45 @@ impl TournamentManager {
         }
     }
 
+    /// Records the outcome of the current penalty shot and ends it. A scored penalty shot adds
+    /// the goal the same way as any other goal.
+    pub fn record_penalty_shot(
+        &mut self,
+        color: Color,
+        player_number: u8,
+        scored: bool,
+        now: Instant,
+    ) -> Result<()> {
+        if !matches!(self.timeout_state, TimeoutState::PenaltyShot(_)) {
+            return Err(TournamentManagerError::NoPenaltyShot);
+        }
+        info!(
+            "{} Penalty shot by {color} player #{player_number} was {}",
+            self.status_string(now),
+            if scored { "scored" } else { "missed" }
+        );
+        self.log_event(
+            GameEventKind::PenaltyShotTaken {
+                color,
+                player_number,
+                scored,
+            },
+            now,
+        );
+        self.end_timeout(now)?;
+        if scored {
+            match color {
+                Color::Black => self.add_b_score(player_number, now),
+                Color::White => self.add_w_score(player_number, now),
+            }
+        }
+        Ok(())
+    }
+
     pub fn end_timeout(&mut self, now: Instant) -> Result<()> {
+        if self.timeout_state != TimeoutState::None {
+            self.log_event(GameEventKind::TimeoutEnded, now);
+        }
         match &self.timeout_state {
             TimeoutState::None => Err(TournamentManagerError::NotInTimeout),
             TimeoutState::Black(cs) | TimeoutState::White(cs) => {
@@ -495,7 +813,22 @@ impl TournamentManager {
 
                 Ok(())
             }
-            TimeoutState::Ref(cs) | TimeoutState::PenaltyShot(cs) => {
+            TimeoutState::PenaltyShot(cs) => {
+                info!("{} Ending penalty shot", self.status_string(now));
+                // A shot that ran out of time was started with the clocks running, so they
+                // restart as if the shot clock were still running
+                let expired =
+                    matches!(cs, ClockState::Stopped { clock_time } if clock_time.is_zero());
+                if cs.is_running() {
+                    self.start_game_clock(now);
+                } else if expired && self.start_game_clock(now) {
+                    self.send_clock_running(true);
+                }
+                self.timeout_state = TimeoutState::None;
+
+                Ok(())
+            }
+            TimeoutState::Ref(cs) => {
                 let timeout_time = match cs.clone() {
                     ClockState::Stopped { clock_time } => {
                         self.timeout_state = TimeoutState::None;
@@ -515,15 +848,12 @@ impl TournamentManager {
 
                 if let Some(dur) = timeout_time {
                     info!(
-                        "{} Ending ref timeout or penalty shot. The timeout duration was {:?}",
+                        "{} Ending ref timeout. The timeout duration was {:?}",
                         self.status_string(now),
                         dur
                     );
                 } else {
-                    info!(
-                        "{} Ending ref timeout or penalty shot",
-                        self.status_string(now)
-                    );
+                    info!("{} Ending ref timeout", self.status_string(now));
                 }
 
                 Ok(())
@@ -546,16 +876,30 @@ impl TournamentManager {
             .game_clock_time(now)
             .ok_or(TournamentManagerError::InvalidNowValue)?;
 
+        self.log_event(
+            GameEventKind::PenaltyStarted {
+                color,
+                player_number,
+                kind,
+            },
+            now,
+        );
+
         let penalty = Penalty {
             start_time,
             start_period: self.current_period,
+            start_index: Some(self.period_index),
             player_number,
             kind,
         };
         match color {
-            Color::Black => self.b_penalties.push(penalty),
-            Color::White => self.w_penalties.push(penalty),
+            Color::Black => self.b_penalties.push(penalty.clone()),
+            Color::White => self.w_penalties.push(penalty.clone()),
         };
+        self.game_stats
+            .penalties
+            .push(PenaltyRecord::new(color, &penalty));
+        self.push_undo(UndoableAction::StartPenalty { color, penalty });
         Ok(())
     }
 
@@ -569,12 +913,22 @@ impl TournamentManager {
             return Err(TournamentManagerError::InvalidIndex(color, index));
         }
         let pen = vec.remove(index);
+        self.game_stats.remove_penalty(color, &pen);
+        let now = Instant::now();
         info!(
             "{} Deleting {color} player #{}'s {:?} penalty",
-            self.status_string(Instant::now()),
+            self.status_string(now),
             pen.player_number,
             pen.kind
         );
+        self.log_event(
+            GameEventKind::PenaltyDeleted {
+                color,
+                player_number: pen.player_number,
+                kind: pen.kind,
+            },
+            now,
+        );
 
         Ok(())
     }
@@ -587,7 +941,8 @@ impl TournamentManager {
         new_player_number: u8,
         new_kind: PenaltyKind,
     ) -> Result<()> {
-        let status_str = self.status_string(Instant::now());
+        let now = Instant::now();
+        let status_str = self.status_string(now);
         let penalty = match old_color {
             Color::Black => self.b_penalties.get_mut(index),
             Color::White => self.w_penalties.get_mut(index),
@@ -598,15 +953,27 @@ impl TournamentManager {
             it is now {new_color} player #{new_player_number}'s {new_kind:?} penalty",
             penalty.player_number, penalty.kind
         );
+        let event = GameEventKind::PenaltyEdited {
+            old_color,
+            old_player_number: penalty.player_number,
+            old_kind: penalty.kind,
+            new_color,
+            new_player_number,
+            new_kind,
+        };
 
+        let old_penalty = penalty.clone();
         penalty.player_number = new_player_number;
         penalty.kind = new_kind;
+        self.game_stats
+            .replace_penalty(old_color, &old_penalty, new_color, penalty);
         if old_color != new_color {
             match old_color {
                 Color::Black => self.w_penalties.push(self.b_penalties.remove(index)),
                 Color::White => self.b_penalties.push(self.w_penalties.remove(index)),
             };
         }
+        self.log_event(event, now);
         Ok(())
     }
 
@@ -614,7 +981,7 @@ impl TournamentManager {
         let time = self
             .game_clock_time(now)
             .ok_or(TournamentManagerError::InvalidNowValue)?;
-        let period = self.current_period;
+        let period = self.period_index;
 
         let list = match color {
             Color::Black => &mut self.b_penalties,
@@ -643,7 +1010,7 @@ impl TournamentManager {
         let time = self
             .game_clock_time(now)
             .ok_or(TournamentManagerError::InvalidNowValue)?;
-        let period = self.current_period;
+        let period = self.period_index;
 
         info!("{} Culling penalties", self.status_string(now));
 
@@ -711,7 +1078,7 @@ impl TournamentManager {
         };
 
         if let Some(ref timing) = next_game_info.timing {
-            self.config = timing.clone().into();
+            self.config = timing.apply_to(&self.config);
         }
 
         let time_remaining_at_start = self.calc_time_to_next_game(now, now);
@@ -732,7 +1099,17 @@ impl TournamentManager {
     fn end_game(&mut self, now: Instant) {
         let was_running = self.clock_is_running();
 
-        self.current_period = GamePeriod::BetweenGames;
+        self.log_event(
+            GameEventKind::GameEnded {
+                black: self.b_score,
+                white: self.w_score,
+            },
+            now,
+        );
+        self.complete_game_log();
+        self.clear_history();
+
+        self.set_period(GamePeriod::BetweenGames);
 
         info!(
             "{} Ending game {}. Score is B({}), W({})",
@@ -782,9 +1159,12 @@ impl TournamentManager {
         }
 
         self.game_number = self.next_game_number();
+        self.game_events.clear();
+        self.game_stats = GameStats::default();
+        self.clear_history();
 
         if let Some(timing) = self.next_game.take().and_then(|info| info.timing) {
-            self.config = timing.into();
+            self.config = timing.apply_to(&self.config);
         }
 
         info!(
@@ -792,19 +1172,15 @@ impl TournamentManager {
             self.status_string(start_time),
             self.game_number
         );
-        self.current_period = GamePeriod::FirstHalf;
+        self.set_period(GamePeriod::FirstHalf);
         self.game_start_time = start_time;
         self.b_timeouts_used = 0;
         self.w_timeouts_used = 0;
         self.has_reset = false;
 
         let sched_start = self.next_scheduled_start.unwrap_or(start_time);
-        self.next_scheduled_start = Some(
-            sched_start
-                + 2 * self.config.half_play_duration
-                + self.config.half_time_duration
-                + self.config.nominal_break,
-        );
+        self.next_scheduled_start =
+            Some(sched_start + self.config.regulation_duration() + self.config.nominal_break);
     }
 
     pub fn would_end_game(&self, now: Instant) -> Result<bool> {
@@ -818,7 +1194,7 @@ impl TournamentManager {
                 .ok_or(TournamentManagerError::InvalidNowValue)?;
 
             Ok(time >= time_remaining_at_start
-                && ((self.current_period == GamePeriod::SecondHalf
+                && ((self.in_final_regulation_period()
                     && (self.b_score != self.w_score
                         || (!self.config.overtime_allowed && !self.config.sudden_death_allowed)))
                     || (self.current_period == GamePeriod::OvertimeSecondHalf
@@ -829,6 +1205,27 @@ impl TournamentManager {
     }
 
     pub(super) fn update(&mut self, now: Instant) -> Result<()> {
+        // The penalty shot clock stops at zero, but the penalty shot lasts until the ref ends it
+        if let TimeoutState::PenaltyShot(ClockState::CountingDown {
+            start_time,
+            time_remaining_at_start,
+        }) = self.timeout_state
+        {
+            let time = now
+                .checked_duration_since(start_time)
+                .ok_or(TournamentManagerError::InvalidNowValue)?;
+            if time >= time_remaining_at_start {
+                info!("{} Penalty shot time has expired", self.status_string(now));
+                self.timeout_state = TimeoutState::PenaltyShot(ClockState::Stopped {
+                    clock_time: Duration::ZERO,
+                });
+                // The stop-clock policy may have left the game clock running
+                if !self.clock_state.is_running() {
+                    self.send_clock_running(false);
+                }
+            }
+        }
+
         // Case of clock running, with no timeout and not SD
         if let ClockState::CountingDown {
             start_time,
@@ -847,24 +1244,37 @@ impl TournamentManager {
                 self.reset();
             };
 
-            if time >= time_remaining_at_start {
+            if time >= time_remaining_at_start
+                && matches!(
+                    self.timeout_state,
+                    TimeoutState::Ref(_) | TimeoutState::PenaltyShot(_)
+                )
+            {
+                // The stop-clock policy left the game clock running through a ref timeout or
+                // penalty shot. The period can't end in the middle of one, so the game clock
+                // waits at zero and the period ends once the timeout is over.
+                info!(
+                    "{} Holding the end of the period until the timeout ends",
+                    self.status_string(now)
+                );
+                self.clock_state = ClockState::Stopped {
+                    clock_time: Duration::ZERO,
+                };
+                if !self.clock_is_running() {
+                    self.send_clock_running(false);
+                }
+            } else if time >= time_remaining_at_start {
                 let mut need_cull = false;
                 match self.current_period {
                     GamePeriod::BetweenGames => {
                         self.start_game(start_time + time_remaining_at_start);
                     }
-                    GamePeriod::FirstHalf => {
-                        info!("{} Entering half time", self.status_string(now));
-                        self.current_period = GamePeriod::HalfTime;
-                    }
-                    GamePeriod::HalfTime => {
-                        info!("{} Entering second half", self.status_string(now));
-                        self.current_period = GamePeriod::SecondHalf;
-                        self.w_timeouts_used = 0;
-                        self.b_timeouts_used = 0;
-                        need_cull = true;
+                    GamePeriod::FirstHalf | GamePeriod::HalfTime | GamePeriod::SecondHalf
+                        if !self.in_final_regulation_period() =>
+                    {
+                        need_cull = self.enter_next_regulation_period(now);
                     }
-                    GamePeriod::SecondHalf => {
+                    GamePeriod::FirstHalf | GamePeriod::HalfTime | GamePeriod::SecondHalf => {
                         if self.b_score != self.w_score
                             || (!self.config.overtime_allowed && !self.config.sudden_death_allowed)
                         {
@@ -876,7 +1286,7 @@ impl TournamentManager {
                                 self.b_score,
                                 self.w_score
                             );
-                            self.current_period = GamePeriod::PreOvertime;
+                            self.set_period(GamePeriod::PreOvertime);
                         } else {
                             info!(
                                 "{} Entering pre-sudden death. Score is B({}), W({})",
@@ -884,21 +1294,21 @@ impl TournamentManager {
                                 self.b_score,
                                 self.w_score
                             );
-                            self.current_period = GamePeriod::PreSuddenDeath;
+                            self.set_period(GamePeriod::PreSuddenDeath);
                         }
                     }
                     GamePeriod::PreOvertime => {
                         info!("{} Entering overtime first half", self.status_string(now));
-                        self.current_period = GamePeriod::OvertimeFirstHalf;
+                        self.set_period(GamePeriod::OvertimeFirstHalf);
                         need_cull = true;
                     }
                     GamePeriod::OvertimeFirstHalf => {
                         info!("{} Entering overtime half time", self.status_string(now));
-                        self.current_period = GamePeriod::OvertimeHalfTime;
+                        self.set_period(GamePeriod::OvertimeHalfTime);
                     }
                     GamePeriod::OvertimeHalfTime => {
                         info!("{} Entering ovetime second half", self.status_string(now));
-                        self.current_period = GamePeriod::OvertimeSecondHalf;
+                        self.set_period(GamePeriod::OvertimeSecondHalf);
                         need_cull = true;
                     }
                     GamePeriod::OvertimeSecondHalf => {
@@ -911,12 +1321,12 @@ impl TournamentManager {
                                 self.b_score,
                                 self.w_score
                             );
-                            self.current_period = GamePeriod::PreSuddenDeath;
+                            self.set_period(GamePeriod::PreSuddenDeath);
                         }
                     }
                     GamePeriod::PreSuddenDeath => {
                         info!("{} Entering sudden death", self.status_string(now));
-                        self.current_period = GamePeriod::SuddenDeath;
+                        self.set_period(GamePeriod::SuddenDeath);
                         need_cull = true;
                     }
                     GamePeriod::SuddenDeath => {
@@ -930,10 +1340,7 @@ impl TournamentManager {
                     self.clock_state = if self.current_period != GamePeriod::SuddenDeath {
                         ClockState::CountingDown {
                             start_time: start_time + time_remaining_at_start,
-                            time_remaining_at_start: self
-                                .current_period
-                                .duration(&self.config)
-                                .unwrap(),
+                            time_remaining_at_start: self.period_duration().unwrap(),
                         }
                     } else {
                         ClockState::CountingUp {
@@ -944,6 +1351,12 @@ impl TournamentManager {
                     if need_cull {
                         self.cull_penalties(now)?;
                     }
+                    self.log_event(
+                        GameEventKind::PeriodStarted {
+                            period: self.current_period,
+                        },
+                        now,
+                    );
                 }
             }
         } else {
@@ -1022,6 +1435,35 @@ impl TournamentManager {
         }
     }
 
+    /// Stops the game clock for a ref timeout or penalty shot, unless the stop-clock policy says
+    /// it should keep running. Breaks are always stopped.
+    fn stop_game_clock_for_ref(&mut self, now: Instant) -> Result<bool> {
+        let in_play = matches!(
+            self.current_period,
+            GamePeriod::FirstHalf
+                | GamePeriod::SecondHalf
+                | GamePeriod::OvertimeFirstHalf
+                | GamePeriod::OvertimeSecondHalf
+                | GamePeriod::SuddenDeath
+        );
+        let clock_time = match self.clock_state {
+            ClockState::CountingDown { .. } => self.clock_state.clock_time(now),
+            ClockState::CountingUp { .. } | ClockState::Stopped { .. } => None,
+        };
+
+        if !in_play
+            || self
+                .config
+                .stop_clock_policy
+                .stops_clock(clock_time, self.config.stop_clock_window)
+        {
+            self.stop_game_clock(now)
+        } else {
+            info!("{} Leaving the game clock running", self.status_string(now));
+            Ok(false)
+        }
+    }
+
     fn send_clock_running(&self, running: bool) {
         self.start_stop_tx.send(running).unwrap();
     }
@@ -1031,7 +1473,9 @@ impl TournamentManager {
         let status_str = self.status_string(now);
         match &mut self.timeout_state {
             TimeoutState::None => need_to_send = self.start_game_clock(now),
-            TimeoutState::Black(ref mut cs) | TimeoutState::White(ref mut cs) => {
+            TimeoutState::Black(ref mut cs)
+            | TimeoutState::White(ref mut cs)
+            | TimeoutState::PenaltyShot(ref mut cs) => {
                 if let ClockState::Stopped { clock_time } = cs {
                     info!("{status_str} Starting the timeout clock");
                     *cs = ClockState::CountingDown {
@@ -1041,7 +1485,7 @@ impl TournamentManager {
                     need_to_send = true;
                 }
             }
-            TimeoutState::Ref(ref mut cs) | TimeoutState::PenaltyShot(ref mut cs) => {
+            TimeoutState::Ref(ref mut cs) => {
                 if let ClockState::Stopped { clock_time } = cs {
                     info!("{status_str} Starting the timeout clock");
                     *cs = ClockState::CountingUp {
@@ -1074,7 +1518,7 @@ impl TournamentManager {
                 }
             }
             TimeoutState::Ref(ref mut cs) | TimeoutState::PenaltyShot(ref mut cs) => {
-                if let ClockState::CountingUp { .. } = cs {
+                if cs.is_running() {
                     info!("{status_str} Stopping the timeout clock");
                     *cs = ClockState::Stopped {
                         clock_time: cs
@@ -1083,6 +1527,8 @@ impl TournamentManager {
                     };
                     need_to_send = true;
                 }
+                // The game clock may have been left running by the stop-clock policy
+                need_to_send |= self.stop_game_clock(now)?;
             }
         };
         if need_to_send {
@@ -1137,6 +1583,7 @@ impl TournamentManager {
         }
 
         let was_running = self.clock_is_running();
+        let prev_state = Box::new(self.capture_undo_state());
 
         let mut need_cull = false;
         match self.current_period {
@@ -1149,35 +1596,31 @@ impl TournamentManager {
                 self.start_game(now);
             }
             GamePeriod::HalfTime => {
-                info!("{} Entering second half", self.status_string(now));
-                self.current_period = GamePeriod::SecondHalf;
-                self.w_timeouts_used = 0;
-                self.b_timeouts_used = 0;
-                need_cull = true;
+                need_cull = self.enter_next_regulation_period(now);
             }
             GamePeriod::PreOvertime => {
                 info!("{} Entering overtime first half", self.status_string(now));
-                self.current_period = GamePeriod::OvertimeFirstHalf;
+                self.set_period(GamePeriod::OvertimeFirstHalf);
                 need_cull = true;
             }
             GamePeriod::OvertimeHalfTime => {
                 info!("{} Entering ovetime second half", self.status_string(now));
-                self.current_period = GamePeriod::OvertimeSecondHalf;
+                self.set_period(GamePeriod::OvertimeSecondHalf);
                 need_cull = true;
             }
             GamePeriod::PreSuddenDeath => {
                 info!("{} Entering sudden death", self.status_string(now));
-                self.current_period = GamePeriod::SuddenDeath;
+                self.set_period(GamePeriod::SuddenDeath);
                 need_cull = true;
             }
         }
         self.clock_state = match self.current_period {
-            p @ GamePeriod::FirstHalf
-            | p @ GamePeriod::SecondHalf
-            | p @ GamePeriod::OvertimeFirstHalf
-            | p @ GamePeriod::OvertimeSecondHalf => ClockState::CountingDown {
+            GamePeriod::FirstHalf
+            | GamePeriod::SecondHalf
+            | GamePeriod::OvertimeFirstHalf
+            | GamePeriod::OvertimeSecondHalf => ClockState::CountingDown {
                 start_time: now,
-                time_remaining_at_start: p.duration(&self.config).unwrap(),
+                time_remaining_at_start: self.period_duration().unwrap(),
             },
             GamePeriod::SuddenDeath => ClockState::CountingUp {
                 start_time: now,
@@ -1188,12 +1631,19 @@ impl TournamentManager {
         if need_cull {
             self.cull_penalties(now)?;
         }
+        self.log_event(
+            GameEventKind::PeriodStarted {
+                period: self.current_period,
+            },
+            now,
+        );
 
         info!(
             "{} {} manually started by refs",
             self.status_string(now),
             self.current_period
         );
+        self.push_undo(UndoableAction::StartPlayNow(prev_state));
 
         if !was_running {
             self.send_clock_running(true);
@@ -1217,15 +1667,22 @@ impl TournamentManager {
                 .chain(self.w_penalties.iter_mut())
             {
                 if (pen.kind != PenaltyKind::TotalDismissal)
-                    && (pen.time_remaining(self.current_period, clock_time, &self.config)?
+                    && (pen.time_remaining(self.period_index, clock_time, &self.config)?
                         > pen.kind.as_duration().unwrap())
                 {
                     pen.start_period = self.current_period;
+                    pen.start_index = Some(self.period_index);
                     pen.start_time = clock_time;
                 }
             }
 
             self.clock_state = ClockState::Stopped { clock_time };
+            self.log_event(
+                GameEventKind::GameClockSet {
+                    secs: clock_time.as_secs(),
+                },
+                Instant::now(),
+            );
             Ok(())
         } else {
             Err(TournamentManagerError::ClockIsRunning)
@@ -1250,12 +1707,373 @@ impl TournamentManager {
                     return Err(TournamentManagerError::NotInTimeout);
                 }
             };
+            self.log_event(
+                GameEventKind::TimeoutClockSet {
+                    secs: clock_time.as_secs(),
+                },
+                Instant::now(),
+            );
             Ok(())
         } else {
             Err(TournamentManagerError::ClockIsRunning)
         }
     }
 
+    pub fn can_undo(&self) -> bool {
+        matches!(self.undo_history.back(), Some(entry) if entry.period == self.current_period)
+    }
+
+    pub fn can_redo(&self) -> bool {
+        matches!(self.redo_history.last(), Some(entry) if entry.period == self.current_period)
+    }
+
+    fn push_undo(&mut self, action: UndoableAction) {
+        if self.undo_history.len() >= MAX_UNDO_HISTORY {
+            self.undo_history.pop_front();
+        }
+        self.undo_history.push_back(HistoryEntry {
+            period: self.current_period,
+            action,
+        });
+        self.redo_history.clear();
+    }
+
+    fn clear_history(&mut self) {
+        self.undo_history.clear();
+        self.redo_history.clear();
+    }
+
+    fn capture_undo_state(&self) -> UndoState {
+        UndoState {
+            config: self.config.clone(),
+            game_number: self.game_number,
+            game_start_time: self.game_start_time,
+            current_period: self.current_period,
+            period_index: self.period_index,
+            clock_state: self.clock_state.clone(),
+            b_timeouts_used: self.b_timeouts_used,
+            w_timeouts_used: self.w_timeouts_used,
+            b_score: self.b_score,
+            w_score: self.w_score,
+            b_penalties: self.b_penalties.clone(),
+            w_penalties: self.w_penalties.clone(),
+            has_reset: self.has_reset,
+            next_game: self.next_game.clone(),
+            next_scheduled_start: self.next_scheduled_start,
+            recent_goal: self.recent_goal,
+            game_events: self.game_events.clone(),
+            game_stats: self.game_stats.clone(),
+        }
+    }
+
+    /// Reverses the most recent undoable action. The game clock is kept consistent with the
+    /// time that has passed since the action was taken.
+    pub fn undo(&mut self, now: Instant) -> Result<()> {
+        if !self.can_undo() {
+            self.clear_history();
+            return Err(TournamentManagerError::NothingToUndo);
+        }
+        let entry = self.undo_history.pop_back().unwrap();
+        info!(
+            "{} Undoing action: {:?}",
+            self.status_string(now),
+            entry.action
+        );
+
+        match entry.action {
+            UndoableAction::AddScore {
+                color,
+                player_number,
+            } => {
+                match color {
+                    Color::Black => self.b_score = self.b_score.saturating_sub(1),
+                    Color::White => self.w_score = self.w_score.saturating_sub(1),
+                };
+                if let Some((c, n, _, _)) = self.recent_goal {
+                    if c == color && n == player_number {
+                        self.recent_goal = None;
+                    }
+                }
+                self.game_stats.remove_goal(color, player_number);
+            }
+            UndoableAction::SetScores {
+                old,
+                ref removed_goals,
+                ..
+            } => {
+                self.b_score = old.black;
+                self.w_score = old.white;
+                self.game_stats.restore_goals(removed_goals);
+            }
+            UndoableAction::StartTimeout {
+                kind,
+                ref prev_timeout,
+            } => {
+                let timeout_clock_running = match (kind, &self.timeout_state) {
+                    (TimeoutKind::Black, TimeoutState::Black(cs))
+                    | (TimeoutKind::White, TimeoutState::White(cs))
+                    | (TimeoutKind::Ref, TimeoutState::Ref(cs))
+                    | (TimeoutKind::PenaltyShot, TimeoutState::PenaltyShot(cs)) => cs.is_running(),
+                    _ => {
+                        self.clear_history();
+                        return Err(TournamentManagerError::UndoNotPossible);
+                    }
+                };
+                match kind {
+                    TimeoutKind::Black => {
+                        self.b_timeouts_used = self.b_timeouts_used.saturating_sub(1)
+                    }
+                    TimeoutKind::White => {
+                        self.w_timeouts_used = self.w_timeouts_used.saturating_sub(1)
+                    }
+                    TimeoutKind::Ref | TimeoutKind::PenaltyShot => {}
+                };
+                self.timeout_state = prev_timeout.clone();
+                // The game clock was stopped for the duration of the timeout, so it resumes from
+                // where it was stopped
+                if timeout_clock_running && *prev_timeout == TimeoutState::None {
+                    self.start_game_clock(now);
+                }
+            }
+            UndoableAction::StartPenalty { color, ref penalty } => {
+                let list = match color {
+                    Color::Black => &mut self.b_penalties,
+                    Color::White => &mut self.w_penalties,
+                };
+                if let Some(index) = list.iter().rposition(|pen| pen == penalty) {
+                    list.remove(index);
+                    self.game_stats.remove_penalty(color, penalty);
+                } else {
+                    self.clear_history();
+                    return Err(TournamentManagerError::UndoNotPossible);
+                }
+            }
+            UndoableAction::StartPlayNow(ref prev_state) => {
+                if self.timeout_state != TimeoutState::None {
+                    self.clear_history();
+                    return Err(TournamentManagerError::UndoNotPossible);
+                }
+                let was_running = self.clock_is_running();
+                self.restore_undo_state((**prev_state).clone());
+                if was_running != self.clock_is_running() {
+                    self.send_clock_running(!was_running);
+                }
+            }
+        };
+
+        self.log_event(GameEventKind::ActionUndone, now);
+        self.redo_history.push(HistoryEntry {
+            period: self.current_period,
+            action: entry.action,
+        });
+        Ok(())
+    }
+
+    /// Repeats the most recently undone action
+    pub fn redo(&mut self, now: Instant) -> Result<()> {
+        if !self.can_redo() {
+            self.redo_history.clear();
+            return Err(TournamentManagerError::NothingToRedo);
+        }
+        let entry = self.redo_history.pop().unwrap();
+        info!(
+            "{} Redoing action: {:?}",
+            self.status_string(now),
+            entry.action
+        );
+
+        // Redoing an action adds it back to the undo history, which would otherwise clear
+        // the remaining redo history
+        let redo_history = std::mem::take(&mut self.redo_history);
+        let result = match entry.action {
+            UndoableAction::AddScore {
+                color: Color::Black,
+                player_number,
+            } => {
+                self.add_b_score(player_number, now);
+                Ok(())
+            }
+            UndoableAction::AddScore {
+                color: Color::White,
+                player_number,
+            } => {
+                self.add_w_score(player_number, now);
+                Ok(())
+            }
+            UndoableAction::SetScores { new, .. } => {
+                self.set_scores(new.black, new.white, now);
+                Ok(())
+            }
+            UndoableAction::StartTimeout { kind, .. } => match kind {
+                TimeoutKind::Black => self.start_b_timeout(now),
+                TimeoutKind::White => self.start_w_timeout(now),
+                TimeoutKind::Ref => self.start_ref_timeout(now),
+                TimeoutKind::PenaltyShot => self.start_penalty_shot(now),
+            },
+            UndoableAction::StartPenalty { color, penalty } => {
+                // Keep the original start time so that the penalty ends when it originally would have
+                self.log_event(
+                    GameEventKind::PenaltyStarted {
+                        color,
+                        player_number: penalty.player_number,
+                        kind: penalty.kind,
+                    },
+                    now,
+                );
+                match color {
+                    Color::Black => self.b_penalties.push(penalty.clone()),
+                    Color::White => self.w_penalties.push(penalty.clone()),
+                };
+                self.game_stats
+                    .penalties
+                    .push(PenaltyRecord::new(color, &penalty));
+                self.push_undo(UndoableAction::StartPenalty { color, penalty });
+                Ok(())
+            }
+            UndoableAction::StartPlayNow(_) => self.start_play_now(now),
+        };
+        self.redo_history = redo_history;
+
+        if result.is_err() {
+            self.redo_history.clear();
+        }
+        result
+    }
+
+    fn restore_undo_state(&mut self, state: UndoState) {
+        let UndoState {
+            config,
+            game_number,
+            game_start_time,
+            current_period,
+            period_index,
+            clock_state,
+            b_timeouts_used,
+            w_timeouts_used,
+            b_score,
+            w_score,
+            b_penalties,
+            w_penalties,
+            has_reset,
+            next_game,
+            next_scheduled_start,
+            recent_goal,
+            game_events,
+            game_stats,
+        } = state;
+
+        self.config = config;
+        self.game_number = game_number;
+        self.game_start_time = game_start_time;
+        self.current_period = current_period;
+        self.period_index = period_index;
+        self.clock_state = clock_state;
+        self.b_timeouts_used = b_timeouts_used;
+        self.w_timeouts_used = w_timeouts_used;
+        self.b_score = b_score;
+        self.w_score = w_score;
+        self.b_penalties = b_penalties;
+        self.w_penalties = w_penalties;
+        self.has_reset = has_reset;
+        self.next_game = next_game;
+        self.next_scheduled_start = next_scheduled_start;
+        self.recent_goal = recent_goal;
+        self.game_events = game_events;
+        self.game_stats = game_stats;
+    }
+
+    /// Returns a record of the current state with all clocks stopped at their value at `now`.
+    /// Returns `None` if any of the clock times can't be calculated.
+    pub(crate) fn saved_state(&self, now: Instant) -> Option<SavedState> {
+        let timeout = match self.timeout_state {
+            TimeoutState::None => SavedTimeout::None,
+            TimeoutState::Black(ref cs) => SavedTimeout::Black(cs.clock_time(now)?),
+            TimeoutState::White(ref cs) => SavedTimeout::White(cs.clock_time(now)?),
+            TimeoutState::Ref(ref cs) => SavedTimeout::Ref(cs.clock_time(now)?),
+            TimeoutState::PenaltyShot(ref cs) => SavedTimeout::PenaltyShot(cs.clock_time(now)?),
+        };
+
+        Some(SavedState {
+            config: self.config.clone(),
+            game_number: self.game_number,
+            current_period: self.current_period,
+            period_index: Some(self.period_index),
+            clock_time: self.game_clock_time(now)?,
+            timeout,
+            b_timeouts_used: self.b_timeouts_used,
+            w_timeouts_used: self.w_timeouts_used,
+            b_score: self.b_score,
+            w_score: self.w_score,
+            b_penalties: self.b_penalties.clone(),
+            w_penalties: self.w_penalties.clone(),
+            has_reset: self.has_reset,
+            next_game: self.next_game.clone(),
+            game_events: self.game_events.clone(),
+            game_stats: self.game_stats.clone(),
+        })
+    }
+
+    /// Replaces the current state with a saved one. The clock is left stopped.
+    pub(crate) fn restore_state(&mut self, state: SavedState, now: Instant) {
+        let SavedState {
+            config,
+            game_number,
+            current_period,
+            period_index,
+            clock_time,
+            timeout,
+            b_timeouts_used,
+            w_timeouts_used,
+            b_score,
+            w_score,
+            b_penalties,
+            w_penalties,
+            has_reset,
+            next_game,
+            game_events,
+            game_stats,
+        } = state;
+
+        self.config = config;
+        self.game_number = game_number;
+        match period_index {
+            Some(index) if index < self.config.period_sequence().len() => {
+                self.set_period_index(index)
+            }
+            _ => self.set_period(current_period),
+        }
+        self.clock_state = ClockState::Stopped { clock_time };
+        self.timeout_state = match timeout {
+            SavedTimeout::None => TimeoutState::None,
+            SavedTimeout::Black(clock_time) => {
+                TimeoutState::Black(ClockState::Stopped { clock_time })
+            }
+            SavedTimeout::White(clock_time) => {
+                TimeoutState::White(ClockState::Stopped { clock_time })
+            }
+            SavedTimeout::Ref(clock_time) => TimeoutState::Ref(ClockState::Stopped { clock_time }),
+            SavedTimeout::PenaltyShot(clock_time) => {
+                TimeoutState::PenaltyShot(ClockState::Stopped { clock_time })
+            }
+        };
+        self.b_timeouts_used = b_timeouts_used;
+        self.w_timeouts_used = w_timeouts_used;
+        self.b_score = b_score;
+        self.w_score = w_score;
+        self.b_penalties = b_penalties;
+        self.w_penalties = w_penalties;
+        self.has_reset = has_reset;
+        self.next_game = next_game;
+        self.game_events = game_events;
+        self.game_stats = game_stats;
+        self.next_scheduled_start = None;
+        self.recent_goal = None;
+        self.clear_history();
+
+        info!("{} Restored saved game state", self.status_string(now));
+        self.send_clock_running(false);
+    }
+
     #[cfg(test)]
     pub(super) fn set_period_and_game_clock_time(
         &mut self,
@@ -1263,7 +2081,7 @@ impl TournamentManager {
         clock_time: Duration,
     ) {
         if let ClockState::Stopped { .. } = self.clock_state {
-            self.current_period = period;
+            self.set_period(period);
             self.clock_state = ClockState::Stopped { clock_time }
         } else {
             panic!("Can't edit period and remaing time while clock is running");
@@ -1274,11 +2092,8 @@ impl TournamentManager {
     pub(super) fn set_game_start(&mut self, time: Instant) {
         if let ClockState::Stopped { .. } = self.clock_state {
             self.game_start_time = time;
-            self.next_scheduled_start = Some(
-                time + 2 * self.config.half_play_duration
-                    + self.config.half_time_duration
-                    + self.config.nominal_break,
-            );
+            self.next_scheduled_start =
+                Some(time + self.config.regulation_duration() + self.config.nominal_break);
         } else {
             panic!("Can't edit game start time while clock is running");
         }
@@ -1303,12 +2118,12 @@ impl TournamentManager {
     pub(crate) fn printable_penalty_time(&self, pen: &Penalty, now: Instant) -> Option<String> {
         let cur_time = self.game_clock_time(now)?;
         if pen
-            .is_complete(self.current_period, cur_time, &self.config)
+            .is_complete(self.period_index, cur_time, &self.config)
             .ok()?
         {
             return Some("Served".to_string());
         }
-        if let Ok(time) = pen.time_remaining(self.current_period, cur_time, &self.config) {
+        if let Ok(time) = pen.time_remaining(self.period_index, cur_time, &self.config) {
             let time = time.whole_seconds();
             Some(format!("{}:{:02}", time / 60, time % 60))
         } else {
@@ -1316,6 +2131,16 @@ impl TournamentManager {
         }
     }
 
+    /// Returns the whole seconds left to serve, with `Some(0)` meaning the penalty has been
+    /// served. Returns `None` for a Total Dismissal, or if the time can't be calculated.
+    pub(crate) fn penalty_secs_remaining(&self, pen: &Penalty, now: Instant) -> Option<u64> {
+        let cur_time = self.game_clock_time(now)?;
+        let remaining = pen
+            .time_remaining(self.period_index, cur_time, &self.config)
+            .ok()?;
+        Some(remaining.whole_seconds().max(0) as u64)
+    }
+
     /// Returns `None` if the clock time would be negative, or if `now` is before the start
     /// of the current period
     pub fn game_clock_time(&self, now: Instant) -> Option<Duration> {
@@ -1348,14 +2173,14 @@ impl TournamentManager {
         let b_penalties = self
             .b_penalties
             .iter()
-            .map(|pen| pen.as_snapshot(self.current_period, cur_time, &self.config))
+            .map(|pen| pen.as_snapshot(self.period_index, cur_time, &self.config))
             .collect::<PenaltyResult<Vec<_>>>()
             .ok()?;
         trace!("Got black penalties");
         let w_penalties = self
             .w_penalties
             .iter()
-            .map(|pen| pen.as_snapshot(self.current_period, cur_time, &self.config))
+            .map(|pen| pen.as_snapshot(self.period_index, cur_time, &self.config))
             .collect::<PenaltyResult<Vec<_>>>()
             .ok()?;
         trace!("Got white penalties");
@@ -1369,8 +2194,7 @@ impl TournamentManager {
         }
 
         let next_period_len_secs = self
-            .current_period
-            .next_period_dur(&self.config)
+            .next_period_duration()
             .map(|dur| dur.as_secs().try_into().unwrap_or(0));
 
         Some(GameSnapshot {
@@ -1387,15 +2211,43 @@ impl TournamentManager {
             tournament_id: 0,
             recent_goal: self.recent_goal.map(|(c, n, _, _)| (c, n)),
             next_period_len_secs,
+            b_team_name: None,
+            w_team_name: None,
+            period_number: self
+                .config
+                .period_sequence()
+                .get(self.period_index)
+                .and_then(|p| p.number),
         })
     }
 
     pub fn next_update_time(&self, now: Instant) -> Option<Instant> {
         match (&self.timeout_state, self.current_period) {
-            // cases where the clock is counting up
-            (TimeoutState::Ref(cs), _) | (TimeoutState::PenaltyShot(cs), _) => cs
-                .clock_time(now)
-                .map(|ct| now + Duration::from_nanos(1_000_000_000 - ct.subsec_nanos() as u64)),
+            // Ref timeouts count up and penalty shots count down
+            (TimeoutState::Ref(cs), _) | (TimeoutState::PenaltyShot(cs), _) => {
+                let timeout_update = match cs {
+                    ClockState::CountingDown { .. } => cs
+                        .clock_time(now)
+                        .map(|ct| now + Duration::from_nanos(ct.subsec_nanos() as u64)),
+                    ClockState::CountingUp { .. } | ClockState::Stopped { .. } => {
+                        cs.clock_time(now).map(|ct| {
+                            now + Duration::from_nanos(1_000_000_000 - ct.subsec_nanos() as u64)
+                        })
+                    }
+                };
+                // The game clock may have been left running by the stop-clock policy
+                let game_update = match self.clock_state {
+                    ClockState::CountingDown { .. } => self
+                        .clock_state
+                        .clock_time(now)
+                        .map(|ct| now + Duration::from_nanos(ct.subsec_nanos() as u64)),
+                    ClockState::CountingUp { .. } | ClockState::Stopped { .. } => None,
+                };
+                match (timeout_update, game_update) {
+                    (Some(timeout), Some(game)) => Some(timeout.min(game)),
+                    (timeout, game) => timeout.or(game),
+                }
+            }
             (TimeoutState::None, GamePeriod::SuddenDeath) => self
                 .clock_state
                 .clock_time(now)
@@ -1527,92 +2379,147 @@ impl TimeoutState {
     }
 }
 
-#[derive(Derivative)]
-#[derivative(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PenaltyKind {
-    #[derivative(Default)]
-    OneMinute,
-    TwoMinute,
-    FiveMinute,
+    /// A penalty that is served once the given amount of play time has elapsed
+    Timed(Duration),
     TotalDismissal,
 }
 
+impl Default for PenaltyKind {
+    fn default() -> Self {
+        Self::from_secs(60)
+    }
+}
+
 impl PenaltyKind {
+    pub(crate) const fn from_secs(secs: u64) -> Self {
+        Self::Timed(Duration::from_secs(secs))
+    }
+
     pub(crate) fn as_duration(self) -> Option<Duration> {
         match self {
-            Self::OneMinute => Some(Duration::from_secs(60)),
-            Self::TwoMinute => Some(Duration::from_secs(120)),
-            Self::FiveMinute => Some(Duration::from_secs(300)),
+            Self::Timed(dur) => Some(dur),
             Self::TotalDismissal => None,
         }
     }
+
+    /// All the kinds of penalty that can be assigned under `config`, in the order they should be
+    /// offered to the ref
+    pub(crate) fn available(config: &GameConfig) -> Vec<Self> {
+        config
+            .penalty_durations
+            .iter()
+            .map(|dur| Self::Timed(*dur))
+            .chain([Self::TotalDismissal])
+            .collect()
+    }
+}
+
+impl fmt::Display for PenaltyKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Timed(dur) => {
+                let (mins, secs) = (dur.as_secs() / 60, dur.as_secs() % 60);
+                match (mins, secs) {
+                    (0, s) => write!(f, "{s}s"),
+                    (m, 0) => write!(f, "{m}m"),
+                    (m, s) => write!(f, "{m}m{s:02}s"),
+                }
+            }
+            Self::TotalDismissal => write!(f, "DSMS"),
+        }
+    }
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub(crate) struct Penalty {
     pub(crate) kind: PenaltyKind,
     pub(crate) player_number: u8,
     pub(crate) start_period: GamePeriod,
     pub(crate) start_time: Duration,
+    /// The index of the starting period in `GameConfig::period_sequence()`. When `None`, the
+    /// first period matching `start_period` is used.
+    #[serde(default)]
+    pub(crate) start_index: Option<usize>,
 }
 
 impl Penalty {
+    fn start_index(&self, config: &GameConfig) -> PenaltyResult<usize> {
+        self.start_index
+            .or_else(|| config.period_index(self.start_period))
+            .ok_or(PenaltyError::UnknownPeriod(self.start_period))
+    }
+
     fn time_elapsed(
         &self,
-        cur_per: GamePeriod,
+        cur_index: usize,
         cur_time: Duration,
         config: &GameConfig,
     ) -> PenaltyResult<SignedDuration> {
-        let calc_time_between = |earlier_period: GamePeriod,
+        let sequence = config.period_sequence();
+        let start_index = self.start_index(config)?;
+        let penalties_run = |index: usize| -> PenaltyResult<bool> {
+            sequence
+                .get(index)
+                .map(|p| p.period.penalties_run(config))
+                .ok_or(PenaltyError::InvalidPeriodIndex(index))
+        };
+
+        let calc_time_between = |earlier_index: usize,
                                  earlier_time: Duration,
-                                 later_period: GamePeriod,
+                                 later_index: usize,
                                  later_time: Duration| {
-            let mut elapsed = if earlier_period.penalties_run(config) {
+            let later_runs = penalties_run(later_index)?;
+            let mut elapsed = if penalties_run(earlier_index)? {
                 earlier_time.try_into()?
             } else {
                 SignedDuration::ZERO
             };
-            let mut period = earlier_period.next_period().unwrap();
-            while period < later_period {
-                if period.penalties_run(config) {
-                    elapsed += period.duration(config).unwrap();
+            for period in &sequence[earlier_index + 1..later_index] {
+                if period.period.penalties_run(config) {
+                    elapsed += period.duration.ok_or(time::error::ConversionRange)?;
                 }
-                period = period.next_period().unwrap();
             }
-            if later_period.penalties_run(config) {
-                elapsed += later_period
-                    .time_elapsed_at(later_time, config)
-                    .ok_or(time::error::ConversionRange)?; // Because we know the period must have a duration
+            if later_runs {
+                let later = sequence[later_index];
+                elapsed += match later.duration {
+                    Some(dur) => SignedDuration::try_from(dur)? - later_time,
+                    // Sudden death counts up from zero
+                    None => later_time.try_into()?,
+                };
             }
             Ok(elapsed)
         };
 
-        match cur_per.cmp(&self.start_period) {
+        match cur_index.cmp(&start_index) {
             Ordering::Equal => {
-                if cur_per.penalties_run(config) {
-                    Ok(cur_per.time_between(self.start_time.try_into()?, cur_time.try_into()?))
+                if penalties_run(cur_index)? {
+                    Ok(sequence[cur_index]
+                        .period
+                        .time_between(self.start_time.try_into()?, cur_time.try_into()?))
                 } else {
                     Ok(SignedDuration::ZERO)
                 }
             }
             Ordering::Greater => {
-                calc_time_between(self.start_period, self.start_time, cur_per, cur_time)
+                calc_time_between(start_index, self.start_time, cur_index, cur_time)
             }
             Ordering::Less => {
-                calc_time_between(cur_per, cur_time, self.start_period, self.start_time).map(|d| -d)
+                calc_time_between(cur_index, cur_time, start_index, self.start_time).map(|d| -d)
             }
         }
     }
 
     fn time_remaining(
         &self,
-        cur_per: GamePeriod,
+        cur_index: usize,
         cur_time: Duration,
         config: &GameConfig,
     ) -> PenaltyResult<SignedDuration> {
-        let elapsed = self.time_elapsed(cur_per, cur_time, config);
+        let elapsed = self.time_elapsed(cur_index, cur_time, config);
 
-        if cur_per == GamePeriod::BetweenGames && self.start_period != GamePeriod::BetweenGames {
+        if cur_index == 0 && self.start_index(config)? != 0 {
             // In this case, the game in which the penalty started has completed, and we
             // are counting down to the next game. By definition, any penalties have been
             // served in this situation.
@@ -1634,25 +2541,25 @@ impl Penalty {
 
     fn is_complete(
         &self,
-        cur_per: GamePeriod,
+        cur_index: usize,
         cur_time: Duration,
         config: &GameConfig,
     ) -> PenaltyResult<bool> {
         match self.kind {
             PenaltyKind::TotalDismissal => Ok(false),
-            PenaltyKind::OneMinute | PenaltyKind::TwoMinute | PenaltyKind::FiveMinute => self
-                .time_remaining(cur_per, cur_time, config)
+            PenaltyKind::Timed(_) => self
+                .time_remaining(cur_index, cur_time, config)
                 .map(|rem| rem <= SignedDuration::ZERO),
         }
     }
 
     fn as_snapshot(
         &self,
-        cur_per: GamePeriod,
+        cur_index: usize,
         cur_time: Duration,
         config: &GameConfig,
     ) -> PenaltyResult<PenaltySnapshot> {
-        let time = match self.time_remaining(cur_per, cur_time, config) {
+        let time = match self.time_remaining(cur_index, cur_time, config) {
             Ok(dur) => {
                 if dur.is_negative() {
                     PenaltyTime::Seconds(0)
@@ -1698,13 +2605,100 @@ impl<T> IndexMut<Color> for BlackWhiteBundle<T> {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct NextGameInfo {
     pub number: u32,
     pub timing: Option<TimingRules>,
     pub start_time: Option<PrimitiveDateTime>,
 }
 
+#[derive(Debug, Clone)]
+struct HistoryEntry {
+    /// The period the game was in after the action was taken
+    period: GamePeriod,
+    action: UndoableAction,
+}
+
+#[derive(Debug, Clone)]
+enum UndoableAction {
+    AddScore {
+        color: Color,
+        player_number: u8,
+    },
+    SetScores {
+        old: BlackWhiteBundle<u8>,
+        new: BlackWhiteBundle<u8>,
+        /// The goals that were removed from the stats because the scores were lowered
+        removed_goals: Vec<(usize, GoalRecord)>,
+    },
+    StartTimeout {
+        kind: TimeoutKind,
+        prev_timeout: TimeoutState,
+    },
+    StartPenalty {
+        color: Color,
+        penalty: Penalty,
+    },
+    StartPlayNow(Box<UndoState>),
+}
+
+/// Everything that can be changed by `start_play_now()`
+#[derive(Debug, Clone)]
+struct UndoState {
+    config: GameConfig,
+    game_number: u32,
+    game_start_time: Instant,
+    current_period: GamePeriod,
+    period_index: usize,
+    clock_state: ClockState,
+    b_timeouts_used: u16,
+    w_timeouts_used: u16,
+    b_score: u8,
+    w_score: u8,
+    b_penalties: Vec<Penalty>,
+    w_penalties: Vec<Penalty>,
+    has_reset: bool,
+    next_game: Option<NextGameInfo>,
+    next_scheduled_start: Option<Instant>,
+    recent_goal: Option<(Color, u8, GamePeriod, Duration)>,
+    game_events: Vec<GameEvent>,
+    game_stats: GameStats,
+}
+
+/// The state of a game with all clocks stopped, used to resume after the app is restarted
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct SavedState {
+    pub(crate) config: GameConfig,
+    pub(crate) game_number: u32,
+    pub(crate) current_period: GamePeriod,
+    /// Missing from states saved by older versions
+    #[serde(default)]
+    pub(crate) period_index: Option<usize>,
+    pub(crate) clock_time: Duration,
+    pub(crate) timeout: SavedTimeout,
+    pub(crate) b_timeouts_used: u16,
+    pub(crate) w_timeouts_used: u16,
+    pub(crate) b_score: u8,
+    pub(crate) w_score: u8,
+    pub(crate) b_penalties: Vec<Penalty>,
+    pub(crate) w_penalties: Vec<Penalty>,
+    pub(crate) has_reset: bool,
+    pub(crate) next_game: Option<NextGameInfo>,
+    #[serde(default)]
+    pub(crate) game_events: Vec<GameEvent>,
+    #[serde(default)]
+    pub(crate) game_stats: GameStats,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum SavedTimeout {
+    None,
+    Black(Duration),
+    White(Duration),
+    Ref(Duration),
+    PenaltyShot(Duration),
+}
+
 #[derive(Debug, PartialEq, Eq, Error)]
 pub enum TournamentManagerError {
     #[error("Can't edit clock time while clock is running")]
@@ -1723,6 +2717,8 @@ pub enum TournamentManagerError {
     NotInBlackTimeout,
     #[error("Can only switch to Black Timeout from White Timeout")]
     NotInWhiteTimeout,
+    #[error("Can only record a penalty shot during a penalty shot")]
+    NoPenaltyShot,
     #[error("Need to be in a timeout to end it")]
     NotInTimeout,
     #[error("`update()` needs to be called before this action can be performed")]
@@ -1741,6 +2737,14 @@ pub enum TournamentManagerError {
     InvalidState,
     #[error("Next Game Info is needed to perform this action")]
     NoNextGameInfo,
+    #[error("There is no action to undo")]
+    NothingToUndo,
+    #[error("There is no action to redo")]
+    NothingToRedo,
+    #[error("The last action can't be undone because the game has changed since")]
+    UndoNotPossible,
+    #[error("A snapshot of the game couldn't be generated")]
+    NoSnapshot,
     #[error("Penalty error: {0}")]
     PenaltyError(#[from] PenaltyError),
 }
@@ -1755,6 +2759,10 @@ pub enum PenaltyError {
     SnapshotOverflow(#[from] core::num::TryFromIntError),
     #[error("A Total Dismissal penalty does not have a duration")]
     NoDuration,
+    #[error("The period {0} is not part of the configured game")]
+    UnknownPeriod(GamePeriod),
+    #[error("There is no period at index {0} of the configured game")]
+    InvalidPeriodIndex(usize),
 }
 
 pub type Result<T> = std::result::Result<T, TournamentManagerError>;
@@ -1764,8 +2772,10 @@ pub type PenaltyResult<T> = std::result::Result<T, PenaltyError>;
 mod test {
     use super::TournamentManagerError as TMErr;
     use super::*;
+    use crate::game_stats::PlayerStats;
     use std::convert::TryInto;
     use std::sync::Once;
+    use uwh_common::config::{PeriodSpec, StopClockPolicy};
 
     static INIT: Once = Once::new();
 
@@ -1887,7 +2897,42 @@ mod test {
         tm.stop_clock(stop).unwrap();
         assert_eq!(tm.clock_is_running(), false);
         assert_eq!(tm.game_clock_time(stop), Some(Duration::from_secs(18)));
-        assert_eq!(tm.timeout_clock_time(stop), Some(Duration::from_secs(7)));
+        assert_eq!(tm.timeout_clock_time(stop), Some(Duration::from_secs(3)));
+    }
+
+    #[test]
+    fn test_timing_rules_keep_local_settings() {
+        initialize();
+        let config = GameConfig {
+            stop_clock_policy: StopClockPolicy::Never,
+            penalty_shot_duration: Duration::from_secs(30),
+            ..Default::default()
+        };
+        let mut tm = TournamentManager::new(config);
+        tm.set_next_game(NextGameInfo {
+            number: 2,
+            timing: Some(TimingRules {
+                game_timeouts: uwh_common::uwhscores::GameTimeouts {
+                    allowed: 2,
+                    duration: Duration::from_secs(45),
+                    per_half: false,
+                },
+                half_duration: Duration::from_secs(600),
+                half_time_duration: Duration::from_secs(120),
+                min_game_break: Duration::from_secs(300),
+                overtime_allowed: false,
+                sudden_death_allowed: true,
+            }),
+            start_time: None,
+        });
+
+        let now = Instant::now();
+        tm.start_clock(now);
+        tm.start_play_now(now).unwrap();
+        assert_eq!(tm.game_number(), 2);
+        assert_eq!(tm.config().half_play_duration, Duration::from_secs(600));
+        assert_eq!(tm.config().stop_clock_policy, StopClockPolicy::Never);
+        assert_eq!(tm.config().penalty_shot_duration, Duration::from_secs(30));
     }
 
     #[test]
@@ -1964,16 +3009,18 @@ mod test {
         let mut now = Instant::now();
 
         let b_pen = Penalty {
-            kind: PenaltyKind::OneMinute,
+            kind: PenaltyKind::from_secs(60),
             player_number: 12,
             start_period: GamePeriod::SecondHalf,
             start_time: Duration::from_secs(234),
+            start_index: None,
         };
         let w_pen = Penalty {
             kind: PenaltyKind::TotalDismissal,
             player_number: 3,
             start_period: GamePeriod::FirstHalf,
             start_time: Duration::from_secs(413),
+            start_index: None,
         };
 
         // Test the internal automatic reset during the BetweenGame Period
@@ -2087,17 +3134,17 @@ mod test {
         };
         let other_config = GameConfig {
             half_play_duration: Duration::from_secs(40),
-            ..config
+            ..config.clone()
         };
         let mut tm = TournamentManager::new(config);
 
-        tm.current_period = GamePeriod::FirstHalf;
+        tm.set_period(GamePeriod::FirstHalf);
         assert_eq!(
             tm.set_config(other_config.clone()),
             Err(TMErr::GameInProgress)
         );
 
-        tm.current_period = GamePeriod::BetweenGames;
+        tm.set_period(GamePeriod::BetweenGames);
         assert_eq!(tm.set_config(other_config), Ok(()));
     }
 
@@ -2291,7 +3338,7 @@ mod test {
         assert_eq!(
             tm.timeout_state,
             TimeoutState::PenaltyShot(ClockState::Stopped {
-                clock_time: Duration::from_secs(0)
+                clock_time: Duration::from_secs(45)
             })
         );
 
@@ -2400,26 +3447,353 @@ mod test {
         assert_eq!(tm.start_penalty_shot(t_o_start), Ok(()));
         assert_eq!(
             tm.timeout_state,
-            TimeoutState::PenaltyShot(ClockState::CountingUp {
+            TimeoutState::PenaltyShot(ClockState::CountingDown {
                 start_time: t_o_start,
-                time_at_start: Duration::from_secs(0)
+                time_remaining_at_start: Duration::from_secs(45)
             })
         );
         assert_eq!(tm.game_clock_time(t_o_start), Some(Duration::from_secs(28)));
-        assert_eq!(tm.timeout_clock_time(mid_t_o), Some(Duration::from_secs(3)));
+        assert_eq!(
+            tm.timeout_clock_time(mid_t_o),
+            Some(Duration::from_secs(42))
+        );
         assert_eq!(tm.game_clock_time(mid_t_o), Some(Duration::from_secs(28)));
         tm.update(mid_t_o).unwrap();
         assert_eq!(
             tm.timeout_state,
-            TimeoutState::PenaltyShot(ClockState::CountingUp {
+            TimeoutState::PenaltyShot(ClockState::CountingDown {
                 start_time: t_o_start,
-                time_at_start: Duration::from_secs(0)
+                time_remaining_at_start: Duration::from_secs(45)
             })
         );
         assert_eq!(
             tm.timeout_clock_time(t_o_end),
-            Some(Duration::from_secs(10))
+            Some(Duration::from_secs(35))
+        );
+
+        // The penalty shot clock stops at zero, but the penalty shot isn't ended
+        let shot_end = t_o_start + Duration::from_secs(45);
+        tm.update(shot_end).unwrap();
+        assert_eq!(
+            tm.timeout_state,
+            TimeoutState::PenaltyShot(ClockState::Stopped {
+                clock_time: Duration::ZERO
+            })
+        );
+        assert!(!tm.clock_is_running());
+        assert_eq!(tm.game_clock_time(shot_end), Some(Duration::from_secs(28)));
+    }
+
+    #[test]
+    fn test_stop_clock_policy() {
+        initialize();
+        let start = Instant::now();
+        let t_o_start = start + Duration::from_secs(2);
+        let t_o_end = t_o_start + Duration::from_secs(5);
+        let later = t_o_end + Duration::from_secs(3);
+
+        let run_ref_timeout = |config: GameConfig, period, clock_secs, penalty_shot: bool| {
+            let mut tm = TournamentManager::new(config);
+            tm.set_period_and_game_clock_time(period, Duration::from_secs(clock_secs));
+            tm.start_clock(start);
+            if penalty_shot {
+                tm.start_penalty_shot(t_o_start).unwrap();
+            } else {
+                tm.start_ref_timeout(t_o_start).unwrap();
+            }
+            // Penalty shots count down, ref timeouts count up
+            let expected = if penalty_shot {
+                tm.config.penalty_shot_duration - Duration::from_secs(5)
+            } else {
+                Duration::from_secs(5)
+            };
+            assert_eq!(tm.timeout_clock_time(t_o_end), Some(expected));
+            tm.end_timeout(t_o_end).unwrap();
+            assert!(tm.clock_is_running());
+            tm.game_clock_time(later).unwrap().as_secs()
+        };
+
+        let always = GameConfig::default();
+        let never = GameConfig {
+            stop_clock_policy: StopClockPolicy::Never,
+            ..Default::default()
+        };
+        let last_minutes = GameConfig {
+            stop_clock_policy: StopClockPolicy::LastMinutes,
+            stop_clock_window: Duration::from_secs(120),
+            ..Default::default()
+        };
+
+        // With the clock stopped for the timeout, 5 of the 10 seconds aren't counted
+        assert_eq!(
+            run_ref_timeout(always.clone(), GamePeriod::FirstHalf, 300, false),
+            295
+        );
+        assert_eq!(
+            run_ref_timeout(always, GamePeriod::SecondHalf, 100, true),
+            95
+        );
+        assert_eq!(
+            run_ref_timeout(never.clone(), GamePeriod::FirstHalf, 300, false),
+            290
+        );
+        assert_eq!(
+            run_ref_timeout(never.clone(), GamePeriod::SecondHalf, 100, true),
+            90
+        );
+        assert_eq!(
+            run_ref_timeout(never.clone(), GamePeriod::HalfTime, 100, false),
+            95
+        );
+        assert_eq!(
+            run_ref_timeout(last_minutes.clone(), GamePeriod::FirstHalf, 300, false),
+            290
+        );
+        assert_eq!(
+            run_ref_timeout(last_minutes.clone(), GamePeriod::FirstHalf, 123, true),
+            113
+        );
+        assert_eq!(
+            run_ref_timeout(last_minutes, GamePeriod::OvertimeSecondHalf, 121, false),
+            116
+        );
+
+        // Stopping the clock during the timeout stops the game clock too
+        let mut tm = TournamentManager::new(never);
+        tm.set_period_and_game_clock_time(GamePeriod::FirstHalf, Duration::from_secs(300));
+        tm.start_clock(start);
+        tm.start_ref_timeout(t_o_start).unwrap();
+        assert_eq!(
+            tm.next_update_time(t_o_start + Duration::from_millis(300)),
+            Some(t_o_start + Duration::from_secs(1))
+        );
+        tm.stop_clock(t_o_end).unwrap();
+        assert!(!tm.clock_is_running());
+        assert_eq!(tm.game_clock_time(later), Some(Duration::from_secs(293)));
+        tm.start_clock(later);
+        assert_eq!(
+            tm.game_clock_time(later + Duration::from_secs(1)),
+            Some(Duration::from_secs(293))
+        );
+        tm.end_timeout(later + Duration::from_secs(1)).unwrap();
+        assert_eq!(
+            tm.game_clock_time(later + Duration::from_secs(2)),
+            Some(Duration::from_secs(292))
+        );
+    }
+
+    #[test]
+    fn test_period_ends_during_ref_timeout() {
+        initialize();
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        let always = GameConfig::default();
+        let never = GameConfig {
+            stop_clock_policy: StopClockPolicy::Never,
+            ..Default::default()
+        };
+        let last_minutes = GameConfig {
+            stop_clock_policy: StopClockPolicy::LastMinutes,
+            stop_clock_window: Duration::from_secs(120),
+            ..Default::default()
+        };
+
+        for penalty_shot in [false, true] {
+            let start_timeout = |tm: &mut TournamentManager, now| {
+                if penalty_shot {
+                    tm.start_penalty_shot(now).unwrap();
+                } else {
+                    tm.start_ref_timeout(now).unwrap();
+                }
+            };
+
+            // When the clock is stopped for the timeout, the period ends 2 seconds after it
+            for config in [always.clone(), last_minutes.clone()] {
+                let mut tm = TournamentManager::new(config);
+                tm.set_period_and_game_clock_time(GamePeriod::FirstHalf, Duration::from_secs(3));
+                tm.start_clock(start);
+                start_timeout(&mut tm, at(1));
+                tm.update(at(5)).unwrap();
+                assert_eq!(tm.current_period, GamePeriod::FirstHalf);
+                assert_eq!(tm.game_clock_time(at(5)), Some(Duration::from_secs(2)));
+
+                tm.end_timeout(at(10)).unwrap();
+                tm.update(at(12)).unwrap();
+                assert_eq!(tm.current_period, GamePeriod::HalfTime);
+                assert_eq!(tm.timeout_state, TimeoutState::None);
+            }
+
+            // When the clock keeps running, the period waits at zero for the timeout to end
+            let mut tm = TournamentManager::new(never.clone());
+            tm.set_period_and_game_clock_time(GamePeriod::FirstHalf, Duration::from_secs(3));
+            tm.start_clock(start);
+            start_timeout(&mut tm, at(1));
+            tm.update(at(5)).unwrap();
+            assert_eq!(tm.current_period, GamePeriod::FirstHalf);
+            assert_eq!(tm.game_clock_time(at(5)), Some(Duration::ZERO));
+            assert!(tm.clock_is_running());
+            assert_ne!(tm.timeout_state, TimeoutState::None);
+
+            tm.end_timeout(at(10)).unwrap();
+            tm.update(at(10)).unwrap();
+            assert_eq!(tm.current_period, GamePeriod::HalfTime);
+            assert_eq!(tm.timeout_state, TimeoutState::None);
+            assert_eq!(
+                tm.game_clock_time(at(10)),
+                Some(tm.config.half_time_duration)
+            );
+
+            // The game doesn't end in the middle of the timeout, and the timeout doesn't carry
+            // over into the break after the game
+            let mut tm = TournamentManager::new(never.clone());
+            tm.set_period_and_game_clock_time(GamePeriod::SecondHalf, Duration::from_secs(3));
+            tm.set_scores(1, 0, start);
+            tm.start_clock(start);
+            start_timeout(&mut tm, at(1));
+            tm.update(at(5)).unwrap();
+            assert_eq!(tm.current_period, GamePeriod::SecondHalf);
+
+            tm.end_timeout(at(10)).unwrap();
+            tm.update(at(10)).unwrap();
+            assert_eq!(tm.current_period, GamePeriod::BetweenGames);
+            assert_eq!(tm.timeout_state, TimeoutState::None);
+        }
+    }
+
+    #[test]
+    fn test_record_penalty_shot() {
+        initialize();
+        let mut tm = TournamentManager::new(GameConfig::default());
+        let start = Instant::now();
+        let shot_start = start + Duration::from_secs(2);
+        let shot_end = shot_start + Duration::from_secs(10);
+
+        tm.set_period_and_game_clock_time(GamePeriod::FirstHalf, Duration::from_secs(300));
+        assert_eq!(
+            tm.record_penalty_shot(Color::Black, 3, true, start),
+            Err(TMErr::NoPenaltyShot)
+        );
+
+        tm.start_clock(start);
+        tm.start_penalty_shot(shot_start).unwrap();
+        assert_eq!(
+            tm.record_penalty_shot(Color::White, 7, false, shot_end),
+            Ok(())
+        );
+        assert_eq!(tm.timeout_state, TimeoutState::None);
+        assert!(tm.clock_is_running());
+        assert_eq!(tm.get_w_score(), 0);
+
+        tm.start_penalty_shot(shot_end).unwrap();
+        assert_eq!(
+            tm.record_penalty_shot(Color::Black, 3, true, shot_end),
+            Ok(())
+        );
+        assert_eq!(tm.timeout_state, TimeoutState::None);
+        assert_eq!(tm.get_b_score(), 1);
+        assert_eq!(tm.game_stats().goals.len(), 1);
+        assert_eq!(
+            tm.game_events
+                .iter()
+                .filter_map(|e| match e.event {
+                    kind @ (GameEventKind::PenaltyShotTaken { .. }
+                    | GameEventKind::GoalScored { .. }) => Some(kind),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+            vec![
+                GameEventKind::PenaltyShotTaken {
+                    color: Color::White,
+                    player_number: 7,
+                    scored: false
+                },
+                GameEventKind::PenaltyShotTaken {
+                    color: Color::Black,
+                    player_number: 3,
+                    scored: true
+                },
+                GameEventKind::GoalScored {
+                    color: Color::Black,
+                    player_number: 3
+                },
+            ]
+        );
+
+        // The goal is undone like any other
+        tm.undo(shot_end).unwrap();
+        assert_eq!(tm.get_b_score(), 0);
+
+        // A scored penalty shot in sudden death ends the game
+        tm.stop_clock(shot_end).unwrap();
+        tm.set_period_and_game_clock_time(GamePeriod::SuddenDeath, Duration::from_secs(30));
+        tm.set_timeout_state(TimeoutState::None);
+        tm.start_penalty_shot(shot_end).unwrap();
+        assert_eq!(
+            tm.record_penalty_shot(Color::White, 7, true, shot_end),
+            Ok(())
         );
+        assert_eq!(tm.current_period, GamePeriod::BetweenGames);
+        assert_eq!(tm.timeout_state, TimeoutState::None);
+    }
+
+    #[test]
+    fn test_record_penalty_shot_after_expiry() {
+        initialize();
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        // The game clock was stopped for the shot, and restarts once the outcome is recorded
+        let mut tm = TournamentManager::new(GameConfig::default());
+        let clock_running = tm.get_start_stop_rx();
+        tm.set_period_and_game_clock_time(GamePeriod::FirstHalf, Duration::from_secs(300));
+        tm.start_clock(start);
+        tm.start_penalty_shot(at(2)).unwrap();
+        tm.update(at(48)).unwrap();
+        assert_eq!(tm.timeout_clock_time(at(48)), Some(Duration::ZERO));
+        assert!(!tm.clock_is_running());
+        assert!(!*clock_running.borrow());
+
+        tm.record_penalty_shot(Color::Black, 3, true, at(50))
+            .unwrap();
+        assert_eq!(tm.timeout_state, TimeoutState::None);
+        assert!(tm.clock_is_running());
+        assert!(*clock_running.borrow());
+        assert_eq!(tm.game_clock_time(at(52)), Some(Duration::from_secs(296)));
+        assert_eq!(tm.get_b_score(), 1);
+
+        // The same goes for ending the shot without recording it
+        tm.start_penalty_shot(at(52)).unwrap();
+        tm.update(at(100)).unwrap();
+        tm.end_timeout(at(100)).unwrap();
+        assert!(tm.clock_is_running());
+        assert_eq!(tm.game_clock_time(at(101)), Some(Duration::from_secs(295)));
+
+        // A shot that never ran leaves the clock stopped
+        tm.stop_clock(at(101)).unwrap();
+        tm.start_penalty_shot(at(101)).unwrap();
+        tm.update(at(150)).unwrap();
+        tm.end_timeout(at(150)).unwrap();
+        assert!(!tm.clock_is_running());
+
+        // When the stop-clock policy leaves the game clock running, it isn't reported as stopped
+        // when the shot clock runs out
+        let mut tm = TournamentManager::new(GameConfig {
+            stop_clock_policy: StopClockPolicy::Never,
+            ..Default::default()
+        });
+        let clock_running = tm.get_start_stop_rx();
+        tm.set_period_and_game_clock_time(GamePeriod::FirstHalf, Duration::from_secs(300));
+        tm.start_clock(start);
+        tm.start_penalty_shot(at(2)).unwrap();
+        tm.update(at(48)).unwrap();
+        assert_eq!(tm.timeout_clock_time(at(48)), Some(Duration::ZERO));
+        assert!(*clock_running.borrow());
+        assert_eq!(tm.game_clock_time(at(48)), Some(Duration::from_secs(252)));
+
+        tm.record_penalty_shot(Color::White, 4, false, at(50))
+            .unwrap();
+        assert_eq!(tm.game_clock_time(at(52)), Some(Duration::from_secs(248)));
     }
 
     #[test]
@@ -2632,7 +4006,10 @@ mod test {
         }));
         assert_eq!(tm.switch_to_b_timeout(), Err(TMErr::NotInWhiteTimeout));
         assert_eq!(tm.switch_to_ref_timeout(), Err(TMErr::NotInPenaltyShot));
-        assert_eq!(tm.switch_to_penalty_shot(), Err(TMErr::NotInRefTimeout));
+        assert_eq!(
+            tm.switch_to_penalty_shot(start),
+            Err(TMErr::NotInRefTimeout)
+        );
         assert_eq!(tm.switch_to_w_timeout(), Ok(()));
 
         assert_eq!(
@@ -2644,7 +4021,10 @@ mod test {
         );
         assert_eq!(tm.switch_to_w_timeout(), Err(TMErr::NotInBlackTimeout));
         assert_eq!(tm.switch_to_ref_timeout(), Err(TMErr::NotInPenaltyShot));
-        assert_eq!(tm.switch_to_penalty_shot(), Err(TMErr::NotInRefTimeout));
+        assert_eq!(
+            tm.switch_to_penalty_shot(start),
+            Err(TMErr::NotInRefTimeout)
+        );
         assert_eq!(tm.switch_to_b_timeout(), Ok(()));
         assert_eq!(
             tm.timeout_state,
@@ -2661,24 +4041,48 @@ mod test {
         assert_eq!(tm.switch_to_b_timeout(), Err(TMErr::NotInWhiteTimeout));
         assert_eq!(tm.switch_to_w_timeout(), Err(TMErr::NotInBlackTimeout));
         assert_eq!(tm.switch_to_ref_timeout(), Err(TMErr::NotInPenaltyShot));
-        assert_eq!(tm.switch_to_penalty_shot(), Ok(()));
+        let switch_time = start + ten_secs;
+        assert_eq!(tm.switch_to_penalty_shot(switch_time), Ok(()));
 
+        // The penalty shot starts from its full duration
         assert_eq!(
             tm.timeout_state,
-            TimeoutState::PenaltyShot(ClockState::CountingUp {
-                start_time: start,
-                time_at_start: ten_secs,
+            TimeoutState::PenaltyShot(ClockState::CountingDown {
+                start_time: switch_time,
+                time_remaining_at_start: Duration::from_secs(45),
             })
         );
         assert_eq!(tm.switch_to_b_timeout(), Err(TMErr::NotInWhiteTimeout));
         assert_eq!(tm.switch_to_w_timeout(), Err(TMErr::NotInBlackTimeout));
-        assert_eq!(tm.switch_to_penalty_shot(), Err(TMErr::NotInRefTimeout));
+        assert_eq!(
+            tm.switch_to_penalty_shot(switch_time),
+            Err(TMErr::NotInRefTimeout)
+        );
         assert_eq!(tm.switch_to_ref_timeout(), Ok(()));
+        // The ref timeout counts up from the time the penalty shot has used
         assert_eq!(
             tm.timeout_state,
             TimeoutState::Ref(ClockState::CountingUp {
-                start_time: start,
-                time_at_start: ten_secs,
+                start_time: switch_time,
+                time_at_start: Duration::ZERO,
+            })
+        );
+
+        tm.set_timeout_state(TimeoutState::PenaltyShot(ClockState::Stopped {
+            clock_time: Duration::from_secs(30),
+        }));
+        assert_eq!(tm.switch_to_ref_timeout(), Ok(()));
+        assert_eq!(
+            tm.timeout_state,
+            TimeoutState::Ref(ClockState::Stopped {
+                clock_time: Duration::from_secs(15),
+            })
+        );
+        assert_eq!(tm.switch_to_penalty_shot(switch_time), Ok(()));
+        assert_eq!(
+            tm.timeout_state,
+            TimeoutState::PenaltyShot(ClockState::Stopped {
+                clock_time: Duration::from_secs(45),
             })
         );
     }
@@ -2764,6 +4168,52 @@ mod test {
         assert_eq!(tm.start_play_now(now), Err(TMErr::AlreadyInPlayPeriod));
     }
 
+    #[test]
+    fn test_timeouts_counted_per_game() {
+        initialize();
+        let config = GameConfig {
+            team_timeouts_per_half: 1,
+            timeouts_counted_per_half: false,
+            half_play_duration: Duration::from_secs(6),
+            ..Default::default()
+        };
+        let mut tm = TournamentManager::new(config);
+        let start = Instant::now();
+
+        tm.set_period_and_game_clock_time(GamePeriod::HalfTime, Duration::from_secs(1));
+        tm.b_timeouts_used = 1;
+        tm.w_timeouts_used = 0;
+        tm.start_game_clock(start);
+        tm.update(start + Duration::from_secs(2)).unwrap();
+
+        assert_eq!(tm.current_period, GamePeriod::SecondHalf);
+        assert_eq!(tm.b_timeouts_used, 1);
+        assert_eq!(tm.w_timeouts_used, 0);
+        assert_eq!(
+            tm.can_start_b_timeout(),
+            Err(TMErr::TooManyTeamTimeouts(Color::Black))
+        );
+        assert_eq!(tm.can_start_w_timeout(), Ok(()));
+
+        let now = start + Duration::from_secs(3);
+        tm.stop_game_clock(now).unwrap();
+        tm.set_period_and_game_clock_time(GamePeriod::HalfTime, Duration::from_secs(15));
+        tm.w_timeouts_used = 1;
+        assert_eq!(tm.start_play_now(now), Ok(()));
+        assert_eq!(tm.current_period, GamePeriod::SecondHalf);
+        assert_eq!(
+            tm.can_start_w_timeout(),
+            Err(TMErr::TooManyTeamTimeouts(Color::White))
+        );
+
+        tm.config.timeouts_counted_per_half = true;
+        tm.stop_game_clock(now).unwrap();
+        tm.set_period_and_game_clock_time(GamePeriod::HalfTime, Duration::from_secs(15));
+        assert_eq!(tm.start_play_now(now), Ok(()));
+        assert_eq!(tm.b_timeouts_used, 0);
+        assert_eq!(tm.w_timeouts_used, 0);
+    }
+
     struct TransitionTestSetup {
         config: GameConfig,
         game_start_offset: i64,
@@ -3258,23 +4708,133 @@ mod test {
             Some(Duration::from_secs(4))
         );
 
-        setup_tm(&mut tm);
+        setup_tm(&mut tm);
+
+        tm.add_b_score(1, third_time);
+        assert_eq!(tm.current_period, GamePeriod::BetweenGames);
+        assert_eq!(
+            tm.game_clock_time(fourth_time),
+            Some(Duration::from_secs(4))
+        );
+
+        setup_tm(&mut tm);
+
+        tm.add_w_score(1, third_time);
+        assert_eq!(tm.current_period, GamePeriod::BetweenGames);
+        assert_eq!(
+            tm.game_clock_time(fourth_time),
+            Some(Duration::from_secs(4))
+        );
+    }
+
+    #[test]
+    fn test_single_period_game() {
+        initialize();
+        let config = GameConfig {
+            overtime_allowed: true,
+            pre_overtime_break: Duration::from_secs(7),
+            periods: vec![PeriodSpec::play(Duration::from_secs(20))],
+            ..Default::default()
+        };
+
+        let start = Instant::now();
+        let mut tm = TournamentManager::new(config);
+
+        tm.set_period_and_game_clock_time(GamePeriod::BetweenGames, Duration::from_secs(1));
+        tm.start_game_clock(start);
+        tm.update(start + Duration::from_secs(1)).unwrap();
+        assert_eq!(tm.current_period, GamePeriod::FirstHalf);
+        assert_eq!(
+            tm.game_clock_time(start + Duration::from_secs(1)),
+            Some(Duration::from_secs(20))
+        );
+        let snapshot = tm
+            .generate_snapshot(start + Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(snapshot.next_period_len_secs, Some(7));
+
+        let end = start + Duration::from_secs(21);
+        assert_eq!(tm.would_end_game(end), Ok(false));
+        tm.set_scores(1, 0, start);
+        assert_eq!(tm.would_end_game(end), Ok(true));
+
+        tm.set_scores(1, 1, start);
+        tm.update(end).unwrap();
+        assert_eq!(tm.current_period, GamePeriod::PreOvertime);
+        assert_eq!(tm.game_clock_time(end), Some(Duration::from_secs(7)));
+    }
+
+    #[test]
+    fn test_three_period_game() {
+        initialize();
+        let config = GameConfig {
+            team_timeouts_per_half: 1,
+            timeouts_counted_per_half: true,
+            overtime_allowed: false,
+            sudden_death_allowed: false,
+            periods: vec![
+                PeriodSpec::play(Duration::from_secs(8)),
+                PeriodSpec::break_time(Duration::from_secs(2)),
+                PeriodSpec::play(Duration::from_secs(8)),
+                PeriodSpec::break_time(Duration::from_secs(3)),
+                PeriodSpec::play(Duration::from_secs(5)),
+            ],
+            ..Default::default()
+        };
+
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut tm = TournamentManager::new(config);
+
+        tm.set_period_and_game_clock_time(GamePeriod::BetweenGames, Duration::from_secs(1));
+        tm.start_game_clock(start);
+        tm.update(at(1)).unwrap();
+        assert_eq!(tm.current_period, GamePeriod::FirstHalf);
+        assert_eq!(tm.game_clock_time(at(1)), Some(Duration::from_secs(8)));
+
+        tm.update(at(9)).unwrap();
+        assert_eq!(tm.current_period, GamePeriod::HalfTime);
+        assert_eq!(tm.game_clock_time(at(9)), Some(Duration::from_secs(2)));
+        let snapshot = tm.generate_snapshot(at(9)).unwrap();
+        assert_eq!(snapshot.next_period_len_secs, Some(8));
+        assert_eq!(snapshot.period_number, Some(1));
 
-        tm.add_b_score(1, third_time);
-        assert_eq!(tm.current_period, GamePeriod::BetweenGames);
-        assert_eq!(
-            tm.game_clock_time(fourth_time),
-            Some(Duration::from_secs(4))
-        );
+        tm.update(at(11)).unwrap();
+        assert_eq!(tm.current_period, GamePeriod::SecondHalf);
+        assert_eq!(tm.game_clock_time(at(11)), Some(Duration::from_secs(8)));
+        assert_eq!(tm.would_end_game(at(19)), Ok(false));
 
-        setup_tm(&mut tm);
+        // 4 seconds of this penalty are served in the second period
+        tm.start_penalty(Color::Black, 3, PenaltyKind::from_secs(10), at(15))
+            .unwrap();
+        tm.w_timeouts_used = 1;
 
-        tm.add_w_score(1, third_time);
+        tm.update(at(19)).unwrap();
+        assert_eq!(tm.current_period, GamePeriod::HalfTime);
+        assert_eq!(tm.game_clock_time(at(19)), Some(Duration::from_secs(3)));
+        let snapshot = tm.generate_snapshot(at(19)).unwrap();
+        assert_eq!(snapshot.next_period_len_secs, Some(5));
+        assert_eq!(snapshot.b_penalties[0].time, PenaltyTime::Seconds(6));
+        assert_eq!(snapshot.period_number, Some(2));
+
+        tm.update(at(22)).unwrap();
+        assert_eq!(tm.current_period, GamePeriod::SecondHalf);
+        assert_eq!(tm.game_clock_time(at(22)), Some(Duration::from_secs(5)));
+        assert_eq!(tm.w_timeouts_used, 0);
+
+        tm.start_penalty(Color::White, 4, PenaltyKind::from_secs(10), at(23))
+            .unwrap();
+        let snapshot = tm.generate_snapshot(at(25)).unwrap();
+        assert_eq!(snapshot.b_penalties[0].time, PenaltyTime::Seconds(3));
+        assert_eq!(snapshot.w_penalties[0].time, PenaltyTime::Seconds(8));
+        assert_eq!(snapshot.current_period, GamePeriod::SecondHalf);
+        assert_eq!(snapshot.period_number, Some(3));
+
+        tm.set_scores(2, 1, at(25));
+        assert_eq!(tm.would_end_game(at(27)), Ok(true));
+        tm.update(at(27)).unwrap();
         assert_eq!(tm.current_period, GamePeriod::BetweenGames);
-        assert_eq!(
-            tm.game_clock_time(fourth_time),
-            Some(Duration::from_secs(4))
-        );
+        assert_eq!(tm.generate_snapshot(at(27)).unwrap().period_number, None);
     }
 
     #[test]
@@ -3294,12 +4854,12 @@ mod test {
         let sd_only_config = GameConfig {
             overtime_allowed: false,
             sudden_death_allowed: true,
-            ..all_periods_config
+            ..all_periods_config.clone()
         };
         let no_sd_no_ot_config = GameConfig {
             overtime_allowed: false,
             sudden_death_allowed: false,
-            ..all_periods_config
+            ..all_periods_config.clone()
         };
 
         // (start_period, start_time, end_period, end_time, config, result, msg)
@@ -3417,12 +4977,13 @@ mod test {
         for (start_period, start_time, end_period, end_time, config, result, msg) in test_cases {
             let penalty = Penalty {
                 player_number: 0,
-                kind: PenaltyKind::OneMinute,
+                kind: PenaltyKind::from_secs(60),
                 start_time,
                 start_period,
+                start_index: None,
             };
             assert_eq!(
-                penalty.time_elapsed(end_period, end_time, config),
+                penalty.time_elapsed(config.period_index(end_period).unwrap(), end_time, config),
                 result,
                 "{}",
                 msg
@@ -3450,7 +5011,7 @@ mod test {
             (
                 GamePeriod::FirstHalf,
                 Duration::from_secs(4),
-                PenaltyKind::OneMinute,
+                PenaltyKind::from_secs(60),
                 GamePeriod::FirstHalf,
                 Duration::from_secs(2),
                 Ok(SignedDuration::seconds(58)),
@@ -3459,7 +5020,7 @@ mod test {
             (
                 GamePeriod::FirstHalf,
                 Duration::from_secs(4),
-                PenaltyKind::TwoMinute,
+                PenaltyKind::from_secs(120),
                 GamePeriod::FirstHalf,
                 Duration::from_secs(2),
                 Ok(SignedDuration::seconds(118)),
@@ -3468,7 +5029,7 @@ mod test {
             (
                 GamePeriod::FirstHalf,
                 Duration::from_secs(4),
-                PenaltyKind::FiveMinute,
+                PenaltyKind::from_secs(300),
                 GamePeriod::FirstHalf,
                 Duration::from_secs(2),
                 Ok(SignedDuration::seconds(298)),
@@ -3486,7 +5047,7 @@ mod test {
             (
                 GamePeriod::SuddenDeath,
                 Duration::from_secs(5),
-                PenaltyKind::OneMinute,
+                PenaltyKind::from_secs(60),
                 GamePeriod::SuddenDeath,
                 Duration::from_secs(70),
                 Ok(SignedDuration::seconds(-5)),
@@ -3495,7 +5056,7 @@ mod test {
             (
                 GamePeriod::FirstHalf,
                 Duration::from_secs(5),
-                PenaltyKind::OneMinute,
+                PenaltyKind::from_secs(60),
                 GamePeriod::BetweenGames,
                 Duration::from_secs(10),
                 Ok(SignedDuration::seconds(0)),
@@ -3518,9 +5079,10 @@ mod test {
                 kind,
                 start_time,
                 start_period,
+                start_index: None,
             };
             assert_eq!(
-                penalty.time_remaining(end_period, end_time, &config),
+                penalty.time_remaining(config.period_index(end_period).unwrap(), end_time, &config),
                 result,
                 "{}",
                 msg
@@ -3542,61 +5104,65 @@ mod test {
             pre_sudden_death_duration: Duration::from_secs(15),
             ..Default::default()
         };
+        let sudden_death = config.period_index(GamePeriod::SuddenDeath).unwrap();
 
         let penalty = Penalty {
             player_number: 0,
-            kind: PenaltyKind::OneMinute,
+            kind: PenaltyKind::from_secs(60),
             start_time: Duration::from_secs(5),
             start_period: GamePeriod::SuddenDeath,
+            start_index: None,
         };
         assert_eq!(
-            penalty.is_complete(GamePeriod::SuddenDeath, Duration::from_secs(60), &config),
+            penalty.is_complete(sudden_death, Duration::from_secs(60), &config),
             Ok(false)
         );
         assert_eq!(
-            penalty.is_complete(GamePeriod::SuddenDeath, Duration::from_secs(65), &config),
+            penalty.is_complete(sudden_death, Duration::from_secs(65), &config),
             Ok(true)
         );
         assert_eq!(
-            penalty.is_complete(GamePeriod::SuddenDeath, Duration::from_secs(70), &config),
+            penalty.is_complete(sudden_death, Duration::from_secs(70), &config),
             Ok(true)
         );
 
         let penalty = Penalty {
             player_number: 0,
-            kind: PenaltyKind::TwoMinute,
+            kind: PenaltyKind::from_secs(120),
             start_time: Duration::from_secs(5),
             start_period: GamePeriod::SuddenDeath,
+            start_index: None,
         };
         assert_eq!(
-            penalty.is_complete(GamePeriod::SuddenDeath, Duration::from_secs(120), &config),
+            penalty.is_complete(sudden_death, Duration::from_secs(120), &config),
             Ok(false)
         );
         assert_eq!(
-            penalty.is_complete(GamePeriod::SuddenDeath, Duration::from_secs(125), &config),
+            penalty.is_complete(sudden_death, Duration::from_secs(125), &config),
             Ok(true)
         );
         assert_eq!(
-            penalty.is_complete(GamePeriod::SuddenDeath, Duration::from_secs(130), &config),
+            penalty.is_complete(sudden_death, Duration::from_secs(130), &config),
             Ok(true)
         );
 
         let penalty = Penalty {
             player_number: 0,
-            kind: PenaltyKind::FiveMinute,
+            kind: PenaltyKind::from_secs(300),
             start_time: Duration::from_secs(5),
             start_period: GamePeriod::SuddenDeath,
+            start_index: None,
         };
         assert_eq!(
-            penalty.is_complete(GamePeriod::SuddenDeath, Duration::from_secs(300), &config),
+            penalty.is_complete(sudden_death, Duration::from_secs(300), &config),
             Ok(false)
         );
         assert_eq!(
-            penalty.is_complete(GamePeriod::SuddenDeath, Duration::from_secs(305), &config),
+            penalty.is_complete(sudden_death, Duration::from_secs(305), &config),
             Ok(true)
         );
         assert_eq!(
-            penalty.is_complete(GamePeriod::SuddenDeath, Duration::from_secs(310), &config),
+            penalty.is_complete(sudden_death, Duration::from_secs(310), &config),
             Ok(true)
         );
 
@@ -3605,17 +5171,18 @@ mod test {
             kind: PenaltyKind::TotalDismissal,
             start_time: Duration::from_secs(5),
             start_period: GamePeriod::SuddenDeath,
+            start_index: None,
         };
         assert_eq!(
-            penalty.is_complete(GamePeriod::SuddenDeath, Duration::from_secs(300), &config),
+            penalty.is_complete(sudden_death, Duration::from_secs(300), &config),
             Ok(false)
         );
         assert_eq!(
-            penalty.is_complete(GamePeriod::SuddenDeath, Duration::from_secs(305), &config),
+            penalty.is_complete(sudden_death, Duration::from_secs(305), &config),
             Ok(false)
         );
         assert_eq!(
-            penalty.is_complete(GamePeriod::SuddenDeath, Duration::from_secs(310), &config),
+            penalty.is_complete(sudden_death, Duration::from_secs(310), &config),
             Ok(false)
         );
     }
@@ -3630,7 +5197,7 @@ mod test {
 
         tm.set_period_and_game_clock_time(GamePeriod::FirstHalf, Duration::from_secs(25));
         tm.start_game_clock(start);
-        tm.start_penalty(Color::Black, 2, PenaltyKind::OneMinute, next_time)
+        tm.start_penalty(Color::Black, 2, PenaltyKind::from_secs(60), next_time)
             .unwrap();
 
         let next_time = next_time + Duration::from_secs(1);
@@ -3638,26 +5205,27 @@ mod test {
         assert_eq!(
             tm.b_penalties,
             vec![Penalty {
-                kind: PenaltyKind::OneMinute,
+                kind: PenaltyKind::from_secs(60),
                 player_number: 2,
                 start_period: GamePeriod::FirstHalf,
-                start_time: Duration::from_secs(24)
+                start_time: Duration::from_secs(24),
+                start_index: Some(1),
             }]
         );
         assert_eq!(tm.w_penalties, vec![]);
 
         let next_time = next_time + Duration::from_secs(1);
-        tm.start_penalty(Color::Black, 3, PenaltyKind::TwoMinute, next_time)
+        tm.start_penalty(Color::Black, 3, PenaltyKind::from_secs(120), next_time)
             .unwrap();
-        tm.start_penalty(Color::Black, 4, PenaltyKind::FiveMinute, next_time)
+        tm.start_penalty(Color::Black, 4, PenaltyKind::from_secs(300), next_time)
             .unwrap();
         tm.start_penalty(Color::Black, 5, PenaltyKind::TotalDismissal, next_time)
             .unwrap();
-        tm.start_penalty(Color::White, 6, PenaltyKind::OneMinute, next_time)
+        tm.start_penalty(Color::White, 6, PenaltyKind::from_secs(60), next_time)
             .unwrap();
-        tm.start_penalty(Color::White, 7, PenaltyKind::TwoMinute, next_time)
+        tm.start_penalty(Color::White, 7, PenaltyKind::from_secs(120), next_time)
             .unwrap();
-        tm.start_penalty(Color::White, 8, PenaltyKind::FiveMinute, next_time)
+        tm.start_penalty(Color::White, 8, PenaltyKind::from_secs(300), next_time)
             .unwrap();
         tm.start_penalty(Color::White, 9, PenaltyKind::TotalDismissal, next_time)
             .unwrap();
@@ -3668,28 +5236,32 @@ mod test {
             tm.b_penalties,
             vec![
                 Penalty {
-                    kind: PenaltyKind::OneMinute,
+                    kind: PenaltyKind::from_secs(60),
                     player_number: 2,
                     start_period: GamePeriod::FirstHalf,
-                    start_time: Duration::from_secs(24)
+                    start_time: Duration::from_secs(24),
+                    start_index: Some(1),
                 },
                 Penalty {
-                    kind: PenaltyKind::TwoMinute,
+                    kind: PenaltyKind::from_secs(120),
                     player_number: 3,
                     start_period: GamePeriod::FirstHalf,
-                    start_time: Duration::from_secs(22)
+                    start_time: Duration::from_secs(22),
+                    start_index: Some(1),
                 },
                 Penalty {
-                    kind: PenaltyKind::FiveMinute,
+                    kind: PenaltyKind::from_secs(300),
                     player_number: 4,
                     start_period: GamePeriod::FirstHalf,
-                    start_time: Duration::from_secs(22)
+                    start_time: Duration::from_secs(22),
+                    start_index: Some(1),
                 },
                 Penalty {
                     kind: PenaltyKind::TotalDismissal,
                     player_number: 5,
                     start_period: GamePeriod::FirstHalf,
-                    start_time: Duration::from_secs(22)
+                    start_time: Duration::from_secs(22),
+                    start_index: Some(1),
                 },
             ]
         );
@@ -3697,28 +5269,32 @@ mod test {
             tm.w_penalties,
             vec![
                 Penalty {
-                    kind: PenaltyKind::OneMinute,
+                    kind: PenaltyKind::from_secs(60),
                     player_number: 6,
                     start_period: GamePeriod::FirstHalf,
-                    start_time: Duration::from_secs(22)
+                    start_time: Duration::from_secs(22),
+                    start_index: Some(1),
                 },
                 Penalty {
-                    kind: PenaltyKind::TwoMinute,
+                    kind: PenaltyKind::from_secs(120),
                     player_number: 7,
                     start_period: GamePeriod::FirstHalf,
-                    start_time: Duration::from_secs(22)
+                    start_time: Duration::from_secs(22),
+                    start_index: Some(1),
                 },
                 Penalty {
-                    kind: PenaltyKind::FiveMinute,
+                    kind: PenaltyKind::from_secs(300),
                     player_number: 8,
                     start_period: GamePeriod::FirstHalf,
-                    start_time: Duration::from_secs(22)
+                    start_time: Duration::from_secs(22),
+                    start_index: Some(1),
                 },
                 Penalty {
                     kind: PenaltyKind::TotalDismissal,
                     player_number: 9,
                     start_period: GamePeriod::FirstHalf,
-                    start_time: Duration::from_secs(22)
+                    start_time: Duration::from_secs(22),
+                    start_index: Some(1),
                 },
             ]
         );
@@ -3734,7 +5310,7 @@ mod test {
 
         tm.set_period_and_game_clock_time(GamePeriod::FirstHalf, Duration::from_secs(25));
         tm.start_game_clock(start);
-        tm.start_penalty(Color::Black, 2, PenaltyKind::OneMinute, next_time)
+        tm.start_penalty(Color::Black, 2, PenaltyKind::from_secs(60), next_time)
             .unwrap();
 
         let next_time = next_time + Duration::from_secs(1);
@@ -3742,10 +5318,11 @@ mod test {
         assert_eq!(
             tm.b_penalties,
             vec![Penalty {
-                kind: PenaltyKind::OneMinute,
+                kind: PenaltyKind::from_secs(60),
                 player_number: 2,
                 start_period: GamePeriod::FirstHalf,
-                start_time: Duration::from_secs(24)
+                start_time: Duration::from_secs(24),
+                start_index: Some(1),
             }],
         );
         assert_eq!(tm.w_penalties, vec![]);
@@ -3768,7 +5345,7 @@ mod test {
         assert_eq!(tm.w_penalties, vec![]);
 
         let next_time = next_time + Duration::from_secs(1);
-        tm.start_penalty(Color::White, 3, PenaltyKind::OneMinute, next_time)
+        tm.start_penalty(Color::White, 3, PenaltyKind::from_secs(60), next_time)
             .unwrap();
 
         let next_time = next_time + Duration::from_secs(1);
@@ -3777,10 +5354,11 @@ mod test {
         assert_eq!(
             tm.w_penalties,
             vec![Penalty {
-                kind: PenaltyKind::OneMinute,
+                kind: PenaltyKind::from_secs(60),
                 player_number: 3,
                 start_period: GamePeriod::FirstHalf,
-                start_time: Duration::from_secs(21)
+                start_time: Duration::from_secs(21),
+                start_index: Some(1),
             }],
         );
 
@@ -3811,7 +5389,7 @@ mod test {
 
         tm.set_period_and_game_clock_time(GamePeriod::FirstHalf, Duration::from_secs(25));
         tm.start_game_clock(start);
-        tm.start_penalty(Color::Black, 2, PenaltyKind::OneMinute, next_time)
+        tm.start_penalty(Color::Black, 2, PenaltyKind::from_secs(60), next_time)
             .unwrap();
 
         let next_time = next_time + Duration::from_secs(1);
@@ -3819,52 +5397,85 @@ mod test {
         assert_eq!(
             tm.b_penalties,
             vec![Penalty {
-                kind: PenaltyKind::OneMinute,
+                kind: PenaltyKind::from_secs(60),
                 player_number: 2,
                 start_period: GamePeriod::FirstHalf,
-                start_time: Duration::from_secs(24)
+                start_time: Duration::from_secs(24),
+                start_index: Some(1),
             }],
         );
         assert_eq!(tm.w_penalties, vec![]);
 
         let next_time = next_time + Duration::from_secs(1);
         assert_eq!(
-            tm.edit_penalty(Color::Black, 1, Color::Black, 2, PenaltyKind::TwoMinute),
+            tm.edit_penalty(
+                Color::Black,
+                1,
+                Color::Black,
+                2,
+                PenaltyKind::from_secs(120)
+            ),
             Err(TournamentManagerError::InvalidIndex(Color::Black, 1))
         );
         assert_eq!(
-            tm.edit_penalty(Color::White, 0, Color::Black, 2, PenaltyKind::TwoMinute),
+            tm.edit_penalty(
+                Color::White,
+                0,
+                Color::Black,
+                2,
+                PenaltyKind::from_secs(120)
+            ),
             Err(TournamentManagerError::InvalidIndex(Color::White, 0))
         );
         assert_eq!(
-            tm.edit_penalty(Color::White, 1, Color::Black, 2, PenaltyKind::TwoMinute),
+            tm.edit_penalty(
+                Color::White,
+                1,
+                Color::Black,
+                2,
+                PenaltyKind::from_secs(120)
+            ),
             Err(TournamentManagerError::InvalidIndex(Color::White, 1))
         );
-        tm.edit_penalty(Color::Black, 0, Color::Black, 3, PenaltyKind::TwoMinute)
-            .unwrap();
+        tm.edit_penalty(
+            Color::Black,
+            0,
+            Color::Black,
+            3,
+            PenaltyKind::from_secs(120),
+        )
+        .unwrap();
         tm.update(next_time).unwrap();
         assert_eq!(
             tm.b_penalties,
             vec![Penalty {
-                kind: PenaltyKind::TwoMinute,
+                kind: PenaltyKind::from_secs(120),
                 player_number: 3,
                 start_period: GamePeriod::FirstHalf,
-                start_time: Duration::from_secs(24)
+                start_time: Duration::from_secs(24),
+                start_index: Some(1),
             }],
         );
         assert_eq!(tm.w_penalties, vec![]);
 
         let next_time = next_time + Duration::from_secs(1);
-        tm.edit_penalty(Color::Black, 0, Color::Black, 4, PenaltyKind::FiveMinute)
-            .unwrap();
+        tm.edit_penalty(
+            Color::Black,
+            0,
+            Color::Black,
+            4,
+            PenaltyKind::from_secs(300),
+        )
+        .unwrap();
         tm.update(next_time).unwrap();
         assert_eq!(
             tm.b_penalties,
             vec![Penalty {
-                kind: PenaltyKind::FiveMinute,
+                kind: PenaltyKind::from_secs(300),
                 player_number: 4,
                 start_period: GamePeriod::FirstHalf,
-                start_time: Duration::from_secs(24)
+                start_time: Duration::from_secs(24),
+                start_index: Some(1),
             }],
         );
         assert_eq!(tm.w_penalties, vec![]);
@@ -3885,7 +5496,8 @@ mod test {
                 kind: PenaltyKind::TotalDismissal,
                 player_number: 5,
                 start_period: GamePeriod::FirstHalf,
-                start_time: Duration::from_secs(24)
+                start_time: Duration::from_secs(24),
+                start_index: Some(1),
             }],
         );
         assert_eq!(tm.w_penalties, vec![]);
@@ -3907,64 +5519,104 @@ mod test {
                 kind: PenaltyKind::TotalDismissal,
                 player_number: 6,
                 start_period: GamePeriod::FirstHalf,
-                start_time: Duration::from_secs(24)
+                start_time: Duration::from_secs(24),
+                start_index: Some(1),
             }],
         );
 
         let next_time = next_time + Duration::from_secs(1);
         assert_eq!(
-            tm.edit_penalty(Color::White, 1, Color::White, 2, PenaltyKind::TwoMinute),
+            tm.edit_penalty(
+                Color::White,
+                1,
+                Color::White,
+                2,
+                PenaltyKind::from_secs(120)
+            ),
             Err(TournamentManagerError::InvalidIndex(Color::White, 1))
         );
         assert_eq!(
-            tm.edit_penalty(Color::Black, 0, Color::Black, 2, PenaltyKind::TwoMinute),
+            tm.edit_penalty(
+                Color::Black,
+                0,
+                Color::Black,
+                2,
+                PenaltyKind::from_secs(120)
+            ),
             Err(TournamentManagerError::InvalidIndex(Color::Black, 0))
         );
         assert_eq!(
-            tm.edit_penalty(Color::Black, 1, Color::Black, 2, PenaltyKind::TwoMinute),
+            tm.edit_penalty(
+                Color::Black,
+                1,
+                Color::Black,
+                2,
+                PenaltyKind::from_secs(120)
+            ),
             Err(TournamentManagerError::InvalidIndex(Color::Black, 1))
         );
-        tm.edit_penalty(Color::White, 0, Color::White, 7, PenaltyKind::FiveMinute)
-            .unwrap();
+        tm.edit_penalty(
+            Color::White,
+            0,
+            Color::White,
+            7,
+            PenaltyKind::from_secs(300),
+        )
+        .unwrap();
         tm.update(next_time).unwrap();
         assert_eq!(tm.b_penalties, vec![]);
         assert_eq!(
             tm.w_penalties,
             vec![Penalty {
-                kind: PenaltyKind::FiveMinute,
+                kind: PenaltyKind::from_secs(300),
                 player_number: 7,
                 start_period: GamePeriod::FirstHalf,
-                start_time: Duration::from_secs(24)
+                start_time: Duration::from_secs(24),
+                start_index: Some(1),
             }],
         );
 
         let next_time = next_time + Duration::from_secs(1);
-        tm.edit_penalty(Color::White, 0, Color::White, 8, PenaltyKind::TwoMinute)
-            .unwrap();
+        tm.edit_penalty(
+            Color::White,
+            0,
+            Color::White,
+            8,
+            PenaltyKind::from_secs(120),
+        )
+        .unwrap();
         tm.update(next_time).unwrap();
         assert_eq!(tm.b_penalties, vec![]);
         assert_eq!(
             tm.w_penalties,
             vec![Penalty {
-                kind: PenaltyKind::TwoMinute,
+                kind: PenaltyKind::from_secs(120),
                 player_number: 8,
                 start_period: GamePeriod::FirstHalf,
-                start_time: Duration::from_secs(24)
+                start_time: Duration::from_secs(24),
+                start_index: Some(1),
             }],
         );
 
         let next_time = next_time + Duration::from_secs(1);
-        tm.edit_penalty(Color::White, 0, Color::White, 10, PenaltyKind::OneMinute)
-            .unwrap();
+        tm.edit_penalty(
+            Color::White,
+            0,
+            Color::White,
+            10,
+            PenaltyKind::from_secs(60),
+        )
+        .unwrap();
         tm.update(next_time).unwrap();
         assert_eq!(tm.b_penalties, vec![]);
         assert_eq!(
             tm.w_penalties,
             vec![Penalty {
-                kind: PenaltyKind::OneMinute,
+                kind: PenaltyKind::from_secs(60),
                 player_number: 10,
                 start_period: GamePeriod::FirstHalf,
-                start_time: Duration::from_secs(24)
+                start_time: Duration::from_secs(24),
+                start_index: Some(1),
             }],
         );
     }
@@ -3985,7 +5637,7 @@ mod test {
 
         tm.set_period_and_game_clock_time(GamePeriod::FirstHalf, Duration::from_secs(25));
         tm.start_game_clock(start);
-        tm.start_penalty(Color::Black, 2, PenaltyKind::OneMinute, next_time)
+        tm.start_penalty(Color::Black, 2, PenaltyKind::from_secs(60), next_time)
             .unwrap();
 
         let next_time = next_time + Duration::from_secs(1);
@@ -4001,7 +5653,7 @@ mod test {
         assert_eq!(snapshot.w_penalties, vec![]);
 
         let next_time = next_time + Duration::from_secs(1);
-        tm.start_penalty(Color::White, 3, PenaltyKind::OneMinute, next_time)
+        tm.start_penalty(Color::White, 3, PenaltyKind::from_secs(60), next_time)
             .unwrap();
 
         let next_time = next_time + Duration::from_secs(1);
@@ -4023,9 +5675,9 @@ mod test {
         );
 
         let next_time = next_time + Duration::from_secs(1);
-        tm.start_penalty(Color::Black, 4, PenaltyKind::TwoMinute, next_time)
+        tm.start_penalty(Color::Black, 4, PenaltyKind::from_secs(120), next_time)
             .unwrap();
-        tm.start_penalty(Color::White, 5, PenaltyKind::TwoMinute, next_time)
+        tm.start_penalty(Color::White, 5, PenaltyKind::from_secs(120), next_time)
             .unwrap();
 
         let next_time = next_time + Duration::from_secs(1);
@@ -4059,9 +5711,9 @@ mod test {
         );
 
         let next_time = next_time + Duration::from_secs(1);
-        tm.start_penalty(Color::Black, 6, PenaltyKind::FiveMinute, next_time)
+        tm.start_penalty(Color::Black, 6, PenaltyKind::from_secs(300), next_time)
             .unwrap();
-        tm.start_penalty(Color::White, 7, PenaltyKind::FiveMinute, next_time)
+        tm.start_penalty(Color::White, 7, PenaltyKind::from_secs(300), next_time)
             .unwrap();
 
         let next_time = next_time + Duration::from_secs(1);
@@ -4360,7 +6012,7 @@ mod test {
         tm.w_score = 5;
         tm.set_period_and_game_clock_time(GamePeriod::SecondHalf, Duration::from_secs(25));
         tm.start_game_clock(start);
-        tm.start_penalty(Color::Black, 2, PenaltyKind::OneMinute, next_time)
+        tm.start_penalty(Color::Black, 2, PenaltyKind::from_secs(60), next_time)
             .unwrap();
 
         let next_time = next_time + Duration::from_secs(1);
@@ -4376,7 +6028,7 @@ mod test {
         assert_eq!(snapshot.w_penalties, vec![]);
 
         let next_time = next_time + Duration::from_secs(1);
-        tm.start_penalty(Color::White, 3, PenaltyKind::TwoMinute, next_time)
+        tm.start_penalty(Color::White, 3, PenaltyKind::from_secs(120), next_time)
             .unwrap();
         tm.start_penalty(Color::Black, 5, PenaltyKind::TotalDismissal, next_time)
             .unwrap();
@@ -4449,7 +6101,7 @@ mod test {
 
         tm.set_period_and_game_clock_time(GamePeriod::FirstHalf, Duration::from_secs(25));
         tm.start_game_clock(tm_start);
-        tm.start_penalty(Color::Black, 2, PenaltyKind::OneMinute, pen_start)
+        tm.start_penalty(Color::Black, 2, PenaltyKind::from_secs(60), pen_start)
             .unwrap();
 
         let snapshot = tm.generate_snapshot(earlier_time).unwrap();
@@ -4485,7 +6137,7 @@ mod test {
 
         tm.set_period_and_game_clock_time(GamePeriod::FirstHalf, start_clock_time);
         tm.start_game_clock(tm_start);
-        tm.start_penalty(Color::Black, 2, PenaltyKind::OneMinute, pen_start)
+        tm.start_penalty(Color::Black, 2, PenaltyKind::from_secs(60), pen_start)
             .unwrap();
 
         tm.stop_clock(clock_stop).unwrap(); // At this point the game clock reads 150s, the penalty has 50s left
@@ -4520,13 +6172,13 @@ mod test {
 
         tm.set_period_and_game_clock_time(GamePeriod::FirstHalf, Duration::from_secs(71));
         tm.start_game_clock(start);
-        tm.start_penalty(Color::Black, 2, PenaltyKind::OneMinute, next_time)
+        tm.start_penalty(Color::Black, 2, PenaltyKind::from_secs(60), next_time)
             .unwrap();
-        tm.start_penalty(Color::White, 3, PenaltyKind::OneMinute, next_time)
+        tm.start_penalty(Color::White, 3, PenaltyKind::from_secs(60), next_time)
             .unwrap();
-        tm.start_penalty(Color::Black, 4, PenaltyKind::TwoMinute, next_time)
+        tm.start_penalty(Color::Black, 4, PenaltyKind::from_secs(120), next_time)
             .unwrap();
-        tm.start_penalty(Color::White, 5, PenaltyKind::TwoMinute, next_time)
+        tm.start_penalty(Color::White, 5, PenaltyKind::from_secs(120), next_time)
             .unwrap();
         tm.start_penalty(Color::Black, 6, PenaltyKind::TotalDismissal, next_time)
             .unwrap();
@@ -4659,13 +6311,13 @@ mod test {
         tm.start_game_clock(now);
 
         let pen_start = now + Duration::from_secs(30);
-        tm.start_penalty(Color::Black, 2, PenaltyKind::OneMinute, pen_start)
+        tm.start_penalty(Color::Black, 2, PenaltyKind::from_secs(60), pen_start)
             .unwrap();
-        tm.start_penalty(Color::White, 3, PenaltyKind::OneMinute, pen_start)
+        tm.start_penalty(Color::White, 3, PenaltyKind::from_secs(60), pen_start)
             .unwrap();
-        tm.start_penalty(Color::Black, 4, PenaltyKind::TwoMinute, pen_start)
+        tm.start_penalty(Color::Black, 4, PenaltyKind::from_secs(120), pen_start)
             .unwrap();
-        tm.start_penalty(Color::White, 5, PenaltyKind::TwoMinute, pen_start)
+        tm.start_penalty(Color::White, 5, PenaltyKind::from_secs(120), pen_start)
             .unwrap();
         tm.start_penalty(Color::Black, 6, PenaltyKind::TotalDismissal, pen_start)
             .unwrap();
@@ -4717,13 +6369,13 @@ mod test {
         tm.start_clock(start_time);
         assert_eq!(Ok(false), tm.would_end_game(next_time));
 
-        tm.current_period = GamePeriod::FirstHalf;
+        tm.set_period(GamePeriod::FirstHalf);
         assert_eq!(Ok(false), tm.would_end_game(next_time));
 
-        tm.current_period = GamePeriod::HalfTime;
+        tm.set_period(GamePeriod::HalfTime);
         assert_eq!(Ok(false), tm.would_end_game(next_time));
 
-        tm.current_period = GamePeriod::SecondHalf;
+        tm.set_period(GamePeriod::SecondHalf);
         assert_eq!(Ok(true), tm.would_end_game(next_time));
 
         tm.set_scores(3, 4, start_time);
@@ -4738,16 +6390,16 @@ mod test {
         tm.set_scores(4, 4, start_time);
         assert_eq!(Ok(false), tm.would_end_game(next_time));
 
-        tm.current_period = GamePeriod::PreOvertime;
+        tm.set_period(GamePeriod::PreOvertime);
         assert_eq!(Ok(false), tm.would_end_game(next_time));
 
-        tm.current_period = GamePeriod::OvertimeFirstHalf;
+        tm.set_period(GamePeriod::OvertimeFirstHalf);
         assert_eq!(Ok(false), tm.would_end_game(next_time));
 
-        tm.current_period = GamePeriod::OvertimeHalfTime;
+        tm.set_period(GamePeriod::OvertimeHalfTime);
         assert_eq!(Ok(false), tm.would_end_game(next_time));
 
-        tm.current_period = GamePeriod::OvertimeSecondHalf;
+        tm.set_period(GamePeriod::OvertimeSecondHalf);
         assert_eq!(Ok(false), tm.would_end_game(next_time));
 
         tm.config.sudden_death_allowed = false;
@@ -4801,4 +6453,469 @@ mod test {
         tm.timeout_state = TimeoutState::None;
         assert_eq!(Err(TMErr::InvalidState), tm.halt_clock(next_time));
     }
+
+    #[test]
+    fn test_custom_penalty_kinds() {
+        initialize();
+        let config = GameConfig {
+            half_play_duration: Duration::from_secs(900),
+            penalty_durations: vec![Duration::from_secs(30), Duration::from_secs(640)],
+            ..Default::default()
+        };
+
+        let kinds = PenaltyKind::available(&config);
+        assert_eq!(
+            kinds,
+            vec![
+                PenaltyKind::from_secs(30),
+                PenaltyKind::from_secs(640),
+                PenaltyKind::TotalDismissal
+            ]
+        );
+        assert_eq!(
+            kinds.iter().map(|k| k.to_string()).collect::<Vec<_>>(),
+            vec!["30s", "10m40s", "DSMS"]
+        );
+        assert_eq!(PenaltyKind::from_secs(240).to_string(), "4m");
+
+        let mut tm = TournamentManager::new(config);
+        let now = Instant::now();
+        tm.set_period_and_game_clock_time(GamePeriod::FirstHalf, Duration::from_secs(800));
+        tm.start_game_clock(now);
+        tm.start_penalty(Color::Black, 3, PenaltyKind::from_secs(30), now)
+            .unwrap();
+        tm.start_penalty(Color::White, 7, PenaltyKind::from_secs(640), now)
+            .unwrap();
+
+        let later = now + Duration::from_secs(20);
+        let snapshot = tm.generate_snapshot(later).unwrap();
+        assert_eq!(
+            snapshot.b_penalties,
+            vec![PenaltySnapshot {
+                player_number: 3,
+                time: PenaltyTime::Seconds(10),
+            }]
+        );
+        assert_eq!(
+            snapshot.w_penalties,
+            vec![PenaltySnapshot {
+                player_number: 7,
+                time: PenaltyTime::Seconds(620),
+            }]
+        );
+
+        let later = now + Duration::from_secs(31);
+        let snapshot = tm.generate_snapshot(later).unwrap();
+        assert_eq!(snapshot.b_penalties[0].time, PenaltyTime::Seconds(0));
+        assert_eq!(snapshot.w_penalties[0].time, PenaltyTime::Seconds(609));
+    }
+
+    #[test]
+    fn test_save_and_restore_state() {
+        initialize();
+        let config = GameConfig {
+            half_play_duration: Duration::from_secs(900),
+            ..Default::default()
+        };
+        let mut tm = TournamentManager::new(config.clone());
+
+        let start_time = Instant::now();
+        let next_time = start_time + Duration::from_secs(10);
+
+        tm.set_period_and_game_clock_time(GamePeriod::FirstHalf, Duration::from_secs(600));
+        tm.set_game_number(7);
+        tm.set_scores(2, 1, start_time);
+        tm.start_penalty(Color::Black, 4, PenaltyKind::from_secs(60), start_time)
+            .unwrap();
+        tm.start_clock(start_time);
+        tm.start_w_timeout(next_time).unwrap();
+
+        let state = tm.saved_state(next_time).unwrap();
+        assert_eq!(state.clock_time, Duration::from_secs(590));
+        assert_eq!(
+            state.timeout,
+            SavedTimeout::White(config.team_timeout_duration)
+        );
+
+        let json = serde_json::to_string(&state).unwrap();
+        let state: SavedState = serde_json::from_str(&json).unwrap();
+
+        let mut new_tm = TournamentManager::new(GameConfig::default());
+        new_tm.restore_state(state, start_time);
+        assert!(!new_tm.clock_is_running());
+        assert_eq!(new_tm.config, config);
+        assert_eq!(new_tm.game_number(), 7);
+        assert_eq!(new_tm.current_period(), GamePeriod::FirstHalf);
+        assert_eq!(
+            new_tm.clock_state,
+            ClockState::Stopped {
+                clock_time: Duration::from_secs(590)
+            }
+        );
+        assert_eq!(
+            new_tm.timeout_state,
+            TimeoutState::White(ClockState::Stopped {
+                clock_time: config.team_timeout_duration
+            })
+        );
+        assert_eq!(new_tm.w_timeouts_used, 1);
+        assert_eq!(new_tm.get_b_score(), 2);
+        assert_eq!(new_tm.get_w_score(), 1);
+        assert_eq!(new_tm.get_penalties(), tm.get_penalties());
+    }
+
+    #[test]
+    fn test_game_event_log() {
+        initialize();
+        let config = GameConfig {
+            half_play_duration: Duration::from_secs(5),
+            half_time_duration: Duration::from_secs(3),
+            overtime_allowed: false,
+            sudden_death_allowed: false,
+            ..Default::default()
+        };
+        let mut tm = TournamentManager::new(config);
+
+        let start_time = Instant::now();
+
+        tm.set_period_and_game_clock_time(GamePeriod::BetweenGames, Duration::from_secs(1));
+        tm.start_clock(start_time);
+        tm.update(start_time + Duration::from_secs(1)).unwrap();
+        assert_eq!(tm.current_period(), GamePeriod::FirstHalf);
+
+        let now = start_time + Duration::from_secs(2);
+        tm.add_b_score(3, now);
+        tm.start_penalty(Color::White, 7, PenaltyKind::from_secs(120), now)
+            .unwrap();
+        tm.start_w_timeout(now).unwrap();
+        tm.end_timeout(now).unwrap();
+
+        tm.update(start_time + Duration::from_secs(6)).unwrap();
+        tm.update(start_time + Duration::from_secs(9)).unwrap();
+        assert_eq!(tm.current_period(), GamePeriod::SecondHalf);
+        assert_eq!(tm.take_completed_game_log(), None);
+
+        tm.update(start_time + Duration::from_secs(14)).unwrap();
+        assert_eq!(tm.current_period(), GamePeriod::BetweenGames);
+
+        let log = tm.take_completed_game_log().unwrap();
+        assert_eq!(log.game_number, 1);
+        assert_eq!(log.b_score, 1);
+        assert_eq!(log.w_score, 0);
+        assert_eq!(
+            log.events.iter().map(|e| e.event).collect::<Vec<_>>(),
+            vec![
+                GameEventKind::PeriodStarted {
+                    period: GamePeriod::FirstHalf
+                },
+                GameEventKind::GoalScored {
+                    color: Color::Black,
+                    player_number: 3
+                },
+                GameEventKind::PenaltyStarted {
+                    color: Color::White,
+                    player_number: 7,
+                    kind: PenaltyKind::from_secs(120)
+                },
+                GameEventKind::TimeoutStarted {
+                    kind: TimeoutKind::White
+                },
+                GameEventKind::TimeoutEnded,
+                GameEventKind::PeriodStarted {
+                    period: GamePeriod::HalfTime
+                },
+                GameEventKind::PeriodStarted {
+                    period: GamePeriod::SecondHalf
+                },
+                GameEventKind::GameEnded { black: 1, white: 0 },
+            ]
+        );
+        assert_eq!(log.events[1].period, GamePeriod::FirstHalf);
+        assert_eq!(log.events[1].clock_secs, 4);
+        assert_eq!(
+            log.stats.goals,
+            vec![GoalRecord {
+                color: Color::Black,
+                player_number: 3,
+                period: GamePeriod::FirstHalf,
+                clock_secs: 4,
+            }]
+        );
+        assert_eq!(log.stats.penalties.len(), 1);
+        assert_eq!(tm.game_stats(), &GameStats::default());
+        assert_eq!(tm.take_completed_game_log(), None);
+    }
+
+    #[test]
+    fn test_set_scores_trims_goals() {
+        initialize();
+        let mut tm = TournamentManager::new(GameConfig::default());
+        let now = Instant::now();
+        let scorers = |tm: &TournamentManager| {
+            tm.game_stats()
+                .goals
+                .iter()
+                .map(|goal| (goal.color, goal.player_number))
+                .collect::<Vec<_>>()
+        };
+
+        tm.set_period_and_game_clock_time(GamePeriod::FirstHalf, Duration::from_secs(300));
+        tm.add_b_score(3, now);
+        tm.add_w_score(5, now);
+        tm.add_b_score(4, now);
+
+        // Lowering a score removes that team's most recent goals
+        tm.set_scores(1, 1, now);
+        assert_eq!(scorers(&tm), vec![(Color::Black, 3), (Color::White, 5)]);
+        tm.set_scores(1, 0, now);
+        assert_eq!(scorers(&tm), vec![(Color::Black, 3)]);
+        assert_eq!(
+            tm.game_stats().player_stats("Black", "White"),
+            vec![PlayerStats {
+                team: "Black".to_string(),
+                player_number: 3,
+                goals: 1,
+                penalties: 0,
+                penalty_secs: 0,
+                total_dismissals: 0,
+            }]
+        );
+
+        // Undo puts the goals back where they were, and redo removes them again
+        tm.undo(now).unwrap();
+        assert_eq!(scorers(&tm), vec![(Color::Black, 3), (Color::White, 5)]);
+        tm.undo(now).unwrap();
+        assert_eq!(
+            scorers(&tm),
+            vec![(Color::Black, 3), (Color::White, 5), (Color::Black, 4)]
+        );
+        tm.redo(now).unwrap();
+        assert_eq!(scorers(&tm), vec![(Color::Black, 3), (Color::White, 5)]);
+        assert_eq!((tm.get_b_score(), tm.get_w_score()), (1, 1));
+    }
+
+    #[test]
+    fn test_game_stats() {
+        initialize();
+        let mut tm = TournamentManager::new(GameConfig::default());
+        let now = Instant::now();
+
+        tm.set_period_and_game_clock_time(GamePeriod::SecondHalf, Duration::from_secs(300));
+        tm.add_b_score(3, now);
+        tm.add_w_score(5, now);
+        tm.add_b_score(3, now);
+        tm.set_scores(5, 5, now);
+        tm.start_penalty(Color::Black, 4, PenaltyKind::from_secs(60), now)
+            .unwrap();
+        tm.start_penalty(Color::White, 5, PenaltyKind::from_secs(120), now)
+            .unwrap();
+
+        // Scores that were raised directly can't be attributed to a player
+        assert_eq!(tm.game_stats().goals.len(), 3);
+        assert!(tm
+            .game_stats()
+            .goals
+            .iter()
+            .all(|goal| goal.period == GamePeriod::SecondHalf && goal.clock_secs == 300));
+        assert_eq!(tm.game_stats().penalties.len(), 2);
+
+        tm.undo(now).unwrap();
+        tm.undo(now).unwrap();
+        tm.undo(now).unwrap();
+        tm.undo(now).unwrap();
+        assert_eq!(
+            tm.game_stats()
+                .goals
+                .iter()
+                .map(|goal| (goal.color, goal.player_number))
+                .collect::<Vec<_>>(),
+            vec![(Color::Black, 3), (Color::White, 5)]
+        );
+        assert!(tm.game_stats().penalties.is_empty());
+
+        tm.redo(now).unwrap();
+        tm.redo(now).unwrap();
+        tm.redo(now).unwrap();
+        assert_eq!(tm.game_stats().goals.len(), 3);
+        assert_eq!(
+            tm.game_stats().penalties,
+            vec![PenaltyRecord {
+                color: Color::Black,
+                player_number: 4,
+                kind: PenaltyKind::from_secs(60),
+                period: GamePeriod::SecondHalf,
+                clock_secs: 300,
+            }]
+        );
+
+        tm.edit_penalty(
+            Color::Black,
+            0,
+            Color::White,
+            7,
+            PenaltyKind::TotalDismissal,
+        )
+        .unwrap();
+        assert_eq!(tm.game_stats().penalties.len(), 1);
+        assert_eq!(tm.game_stats().penalties[0].color, Color::White);
+        assert_eq!(tm.game_stats().penalties[0].player_number, 7);
+        assert_eq!(
+            tm.game_stats().penalties[0].kind,
+            PenaltyKind::TotalDismissal
+        );
+
+        // The stats are kept when the game is saved and restored
+        let state = tm.saved_state(now).unwrap();
+        let mut new_tm = TournamentManager::new(GameConfig::default());
+        new_tm.restore_state(state, now);
+        assert_eq!(new_tm.game_stats(), tm.game_stats());
+
+        tm.delete_penalty(Color::White, 0).unwrap();
+        assert!(tm.game_stats().penalties.is_empty());
+        assert_eq!(tm.game_stats().goals.len(), 3);
+    }
+
+    #[test]
+    fn test_undo_redo_scores() {
+        initialize();
+        let mut tm = TournamentManager::new(GameConfig::default());
+        let now = Instant::now();
+
+        tm.set_period_and_game_clock_time(GamePeriod::FirstHalf, Duration::from_secs(300));
+        assert!(!tm.can_undo());
+        assert_eq!(tm.undo(now), Err(TMErr::NothingToUndo));
+
+        tm.add_b_score(3, now);
+        tm.add_w_score(5, now);
+        tm.set_scores(4, 4, now);
+        assert_eq!((tm.get_b_score(), tm.get_w_score()), (4, 4));
+
+        tm.undo(now).unwrap();
+        assert_eq!((tm.get_b_score(), tm.get_w_score()), (1, 1));
+        tm.undo(now).unwrap();
+        assert_eq!((tm.get_b_score(), tm.get_w_score()), (1, 0));
+        assert!(tm.can_redo());
+
+        tm.redo(now).unwrap();
+        assert_eq!((tm.get_b_score(), tm.get_w_score()), (1, 1));
+        assert_eq!(
+            tm.recent_goal.map(|(c, n, _, _)| (c, n)),
+            Some((Color::White, 5))
+        );
+        tm.redo(now).unwrap();
+        assert_eq!((tm.get_b_score(), tm.get_w_score()), (4, 4));
+        assert_eq!(tm.redo(now), Err(TMErr::NothingToRedo));
+
+        tm.undo(now).unwrap();
+        tm.undo(now).unwrap();
+        tm.undo(now).unwrap();
+        assert_eq!((tm.get_b_score(), tm.get_w_score()), (0, 0));
+        assert!(!tm.can_undo());
+
+        // A new action clears the redo history
+        tm.add_w_score(2, now);
+        assert!(!tm.can_redo());
+
+        // The history is bounded
+        for _ in 0..MAX_UNDO_HISTORY + 5 {
+            tm.add_b_score(1, now);
+        }
+        let mut count = 0;
+        while tm.undo(now).is_ok() {
+            count += 1;
+        }
+        assert_eq!(count, MAX_UNDO_HISTORY);
+    }
+
+    #[test]
+    fn test_undo_timeout_and_penalty() {
+        initialize();
+        let config = GameConfig::default();
+        let mut tm = TournamentManager::new(config.clone());
+        let start_time = Instant::now();
+
+        tm.set_period_and_game_clock_time(GamePeriod::FirstHalf, Duration::from_secs(300));
+        tm.start_clock(start_time);
+
+        let to_time = start_time + Duration::from_secs(10);
+        tm.start_penalty(Color::Black, 6, PenaltyKind::from_secs(60), to_time)
+            .unwrap();
+        tm.start_w_timeout(to_time).unwrap();
+        assert_eq!(tm.w_timeouts_used, 1);
+
+        // The game clock resumes from where the timeout stopped it
+        let undo_time = to_time + Duration::from_secs(5);
+        tm.undo(undo_time).unwrap();
+        assert_eq!(tm.timeout_state, TimeoutState::None);
+        assert_eq!(tm.w_timeouts_used, 0);
+        assert!(tm.clock_is_running());
+        assert_eq!(
+            tm.game_clock_time(undo_time + Duration::from_secs(1)),
+            Some(Duration::from_secs(289))
+        );
+
+        tm.undo(undo_time).unwrap();
+        assert!(tm.get_penalties().black.is_empty());
+
+        tm.redo(undo_time).unwrap();
+        assert_eq!(tm.get_penalties().black.len(), 1);
+        assert_eq!(
+            tm.get_penalties().black[0].start_time,
+            Duration::from_secs(290)
+        );
+
+        tm.redo(undo_time).unwrap();
+        assert_eq!(
+            tm.timeout_state,
+            TimeoutState::White(ClockState::CountingDown {
+                start_time: undo_time,
+                time_remaining_at_start: config.team_timeout_duration,
+            })
+        );
+        assert_eq!(tm.w_timeouts_used, 1);
+
+        // Switching the timeout means the start can no longer be undone
+        tm.switch_to_b_timeout().unwrap();
+        assert_eq!(tm.undo(undo_time), Err(TMErr::UndoNotPossible));
+        assert!(!tm.can_undo());
+    }
+
+    #[test]
+    fn test_undo_start_play_now() {
+        initialize();
+        let config = GameConfig::default();
+        let mut tm = TournamentManager::new(config.clone());
+        let start_time = Instant::now();
+
+        tm.set_period_and_game_clock_time(GamePeriod::HalfTime, Duration::from_secs(100));
+        tm.start_clock(start_time);
+
+        let now = start_time + Duration::from_secs(10);
+        tm.start_play_now(now).unwrap();
+        assert_eq!(tm.current_period(), GamePeriod::SecondHalf);
+
+        let undo_time = now + Duration::from_secs(20);
+        tm.undo(undo_time).unwrap();
+        assert_eq!(tm.current_period(), GamePeriod::HalfTime);
+        assert_eq!(tm.game_clock_time(undo_time), Some(Duration::from_secs(70)));
+
+        tm.redo(undo_time).unwrap();
+        assert_eq!(tm.current_period(), GamePeriod::SecondHalf);
+        assert_eq!(
+            tm.game_clock_time(undo_time),
+            Some(config.half_play_duration)
+        );
+
+        // Actions from a previous period can't be undone
+        tm.undo(undo_time).unwrap();
+        tm.add_b_score(1, undo_time);
+        tm.update(start_time + Duration::from_secs(101)).unwrap();
+        assert_eq!(tm.current_period(), GamePeriod::SecondHalf);
+        assert!(!tm.can_undo());
+        assert_eq!(
+            tm.undo(start_time + Duration::from_secs(101)),
+            Err(TMErr::NothingToUndo)
+        );
+        assert_eq!(tm.get_b_score(), 1);
+    }
 }
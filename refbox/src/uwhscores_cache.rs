@@ -0,0 +1,450 @@
+use log::*;
+use reqwest::{Client, Method, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+use thiserror::Error;
+use tokio::{
+    sync::Notify,
+    task,
+    time::{sleep_until, timeout, Duration, Instant},
+};
+use uwh_common::uwhscores::{GameScoreInfo, GameScorePostData, LoginResponse};
+
+const MAX_RETRIES: usize = 6;
+const UPLOAD_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Keeps a copy on disk of the most recent response to each uwhscores request, so that tournament
+/// and game info are still available when the network is down
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    dir: PathBuf,
+}
+
+impl ResponseCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn path_for(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key.replace('/', "-")))
+    }
+
+    /// Saves the raw body of a response. `key` is the path of the request, relative to the
+    /// uwhscores API url
+    pub(crate) fn store(&self, key: &str, body: &str) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.path_for(key), body)
+    }
+
+    /// Reads and parses a cached response, returns `None` if there is no valid cached response
+    pub(crate) fn load<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let path = self.path_for(key);
+        let contents = match fs::read_to_string(&path) {
+            Ok(c) => c,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                warn!("Failed to read cached response from {path:?}: {e}");
+                return None;
+            }
+        };
+
+        match serde_json::from_str(&contents) {
+            Ok(parsed) => Some(parsed),
+            Err(e) => {
+                warn!("Failed to parse cached response from {path:?}: {e}");
+                None
+            }
+        }
+    }
+}
+
+/// Requests `path` (relative to the uwhscores API `url`), retrying on failure, and passes the
+/// parsed response to `deliver`. If the network is down the cached response is delivered while
+/// the request is retried, so `deliver` may be called a second time with the fresh response.
+pub(crate) async fn fetch<T, F>(
+    client: &Client,
+    url: &str,
+    path: &str,
+    cache: &ResponseCache,
+    short_name: &str,
+    retry_delay: Duration,
+    mut deliver: F,
+) where
+    T: DeserializeOwned,
+    F: FnMut(T),
+{
+    info!("Starting request for {short_name}");
+    let request = client
+        .request(Method::GET, format!("{url}{path}"))
+        .build()
+        .unwrap();
+
+    let mut delay_until = None;
+    let mut result = None;
+    let mut sent_cached = false;
+    for _ in 0..MAX_RETRIES {
+        if let Some(time) = delay_until.take() {
+            sleep_until(time).await;
+        }
+
+        let start = Instant::now();
+        result = match client.execute(request.try_clone().unwrap()).await {
+            Ok(resp) => {
+                if resp.status() != StatusCode::OK {
+                    error!(
+                        "Got bad status code from uwhscores when requesting {}: {}",
+                        short_name,
+                        resp.status()
+                    );
+                    info!("Maybe retrying");
+                    continue;
+                }
+                match resp.text().await {
+                    Ok(body) => match serde_json::from_str::<T>(&body) {
+                        Ok(parsed) => {
+                            if let Err(e) = cache.store(path, &body) {
+                                warn!("Failed to cache {short_name}: {e}");
+                            }
+                            Some(parsed)
+                        }
+                        Err(e) => {
+                            error!("Couldn't desesrialize {}: {e}", short_name);
+                            None
+                        }
+                    },
+                    Err(e) => {
+                        error!("Couldn't read response for {}: {e}", short_name);
+                        None
+                    }
+                }
+            }
+            Err(e) => {
+                error!("Request for {} failed: {e}", short_name);
+                // We're probably offline, so use the cached copy while we keep trying
+                if !sent_cached {
+                    if let Some(parsed) = cache.load(path) {
+                        info!("Using cached {short_name} until the request succeeds");
+                        deliver(parsed);
+                        sent_cached = true;
+                    }
+                }
+                info!("Maybe retrying");
+                delay_until = Some(start + retry_delay);
+                continue;
+            }
+        };
+        break;
+    }
+
+    if let Some(parsed) = result {
+        deliver(parsed);
+    } else if sent_cached {
+        warn!("Too many failures when requesting {short_name}, keeping cached copy");
+    } else if let Some(parsed) = cache.load(path) {
+        warn!("Too many failures when requesting {short_name}, using cached copy");
+        deliver(parsed);
+    } else {
+        error!("Too many failures when requesting {short_name}, stopping");
+    }
+}
+
+#[derive(Debug, Error)]
+enum UploadError {
+    #[error("Request failed: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("Got bad status code from uwhscores: {0}")]
+    BadStatus(StatusCode),
+}
+
+impl UploadError {
+    /// Whether uwhscores refused the request in a way that retrying won't fix
+    fn is_permanent(&self) -> bool {
+        match self {
+            Self::BadStatus(status) => {
+                status.is_client_error()
+                    && !matches!(
+                        *status,
+                        StatusCode::UNAUTHORIZED
+                            | StatusCode::REQUEST_TIMEOUT
+                            | StatusCode::TOO_MANY_REQUESTS
+                    )
+            }
+            Self::Request(_) => false,
+        }
+    }
+}
+
+/// A result that uwhscores refused to accept, kept so that it can be entered by hand
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct RejectedScore {
+    pub(crate) score: GameScoreInfo,
+    pub(crate) error: String,
+}
+
+/// Game results that have not yet been accepted by uwhscores. The queue is kept on disk so that
+/// results aren't lost if the app is closed while offline.
+#[derive(Debug, Clone)]
+pub struct ScoreOutbox {
+    path: PathBuf,
+    pending: Arc<Mutex<Vec<GameScoreInfo>>>,
+    wake: Arc<Notify>,
+}
+
+impl ScoreOutbox {
+    /// Creates an outbox backed by the file at `path`, including any results that were still
+    /// pending when the app was last closed
+    pub fn load(path: PathBuf) -> Self {
+        let pending = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                warn!("Failed to parse pending score uploads: {e}");
+                vec![]
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => vec![],
+            Err(e) => {
+                warn!("Failed to read pending score uploads from {path:?}: {e}");
+                vec![]
+            }
+        };
+
+        if !pending.is_empty() {
+            info!("Found {} score(s) waiting to be uploaded", pending.len());
+        }
+
+        Self {
+            path,
+            pending: Arc::new(Mutex::new(pending)),
+            wake: Arc::new(Notify::new()),
+        }
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    /// Adds a result to the queue, replacing any result for the same game that is still pending
+    pub fn push(&self, score: GameScoreInfo) {
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|p| (p.tid, p.gid) != (score.tid, score.gid));
+        pending.push(score);
+        self.save(&pending);
+        std::mem::drop(pending);
+        self.wake.notify_one();
+    }
+
+    fn remove(&self, score: &GameScoreInfo) {
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|p| p != score);
+        self.save(&pending);
+    }
+
+    fn rejected_path(&self) -> PathBuf {
+        self.path.with_extension("rejected.json")
+    }
+
+    /// The results that uwhscores has refused to accept
+    pub(crate) fn rejected(&self) -> Vec<RejectedScore> {
+        let path = self.rejected_path();
+        match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                warn!("Failed to parse rejected score uploads: {e}");
+                vec![]
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => vec![],
+            Err(e) => {
+                warn!("Failed to read rejected score uploads from {path:?}: {e}");
+                vec![]
+            }
+        }
+    }
+
+    /// Moves a result that uwhscores won't accept out of the queue, so that it isn't retried
+    fn reject(&self, score: &GameScoreInfo, error: &UploadError) {
+        let mut rejected = self.rejected();
+        rejected.push(RejectedScore {
+            score: score.clone(),
+            error: error.to_string(),
+        });
+        let path = self.rejected_path();
+        if let Err(e) = serde_json::to_string(&rejected)
+            .map_err(io::Error::from)
+            .and_then(|contents| fs::write(&path, contents))
+        {
+            error!("Failed to save rejected score upload to {path:?}: {e}");
+        }
+        self.remove(score);
+    }
+
+    fn save(&self, pending: &[GameScoreInfo]) {
+        let write = || -> io::Result<()> {
+            if let Some(parent) = self.path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let tmp_path = self.path.with_extension("json.tmp");
+            let mut file = File::create(&tmp_path)?;
+            file.write_all(serde_json::to_string(pending)?.as_bytes())?;
+            file.sync_all()?;
+            fs::rename(&tmp_path, &self.path)
+        };
+
+        if let Err(e) = write() {
+            error!("Failed to save pending score uploads: {e}");
+        }
+    }
+
+    /// Starts a background task that keeps trying to upload the pending results until uwhscores
+    /// accepts them
+    pub fn start_uploader(&self, client: Client, url: String, email: String, password: String) {
+        let outbox = self.clone();
+        task::spawn(async move {
+            loop {
+                let pending = outbox.pending.lock().unwrap().clone();
+
+                if pending.is_empty() {
+                    outbox.wake.notified().await;
+                    continue;
+                }
+
+                info!("Trying to upload {} pending score(s)", pending.len());
+                match login(&client, &url, &email, &password).await {
+                    Ok(token) => {
+                        for score in pending {
+                            match post_score(&client, &url, &token, &score).await {
+                                Ok(()) => {
+                                    info!("Uploaded score: {score:?}");
+                                    outbox.remove(&score);
+                                }
+                                Err(e) if e.is_permanent() => {
+                                    error!(
+                                        "uwhscores rejected score {score:?}, it will not be \
+                                        retried: {e}"
+                                    );
+                                    outbox.reject(&score, &e);
+                                }
+                                Err(e) => error!("Failed to upload score {score:?}: {e}"),
+                            }
+                        }
+                    }
+                    Err(e) => error!("Failed to log in to uwhscores: {e}"),
+                }
+
+                if outbox.pending_count() != 0 {
+                    let _ = timeout(UPLOAD_RETRY_INTERVAL, outbox.wake.notified()).await;
+                }
+            }
+        });
+    }
+}
+
+async fn login(
+    client: &Client,
+    url: &str,
+    email: &str,
+    password: &str,
+) -> Result<String, UploadError> {
+    let resp = client
+        .request(Method::GET, format!("{url}login"))
+        .basic_auth(email, Some(password))
+        .send()
+        .await?;
+    if resp.status() != StatusCode::OK {
+        return Err(UploadError::BadStatus(resp.status()));
+    }
+    Ok(resp.json::<LoginResponse>().await?.token)
+}
+
+async fn post_score(
+    client: &Client,
+    url: &str,
+    token: &str,
+    score: &GameScoreInfo,
+) -> Result<(), UploadError> {
+    let resp = client
+        .request(
+            Method::POST,
+            format!("{url}tournaments/{}/games/{}", score.tid, score.gid),
+        )
+        .basic_auth::<_, String>(token, None)
+        .json(&GameScorePostData::new(score.clone()))
+        .send()
+        .await?;
+    if resp.status() != StatusCode::OK {
+        return Err(UploadError::BadStatus(resp.status()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use uwh_common::uwhscores::GameListResponse;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let mut path = std::env::temp_dir();
+        path.push(format!("refbox-{name}-test-{}", std::process::id()));
+        path
+    }
+
+    #[test]
+    fn test_response_cache() {
+        let dir = temp_path("response-cache");
+        let cache = ResponseCache::new(dir.clone());
+
+        assert_eq!(cache.load::<GameListResponse>("tournaments/1/games"), None);
+
+        cache
+            .store("tournaments/1/games", r#"{"games": []}"#)
+            .unwrap();
+        assert_eq!(
+            cache.load::<GameListResponse>("tournaments/1/games"),
+            Some(GameListResponse { games: vec![] })
+        );
+        assert_eq!(cache.load::<GameListResponse>("tournaments/2/games"), None);
+
+        cache.store("tournaments/1/games", "not json").unwrap();
+        assert_eq!(cache.load::<GameListResponse>("tournaments/1/games"), None);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_score_outbox_persists() {
+        let dir = temp_path("score-outbox");
+        let path = dir.join("score-outbox.json");
+
+        let score = |gid, score_b| GameScoreInfo {
+            tid: 1,
+            gid,
+            score_b,
+            score_w: 0,
+            black_id: 3,
+            white_id: 4,
+        };
+
+        let outbox = ScoreOutbox::load(path.clone());
+        assert_eq!(outbox.pending_count(), 0);
+
+        outbox.push(score(10, 1));
+        outbox.push(score(11, 2));
+        // A corrected result replaces the pending one for the same game
+        outbox.push(score(10, 3));
+        assert_eq!(outbox.pending_count(), 2);
+
+        let reloaded = ScoreOutbox::load(path.clone());
+        assert_eq!(
+            *reloaded.pending.lock().unwrap(),
+            vec![score(11, 2), score(10, 3)]
+        );
+
+        reloaded.remove(&score(11, 2));
+        // Removing a result that has since been replaced leaves the newer one in place
+        reloaded.remove(&score(10, 1));
+        assert_eq!(ScoreOutbox::load(path).pending_count(), 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
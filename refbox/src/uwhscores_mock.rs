@@ -0,0 +1,518 @@
+//! A local stand-in for the uwhscores API, serving fixture data so that the uwhscores flows can be
+//! tested without network access
+
+use log::*;
+use serde_json::{json, Value};
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    task::{self, JoinHandle},
+};
+use uwh_common::uwhscores::{GameScoreInfo, GameScorePostData};
+
+const TOURNAMENTS: &str = include_str!("../resources/uwhscores-mock/tournaments.json");
+const GAMES: &str = include_str!("../resources/uwhscores-mock/games.json");
+const TEAMS: &str = include_str!("../resources/uwhscores-mock/teams.json");
+
+pub const MOCK_TOKEN: &str = "mock-token";
+
+#[derive(Debug)]
+struct MockState {
+    tournaments: Vec<Value>,
+    games: Vec<Value>,
+    teams: Vec<Value>,
+    available: bool,
+    refused_requests: usize,
+    posted_scores: Vec<GameScoreInfo>,
+}
+
+#[derive(Debug)]
+pub struct MockUwhScores {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    join: JoinHandle<()>,
+}
+
+impl MockUwhScores {
+    /// Starts the server on a free port on localhost
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let fixture_list = |fixture: &str, key: &str| -> Vec<Value> {
+            let mut parsed: Value = serde_json::from_str(fixture).unwrap();
+            serde_json::from_value(parsed[key].take()).unwrap()
+        };
+
+        let state = Arc::new(Mutex::new(MockState {
+            tournaments: fixture_list(TOURNAMENTS, "tournaments"),
+            games: fixture_list(GAMES, "games"),
+            teams: fixture_list(TEAMS, "teams"),
+            available: true,
+            refused_requests: 0,
+            posted_scores: vec![],
+        }));
+
+        let state_ = state.clone();
+        let join = task::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                task::spawn(handle_connection(stream, state_.clone()));
+            }
+        });
+
+        Self { addr, state, join }
+    }
+
+    /// The base url of the API, equivalent to `https://uwhscores.com/api/v1/`
+    pub fn url(&self) -> String {
+        format!("http://{}/api/v1/", self.addr)
+    }
+
+    /// While unavailable, every request gets a `503 Service Unavailable` response
+    pub fn set_available(&self, available: bool) {
+        self.state.lock().unwrap().available = available;
+    }
+
+    /// How many requests have been refused because the server was unavailable
+    pub fn refused_requests(&self) -> usize {
+        self.state.lock().unwrap().refused_requests
+    }
+
+    /// All the scores that have been successfully posted, in the order they were received
+    pub fn posted_scores(&self) -> Vec<GameScoreInfo> {
+        self.state.lock().unwrap().posted_scores.clone()
+    }
+}
+
+impl Drop for MockUwhScores {
+    fn drop(&mut self) {
+        self.join.abort();
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    authorized: bool,
+    body: Vec<u8>,
+}
+
+async fn read_request(reader: &mut BufReader<TcpStream>) -> Option<Request> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).await.ok()? == 0 {
+        return None;
+    }
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let path = parts.next()?.to_string();
+
+    let mut content_len = 0;
+    let mut authorized = false;
+    loop {
+        line.clear();
+        reader.read_line(&mut line).await.ok()?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let (name, value) = header.split_once(':')?;
+        match name.to_ascii_lowercase().as_str() {
+            "content-length" => content_len = value.trim().parse().ok()?,
+            "authorization" => authorized = value.trim().starts_with("Basic "),
+            _ => {}
+        }
+    }
+
+    let mut body = vec![0; content_len];
+    reader.read_exact(&mut body).await.ok()?;
+
+    Some(Request {
+        method,
+        path,
+        authorized,
+        body,
+    })
+}
+
+async fn handle_connection(stream: TcpStream, state: Arc<Mutex<MockState>>) {
+    let mut reader = BufReader::new(stream);
+    while let Some(request) = read_request(&mut reader).await {
+        let (status, body) = route(&request, &mut state.lock().unwrap());
+        debug!(
+            "Mock uwhscores: {} {} -> {status}",
+            request.method, request.path
+        );
+        let body = body.to_string();
+        let response = format!(
+            "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        if reader
+            .get_mut()
+            .write_all(response.as_bytes())
+            .await
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+fn id_matches(value: &Value, id: &str) -> bool {
+    match (value.as_u64(), id.parse::<u64>()) {
+        (Some(value), Ok(id)) => value == id,
+        _ => false,
+    }
+}
+
+fn route(request: &Request, state: &mut MockState) -> (&'static str, Value) {
+    const NOT_FOUND: (&str, Value) = ("404 Not Found", Value::Null);
+
+    if !state.available {
+        state.refused_requests += 1;
+        return ("503 Service Unavailable", Value::Null);
+    }
+
+    let path = match request.path.strip_prefix("/api/v1/") {
+        Some(p) => p,
+        None => return NOT_FOUND,
+    };
+    let segments: Vec<&str> = path.split('/').collect();
+    let find = |list: &[Value], key: &str, val: &str| -> Option<Value> {
+        list.iter()
+            .find(|item| id_matches(&item[key], val))
+            .cloned()
+    };
+
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["login"]) => {
+            if request.authorized {
+                (
+                    "200 OK",
+                    json!({"token": MOCK_TOKEN, "ttl": 3600, "user_id": "1"}),
+                )
+            } else {
+                ("401 Unauthorized", Value::Null)
+            }
+        }
+        ("GET", ["tournaments"]) => ("200 OK", json!({ "tournaments": state.tournaments })),
+        ("GET", ["tournaments", tid]) => match find(&state.tournaments, "tid", tid) {
+            Some(t) => ("200 OK", json!({ "tournament": t })),
+            None => NOT_FOUND,
+        },
+        ("GET", ["tournaments", tid, "games"]) => {
+            let games: Vec<&Value> = state
+                .games
+                .iter()
+                .filter(|g| id_matches(&g["tid"], tid))
+                .collect();
+            ("200 OK", json!({ "games": games }))
+        }
+        ("GET", ["tournaments", tid, "games", gid]) => {
+            match state
+                .games
+                .iter()
+                .find(|g| id_matches(&g["tid"], tid) && id_matches(&g["gid"], gid))
+            {
+                Some(g) => ("200 OK", json!({ "game": g })),
+                None => NOT_FOUND,
+            }
+        }
+        ("GET", ["tournaments", tid, "teams", team_id]) => {
+            match state
+                .teams
+                .iter()
+                .find(|t| id_matches(&t["tid"], tid) && id_matches(&t["team_id"], team_id))
+            {
+                Some(t) => ("200 OK", json!({ "team": t })),
+                None => NOT_FOUND,
+            }
+        }
+        ("POST", ["tournaments", tid, "games", gid]) => {
+            if !request.authorized {
+                return ("401 Unauthorized", Value::Null);
+            }
+            if !state
+                .games
+                .iter()
+                .any(|g| id_matches(&g["tid"], tid) && id_matches(&g["gid"], gid))
+            {
+                return NOT_FOUND;
+            }
+            match serde_json::from_slice::<GameScorePostData>(&request.body) {
+                Ok(data) => {
+                    state.posted_scores.push(data.game_score);
+                    ("200 OK", json!({"success": true}))
+                }
+                Err(_) => ("400 Bad Request", Value::Null),
+            }
+        }
+        _ => NOT_FOUND,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        app::{next_game_in_pool, schedule_next_game},
+        tournament_manager::{NextGameInfo, TournamentManager},
+        uwhscores_cache::{fetch, ResponseCache, ScoreOutbox},
+    };
+    use reqwest::Client;
+    use std::{collections::BTreeMap, fs, path::PathBuf};
+    use tokio::time::{sleep, Duration, Instant};
+    use uwh_common::{
+        config::Game as GameConfig,
+        game_snapshot::GamePeriod,
+        uwhscores::{GameInfo, GameListResponse, TeamSingleResponse, TournamentListResponse},
+    };
+
+    const RETRY_DELAY: Duration = Duration::from_millis(10);
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let mut path = std::env::temp_dir();
+        path.push(format!("refbox-mock-{name}-test-{}", std::process::id()));
+        path
+    }
+
+    async fn fetch_games(client: &Client, url: &str, cache: &ResponseCache) -> Vec<Vec<GameInfo>> {
+        let mut received = vec![];
+        fetch(
+            client,
+            url,
+            "tournaments/1/games",
+            cache,
+            "game list",
+            RETRY_DELAY,
+            |parsed: GameListResponse| received.push(parsed.games),
+        )
+        .await;
+        received
+    }
+
+    async fn wait_for(mut cond: impl FnMut() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !cond() {
+            assert!(Instant::now() < deadline, "Timed out waiting for condition");
+            sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_with_cache() {
+        let server = MockUwhScores::start().await;
+        let url = server.url();
+        let client = Client::new();
+        let dir = temp_dir("fetch");
+        let cache = ResponseCache::new(dir.clone());
+
+        let mut tournaments = vec![];
+        fetch(
+            &client,
+            &url,
+            "tournaments",
+            &cache,
+            "tournament list",
+            RETRY_DELAY,
+            |parsed: TournamentListResponse| tournaments.push(parsed.tournaments),
+        )
+        .await;
+        assert_eq!(tournaments.len(), 1);
+        assert_eq!(
+            tournaments[0].iter().map(|t| t.tid).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+
+        let games = fetch_games(&client, &url, &cache).await;
+        assert_eq!(games.len(), 1);
+        assert_eq!(
+            games[0].iter().map(|g| g.gid).collect::<Vec<_>>(),
+            vec![1, 2, 3, 4]
+        );
+
+        // The server is up but failing, so the cached copy is used once the retries run out
+        server.set_available(false);
+        assert_eq!(fetch_games(&client, &url, &cache).await, games);
+
+        // The server is gone, so the cached copy is used straight away
+        std::mem::drop(server);
+        assert_eq!(fetch_games(&client, &url, &cache).await, games);
+
+        // With no server and no cache there is nothing to deliver
+        fs::remove_dir_all(dir).unwrap();
+        assert_eq!(
+            fetch_games(&client, &url, &cache).await,
+            Vec::<Vec<_>>::new()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_roster() {
+        let server = MockUwhScores::start().await;
+        let url = server.url();
+        let client = Client::new();
+        let dir = temp_dir("roster");
+        let cache = ResponseCache::new(dir.clone());
+
+        let mut teams = vec![];
+        for team_id in [11, 14] {
+            fetch(
+                &client,
+                &url,
+                &format!("tournaments/1/teams/{team_id}"),
+                &cache,
+                "team details",
+                RETRY_DELAY,
+                |parsed: TeamSingleResponse| teams.push(parsed.team),
+            )
+            .await;
+        }
+        assert_eq!(teams.len(), 2);
+
+        assert_eq!(teams[0].name, "Sharks");
+        assert_eq!(
+            teams[0]
+                .roster
+                .iter()
+                .map(|p| (p.name.as_str(), p.number))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Alex Finn", Some(1)),
+                ("Jordan Reef", Some(4)),
+                ("Sam Tide", Some(7)),
+                ("Casey Marlin", None)
+            ]
+        );
+
+        // A missing roster is treated as an empty one
+        assert_eq!(teams[1].name, "Crabs");
+        assert!(teams[1].roster.is_empty());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_follow_schedule() {
+        let server = MockUwhScores::start().await;
+        let dir = temp_dir("schedule");
+        let cache = ResponseCache::new(dir.clone());
+
+        let games: BTreeMap<u32, GameInfo> = fetch_games(&Client::new(), &server.url(), &cache)
+            .await[0]
+            .iter()
+            .map(|g| (g.gid, g.clone()))
+            .collect();
+
+        let next_gid =
+            |gid: u32| next_game_in_pool(&games, "1", games[&gid].start_time).map(|game| game.gid);
+        assert_eq!(next_gid(1), Some(3));
+        assert_eq!(next_gid(3), Some(4));
+        assert_eq!(next_gid(4), None);
+
+        let first_game = &games[&1];
+        let mut tm = TournamentManager::new(GameConfig::default());
+        tm.set_next_game(NextGameInfo {
+            number: first_game.gid,
+            timing: first_game.timing_rules.clone(),
+            start_time: Some(first_game.start_time),
+        });
+        let start = Instant::now();
+        tm.start_play_now(start).unwrap();
+        assert_eq!(tm.game_number(), 1);
+
+        // What the app does when a game starts
+        let gid = tm.game_number();
+        schedule_next_game(&mut tm, &games, "1", gid);
+        let next_game = tm.next_game_info().clone().unwrap();
+        assert_eq!(next_game.number, 3);
+        assert_eq!(next_game.start_time, Some(games[&3].start_time));
+
+        // Play out the rest of game 1, then start the scheduled game
+        tm.add_b_score(1, start);
+        tm.stop_clock(start).unwrap();
+        tm.set_period_and_game_clock_time(GamePeriod::SecondHalf, Duration::from_secs(1));
+        tm.start_clock(start);
+        let end = start + Duration::from_secs(2);
+        tm.update(end).unwrap();
+        assert_eq!(tm.current_period(), GamePeriod::BetweenGames);
+        tm.start_play_now(end).unwrap();
+
+        assert_eq!(tm.current_period(), GamePeriod::FirstHalf);
+        assert_eq!(tm.game_number(), 3);
+        assert_eq!(tm.config().half_play_duration, Duration::from_secs(600));
+        assert_eq!(tm.config().team_timeouts_per_half, 2);
+        assert!(!tm.config().timeouts_counted_per_half);
+
+        let gid = tm.game_number();
+        schedule_next_game(&mut tm, &games, "1", gid);
+        assert_eq!(tm.next_game_info().as_ref().unwrap().number, 4);
+
+        // After the last game in the pool, the next game is left as it was
+        let mut tm = TournamentManager::new(GameConfig::default());
+        schedule_next_game(&mut tm, &games, "1", 4);
+        assert_eq!(*tm.next_game_info(), None);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_score_upload() {
+        let server = MockUwhScores::start().await;
+        let dir = temp_dir("upload");
+
+        let score = |gid, score_b, score_w| GameScoreInfo {
+            tid: 1,
+            gid,
+            score_b,
+            score_w,
+            black_id: 11,
+            white_id: 12,
+        };
+
+        let outbox = ScoreOutbox::load(dir.join("score-outbox.json"));
+        outbox.start_uploader(
+            Client::new(),
+            server.url(),
+            "ref@example.com".to_string(),
+            "password".to_string(),
+        );
+
+        outbox.push(score(1, 3, 2));
+        wait_for(|| outbox.pending_count() == 0).await;
+        assert_eq!(server.posted_scores(), vec![score(1, 3, 2)]);
+
+        // Results stay queued while uwhscores is down
+        server.set_available(false);
+        let refused = server.refused_requests();
+        outbox.push(score(3, 0, 1));
+        wait_for(|| server.refused_requests() > refused).await;
+        assert_eq!(outbox.pending_count(), 1);
+
+        // Once it's back, the next attempt uploads everything that is pending
+        server.set_available(true);
+        outbox.push(score(4, 5, 5));
+        wait_for(|| outbox.pending_count() == 0).await;
+        assert_eq!(
+            server.posted_scores(),
+            vec![score(1, 3, 2), score(3, 0, 1), score(4, 5, 5)]
+        );
+        assert!(outbox.rejected().is_empty());
+
+        // A result that uwhscores refuses is set aside instead of being retried forever
+        outbox.push(score(99, 1, 1));
+        wait_for(|| outbox.pending_count() == 0).await;
+        let rejected = outbox.rejected();
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(rejected[0].score, score(99, 1, 1));
+        assert!(rejected[0].error.contains("404"));
+        assert_eq!(server.posted_scores().len(), 3);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,3 +1,4 @@
+use crate::game_snapshot::GamePeriod;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
@@ -6,6 +7,10 @@ use std::time::Duration;
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Game {
     pub team_timeouts_per_half: u16,
+    /// When `false`, team timeouts are allotted per game instead of per half, and the count of
+    /// timeouts used is not reset at half time
+    #[serde(default = "default_true")]
+    pub timeouts_counted_per_half: bool,
     pub overtime_allowed: bool,
     pub sudden_death_allowed: bool,
     #[serde(with = "secs_only_duration")]
@@ -30,12 +35,36 @@ pub struct Game {
     pub nominal_break: Duration,
     #[serde(with = "secs_only_duration")]
     pub minimum_break: Duration,
+    /// The lengths of the timed penalties that can be assigned, in the order they should be
+    /// offered. Total dismissals are always available.
+    #[serde(
+        with = "secs_only_duration_list",
+        default = "default_penalty_durations"
+    )]
+    pub penalty_durations: Vec<Duration>,
+    /// When the game clock is stopped for ref timeouts and penalty shots during play
+    #[serde(default)]
+    pub stop_clock_policy: StopClockPolicy,
+    /// With `StopClockPolicy::LastMinutes`, how close to the end of a play period the clock
+    /// starts being stopped
+    #[serde(with = "secs_only_duration", default = "default_stop_clock_window")]
+    pub stop_clock_window: Duration,
+    /// How long the shooter has to complete a penalty shot
+    #[serde(with = "secs_only_duration", default = "default_penalty_shot_duration")]
+    pub penalty_shot_duration: Duration,
+    /// The play and break periods that make up regulation time, in order. When empty, the game is
+    /// two halves of `half_play_duration` separated by `half_time_duration`. The list must start
+    /// and end with play and can't have two breaks in a row, otherwise it is ignored and the two
+    /// halves are used.
+    #[serde(default)]
+    pub periods: Vec<PeriodSpec>,
 }
 
 impl Default for Game {
     fn default() -> Self {
         Self {
             team_timeouts_per_half: 1,
+            timeouts_counted_per_half: true,
             overtime_allowed: true,
             sudden_death_allowed: true,
             half_play_duration: Duration::from_secs(900),
@@ -49,10 +78,197 @@ impl Default for Game {
             post_game_duration: Duration::from_secs(120),
             nominal_break: Duration::from_secs(900),
             minimum_break: Duration::from_secs(240),
+            penalty_durations: default_penalty_durations(),
+            stop_clock_policy: StopClockPolicy::Always,
+            stop_clock_window: default_stop_clock_window(),
+            penalty_shot_duration: default_penalty_shot_duration(),
+            periods: vec![],
         }
     }
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StopClockPolicy {
+    /// The game clock is stopped for every ref timeout and penalty shot
+    #[default]
+    Always,
+    /// The game clock keeps running through ref timeouts and penalty shots
+    Never,
+    /// The game clock is only stopped when there is less than `stop_clock_window` left in the
+    /// period. In sudden death the clock is always stopped.
+    LastMinutes,
+}
+
+impl StopClockPolicy {
+    /// Whether the game clock should be stopped with `clock_time` left in a play period. Pass
+    /// `None` when the clock is counting up.
+    pub fn stops_clock(self, clock_time: Option<Duration>, window: Duration) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            Self::LastMinutes => clock_time.map_or(true, |time| time <= window),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PeriodKind {
+    Play,
+    Break,
+}
+
+/// One period of regulation time
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeriodSpec {
+    pub kind: PeriodKind,
+    #[serde(with = "secs_only_duration")]
+    pub duration: Duration,
+}
+
+impl PeriodSpec {
+    pub fn play(duration: Duration) -> Self {
+        Self {
+            kind: PeriodKind::Play,
+            duration,
+        }
+    }
+
+    pub fn break_time(duration: Duration) -> Self {
+        Self {
+            kind: PeriodKind::Break,
+            duration,
+        }
+    }
+}
+
+/// A period in the order it will be played, as described by `Game::period_sequence()`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SequencePeriod {
+    pub period: GamePeriod,
+    /// `None` for periods that don't have a fixed length
+    pub duration: Option<Duration>,
+    /// For the regulation periods of a custom `periods` list, which play period or break this is,
+    /// counting from 1. `None` for all other periods.
+    pub number: Option<u8>,
+}
+
+impl Game {
+    /// Whether regulation time is made of the custom `periods` list instead of two halves
+    pub fn uses_custom_periods(&self) -> bool {
+        matches!(self.periods.first(), Some(p) if p.kind == PeriodKind::Play)
+            && matches!(self.periods.last(), Some(p) if p.kind == PeriodKind::Play)
+            && !self
+                .periods
+                .windows(2)
+                .any(|w| w[0].kind == PeriodKind::Break && w[1].kind == PeriodKind::Break)
+    }
+
+    /// The periods of regulation time that will actually be played
+    pub fn regulation_periods(&self) -> Vec<PeriodSpec> {
+        if self.uses_custom_periods() {
+            self.periods.clone()
+        } else {
+            vec![
+                PeriodSpec::play(self.half_play_duration),
+                PeriodSpec::break_time(self.half_time_duration),
+                PeriodSpec::play(self.half_play_duration),
+            ]
+        }
+    }
+
+    /// The total length of regulation time, including the breaks
+    pub fn regulation_duration(&self) -> Duration {
+        self.regulation_periods().iter().map(|p| p.duration).sum()
+    }
+
+    /// Every period a game can go through, in order, starting with `BetweenGames`. Regulation play
+    /// periods are reported as `FirstHalf` for the first one and `SecondHalf` for the rest, and
+    /// regulation breaks are reported as `HalfTime`, with `number` telling them apart when a
+    /// custom `periods` list is used. With the standard two halves the index of each period
+    /// matches the order of `GamePeriod`.
+    pub fn period_sequence(&self) -> Vec<SequencePeriod> {
+        let fixed = |period, duration| SequencePeriod {
+            period,
+            duration: Some(duration),
+            number: None,
+        };
+
+        let mut sequence = vec![SequencePeriod {
+            period: GamePeriod::BetweenGames,
+            duration: None,
+            number: None,
+        }];
+        let custom = self.uses_custom_periods();
+        let (mut plays, mut breaks) = (0u8, 0u8);
+        for spec in self.regulation_periods() {
+            let (period, number) = match spec.kind {
+                PeriodKind::Play => {
+                    plays = plays.saturating_add(1);
+                    let period = if plays == 1 {
+                        GamePeriod::FirstHalf
+                    } else {
+                        GamePeriod::SecondHalf
+                    };
+                    (period, plays)
+                }
+                PeriodKind::Break => {
+                    breaks = breaks.saturating_add(1);
+                    (GamePeriod::HalfTime, breaks)
+                }
+            };
+            sequence.push(SequencePeriod {
+                number: custom.then_some(number),
+                ..fixed(period, spec.duration)
+            });
+        }
+        sequence.extend([
+            fixed(GamePeriod::PreOvertime, self.pre_overtime_break),
+            fixed(GamePeriod::OvertimeFirstHalf, self.ot_half_play_duration),
+            fixed(GamePeriod::OvertimeHalfTime, self.ot_half_time_duration),
+            fixed(GamePeriod::OvertimeSecondHalf, self.ot_half_play_duration),
+            fixed(GamePeriod::PreSuddenDeath, self.pre_sudden_death_duration),
+            SequencePeriod {
+                period: GamePeriod::SuddenDeath,
+                duration: None,
+                number: None,
+            },
+        ]);
+        sequence
+    }
+
+    /// The index in `period_sequence()` of the first period reported as `period`
+    pub fn period_index(&self, period: GamePeriod) -> Option<usize> {
+        self.period_sequence()
+            .iter()
+            .position(|p| p.period == period)
+    }
+
+    /// The index in `period_sequence()` of the last period of regulation play
+    pub fn final_regulation_index(&self) -> usize {
+        self.regulation_periods().len()
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_stop_clock_window() -> Duration {
+    Duration::from_secs(120)
+}
+
+fn default_penalty_shot_duration() -> Duration {
+    Duration::from_secs(45)
+}
+
+fn default_penalty_durations() -> Vec<Duration> {
+    vec![
+        Duration::from_secs(60),
+        Duration::from_secs(120),
+        Duration::from_secs(300),
+    ]
+}
+
 mod secs_only_duration {
     use serde::{self, Deserialize, Deserializer, Serializer};
     use std::time::Duration;
@@ -72,6 +288,28 @@ mod secs_only_duration {
     }
 }
 
+mod secs_only_duration_list {
+    use serde::{self, Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S>(durs: &[Duration], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(durs.iter().map(Duration::as_secs))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<Duration>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Vec::<u64>::deserialize(deserializer)?
+            .into_iter()
+            .map(Duration::from_secs)
+            .collect())
+    }
+}
+
 #[cfg(test)]
 pub mod test {
     use super::*;
@@ -83,4 +321,208 @@ pub mod test {
         let deser = toml::from_str(&serialized);
         assert_eq!(deser, Ok(gm));
     }
+
+    #[test]
+    fn test_deser_game_without_timeout_mode() {
+        let gm = Game {
+            timeouts_counted_per_half: false,
+            ..Default::default()
+        };
+        let serialized = toml::to_string(&gm)
+            .unwrap()
+            .replace("timeouts_counted_per_half = false\n", "");
+        let deser: Game = toml::from_str(&serialized).unwrap();
+        assert!(deser.timeouts_counted_per_half);
+    }
+
+    #[test]
+    fn test_ser_penalty_durations() {
+        let gm = Game {
+            penalty_durations: vec![Duration::from_secs(30), Duration::from_secs(240)],
+            ..Default::default()
+        };
+        let serialized = toml::to_string(&gm).unwrap();
+        assert!(serialized.contains("penalty_durations = [30, 240]"));
+        let deser = toml::from_str(&serialized);
+        assert_eq!(deser, Ok(gm));
+    }
+
+    #[test]
+    fn test_ser_stop_clock_policy() {
+        let gm = Game {
+            stop_clock_policy: StopClockPolicy::LastMinutes,
+            stop_clock_window: Duration::from_secs(60),
+            ..Default::default()
+        };
+        let serialized = toml::to_string(&gm).unwrap();
+        assert!(
+            serialized.contains("stop_clock_policy = \"LastMinutes\"\nstop_clock_window = 60\n")
+        );
+        let deser = toml::from_str(&serialized);
+        assert_eq!(deser, Ok(gm));
+
+        let serialized = serialized
+            .replace("stop_clock_policy = \"LastMinutes\"\n", "")
+            .replace("stop_clock_window = 60\n", "");
+        let deser: Game = toml::from_str(&serialized).unwrap();
+        assert_eq!(deser.stop_clock_policy, StopClockPolicy::Always);
+        assert_eq!(deser.stop_clock_window, Duration::from_secs(120));
+    }
+
+    #[test]
+    fn test_ser_penalty_shot_duration() {
+        let gm = Game {
+            penalty_shot_duration: Duration::from_secs(30),
+            ..Default::default()
+        };
+        let serialized = toml::to_string(&gm).unwrap();
+        assert!(serialized.contains("penalty_shot_duration = 30\n"));
+        let deser = toml::from_str(&serialized);
+        assert_eq!(deser, Ok(gm));
+
+        let serialized = serialized.replace("penalty_shot_duration = 30\n", "");
+        let deser: Game = toml::from_str(&serialized).unwrap();
+        assert_eq!(deser.penalty_shot_duration, Duration::from_secs(45));
+    }
+
+    #[test]
+    fn test_stops_clock() {
+        let window = Duration::from_secs(120);
+        let early = Some(Duration::from_secs(121));
+        let late = Some(Duration::from_secs(120));
+
+        assert!(StopClockPolicy::Always.stops_clock(early, window));
+        assert!(StopClockPolicy::Always.stops_clock(None, window));
+        assert!(!StopClockPolicy::Never.stops_clock(late, window));
+        assert!(!StopClockPolicy::Never.stops_clock(None, window));
+        assert!(!StopClockPolicy::LastMinutes.stops_clock(early, window));
+        assert!(StopClockPolicy::LastMinutes.stops_clock(late, window));
+        assert!(StopClockPolicy::LastMinutes.stops_clock(None, window));
+    }
+
+    #[test]
+    fn test_ser_periods() {
+        let gm = Game {
+            periods: vec![
+                PeriodSpec::play(Duration::from_secs(600)),
+                PeriodSpec::break_time(Duration::from_secs(120)),
+                PeriodSpec::play(Duration::from_secs(600)),
+                PeriodSpec::break_time(Duration::from_secs(120)),
+                PeriodSpec::play(Duration::from_secs(600)),
+            ],
+            ..Default::default()
+        };
+        let serialized = toml::to_string(&gm).unwrap();
+        assert!(serialized.contains("[[periods]]\nkind = \"Break\"\nduration = 120\n"));
+        let deser = toml::from_str(&serialized);
+        assert_eq!(deser, Ok(gm));
+    }
+
+    #[test]
+    fn test_period_sequence() {
+        use GamePeriod::*;
+
+        let periods = |gm: &Game| -> Vec<_> {
+            gm.period_sequence()
+                .into_iter()
+                .map(|p| (p.period, p.duration.map(|d| d.as_secs())))
+                .collect()
+        };
+
+        let standard = Game::default();
+        assert_eq!(
+            periods(&standard),
+            vec![
+                (BetweenGames, None),
+                (FirstHalf, Some(900)),
+                (HalfTime, Some(180)),
+                (SecondHalf, Some(900)),
+                (PreOvertime, Some(180)),
+                (OvertimeFirstHalf, Some(300)),
+                (OvertimeHalfTime, Some(180)),
+                (OvertimeSecondHalf, Some(300)),
+                (PreSuddenDeath, Some(60)),
+                (SuddenDeath, None),
+            ]
+        );
+        assert_eq!(standard.final_regulation_index(), 3);
+        assert_eq!(standard.regulation_duration(), Duration::from_secs(1980));
+
+        let single = Game {
+            periods: vec![PeriodSpec::play(Duration::from_secs(1200))],
+            ..Default::default()
+        };
+        assert_eq!(
+            periods(&single)[..3],
+            [
+                (BetweenGames, None),
+                (FirstHalf, Some(1200)),
+                (PreOvertime, Some(180))
+            ]
+        );
+        assert_eq!(single.final_regulation_index(), 1);
+        assert_eq!(single.period_index(SecondHalf), None);
+        assert_eq!(single.period_index(SuddenDeath), Some(7));
+
+        let three = Game {
+            periods: vec![
+                PeriodSpec::play(Duration::from_secs(480)),
+                PeriodSpec::break_time(Duration::from_secs(60)),
+                PeriodSpec::play(Duration::from_secs(480)),
+                PeriodSpec::break_time(Duration::from_secs(90)),
+                PeriodSpec::play(Duration::from_secs(300)),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            periods(&three)[..7],
+            [
+                (BetweenGames, None),
+                (FirstHalf, Some(480)),
+                (HalfTime, Some(60)),
+                (SecondHalf, Some(480)),
+                (HalfTime, Some(90)),
+                (SecondHalf, Some(300)),
+                (PreOvertime, Some(180)),
+            ]
+        );
+        assert_eq!(
+            three
+                .period_sequence()
+                .iter()
+                .map(|p| p.number)
+                .collect::<Vec<_>>()[..7],
+            [None, Some(1), Some(1), Some(2), Some(2), Some(3), None]
+        );
+        assert!(three.uses_custom_periods());
+        assert!(standard
+            .period_sequence()
+            .iter()
+            .all(|p| p.number.is_none()));
+        assert!(!standard.uses_custom_periods());
+        assert_eq!(three.final_regulation_index(), 5);
+        assert_eq!(three.period_index(SecondHalf), Some(3));
+        assert_eq!(three.regulation_duration(), Duration::from_secs(1410));
+
+        for periods in [
+            vec![PeriodSpec::break_time(Duration::from_secs(60))],
+            vec![
+                PeriodSpec::play(Duration::from_secs(60)),
+                PeriodSpec::break_time(Duration::from_secs(60)),
+            ],
+            vec![
+                PeriodSpec::play(Duration::from_secs(60)),
+                PeriodSpec::break_time(Duration::from_secs(60)),
+                PeriodSpec::break_time(Duration::from_secs(60)),
+                PeriodSpec::play(Duration::from_secs(60)),
+            ],
+        ] {
+            let invalid = Game {
+                periods,
+                ..Default::default()
+            };
+            assert!(!invalid.uses_custom_periods());
+            assert_eq!(invalid.period_sequence(), standard.period_sequence());
+        }
+    }
 }
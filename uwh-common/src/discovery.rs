@@ -0,0 +1,166 @@
+//! Lets displays find a refbox on the local network without being told its address.
+//!
+//! The refbox periodically sends an [`Announcement`] to [`DISCOVERY_PORT`], both as a broadcast
+//! and to the [`DISCOVERY_MULTICAST_ADDR`] group. A display listens on that port and connects to
+//! whichever address the announcement came from.
+
+use serde::{Deserialize, Serialize};
+use socket2::{Domain, Protocol, Socket, Type};
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket},
+    time::{Duration, Instant},
+};
+
+pub const DISCOVERY_PORT: u16 = 8010;
+pub const DISCOVERY_MULTICAST_ADDR: Ipv4Addr = Ipv4Addr::new(239, 255, 85, 72);
+pub const SNAPSHOT_MULTICAST_GROUP: Ipv4Addr = Ipv4Addr::new(239, 255, 85, 73);
+pub const SNAPSHOT_MULTICAST_PORT: u16 = 8011;
+
+/// Default destination for `TransmittedData` frames when the refbox publishes them by multicast
+pub fn snapshot_multicast_addr() -> SocketAddrV4 {
+    SocketAddrV4::new(SNAPSHOT_MULTICAST_GROUP, SNAPSHOT_MULTICAST_PORT)
+}
+
+const MAGIC: &str = "UWH-REFBOX-ANNOUNCE 1\n";
+const MAX_ANNOUNCEMENT_LEN: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Announcement {
+    pub binary_port: u16,
+    pub json_port: u16,
+    pub websocket_port: Option<u16>,
+    pub rest_port: Option<u16>,
+    /// Where `TransmittedData` frames are being published by multicast, if they are
+    pub snapshot_multicast: Option<SocketAddrV4>,
+}
+
+impl Announcement {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut encoded = MAGIC.to_string();
+        encoded.push_str(&toml::to_string(self).unwrap());
+        encoded.into_bytes()
+    }
+
+    /// Returns `None` if `data` is not a valid announcement
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let data = std::str::from_utf8(data).ok()?;
+        toml::from_str(data.strip_prefix(MAGIC)?).ok()
+    }
+}
+
+/// Waits up to `timeout` for an announcement, returning it along with the address of the refbox
+/// that sent it. Returns `Ok(None)` if no refbox was found.
+pub fn discover(timeout: Duration) -> io::Result<Option<(IpAddr, Announcement)>> {
+    let socket = bind_shared(DISCOVERY_PORT)?;
+    if let Err(e) = socket.join_multicast_v4(&DISCOVERY_MULTICAST_ADDR, &Ipv4Addr::UNSPECIFIED) {
+        // Broadcasts will still be received
+        log::warn!("Could not join the discovery multicast group: {e}");
+    }
+    wait_for_announcement(&socket, timeout)
+}
+
+/// Binds a UDP socket to `port` on all interfaces, allowing other programs on the same machine
+/// (e.g. a second display) to listen on the port at the same time
+fn bind_shared(port: u16) -> io::Result<UdpSocket> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_reuse_address(true)?;
+    socket.bind(&SocketAddr::from((Ipv4Addr::UNSPECIFIED, port)).into())?;
+    Ok(socket.into())
+}
+
+fn wait_for_announcement(
+    socket: &UdpSocket,
+    timeout: Duration,
+) -> io::Result<Option<(IpAddr, Announcement)>> {
+    let deadline = Instant::now() + timeout;
+    let mut buf = [0u8; MAX_ANNOUNCEMENT_LEN];
+
+    loop {
+        let remaining = match deadline.checked_duration_since(Instant::now()) {
+            Some(rem) if !rem.is_zero() => rem,
+            _ => return Ok(None),
+        };
+        socket.set_read_timeout(Some(remaining))?;
+
+        let (len, addr): (usize, SocketAddr) = match socket.recv_from(&mut buf) {
+            Ok(recvd) => recvd,
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                return Ok(None)
+            }
+            Err(e) => return Err(e),
+        };
+
+        if let Some(announcement) = Announcement::decode(&buf[..len]) {
+            return Ok(Some((addr.ip(), announcement)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn announcement() -> Announcement {
+        Announcement {
+            binary_port: 8001,
+            json_port: 8000,
+            websocket_port: Some(8002),
+            rest_port: None,
+            snapshot_multicast: Some(snapshot_multicast_addr()),
+        }
+    }
+
+    #[test]
+    fn test_announcement_round_trip() {
+        let ann = announcement();
+        let encoded = ann.encode();
+        assert!(encoded.starts_with(MAGIC.as_bytes()));
+        assert!(encoded.len() <= MAX_ANNOUNCEMENT_LEN);
+        assert_eq!(Announcement::decode(&encoded), Some(ann));
+    }
+
+    #[test]
+    fn test_announcement_decode_rejects_other_data() {
+        assert_eq!(Announcement::decode(b""), None);
+        assert_eq!(Announcement::decode(b"binary_port = 8001"), None);
+        assert_eq!(Announcement::decode(&[0xff, 0xfe]), None);
+
+        let mut encoded = announcement().encode();
+        encoded.truncate(MAGIC.len() + 5);
+        assert_eq!(Announcement::decode(&encoded), None);
+    }
+
+    #[test]
+    fn test_bind_shared() {
+        let first = bind_shared(0).unwrap();
+        let port = first.local_addr().unwrap().port();
+        let second = bind_shared(port).unwrap();
+        assert_eq!(second.local_addr().unwrap().port(), port);
+    }
+
+    #[test]
+    fn test_wait_for_announcement() {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let addr = socket.local_addr().unwrap();
+        let sender = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+
+        assert_eq!(
+            wait_for_announcement(&socket, Duration::from_millis(10)).unwrap(),
+            None
+        );
+
+        // Unrelated packets are skipped
+        sender.send_to(b"hello", addr).unwrap();
+        sender.send_to(&announcement().encode(), addr).unwrap();
+        assert_eq!(
+            wait_for_announcement(&socket, Duration::from_secs(5)).unwrap(),
+            Some((IpAddr::V4(Ipv4Addr::LOCALHOST), announcement()))
+        );
+    }
+}
@@ -0,0 +1,478 @@
+//! An extended version of the panel snapshot encoding that also carries the team names, the game
+//! number, the most recent goal and more penalties. The extended encoding starts with the 19 byte
+//! `GameSnapshotNoHeap` encoding, so firmware that only understands that encoding can still decode
+//! the start of an extended snapshot. Every penalty is then repeated in the wide penalty encoding,
+//! since the 19 byte encoding can only hold three penalties per team and clamps their times.
+
+use crate::game_snapshot::{
+    Color, DecodingError, EncodingError, GamePeriod, GameSnapshotNoHeap, PenaltySnapshot,
+    TimeoutSnapshot, PANEL_PENALTY_COUNT,
+};
+#[cfg(feature = "std")]
+use crate::game_snapshot::{GameSnapshot, PenaltyTime};
+use arrayref::array_ref;
+use arrayvec::{ArrayString, ArrayVec};
+use serde::{Deserialize, Serialize};
+
+pub const TEAM_NAME_LEN: usize = 8;
+pub const DEFAULT_EXTENDED_PENALTY_COUNT: usize = 8;
+
+const GAME_NUMBER_POS: usize = GameSnapshotNoHeap::ENCODED_LEN;
+const RECENT_GOAL_POS: usize = GAME_NUMBER_POS + 4;
+const B_NAME_POS: usize = RECENT_GOAL_POS + 1;
+const W_NAME_POS: usize = B_NAME_POS + TEAM_NAME_LEN;
+const PENALTY_COUNTS_POS: usize = W_NAME_POS + TEAM_NAME_LEN;
+const PENALTIES_POS: usize = PENALTY_COUNTS_POS + 2;
+
+const NO_GOAL: u8 = 0xff;
+const WHITE_GOAL_FLAG: u8 = 0x80;
+
+/// Game snapshot information for panels that can show more than the original encoding allows.
+/// Holds up to `PENALTIES` penalties per team on the stack. Team names are limited to
+/// `TEAM_NAME_LEN` ASCII characters.
+#[derive(Debug, PartialEq, Eq, Default, Clone, Serialize, Deserialize)]
+pub struct GameSnapshotExtended<const PENALTIES: usize = DEFAULT_EXTENDED_PENALTY_COUNT> {
+    pub current_period: GamePeriod,
+    pub secs_in_period: u16,
+    pub timeout: TimeoutSnapshot,
+    pub b_score: u8,
+    pub w_score: u8,
+    pub b_penalties: ArrayVec<PenaltySnapshot, PENALTIES>,
+    pub w_penalties: ArrayVec<PenaltySnapshot, PENALTIES>,
+    pub is_old_game: bool,
+    pub game_number: u32,
+    pub recent_goal: Option<(Color, u8)>,
+    pub b_team_name: ArrayString<TEAM_NAME_LEN>,
+    pub w_team_name: ArrayString<TEAM_NAME_LEN>,
+}
+
+impl<const PENALTIES: usize> GameSnapshotExtended<PENALTIES> {
+    /// The length of an encoded snapshot with no penalties
+    pub const MIN_ENCODED_LEN: usize = PENALTIES_POS;
+    /// The length of an encoded snapshot with `PENALTIES` penalties for each team
+    pub const MAX_ENCODED_LEN: usize =
+        PENALTIES_POS + 2 * PenaltySnapshot::WIDE_ENCODED_LEN * PENALTIES;
+
+    /// The number of bytes that `encode()` will use for this snapshot
+    pub fn encoded_len(&self) -> usize {
+        PENALTIES_POS
+            + PenaltySnapshot::WIDE_ENCODED_LEN * (self.b_penalties.len() + self.w_penalties.len())
+    }
+
+    /// Converts to the original snapshot, keeping only the first three penalties of each team
+    pub fn to_legacy(&self) -> GameSnapshotNoHeap {
+        GameSnapshotNoHeap {
+            current_period: self.current_period,
+            secs_in_period: self.secs_in_period,
+            timeout: self.timeout,
+            b_score: self.b_score,
+            w_score: self.w_score,
+            b_penalties: self
+                .b_penalties
+                .iter()
+                .take(PANEL_PENALTY_COUNT)
+                .cloned()
+                .collect(),
+            w_penalties: self
+                .w_penalties
+                .iter()
+                .take(PANEL_PENALTY_COUNT)
+                .cloned()
+                .collect(),
+            is_old_game: self.is_old_game,
+        }
+    }
+
+    /// Encodes the snapshot into the start of `buf`, returning the number of bytes used
+    pub fn encode(&self, buf: &mut [u8]) -> Result<usize, EncodingError> {
+        let len = self.encoded_len();
+        if buf.len() < len {
+            return Err(EncodingError::BufferTooSmall(buf.len()));
+        }
+
+        buf[..GAME_NUMBER_POS].copy_from_slice(&self.to_legacy().encode()?);
+        buf[GAME_NUMBER_POS..RECENT_GOAL_POS].copy_from_slice(&self.game_number.to_be_bytes());
+        buf[RECENT_GOAL_POS] = encode_goal(self.recent_goal)?;
+        encode_team_name(&self.b_team_name, &mut buf[B_NAME_POS..W_NAME_POS])?;
+        encode_team_name(&self.w_team_name, &mut buf[W_NAME_POS..PENALTY_COUNTS_POS])?;
+
+        // `PENALTIES` is not limited, but the counts must each fit in a byte
+        buf[PENALTY_COUNTS_POS] = self
+            .b_penalties
+            .len()
+            .try_into()
+            .map_err(|_| EncodingError::TooManyPenalties(self.b_penalties.len()))?;
+        buf[PENALTY_COUNTS_POS + 1] = self
+            .w_penalties
+            .len()
+            .try_into()
+            .map_err(|_| EncodingError::TooManyPenalties(self.w_penalties.len()))?;
+
+        for (pen, chunk) in self
+            .b_penalties
+            .iter()
+            .chain(self.w_penalties.iter())
+            .zip(buf[PENALTIES_POS..len].chunks_exact_mut(PenaltySnapshot::WIDE_ENCODED_LEN))
+        {
+            chunk.copy_from_slice(&pen.encode_wide()?);
+        }
+
+        Ok(len)
+    }
+
+    /// Decodes a snapshot from the start of `bytes`. If the snapshot holds more than `PENALTIES`
+    /// penalties for a team, the extra penalties are dropped.
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodingError> {
+        if bytes.len() < PENALTIES_POS {
+            return Err(DecodingError::TooShort(bytes.len()));
+        }
+
+        let b_count = bytes[PENALTY_COUNTS_POS] as usize;
+        let w_count = bytes[PENALTY_COUNTS_POS + 1] as usize;
+        let len = PENALTIES_POS + PenaltySnapshot::WIDE_ENCODED_LEN * (b_count + w_count);
+        if bytes.len() < len {
+            return Err(DecodingError::TooShort(bytes.len()));
+        }
+
+        // The penalties in the legacy part are ignored, since they are all repeated below
+        let legacy =
+            GameSnapshotNoHeap::decode(array_ref![bytes, 0, GameSnapshotNoHeap::ENCODED_LEN])?;
+
+        let mut penalties = bytes[PENALTIES_POS..len]
+            .chunks_exact(PenaltySnapshot::WIDE_ENCODED_LEN)
+            .map(|chunk| {
+                PenaltySnapshot::decode_wide(array_ref![
+                    chunk,
+                    0,
+                    PenaltySnapshot::WIDE_ENCODED_LEN
+                ])
+            });
+        // Ignoring the errors drops the extra penalties
+        let mut b_penalties = ArrayVec::new();
+        for pen in penalties.by_ref().take(b_count).flatten() {
+            let _ = b_penalties.try_push(pen);
+        }
+        let mut w_penalties = ArrayVec::new();
+        for pen in penalties.flatten() {
+            let _ = w_penalties.try_push(pen);
+        }
+
+        Ok(Self {
+            current_period: legacy.current_period,
+            secs_in_period: legacy.secs_in_period,
+            timeout: legacy.timeout,
+            b_score: legacy.b_score,
+            w_score: legacy.w_score,
+            b_penalties,
+            w_penalties,
+            is_old_game: legacy.is_old_game,
+            game_number: u32::from_be_bytes(*array_ref![bytes, GAME_NUMBER_POS, 4]),
+            recent_goal: decode_goal(bytes[RECENT_GOAL_POS])?,
+            b_team_name: decode_team_name(array_ref![bytes, B_NAME_POS, TEAM_NAME_LEN])?,
+            w_team_name: decode_team_name(array_ref![bytes, W_NAME_POS, TEAM_NAME_LEN])?,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const PENALTIES: usize> GameSnapshotExtended<PENALTIES> {
+    /// Builds an extended snapshot, keeping the `PENALTIES` penalties of each team with the
+    /// least time remaining. Team names that are too long are truncated, and non-ASCII characters
+    /// are replaced with `?`.
+    pub fn new(snapshot: GameSnapshot, b_team_name: &str, w_team_name: &str) -> Self {
+        let game_number = snapshot.game_number;
+        let recent_goal = snapshot.recent_goal;
+        let process_penalties = |mut orig: Vec<PenaltySnapshot>| {
+            orig.retain(|pen| pen.time != PenaltyTime::Seconds(0));
+            orig.sort_by_key(|pen| pen.time);
+            orig.into_iter().take(PENALTIES).collect()
+        };
+        let b_penalties = process_penalties(snapshot.b_penalties.clone());
+        let w_penalties = process_penalties(snapshot.w_penalties.clone());
+        let legacy: GameSnapshotNoHeap = snapshot.into();
+
+        Self {
+            current_period: legacy.current_period,
+            secs_in_period: legacy.secs_in_period,
+            timeout: legacy.timeout,
+            b_score: legacy.b_score,
+            w_score: legacy.w_score,
+            b_penalties,
+            w_penalties,
+            is_old_game: legacy.is_old_game,
+            game_number,
+            recent_goal,
+            b_team_name: short_team_name(b_team_name),
+            w_team_name: short_team_name(w_team_name),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+fn short_team_name(name: &str) -> ArrayString<TEAM_NAME_LEN> {
+    let mut short = ArrayString::new();
+    for c in name.trim().chars().take(TEAM_NAME_LEN) {
+        short.push(if c.is_ascii() && !c.is_ascii_control() {
+            c
+        } else {
+            '?'
+        });
+    }
+    short
+}
+
+fn encode_goal(goal: Option<(Color, u8)>) -> Result<u8, EncodingError> {
+    match goal {
+        None => Ok(NO_GOAL),
+        Some((_, num)) if num >= 100 => Err(EncodingError::PlayerNumTooLarge(num)),
+        Some((Color::Black, num)) => Ok(num),
+        Some((Color::White, num)) => Ok(num | WHITE_GOAL_FLAG),
+    }
+}
+
+fn decode_goal(val: u8) -> Result<Option<(Color, u8)>, DecodingError> {
+    let num = val & !WHITE_GOAL_FLAG;
+    match val {
+        NO_GOAL => Ok(None),
+        _ if num >= 100 => Err(DecodingError::InvalidRecentGoal(val)),
+        _ if val & WHITE_GOAL_FLAG != 0 => Ok(Some((Color::White, num))),
+        _ => Ok(Some((Color::Black, num))),
+    }
+}
+
+fn encode_team_name(name: &str, buf: &mut [u8]) -> Result<(), EncodingError> {
+    if !name.bytes().all(|b| b.is_ascii() && b != 0) {
+        return Err(EncodingError::InvalidTeamName);
+    }
+    buf.fill(0);
+    buf[..name.len()].copy_from_slice(name.as_bytes());
+    Ok(())
+}
+
+fn decode_team_name(
+    bytes: &[u8; TEAM_NAME_LEN],
+) -> Result<ArrayString<TEAM_NAME_LEN>, DecodingError> {
+    let len = bytes.iter().position(|b| *b == 0).unwrap_or(TEAM_NAME_LEN);
+    if !bytes[..len].is_ascii() {
+        return Err(DecodingError::InvalidTeamName);
+    }
+    // The bytes are ASCII, so they are valid UTF-8, and `len` fits in the `ArrayString`
+    let name = core::str::from_utf8(&bytes[..len]).map_err(|_| DecodingError::InvalidTeamName)?;
+    ArrayString::from(name).map_err(|_| DecodingError::InvalidTeamName)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn pen(player_number: u8, secs: u16) -> PenaltySnapshot {
+        PenaltySnapshot {
+            player_number,
+            time: PenaltyTime::Seconds(secs),
+        }
+    }
+
+    fn sample() -> GameSnapshotExtended {
+        GameSnapshotExtended {
+            current_period: GamePeriod::SecondHalf,
+            secs_in_period: 431,
+            timeout: TimeoutSnapshot::White(45),
+            b_score: 3,
+            w_score: 5,
+            b_penalties: [pen(1, 20), pen(2, 40), pen(3, 60), pen(4, 80), pen(5, 100)]
+                .into_iter()
+                .collect(),
+            w_penalties: [
+                pen(6, 600),
+                PenaltySnapshot {
+                    player_number: 7,
+                    time: PenaltyTime::TotalDismissal,
+                },
+            ]
+            .into_iter()
+            .collect(),
+            is_old_game: false,
+            game_number: 123_456,
+            recent_goal: Some((Color::White, 12)),
+            b_team_name: ArrayString::from("Sharks").unwrap(),
+            w_team_name: ArrayString::from("Orcas UK").unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let snapshot = sample();
+        let mut buf = [0u8; GameSnapshotExtended::<8>::MAX_ENCODED_LEN];
+        let len = snapshot.encode(&mut buf).unwrap();
+        assert_eq!(len, snapshot.encoded_len());
+        assert_eq!(len, GameSnapshotExtended::<8>::MIN_ENCODED_LEN + 7 * 3);
+        assert_eq!(GameSnapshotExtended::decode(&buf[..len]), Ok(snapshot));
+
+        let empty = GameSnapshotExtended::<8>::default();
+        let len = empty.encode(&mut buf).unwrap();
+        assert_eq!(len, GameSnapshotExtended::<8>::MIN_ENCODED_LEN);
+        assert_eq!(GameSnapshotExtended::decode(&buf[..len]), Ok(empty));
+    }
+
+    /// The `GameSnapshotNoHeap` decoder from before the extended encoding was added, with its
+    /// penalty decoding inlined
+    fn pre_extended_decode(bytes: &[u8; 19]) -> Result<GameSnapshotNoHeap, DecodingError> {
+        let decode_pen = |bytes: &[u8; 2]| {
+            let val = u16::from_be_bytes(*bytes);
+            if (val & 0xfe00) == 0xfe00 {
+                return None;
+            }
+            Some(PenaltySnapshot {
+                player_number: ((val & 0xfe00) >> 9) as u8,
+                time: match val & 0x01ff {
+                    0x01ff => PenaltyTime::TotalDismissal,
+                    time => PenaltyTime::Seconds(time),
+                },
+            })
+        };
+
+        let mut b_penalties = ArrayVec::new();
+        let mut w_penalties = ArrayVec::new();
+        if let Some(pen) = decode_pen(array_ref![bytes, 13, 2]) {
+            w_penalties.push(pen);
+        }
+        if let Some(pen) = decode_pen(array_ref![bytes, 15, 2]) {
+            w_penalties.push(pen);
+        }
+        if let Some(pen) = decode_pen(array_ref![bytes, 17, 2]) {
+            w_penalties.push(pen);
+        }
+        if let Some(pen) = decode_pen(array_ref![bytes, 7, 2]) {
+            b_penalties.push(pen);
+        }
+        if let Some(pen) = decode_pen(array_ref![bytes, 9, 2]) {
+            b_penalties.push(pen);
+        }
+        if let Some(pen) = decode_pen(array_ref![bytes, 11, 2]) {
+            b_penalties.push(pen);
+        }
+
+        Ok(GameSnapshotNoHeap {
+            current_period: GamePeriod::decode(bytes[0] & 0x7f)?,
+            secs_in_period: u16::from_be_bytes(*array_ref![bytes, 1, 2]),
+            timeout: TimeoutSnapshot::decode(array_ref![bytes, 3, 2])?,
+            b_score: bytes[5],
+            w_score: bytes[6],
+            b_penalties,
+            w_penalties,
+            is_old_game: ((bytes[0] & 0x80) != 0x00),
+        })
+    }
+
+    #[test]
+    fn test_legacy_compatible() {
+        let snapshot = sample();
+        let mut buf = [0u8; GameSnapshotExtended::<8>::MAX_ENCODED_LEN];
+        snapshot.encode(&mut buf).unwrap();
+
+        let legacy = pre_extended_decode(array_ref![buf, 0, 19]).unwrap();
+        assert_eq!(legacy.secs_in_period, 431);
+        assert_eq!(legacy.timeout, TimeoutSnapshot::White(45));
+        assert_eq!((legacy.b_score, legacy.w_score), (3, 5));
+        assert_eq!(legacy.b_penalties.as_slice(), &snapshot.b_penalties[..3]);
+        // The legacy encoding clamps long penalty times
+        assert_eq!(legacy.w_penalties[0], pen(6, 510));
+        assert_eq!(legacy.w_penalties[1], snapshot.w_penalties[1]);
+
+        let mut clamped = snapshot.to_legacy();
+        clamped.w_penalties[0] = pen(6, 510);
+        assert_eq!(legacy, clamped);
+        assert_eq!(
+            GameSnapshotNoHeap::decode(array_ref![buf, 0, GameSnapshotNoHeap::ENCODED_LEN]),
+            Ok(clamped)
+        );
+
+        // The extended decoder still gets the full time
+        let extended = GameSnapshotExtended::<8>::decode(&buf).unwrap();
+        assert_eq!(extended.w_penalties[0], pen(6, 600));
+    }
+
+    #[test]
+    fn test_smaller_capacity() {
+        let snapshot = sample();
+        let mut buf = [0u8; GameSnapshotExtended::<8>::MAX_ENCODED_LEN];
+        let len = snapshot.encode(&mut buf).unwrap();
+
+        // A decoder with less room keeps the penalties with the least time remaining
+        let small = GameSnapshotExtended::<4>::decode(&buf[..len]).unwrap();
+        assert_eq!(small.b_penalties.as_slice(), &snapshot.b_penalties[..4]);
+        assert_eq!(small.w_penalties.as_slice(), &snapshot.w_penalties[..]);
+        assert_eq!(small.b_team_name, snapshot.b_team_name);
+        assert_eq!(small.recent_goal, snapshot.recent_goal);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut snapshot = sample();
+        let mut buf = [0u8; GameSnapshotExtended::<8>::MAX_ENCODED_LEN];
+
+        assert_eq!(
+            snapshot.encode(&mut buf[..20]),
+            Err(EncodingError::BufferTooSmall(20))
+        );
+
+        let len = snapshot.encode(&mut buf).unwrap();
+        assert_eq!(
+            GameSnapshotExtended::<8>::decode(&buf[..len - 1]),
+            Err(DecodingError::TooShort(len - 1))
+        );
+        assert_eq!(
+            GameSnapshotExtended::<8>::decode(&buf[..10]),
+            Err(DecodingError::TooShort(10))
+        );
+
+        let mut bad = buf;
+        bad[RECENT_GOAL_POS] = 0x70;
+        assert_eq!(
+            GameSnapshotExtended::<8>::decode(&bad[..len]),
+            Err(DecodingError::InvalidRecentGoal(0x70))
+        );
+
+        let mut bad = buf;
+        bad[B_NAME_POS] = 0xC3;
+        assert_eq!(
+            GameSnapshotExtended::<8>::decode(&bad[..len]),
+            Err(DecodingError::InvalidTeamName)
+        );
+
+        snapshot.w_team_name = ArrayString::from("Équipe").unwrap();
+        assert_eq!(
+            snapshot.encode(&mut buf),
+            Err(EncodingError::InvalidTeamName)
+        );
+
+        snapshot.w_team_name = ArrayString::new();
+        snapshot.recent_goal = Some((Color::Black, 100));
+        assert_eq!(
+            snapshot.encode(&mut buf),
+            Err(EncodingError::PlayerNumTooLarge(100))
+        );
+    }
+
+    #[test]
+    fn test_new() {
+        let snapshot = GameSnapshot {
+            current_period: GamePeriod::FirstHalf,
+            secs_in_period: 700,
+            b_score: 1,
+            b_penalties: vec![pen(4, 0), pen(3, 90), pen(2, 30)],
+            game_number: 42,
+            recent_goal: Some((Color::Black, 9)),
+            ..Default::default()
+        };
+
+        let extended = GameSnapshotExtended::<8>::new(snapshot, " Tigerfish Club ", "Étoile");
+        assert_eq!(extended.secs_in_period, 700);
+        assert_eq!(extended.game_number, 42);
+        assert_eq!(extended.recent_goal, Some((Color::Black, 9)));
+        // Served penalties are dropped and the rest are sorted
+        assert_eq!(extended.b_penalties.as_slice(), &[pen(2, 30), pen(3, 90)]);
+        assert_eq!(extended.b_team_name.as_str(), "Tigerfis");
+        assert_eq!(extended.w_team_name.as_str(), "?toile");
+    }
+}
@@ -14,7 +14,7 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "std")]
 use time::Duration as SignedDuration;
 
-const PANEL_PENALTY_COUNT: usize = 3;
+pub const PANEL_PENALTY_COUNT: usize = 3;
 
 /// Game snapshot information that the LED matrices need. Excludes some fields, limits to three
 /// penalties (the three with the lowest remaining time), and places the penalties on a stack-based
@@ -49,6 +49,17 @@ pub struct GameSnapshot {
     pub tournament_id: u32,
     pub recent_goal: Option<(Color, u8)>,
     pub next_period_len_secs: Option<u32>,
+    /// The name of the black team, if known
+    #[serde(default)]
+    pub b_team_name: Option<String>,
+    /// The name of the white team, if known
+    #[serde(default)]
+    pub w_team_name: Option<String>,
+    /// When the game config has a custom list of periods, which regulation play period or break
+    /// the game is in, counting from 1. `current_period` can't tell these apart, since every play
+    /// period after the first is a `SecondHalf` and every break is a `HalfTime`.
+    #[serde(default)]
+    pub period_number: Option<u8>,
 }
 
 #[cfg(feature = "std")]
@@ -123,22 +134,24 @@ impl GamePeriod {
     }
 
     #[cfg(feature = "std")]
-    pub fn duration(self, config: &Game) -> Option<Duration> {
-        match self {
-            Self::BetweenGames | Self::SuddenDeath => None,
-            Self::FirstHalf | Self::SecondHalf => Some(config.half_play_duration),
-            Self::HalfTime => Some(config.half_time_duration),
-            Self::PreOvertime => Some(config.pre_overtime_break),
-            Self::OvertimeFirstHalf | Self::OvertimeSecondHalf => {
-                Some(config.ot_half_play_duration)
-            }
-            Self::OvertimeHalfTime => Some(config.ot_half_time_duration),
-            Self::PreSuddenDeath => Some(config.pre_sudden_death_duration),
-        }
+    /// The length of the period at `index` in `config.period_sequence()`. Several periods can be
+    /// reported as `self`, so the index tells them apart. `None` if the period at `index` isn't
+    /// reported as `self`, or has no set length.
+    pub fn duration(self, index: usize, config: &Game) -> Option<Duration> {
+        config
+            .period_sequence()
+            .get(index)
+            .filter(|p| p.period == self)
+            .and_then(|p| p.duration)
     }
 
     #[cfg(feature = "std")]
-    pub fn time_elapsed_at(self, time: Duration, config: &Game) -> Option<SignedDuration> {
+    pub fn time_elapsed_at(
+        self,
+        index: usize,
+        time: Duration,
+        config: &Game,
+    ) -> Option<SignedDuration> {
         match self {
             p @ Self::BetweenGames
             | p @ Self::FirstHalf
@@ -149,7 +162,7 @@ impl GamePeriod {
             | p @ Self::OvertimeHalfTime
             | p @ Self::OvertimeSecondHalf
             | p @ Self::PreSuddenDeath => p
-                .duration(config)
+                .duration(index, config)
                 .and_then(|d| d.try_into().ok().map(|sd: SignedDuration| sd - time)),
             Self::SuddenDeath => time.try_into().ok(),
         }
@@ -326,6 +339,12 @@ pub enum EncodingError {
     PenaltyTimeTooLarge(u16),
     /// Timeout time was too large for a u16: {0}
     TimeoutTimeTooLarge(u16),
+    /// Buffer too small for encoded data: {0} bytes
+    BufferTooSmall(usize),
+    /// Too many penalties to encode: {0}
+    TooManyPenalties(usize),
+    /// Team name must be ASCII without NUL characters
+    InvalidTeamName,
 }
 
 #[cfg(feature = "std")]
@@ -338,32 +357,40 @@ pub enum DecodingError {
     InvalidTimeoutType(u16),
     /// Invalid game period: {0:#04x}
     InvalidGamePeriod(u8),
+    /// Not enough data to decode: {0} bytes
+    TooShort(usize),
+    /// Invalid recent goal: {0:#04x}
+    InvalidRecentGoal(u8),
+    /// Team name was not ASCII
+    InvalidTeamName,
 }
 
 #[cfg(feature = "std")]
 impl std::error::Error for DecodingError {}
 
 impl PenaltySnapshot {
-    pub fn encode(&self) -> Result<[u8; 2], EncodingError> {
+    pub const ENCODED_LEN: usize = 2;
+    /// The longest time that fits in the panel encoding. Longer times are sent as this value.
+    pub const MAX_ENCODED_SECS: u16 = 510;
+    pub const WIDE_ENCODED_LEN: usize = 3;
+
+    pub fn encode(&self) -> Result<[u8; Self::ENCODED_LEN], EncodingError> {
         if self.player_number >= 100 {
             return Err(EncodingError::PlayerNumTooLarge(self.player_number));
         }
-        if let PenaltyTime::Seconds(time @ 511..) = self.time {
-            return Err(EncodingError::PenaltyTimeTooLarge(time));
-        }
         let num = (self.player_number as u16) << 9;
         let time = match self.time {
-            PenaltyTime::Seconds(time) => time,
+            PenaltyTime::Seconds(time) => time.min(Self::MAX_ENCODED_SECS),
             PenaltyTime::TotalDismissal => 511,
         };
         Ok((num | time).to_be_bytes())
     }
 
-    pub fn encode_none() -> [u8; 2] {
+    pub fn encode_none() -> [u8; Self::ENCODED_LEN] {
         [0xfe, 0x00]
     }
 
-    pub fn decode(bytes: &[u8; 2]) -> Option<Self> {
+    pub fn decode(bytes: &[u8; Self::ENCODED_LEN]) -> Option<Self> {
         let val = u16::from_be_bytes(*bytes);
         if (val & 0xfe00) == 0xfe00 {
             return None;
@@ -376,6 +403,40 @@ impl PenaltySnapshot {
             },
         })
     }
+
+    /// Encodes the penalty with room for any time that can be displayed, for formats that aren't
+    /// limited to the panel encoding
+    pub fn encode_wide(&self) -> Result<[u8; Self::WIDE_ENCODED_LEN], EncodingError> {
+        if self.player_number >= 100 {
+            return Err(EncodingError::PlayerNumTooLarge(self.player_number));
+        }
+        let time = match self.time {
+            PenaltyTime::Seconds(time) if time > MAX_STRINGABLE_SECS => {
+                return Err(EncodingError::PenaltyTimeTooLarge(time));
+            }
+            PenaltyTime::Seconds(time) => time,
+            PenaltyTime::TotalDismissal => 0xffff,
+        };
+        let time = time.to_be_bytes();
+        Ok([self.player_number, time[0], time[1]])
+    }
+
+    pub fn encode_none_wide() -> [u8; Self::WIDE_ENCODED_LEN] {
+        [0xff, 0x00, 0x00]
+    }
+
+    pub fn decode_wide(bytes: &[u8; Self::WIDE_ENCODED_LEN]) -> Option<Self> {
+        if bytes[0] == 0xff {
+            return None;
+        }
+        Some(Self {
+            player_number: bytes[0],
+            time: match u16::from_be_bytes(*array_ref![bytes, 1, 2]) {
+                0xffff => PenaltyTime::TotalDismissal,
+                time => PenaltyTime::Seconds(time),
+            },
+        })
+    }
 }
 
 impl TimeoutSnapshot {
@@ -460,11 +521,9 @@ impl GameSnapshotNoHeap {
         val[5] = self.b_score;
         val[6] = self.w_score;
 
-        let encode_pen = |pen_opt: Option<&PenaltySnapshot>| -> Result<[u8; 2], EncodingError> {
-            match pen_opt {
-                Some(pen) => pen.encode(),
-                None => Ok(PenaltySnapshot::encode_none()),
-            }
+        let encode_pen = |pen_opt: Option<&PenaltySnapshot>| match pen_opt {
+            Some(pen) => pen.encode(),
+            None => Ok(PenaltySnapshot::encode_none()),
         };
 
         let mut pen_iter = self.b_penalties.iter();
@@ -520,6 +579,7 @@ impl GameSnapshotNoHeap {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::config::PeriodSpec;
 
     #[test]
     fn test_penalty_time_ord() {
@@ -672,41 +732,114 @@ mod test {
             pre_sudden_death_duration: Duration::from_secs(15),
             ..Default::default()
         };
+        let index = |period| config.period_index(period).unwrap();
 
-        assert_eq!(GamePeriod::BetweenGames.duration(&config), None);
         assert_eq!(
-            GamePeriod::FirstHalf.duration(&config),
+            GamePeriod::BetweenGames.duration(index(GamePeriod::BetweenGames), &config),
+            None
+        );
+        assert_eq!(
+            GamePeriod::FirstHalf.duration(index(GamePeriod::FirstHalf), &config),
             Some(Duration::from_secs(5))
         );
         assert_eq!(
-            GamePeriod::HalfTime.duration(&config),
+            GamePeriod::HalfTime.duration(index(GamePeriod::HalfTime), &config),
             Some(Duration::from_secs(7))
         );
         assert_eq!(
-            GamePeriod::SecondHalf.duration(&config),
+            GamePeriod::SecondHalf.duration(index(GamePeriod::SecondHalf), &config),
             Some(Duration::from_secs(5))
         );
         assert_eq!(
-            GamePeriod::PreOvertime.duration(&config),
+            GamePeriod::PreOvertime.duration(index(GamePeriod::PreOvertime), &config),
             Some(Duration::from_secs(9))
         );
         assert_eq!(
-            GamePeriod::OvertimeFirstHalf.duration(&config),
+            GamePeriod::OvertimeFirstHalf.duration(index(GamePeriod::OvertimeFirstHalf), &config),
             Some(Duration::from_secs(11))
         );
         assert_eq!(
-            GamePeriod::OvertimeHalfTime.duration(&config),
+            GamePeriod::OvertimeHalfTime.duration(index(GamePeriod::OvertimeHalfTime), &config),
             Some(Duration::from_secs(13))
         );
         assert_eq!(
-            GamePeriod::OvertimeSecondHalf.duration(&config),
+            GamePeriod::OvertimeSecondHalf.duration(index(GamePeriod::OvertimeSecondHalf), &config),
             Some(Duration::from_secs(11))
         );
         assert_eq!(
-            GamePeriod::PreSuddenDeath.duration(&config),
+            GamePeriod::PreSuddenDeath.duration(index(GamePeriod::PreSuddenDeath), &config),
             Some(Duration::from_secs(15))
         );
-        assert_eq!(GamePeriod::SuddenDeath.duration(&config), None);
+        assert_eq!(
+            GamePeriod::SuddenDeath.duration(index(GamePeriod::SuddenDeath), &config),
+            None
+        );
+
+        let config = Game {
+            periods: vec![
+                PeriodSpec::play(Duration::from_secs(20)),
+                PeriodSpec::break_time(Duration::from_secs(3)),
+                PeriodSpec::play(Duration::from_secs(10)),
+            ],
+            ..config
+        };
+
+        assert_eq!(
+            GamePeriod::FirstHalf.duration(1, &config),
+            Some(Duration::from_secs(20))
+        );
+        assert_eq!(
+            GamePeriod::HalfTime.duration(2, &config),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(
+            GamePeriod::SecondHalf.duration(3, &config),
+            Some(Duration::from_secs(10))
+        );
+        // The period at the index has to be reported as `self`
+        assert_eq!(GamePeriod::SecondHalf.duration(2, &config), None);
+        assert_eq!(GamePeriod::FirstHalf.duration(20, &config), None);
+
+        // Later periods that share a label get their own lengths
+        let config = Game {
+            periods: vec![
+                PeriodSpec::play(Duration::from_secs(600)),
+                PeriodSpec::break_time(Duration::from_secs(120)),
+                PeriodSpec::play(Duration::from_secs(900)),
+                PeriodSpec::break_time(Duration::from_secs(180)),
+                PeriodSpec::play(Duration::from_secs(1200)),
+            ],
+            ..config
+        };
+
+        assert_eq!(
+            GamePeriod::SecondHalf.duration(3, &config),
+            Some(Duration::from_secs(900))
+        );
+        assert_eq!(
+            GamePeriod::HalfTime.duration(4, &config),
+            Some(Duration::from_secs(180))
+        );
+        assert_eq!(
+            GamePeriod::SecondHalf.duration(5, &config),
+            Some(Duration::from_secs(1200))
+        );
+        assert_eq!(
+            GamePeriod::PreOvertime.duration(6, &config),
+            Some(Duration::from_secs(9))
+        );
+
+        let config = Game {
+            periods: vec![PeriodSpec::play(Duration::from_secs(20))],
+            ..config
+        };
+
+        assert_eq!(GamePeriod::HalfTime.duration(2, &config), None);
+        assert_eq!(GamePeriod::SecondHalf.duration(3, &config), None);
+        assert_eq!(
+            GamePeriod::PreOvertime.duration(2, &config),
+            Some(Duration::from_secs(9))
+        );
     }
 
     #[test]
@@ -801,78 +934,151 @@ mod test {
             pre_sudden_death_duration: Duration::from_secs(15),
             ..Default::default()
         };
+        let index = |period| config.period_index(period).unwrap();
 
         assert_eq!(
-            GamePeriod::BetweenGames.time_elapsed_at(Duration::from_secs(5), &config),
+            GamePeriod::BetweenGames.time_elapsed_at(
+                index(GamePeriod::BetweenGames),
+                Duration::from_secs(5),
+                &config
+            ),
             None
         );
         assert_eq!(
-            GamePeriod::FirstHalf.time_elapsed_at(Duration::from_secs(3), &config),
+            GamePeriod::FirstHalf.time_elapsed_at(
+                index(GamePeriod::FirstHalf),
+                Duration::from_secs(3),
+                &config
+            ),
             Some(SignedDuration::seconds(2))
         );
         assert_eq!(
-            GamePeriod::HalfTime.time_elapsed_at(Duration::from_secs(4), &config),
+            GamePeriod::HalfTime.time_elapsed_at(
+                index(GamePeriod::HalfTime),
+                Duration::from_secs(4),
+                &config
+            ),
             Some(SignedDuration::seconds(3))
         );
         assert_eq!(
-            GamePeriod::SecondHalf.time_elapsed_at(Duration::from_secs(3), &config),
+            GamePeriod::SecondHalf.time_elapsed_at(
+                index(GamePeriod::SecondHalf),
+                Duration::from_secs(3),
+                &config
+            ),
             Some(SignedDuration::seconds(2))
         );
         assert_eq!(
-            GamePeriod::PreOvertime.time_elapsed_at(Duration::from_secs(4), &config),
+            GamePeriod::PreOvertime.time_elapsed_at(
+                index(GamePeriod::PreOvertime),
+                Duration::from_secs(4),
+                &config
+            ),
             Some(SignedDuration::seconds(5))
         );
         assert_eq!(
-            GamePeriod::OvertimeFirstHalf.time_elapsed_at(Duration::from_secs(7), &config),
+            GamePeriod::OvertimeFirstHalf.time_elapsed_at(
+                index(GamePeriod::OvertimeFirstHalf),
+                Duration::from_secs(7),
+                &config
+            ),
             Some(SignedDuration::seconds(4))
         );
         assert_eq!(
-            GamePeriod::OvertimeHalfTime.time_elapsed_at(Duration::from_secs(8), &config),
+            GamePeriod::OvertimeHalfTime.time_elapsed_at(
+                index(GamePeriod::OvertimeHalfTime),
+                Duration::from_secs(8),
+                &config
+            ),
             Some(SignedDuration::seconds(5))
         );
         assert_eq!(
-            GamePeriod::OvertimeSecondHalf.time_elapsed_at(Duration::from_secs(7), &config),
+            GamePeriod::OvertimeSecondHalf.time_elapsed_at(
+                index(GamePeriod::OvertimeSecondHalf),
+                Duration::from_secs(7),
+                &config
+            ),
             Some(SignedDuration::seconds(4))
         );
         assert_eq!(
-            GamePeriod::PreSuddenDeath.time_elapsed_at(Duration::from_secs(9), &config),
+            GamePeriod::PreSuddenDeath.time_elapsed_at(
+                index(GamePeriod::PreSuddenDeath),
+                Duration::from_secs(9),
+                &config
+            ),
             Some(SignedDuration::seconds(6))
         );
         assert_eq!(
-            GamePeriod::SuddenDeath.time_elapsed_at(Duration::from_secs(3), &config),
+            GamePeriod::SuddenDeath.time_elapsed_at(
+                index(GamePeriod::SuddenDeath),
+                Duration::from_secs(3),
+                &config
+            ),
             Some(SignedDuration::seconds(3))
         );
 
         assert_eq!(
-            GamePeriod::FirstHalf.time_elapsed_at(Duration::from_secs(9), &config),
+            GamePeriod::FirstHalf.time_elapsed_at(
+                index(GamePeriod::FirstHalf),
+                Duration::from_secs(9),
+                &config
+            ),
             Some(SignedDuration::seconds(-4))
         );
         assert_eq!(
-            GamePeriod::HalfTime.time_elapsed_at(Duration::from_secs(9), &config),
+            GamePeriod::HalfTime.time_elapsed_at(
+                index(GamePeriod::HalfTime),
+                Duration::from_secs(9),
+                &config
+            ),
             Some(SignedDuration::seconds(-2))
         );
         assert_eq!(
-            GamePeriod::SecondHalf.time_elapsed_at(Duration::from_secs(9), &config),
+            GamePeriod::SecondHalf.time_elapsed_at(
+                index(GamePeriod::SecondHalf),
+                Duration::from_secs(9),
+                &config
+            ),
             Some(SignedDuration::seconds(-4))
         );
         assert_eq!(
-            GamePeriod::PreOvertime.time_elapsed_at(Duration::from_secs(25), &config),
+            GamePeriod::PreOvertime.time_elapsed_at(
+                index(GamePeriod::PreOvertime),
+                Duration::from_secs(25),
+                &config
+            ),
             Some(SignedDuration::seconds(-16))
         );
         assert_eq!(
-            GamePeriod::OvertimeFirstHalf.time_elapsed_at(Duration::from_secs(25), &config),
+            GamePeriod::OvertimeFirstHalf.time_elapsed_at(
+                index(GamePeriod::OvertimeFirstHalf),
+                Duration::from_secs(25),
+                &config
+            ),
             Some(SignedDuration::seconds(-14))
         );
         assert_eq!(
-            GamePeriod::OvertimeHalfTime.time_elapsed_at(Duration::from_secs(25), &config),
+            GamePeriod::OvertimeHalfTime.time_elapsed_at(
+                index(GamePeriod::OvertimeHalfTime),
+                Duration::from_secs(25),
+                &config
+            ),
             Some(SignedDuration::seconds(-12))
         );
         assert_eq!(
-            GamePeriod::OvertimeSecondHalf.time_elapsed_at(Duration::from_secs(25), &config),
+            GamePeriod::OvertimeSecondHalf.time_elapsed_at(
+                index(GamePeriod::OvertimeSecondHalf),
+                Duration::from_secs(25),
+                &config
+            ),
             Some(SignedDuration::seconds(-14))
         );
         assert_eq!(
-            GamePeriod::PreSuddenDeath.time_elapsed_at(Duration::from_secs(25), &config),
+            GamePeriod::PreSuddenDeath.time_elapsed_at(
+                index(GamePeriod::PreSuddenDeath),
+                Duration::from_secs(25),
+                &config
+            ),
             Some(SignedDuration::seconds(-10))
         );
     }
@@ -944,6 +1150,47 @@ mod test {
         assert_eq!(GamePeriod::SuddenDeath.next_period(), None);
     }
 
+    #[test]
+    fn test_penalty_wide_encoding() {
+        let pens = [
+            PenaltySnapshot {
+                player_number: 0,
+                time: PenaltyTime::Seconds(0),
+            },
+            PenaltySnapshot {
+                player_number: 99,
+                time: PenaltyTime::Seconds(600),
+            },
+            PenaltySnapshot {
+                player_number: 12,
+                time: PenaltyTime::Seconds(MAX_STRINGABLE_SECS),
+            },
+            PenaltySnapshot {
+                player_number: 7,
+                time: PenaltyTime::TotalDismissal,
+            },
+        ];
+        for pen in pens {
+            assert_eq!(
+                PenaltySnapshot::decode_wide(&pen.encode_wide().unwrap()),
+                Some(pen)
+            );
+        }
+        assert_eq!(
+            PenaltySnapshot::decode_wide(&PenaltySnapshot::encode_none_wide()),
+            None
+        );
+
+        let too_long = PenaltySnapshot {
+            player_number: 3,
+            time: PenaltyTime::Seconds(MAX_STRINGABLE_SECS + 1),
+        };
+        assert_eq!(
+            too_long.encode_wide(),
+            Err(EncodingError::PenaltyTimeTooLarge(MAX_STRINGABLE_SECS + 1))
+        );
+    }
+
     #[test]
     fn test_custom_serialize_and_desereialize() -> Result<(), Box<dyn std::error::Error>> {
         let mut state = GameSnapshotNoHeap {
@@ -1003,6 +1250,21 @@ mod test {
 
         test_state(&mut state)?;
 
+        // Times that don't fit are sent as the longest time that does
+        let mut long = state.clone();
+        long.b_penalties[1].time = PenaltyTime::Seconds(600);
+        long.w_penalties[1].time = PenaltyTime::Seconds(MAX_STRINGABLE_SECS);
+        let decoded = GameSnapshotNoHeap::decode(&long.encode()?)?;
+        assert_eq!(
+            decoded.b_penalties[1].time,
+            PenaltyTime::Seconds(PenaltySnapshot::MAX_ENCODED_SECS)
+        );
+        assert_eq!(
+            decoded.w_penalties[1].time,
+            PenaltyTime::Seconds(PenaltySnapshot::MAX_ENCODED_SECS)
+        );
+        assert_eq!(decoded.b_penalties[0], long.b_penalties[0]);
+
         state.current_period = GamePeriod::SecondHalf;
         state.secs_in_period = 900;
         state.timeout = TimeoutSnapshot::Ref(432);
@@ -1,10 +1,17 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
+pub mod extended_snapshot;
 pub mod game_snapshot;
 
 #[cfg(feature = "std")]
 pub mod config;
 
+#[cfg(feature = "std")]
+pub mod discovery;
+
+#[cfg(feature = "std")]
+pub mod recording;
+
 #[cfg(feature = "std")]
 pub mod uwhscores;
 
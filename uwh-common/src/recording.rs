@@ -0,0 +1,124 @@
+//! The format of the snapshot recordings made by the refbox.
+//!
+//! A recording is a file with one JSON encoded `RecordedSnapshot` per line.
+
+use crate::game_snapshot::GameSnapshot;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufReader, Write},
+    path::Path,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedSnapshot {
+    /// Milliseconds since the start of the recording
+    pub millis: u64,
+    pub white_on_right: bool,
+    pub snapshot: GameSnapshot,
+}
+
+impl RecordedSnapshot {
+    /// Writes the snapshot as one line of a recording
+    pub fn write_line<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        serde_json::to_writer(&mut writer, self)?;
+        writer.write_all(b"\n")
+    }
+}
+
+#[derive(Debug)]
+pub enum RecordingError {
+    IoError(io::Error),
+    Parse {
+        line: usize,
+        source: serde_json::Error,
+    },
+}
+
+impl fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IoError(e) => write!(f, "{e}"),
+            Self::Parse { line, source } => write!(f, "Invalid recording on line {line}: {source}"),
+        }
+    }
+}
+
+impl std::error::Error for RecordingError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::IoError(e) => Some(e),
+            Self::Parse { source, .. } => Some(source),
+        }
+    }
+}
+
+impl From<io::Error> for RecordingError {
+    fn from(e: io::Error) -> Self {
+        Self::IoError(e)
+    }
+}
+
+/// Reads a whole recording. Blank lines are ignored.
+pub fn read_recording(path: &Path) -> Result<Vec<RecordedSnapshot>, RecordingError> {
+    parse_recording(BufReader::new(File::open(path)?))
+}
+
+/// Parses a whole recording from `reader`. Blank lines are ignored.
+pub fn parse_recording<R: BufRead>(reader: R) -> Result<Vec<RecordedSnapshot>, RecordingError> {
+    let mut records = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        records.push(
+            serde_json::from_str(&line).map_err(|source| RecordingError::Parse {
+                line: i + 1,
+                source,
+            })?,
+        );
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_write_and_parse() {
+        let records = vec![
+            RecordedSnapshot {
+                millis: 0,
+                white_on_right: false,
+                snapshot: GameSnapshot {
+                    b_score: 1,
+                    ..Default::default()
+                },
+            },
+            RecordedSnapshot {
+                millis: 1500,
+                white_on_right: true,
+                snapshot: GameSnapshot {
+                    w_score: 3,
+                    secs_in_period: 412,
+                    ..Default::default()
+                },
+            },
+        ];
+
+        let mut data = Vec::new();
+        for record in records.iter() {
+            record.write_line(&mut data).unwrap();
+        }
+        data.extend_from_slice(b"\n  \n");
+        assert_eq!(parse_recording(&data[..]).unwrap(), records);
+
+        assert!(matches!(
+            parse_recording(&b"\n{\"millis\": 5}\n"[..]),
+            Err(RecordingError::Parse { line: 2, .. })
+        ));
+    }
+}
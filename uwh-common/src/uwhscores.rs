@@ -67,18 +67,20 @@ pub struct TimingRules {
     pub sudden_death_allowed: bool,
 }
 
-#[allow(clippy::from_over_into)]
-impl Into<GameConfig> for TimingRules {
-    fn into(self) -> GameConfig {
+impl TimingRules {
+    /// Returns `config` with the settings that uwhscores controls replaced by these rules. The
+    /// other settings (e.g. the penalty lengths) are kept from `config`.
+    pub fn apply_to(&self, config: &GameConfig) -> GameConfig {
         GameConfig {
             team_timeouts_per_half: self.game_timeouts.allowed,
+            timeouts_counted_per_half: self.game_timeouts.per_half,
             team_timeout_duration: self.game_timeouts.duration,
             half_play_duration: self.half_duration,
             half_time_duration: self.half_time_duration,
             minimum_break: self.min_game_break,
             overtime_allowed: self.overtime_allowed,
             sudden_death_allowed: self.sudden_death_allowed,
-            ..Default::default()
+            ..config.clone()
         }
     }
 }
@@ -126,6 +128,25 @@ pub struct GameSingleResponse {
     pub game: GameInfo,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct TeamInfo {
+    pub name: String,
+    #[serde(default, deserialize_with = "deser_with_null_to_default")]
+    pub roster: Vec<PlayerInfo>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct PlayerInfo {
+    pub name: String,
+    #[serde(default, deserialize_with = "deser_or_default")]
+    pub number: Option<u8>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct TeamSingleResponse {
+    pub team: TeamInfo,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct LoginResponse {
     pub token: String,
@@ -157,6 +178,7 @@ impl GameScorePostData {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::config::StopClockPolicy;
     use log::*;
     use reqwest::{blocking::Client, Method, StatusCode};
     use std::sync::Once;
@@ -169,6 +191,38 @@ mod test {
         });
     }
 
+    #[test]
+    fn test_timing_rules_apply_to() {
+        let rules = TimingRules {
+            game_timeouts: GameTimeouts {
+                allowed: 2,
+                duration: Duration::from_secs(45),
+                per_half: false,
+            },
+            half_duration: Duration::from_secs(600),
+            half_time_duration: Duration::from_secs(120),
+            min_game_break: Duration::from_secs(300),
+            overtime_allowed: false,
+            sudden_death_allowed: true,
+        };
+        let base = GameConfig {
+            stop_clock_policy: StopClockPolicy::Never,
+            penalty_shot_duration: Duration::from_secs(30),
+            penalty_durations: vec![Duration::from_secs(30), Duration::from_secs(240)],
+            ..Default::default()
+        };
+        let config = rules.apply_to(&base);
+        assert_eq!(config.team_timeouts_per_half, 2);
+        assert!(!config.timeouts_counted_per_half);
+        assert_eq!(config.team_timeout_duration, Duration::from_secs(45));
+        assert_eq!(config.half_play_duration, Duration::from_secs(600));
+
+        // Settings that uwhscores doesn't have are kept
+        assert_eq!(config.stop_clock_policy, StopClockPolicy::Never);
+        assert_eq!(config.penalty_shot_duration, Duration::from_secs(30));
+        assert_eq!(config.penalty_durations, base.penalty_durations);
+    }
+
     #[test]
     #[ignore]
     fn test_all_uwhscores_requests() {
@@ -213,7 +267,7 @@ mod test {
             let games = resp.json::<GameListResponse>().unwrap().games;
 
             info!("Getting the details of each game");
-            for g in games {
+            for g in games.iter() {
                 info!("Getting details of game {}", g.gid);
                 let request = client
                     .request(
@@ -227,7 +281,25 @@ mod test {
                 resp.json::<GameSingleResponse>().unwrap();
             }
 
-            // TODO: test gettting team details once added
+            info!("Getting the details of each team");
+            let team_ids: std::collections::BTreeSet<u32> = games
+                .iter()
+                .flat_map(|g| [g.black_id, g.white_id])
+                .filter(|&id| id != 0)
+                .collect();
+            for team_id in team_ids {
+                info!("Getting details of team {team_id}");
+                let request = client
+                    .request(
+                        Method::GET,
+                        format!("{URL}tournaments/{}/teams/{team_id}", t.tid),
+                    )
+                    .build()
+                    .unwrap();
+                let resp = client.execute(request).unwrap();
+                assert_eq!(resp.status(), StatusCode::OK);
+                resp.json::<TeamSingleResponse>().unwrap();
+            }
         }
     }
 }